@@ -1,13 +1,21 @@
 use crate::stmt::Stmt;
+use serde::Serialize;
+use span::*;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct Block {
     pub stmts: Vec<Stmt>,
+
+    /// The span from `{` to `}`.
+    pub span: Span,
 }
 
 impl Block {
     pub fn new(stmts: Vec<Stmt>) -> Block {
-        Block { stmts }
+        Block {
+            stmts,
+            span: DUMMY_SP,
+        }
     }
 }
 
@@ -15,6 +23,7 @@ impl<T: Into<Vec<Stmt>>> From<T> for Block {
     fn from(stmts: T) -> Block {
         Block {
             stmts: stmts.into(),
+            span: DUMMY_SP,
         }
     }
 }
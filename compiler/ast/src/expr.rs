@@ -1,10 +1,27 @@
 use crate::{block::*, lit::*, op::*, stmt::Stmt, *};
+use serde::Serialize;
 use span::*;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub enum Expr {
     /// A function call: `foo(a, b)`
-    Call { fun: Box<Expr>, args: Vec<Expr> },
+    Call {
+        fun: Box<Expr>,
+        args: Vec<Expr>,
+
+        /// The span of the arguments from `(` to `)`.
+        span: Span,
+    },
+
+    /// A method call: `x.foo(a, b)`
+    MethodCall {
+        receiver: Box<Expr>,
+        method: Ident,
+        args: Vec<Expr>,
+
+        /// The span of the arguments from `(` to `)`.
+        span: Span,
+    },
 
     /// A binary operation: `a + b`, "a * b"
     Binary {
@@ -13,9 +30,12 @@ pub enum Expr {
         rhs: Box<Expr>,
     },
 
-    /// A unary operation: `-x`
+    /// A unary operation: `-x`, `*x`
     Unary { op: UnOp, expr: Box<Expr> },
 
+    /// A referencing operation: `&a`, `&mut a`
+    AddrOf { mutbl: Mutability, expr: Box<Expr> },
+
     /// If expression: `if a == b { 0 } else { 1 }`
     If {
         cond: Box<Expr>,
@@ -46,6 +66,44 @@ pub enum Expr {
 
     /// A path such as variables, functions, etx: `foo`, `bar`
     Path(Path),
+
+    /// A closure expression: `|x: i32| x + a`, `move || a`
+    Closure {
+        capture_by: CaptureBy,
+        inputs: Vec<Param>,
+        output: Option<Ty>,
+        body: Box<Expr>,
+        id: NodeId,
+    },
+}
+
+pub const PREC_CLOSURE: i8 = -40;
+pub const PREC_JUMP: i8 = -30;
+/// The parser takes an expression with a block only at the start of an expression,
+/// so it is parenthesized as an operand.
+pub const PREC_BLOCK: i8 = -20;
+
+// The range 2..=14 is reserved for AssocOp binary operator precedences.
+pub const PREC_ASSIGN: i8 = 2;
+
+pub const PREC_PREFIX: i8 = 50;
+pub const PREC_POSTFIX: i8 = 60;
+pub const PREC_PAREN: i8 = 99;
+
+impl Expr {
+    pub fn precedence(&self) -> i8 {
+        use Expr::*;
+        match self {
+            Closure { .. } => PREC_CLOSURE,
+            Break { .. } | Continue { .. } | Return { .. } => PREC_JUMP,
+            If { .. } | Loop { .. } | Block { .. } => PREC_BLOCK,
+            Binary { op, .. } => op.precedence() as i8,
+            Assign { .. } => PREC_ASSIGN,
+            Unary { .. } | AddrOf { .. } => PREC_PREFIX,
+            Call { .. } | MethodCall { .. } => PREC_POSTFIX,
+            Lit { .. } | Path(_) => PREC_PAREN,
+        }
+    }
 }
 
 impl Expr {
@@ -53,6 +111,16 @@ impl Expr {
         Expr::Call {
             fun: Box::new(fun),
             args: args.into(),
+            span: DUMMY_SP,
+        }
+    }
+
+    pub fn method_call<T: Into<Vec<Expr>>>(receiver: Expr, method: Symbol, args: T) -> Expr {
+        Expr::MethodCall {
+            receiver: Box::new(receiver),
+            method: Ident::with_dummy_span(method),
+            args: args.into(),
+            span: DUMMY_SP,
         }
     }
 
@@ -71,6 +139,13 @@ impl Expr {
         }
     }
 
+    pub fn addr_of(mutbl: Mutability, expr: Expr) -> Expr {
+        Expr::AddrOf {
+            mutbl,
+            expr: Box::new(expr),
+        }
+    }
+
     pub fn if_<T: Into<Block>>(cond: Expr, then: T, else_opt: Option<Expr>) -> Expr {
         Expr::If {
             cond: Box::new(cond),
@@ -148,9 +223,22 @@ impl Expr {
         Expr::Path(path)
     }
 
+    pub fn closure_dummy<T: Into<Vec<Param>>>(
+        capture_by: CaptureBy,
+        inputs: T,
+        output: Option<Ty>,
+        body: Expr,
+    ) -> Expr {
+        Expr::Closure {
+            capture_by,
+            inputs: inputs.into(),
+            output,
+            body: Box::new(body),
+            id: DUMMY_NODE_ID,
+        }
+    }
+
     pub fn path_dummy(symbol: Symbol) -> Expr {
-        Expr::Path(Path {
-            ident: Ident::with_dummy_span(symbol),
-        })
+        Expr::Path(Path::from_ident(Ident::with_dummy_span(symbol)))
     }
 }
@@ -1,10 +1,13 @@
-use crate::{block::Block, stmt::*, ty::Ty};
+use crate::{block::Block, expr::Expr, stmt::*, ty::Ty, Mutability, NodeId, Path, DUMMY_NODE_ID};
 
+use serde::Serialize;
 use span::*;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct Item {
+    pub id: NodeId,
     pub ident: Ident,
+    pub vis: Visibility,
     pub kind: ItemKind,
 }
 
@@ -16,8 +19,12 @@ impl Item {
         block: U,
     ) -> Item {
         Item {
+            id: DUMMY_NODE_ID,
             ident: Ident::with_dummy_span(name),
+            vis: Visibility::Inherited,
             kind: ItemKind::Fn(Box::new(Fn {
+                inline: InlineAttr::None,
+                generics: Generics::default(),
                 inputs: inputs.into(),
                 output,
                 body: Block::from(block),
@@ -26,33 +33,204 @@ impl Item {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub enum ItemKind {
     Fn(Box<Fn>),
+
+    /// A trait declaration: `trait Foo { fn foo(&self) -> i32; }`
+    Trait(Box<Trait>),
+
+    /// An implementation: `impl Foo for i32 { .. }`, `impl i32 { .. }`
+    /// The ident of the item is the `impl` keyword.
+    Impl(Box<Impl>),
+
+    /// A module: `mod foo { .. }`, `mod foo;`
+    Mod(Box<Mod>),
+
+    /// A use declaration: `use foo::bar;`, `use foo::bar as baz;`, `use foo::*;`
+    /// The ident of the item is the `use` keyword.
+    Use(Box<UseTree>),
+
+    /// A constant item: `const N: i32 = 4 * 8;`
+    Const(Box<Const>),
+
+    /// A static item: `static mut COUNTER: i32 = 0;`
+    Static(Box<Static>),
+}
+
+/// The visibility of an item.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub enum Visibility {
+    /// `pub`, visible from everywhere.
+    Public,
+
+    /// No qualifier, visible in the module which defines the item and its descendants.
+    Inherited,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Mod {
+    pub items: Vec<Item>,
+
+    /// `true` for `mod foo { .. }`, `false` for `mod foo;` whose items are loaded from `foo.elaina`.
+    pub inline: bool,
+
+    /// The span from `{` to `}`, or `DUMMY_SP` for `mod foo;`.
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct UseTree {
+    pub prefix: Path,
+    pub kind: UseTreeKind,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub enum UseTreeKind {
+    /// `use foo::bar;`, or `use foo::bar as baz;` with the alias.
+    Simple(Option<Ident>),
+
+    /// `use foo::*;`
+    Glob,
+}
+
+/// The type and the initializer of a constant item.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Const {
+    pub ty: Ty,
+    pub expr: Expr,
+}
+
+/// The type and the initializer of a static item.
+/// Unlike a constant, a static is a single memory location, which is mutable with `static mut`.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Static {
+    pub mutbl: Mutability,
+    pub ty: Ty,
+    pub expr: Expr,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct Fn {
+    pub inline: InlineAttr,
+    pub generics: Generics,
     pub inputs: Vec<Param>,
     pub output: Option<Ty>,
     pub body: Block,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// The inlining attribute of a function.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub enum InlineAttr {
+    /// No attribute, the optimizer decides whether to inline the function.
+    None,
+
+    /// `#[inline]`, the function is inlined wherever it is possible.
+    Hint,
+
+    /// `#[inline(never)]`
+    Never,
+}
+
+/// Generic parameters of an item: `<T, U>`
+#[derive(Debug, PartialEq, Clone, Default, Serialize)]
+pub struct Generics {
+    pub params: Vec<GenericParam>,
+}
+
+/// A generic parameter with its trait bounds: `T`, `T: Foo + Bar`
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct GenericParam {
+    pub id: NodeId,
+    pub ident: Ident,
+    pub bounds: Vec<Path>,
+}
+
+impl GenericParam {
+    pub fn new_dummy(name: Symbol) -> GenericParam {
+        GenericParam {
+            id: DUMMY_NODE_ID,
+            ident: Ident::with_dummy_span(name),
+            bounds: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Trait {
+    pub items: Vec<TraitItem>,
+
+    /// The span from `{` to `}`.
+    pub span: Span,
+}
+
+/// A method signature in a trait: `fn foo(&self, a: i32) -> i32;`
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct TraitItem {
+    pub id: NodeId,
+    pub ident: Ident,
+    pub inputs: Vec<Param>,
+    pub output: Option<Ty>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Impl {
+    /// The implemented trait, or `None` for an inherent impl.
+    pub of_trait: Option<Path>,
+    pub self_ty: Ty,
+
+    /// Methods of the impl. These are always function items.
+    pub items: Vec<Item>,
+
+    /// The span from `{` to `}`.
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct Param {
+    pub id: NodeId,
+    pub mutbl: Mutability,
     pub ty: Ty,
     pub ident: Ident,
 }
 
 impl Param {
     pub fn new(ty: Ty, ident: Ident) -> Param {
-        Param { ty, ident }
+        Param {
+            id: DUMMY_NODE_ID,
+            mutbl: Mutability::Not,
+            ty,
+            ident,
+        }
     }
 
     pub fn new_dummy(ty: Ty, name: Symbol) -> Param {
         Param {
+            id: DUMMY_NODE_ID,
+            mutbl: Mutability::Not,
             ty,
             ident: Ident::with_dummy_span(name),
         }
     }
+
+    pub fn new_mut_dummy(ty: Ty, name: Symbol) -> Param {
+        Param {
+            id: DUMMY_NODE_ID,
+            mutbl: Mutability::Mut,
+            ty,
+            ident: Ident::with_dummy_span(name),
+        }
+    }
+
+    /// Returns `self`, `&self` or `&mut self` parameter.
+    /// `ref_mutbl` is the mutability of the reference, `None` for `self` taken by value.
+    pub fn self_dummy(ref_mutbl: Option<Mutability>) -> Param {
+        let self_ty = Ty::path_with_dummy_span(Kw::SelfUpper);
+        let ty = match ref_mutbl {
+            Some(mutbl) => Ty::ref_(mutbl, self_ty),
+            None => self_ty,
+        };
+
+        Param::new_dummy(ty, Kw::SelfLower.into())
+    }
 }
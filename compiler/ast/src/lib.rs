@@ -3,6 +3,7 @@ pub mod expr;
 pub mod item;
 pub mod lit;
 pub mod op;
+pub mod pp;
 pub mod stmt;
 pub mod token;
 pub mod ty;
@@ -15,9 +16,75 @@ pub use op::*;
 pub use stmt::*;
 pub use ty::*;
 
+use serde::Serialize;
 use span::*;
 
-#[derive(Debug, PartialEq, Clone)]
+/// Identifies a node of the AST which defines or refers to a name. Name resolution is keyed by it.
+/// Only these nodes carry an id, which the parser assigns distinctly across the crate:
+/// the items, the trait items, the generic parameters, the parameters of the functions,
+/// the locals, the paths and the closures.
+/// The other nodes, like the blocks and the other expressions, have no id.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize)]
+pub struct NodeId(u32);
+
+/// The id of the nodes built outside of the parser, which are never resolved.
+pub const DUMMY_NODE_ID: NodeId = NodeId(u32::MAX);
+
+impl NodeId {
+    pub fn from_usize(id: usize) -> NodeId {
+        NodeId(id as u32)
+    }
+
+    pub fn as_usize(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// A path to an item or a local: `foo`, `shapes::area`, `crate::math::Add`
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct Path {
-    pub ident: Ident,
+    pub segments: Vec<Ident>,
+
+    /// The id which the item or the local the path refers to is resolved for.
+    pub id: NodeId,
+}
+
+impl Path {
+    pub fn from_ident(ident: Ident) -> Path {
+        Path {
+            segments: vec![ident],
+            id: DUMMY_NODE_ID,
+        }
+    }
+
+    /// Returns the last segment, which names the item or the local the path refers to.
+    pub fn ident(&self) -> &Ident {
+        self.segments.last().unwrap()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub enum Mutability {
+    Mut,
+    Not,
+}
+
+/// How a closure captures the variables of its environment.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub enum CaptureBy {
+    /// By value: `move |x| x + a`
+    Value,
+
+    /// By reference: `|x| x + a`
+    Ref,
+}
+
+impl Mutability {
+    /// Returns `"mut "` or `""` depending on the mutability.
+    pub fn prefix_str(&self) -> &'static str {
+        match self {
+            Mutability::Mut => "mut ",
+            Mutability::Not => "",
+        }
+    }
 }
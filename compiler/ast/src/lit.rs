@@ -1,6 +1,7 @@
+use serde::Serialize;
 use span::*;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct Lit {
     pub kind: LitKind,
     pub span: Span,
@@ -19,7 +20,7 @@ impl Lit {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub enum LitKind {
     /// An integer literal: `0`, `1`, `64`
     Int(u128),
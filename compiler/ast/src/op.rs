@@ -1,3 +1,5 @@
+use serde::Serialize;
+
 use std::fmt;
 
 #[derive(PartialEq, Debug)]
@@ -10,7 +12,7 @@ pub enum Fixity {
     None,
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
 pub enum BinOp {
     /// The `*` operator (multiplication)
     Mul,
@@ -70,6 +72,14 @@ impl BinOp {
         }
     }
 
+    pub fn is_comparison(&self) -> bool {
+        use BinOp::*;
+        match *self {
+            Eq | Lt | Le | Ne | Ge | Gt => true,
+            Mul | Div | Add | Sub => false,
+        }
+    }
+
     pub fn fixity(&self) -> Fixity {
         use BinOp::*;
         match *self {
@@ -78,16 +88,20 @@ impl BinOp {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
 pub enum UnOp {
     /// The `-` operator (negation)
     Neg,
+
+    /// The `*` operator (dereferencing)
+    Deref,
 }
 
 impl fmt::Display for UnOp {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             UnOp::Neg => write!(f, "-"),
+            UnOp::Deref => write!(f, "*"),
         }
     }
 }
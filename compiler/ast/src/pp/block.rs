@@ -0,0 +1,63 @@
+use super::{span_hi, span_lo, ASTPrinter};
+use crate::*;
+
+use printer::Printer;
+
+impl ASTPrinter<'_> {
+    /// Print a block with each statement on a new line, or `{}` if it is empty.
+    pub fn print_block(&mut self, block: &Block) {
+        let hi = span_hi(block.span);
+
+        let has_comments = hi.and_then(|hi| self.next_comment_before(hi)).is_some();
+        if block.stmts.is_empty() && !has_comments {
+            self.print("{}");
+            return;
+        }
+
+        self.print("{");
+        self.indent += 1;
+        for stmt in &block.stmts {
+            let lo = stmt_lo(stmt);
+            self.print_comments_before(lo);
+            if let Some(lo) = lo {
+                self.print_blank_line(lo);
+            }
+            self.newline();
+            self.print_stmt(stmt);
+        }
+        self.print_closing_comments(hi);
+        self.indent -= 1;
+        self.newline();
+        self.print("}");
+    }
+}
+
+/// Returns the start of the first span in the statement, before which its leading comments are.
+fn stmt_lo(stmt: &Stmt) -> Option<u32> {
+    match stmt {
+        Stmt::Local { ident, .. } => span_lo(ident.span),
+        Stmt::Expr(expr) | Stmt::Semi(expr) | Stmt::Println(expr) => expr_lo(expr),
+    }
+}
+
+pub(super) fn expr_lo(expr: &Expr) -> Option<u32> {
+    match expr {
+        Expr::Call { fun: expr, .. }
+        | Expr::MethodCall { receiver: expr, .. }
+        | Expr::Binary { lhs: expr, .. }
+        | Expr::Assign { lhs: expr, .. }
+        | Expr::Unary { expr, .. }
+        | Expr::AddrOf { expr, .. } => expr_lo(expr),
+        Expr::If { cond, then, .. } => expr_lo(cond).or_else(|| span_lo(then.span)),
+        Expr::Loop { block } | Expr::Block { block } => span_lo(block.span),
+        Expr::Break { expr } | Expr::Continue { expr } | Expr::Return { expr } => {
+            expr.as_deref().and_then(expr_lo)
+        }
+        Expr::Lit { lit } => span_lo(lit.span),
+        Expr::Path(path) => span_lo(path.segments[0].span),
+        Expr::Closure { inputs, body, .. } => inputs
+            .first()
+            .and_then(|param| span_lo(param.ident.span))
+            .or_else(|| expr_lo(body)),
+    }
+}
@@ -0,0 +1,186 @@
+use super::{block::expr_lo, span_hi, ASTPrinter};
+use crate::*;
+
+use printer::{Delim, Printer};
+
+impl ASTPrinter<'_> {
+    pub fn print_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Call { fun, args, span } => {
+                self.print_expr_maybe_paren(fun, PREC_POSTFIX);
+                self.print_args(args, *span);
+            }
+            Expr::MethodCall {
+                receiver,
+                method,
+                args,
+                span,
+            } => {
+                self.print_expr_maybe_paren(receiver, PREC_POSTFIX);
+                self.dot();
+                self.print_ident(method);
+                self.print_args(args, *span);
+            }
+            Expr::Binary { op, lhs, rhs } => {
+                self.print_expr_binary(op, lhs, rhs);
+            }
+            Expr::Unary { op, expr } => {
+                self.print(op.to_string());
+                self.print_expr_maybe_paren(expr, PREC_PREFIX);
+            }
+            Expr::AddrOf { mutbl, expr } => {
+                self.print("&");
+                self.print(mutbl.prefix_str());
+                self.print_expr_maybe_paren(expr, PREC_PREFIX);
+            }
+            Expr::If {
+                cond,
+                then,
+                else_opt,
+            } => {
+                self.print_space("if");
+                self.print_expr(cond);
+                self.space();
+                self.print_block(then);
+                self.print_else(else_opt.as_deref());
+            }
+            Expr::Loop { block } => {
+                self.print_space("loop");
+                self.print_block(block);
+            }
+            Expr::Break { expr } => self.print_expr_jump("break", expr.as_deref()),
+            Expr::Continue { expr } => self.print_expr_jump("continue", expr.as_deref()),
+            Expr::Return { expr } => self.print_expr_jump("return", expr.as_deref()),
+            Expr::Block { block } => {
+                self.print_block(block);
+            }
+            Expr::Assign { lhs, rhs } => {
+                // The left-hand side is parsed as an equality.
+                self.print_expr_maybe_paren(lhs, BinOp::Eq.precedence() as i8);
+                self.space();
+                self.eq();
+                self.space();
+                self.print_expr(rhs);
+            }
+            Expr::Lit { lit } => match lit.kind {
+                LitKind::Int(value) => self.print(value),
+                LitKind::Bool(value) => self.print(value),
+            },
+            Expr::Path(path) => self.print_path(path),
+            Expr::Closure {
+                capture_by,
+                inputs,
+                output,
+                body,
+                ..
+            } => {
+                if *capture_by == CaptureBy::Value {
+                    self.print_space("move");
+                }
+                self.print("|");
+                self.separated(
+                    inputs.iter(),
+                    |this| {
+                        this.comma();
+                        this.space();
+                    },
+                    |this, param| this.print_param(param),
+                );
+                self.print("|");
+                self.print_output(output);
+                self.space();
+                self.print_expr(body);
+            }
+        }
+    }
+
+    fn print_expr_maybe_paren(&mut self, expr: &Expr, prec: i8) {
+        if expr.precedence() < prec {
+            self.with_delim(Delim::Paren, false, |this| {
+                this.print_expr(expr);
+            });
+        } else {
+            self.print_expr(expr);
+        }
+    }
+
+    fn print_expr_binary(&mut self, op: &BinOp, lhs: &Expr, rhs: &Expr) {
+        let prec = op.precedence() as i8;
+
+        // The parser reads the arithmetic operators as right-associative: `a - b - c` is `a - (b - c)`,
+        // and a comparison takes no comparison as its operand.
+        let (left_prec, right_prec) = if op.is_comparison() {
+            (prec + 1, prec + 1)
+        } else {
+            (prec + 1, prec)
+        };
+
+        self.print_expr_maybe_paren(lhs, left_prec);
+        self.space_print_space(op);
+        self.print_expr_maybe_paren(rhs, right_prec);
+    }
+
+    fn print_else(&mut self, else_opt: Option<&Expr>) {
+        if let Some(else_) = else_opt {
+            match else_ {
+                Expr::If {
+                    cond,
+                    then,
+                    else_opt,
+                } => {
+                    self.space_print_space("else if");
+                    self.print_expr(cond);
+                    self.space();
+                    self.print_block(then);
+                    self.print_else(else_opt.as_deref());
+                }
+                Expr::Block { block } => {
+                    self.space_print_space("else");
+                    self.print_block(block);
+                }
+                _ => {
+                    panic!("print_if saw if with weird alternative");
+                }
+            }
+        }
+    }
+
+    /// print `break`, `continue` or `return` with the optional operand.
+    fn print_expr_jump(&mut self, keyword: &str, expr: Option<&Expr>) {
+        self.print(keyword);
+        if let Some(expr) = expr {
+            self.space();
+            self.print_expr(expr);
+        }
+    }
+
+    /// Print the arguments of a call within `span`: `(a, b)`
+    /// If there are comments between the parentheses, each argument is put on its own line
+    /// with a trailing comma, so that the comments stay next to the arguments.
+    fn print_args(&mut self, args: &[Expr], span: Span) {
+        let hi = span_hi(span);
+        if hi.and_then(|hi| self.next_comment_before(hi)).is_none() {
+            self.list(args.iter(), Delim::Paren, |this, arg| {
+                this.print_expr(arg);
+            });
+            return;
+        }
+
+        self.print("(");
+        self.indent += 1;
+        for arg in args {
+            let lo = expr_lo(arg);
+            if let Some(lo) = lo {
+                self.skip_blank_lines(lo);
+            }
+            self.print_comments_before(lo);
+            self.newline();
+            self.print_expr(arg);
+            self.comma();
+        }
+        self.print_closing_comments(hi);
+        self.indent -= 1;
+        self.newline();
+        self.print(")");
+    }
+}
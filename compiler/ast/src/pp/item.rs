@@ -0,0 +1,196 @@
+use super::{span_hi, span_lo, ASTPrinter};
+use crate::*;
+
+use printer::{Delim, Printer};
+
+impl ASTPrinter<'_> {
+    /// Print items, each on a new line and separated by a blank line.
+    pub fn print_items(&mut self, items: &[Item]) {
+        for (i, item) in items.iter().enumerate() {
+            let lo = span_lo(item.ident.span);
+            if let Some(lo) = lo {
+                self.print_trailing_comments(lo);
+                self.skip_blank_lines(lo);
+            }
+            if i > 0 {
+                self.newline();
+            }
+            self.print_comments_before(lo);
+            self.newline();
+            self.print_item(item);
+        }
+    }
+
+    pub fn print_item(&mut self, item: &Item) {
+        if let ItemKind::Fn(fun) = &item.kind {
+            match fun.inline {
+                InlineAttr::None => (),
+                InlineAttr::Hint => {
+                    self.print("#[inline]");
+                    self.newline();
+                }
+                InlineAttr::Never => {
+                    self.print("#[inline(never)]");
+                    self.newline();
+                }
+            }
+        }
+
+        if item.vis == Visibility::Public {
+            self.print_space("pub");
+        }
+
+        match &item.kind {
+            ItemKind::Fn(fun) => self.print_item_fn(&item.ident, fun),
+            ItemKind::Trait(tr) => self.print_item_trait(&item.ident, tr),
+            ItemKind::Impl(imp) => self.print_item_impl(imp),
+            ItemKind::Mod(module) => {
+                self.print_space("mod");
+                self.print_ident(&item.ident);
+                if module.inline {
+                    self.space();
+                    self.print_braced_items(&module.items, module.span);
+                } else {
+                    self.semi();
+                }
+            }
+            ItemKind::Use(tree) => self.print_item_use(tree),
+            ItemKind::Const(c) => {
+                self.print_space("const");
+                self.print_item_const(&item.ident, &c.ty, &c.expr);
+            }
+            ItemKind::Static(s) => {
+                self.print_space("static");
+                self.print(s.mutbl.prefix_str());
+                self.print_item_const(&item.ident, &s.ty, &s.expr);
+            }
+        }
+    }
+
+    /// print the items of a module or an impl: `{ fn foo() {} }`
+    fn print_braced_items(&mut self, items: &[Item], span: Span) {
+        let hi = span_hi(span);
+        if self.print_empty_braces(items.is_empty(), hi) {
+            return;
+        }
+
+        self.print("{");
+        self.indent += 1;
+        self.print_items(items);
+        self.print_closing_comments(hi);
+        self.indent -= 1;
+        self.newline();
+        self.print("}");
+    }
+
+    /// Print `{}` if the braces ending at `hi` hold neither items nor comments,
+    /// and returns `true` if it does.
+    fn print_empty_braces(&mut self, empty: bool, hi: Option<u32>) -> bool {
+        let has_comments = hi.and_then(|hi| self.next_comment_before(hi)).is_some();
+        if empty && !has_comments {
+            self.print("{}");
+            return true;
+        }
+
+        false
+    }
+
+    /// print the rest of a constant or a static: `N: i32 = 4 * 8;`
+    fn print_item_const(&mut self, ident: &Ident, ty: &Ty, expr: &Expr) {
+        self.print_ident(ident);
+        self.colon();
+        self.space();
+        self.print_ty(ty);
+        self.space();
+        self.eq();
+        self.space();
+        self.print_expr(expr);
+        self.semi();
+    }
+
+    fn print_item_use(&mut self, tree: &UseTree) {
+        self.print_space("use");
+        self.print_path(&tree.prefix);
+        match &tree.kind {
+            UseTreeKind::Simple(None) => (),
+            UseTreeKind::Simple(Some(alias)) => {
+                self.space_print_space("as");
+                self.print_ident(alias);
+            }
+            UseTreeKind::Glob => self.print("::*"),
+        }
+        self.semi();
+    }
+
+    fn print_item_trait(&mut self, ident: &Ident, tr: &Trait) {
+        self.print_space("trait");
+        self.print_ident(ident);
+        self.space();
+        let hi = span_hi(tr.span);
+        if self.print_empty_braces(tr.items.is_empty(), hi) {
+            return;
+        }
+
+        self.print("{");
+        self.indent += 1;
+        for item in &tr.items {
+            let lo = span_lo(item.ident.span);
+            if let Some(lo) = lo {
+                self.print_trailing_comments(lo);
+                self.skip_blank_lines(lo);
+            }
+            self.print_comments_before(lo);
+            self.newline();
+            self.print_space("fn");
+            self.print_ident(&item.ident);
+            self.print_params(&item.inputs);
+            self.print_output(&item.output);
+            self.semi();
+        }
+        self.print_closing_comments(hi);
+        self.indent -= 1;
+        self.newline();
+        self.print("}");
+    }
+
+    fn print_item_impl(&mut self, imp: &Impl) {
+        self.print_space("impl");
+        if let Some(path) = &imp.of_trait {
+            self.print_path(path);
+            self.space_print_space("for");
+        }
+        self.print_ty(&imp.self_ty);
+        self.space();
+        self.print_braced_items(&imp.items, imp.span);
+    }
+
+    fn print_item_fn(&mut self, ident: &Ident, fun: &Fn) {
+        self.print_space("fn");
+        self.print_ident(ident);
+        if !fun.generics.params.is_empty() {
+            self.list(fun.generics.params.iter(), Delim::Angle, |this, param| {
+                this.print_ident(&param.ident);
+                if !param.bounds.is_empty() {
+                    this.colon();
+                    this.space();
+                    this.separated(
+                        param.bounds.iter(),
+                        |this| this.space_print_space("+"),
+                        |this, path| this.print_path(path),
+                    );
+                }
+            });
+        }
+        self.print_params(&fun.inputs);
+        self.print_output(&fun.output);
+        self.space();
+        self.print_block(&fun.body);
+    }
+
+    /// print fn params: `(a: i32, &self)`
+    fn print_params(&mut self, inputs: &[Param]) {
+        self.list(inputs.iter(), Delim::Paren, |this, param| {
+            this.print_param(param);
+        });
+    }
+}
@@ -0,0 +1,258 @@
+mod block;
+mod expr;
+mod item;
+mod stmt;
+mod tree;
+
+pub use tree::print_tree;
+
+use crate::{token::Comment, *};
+
+use printer::*;
+use span::*;
+
+/// Prints the items back to canonical source, putting the comments of the file back between them.
+/// The blank lines between statements are kept, given as the positions where they start.
+/// The output parses to the same items, and printing it again gives the same output.
+pub fn print_items(
+    map: &SymbolMap,
+    items: &[Item],
+    comments: &[Comment],
+    blank_lines: &[u32],
+) -> String {
+    let mut p = ASTPrinter::new(map, comments, blank_lines);
+
+    p.print_items(items);
+    p.print_trailing_comments(u32::MAX);
+    if let Some(comment) = p.next_comment_before(u32::MAX) {
+        // the items are already followed by a blank line.
+        p.skip_blank_lines(comment.span.data().lo);
+        p.newline();
+        p.print_comments(u32::MAX);
+    }
+
+    // Every item starts on a new line, so the output starts with a newline.
+    let output = p.finish();
+    let mut output = output.trim().to_string();
+    output.push('\n');
+    output
+}
+
+struct ASTPrinter<'a> {
+    pub map: &'a SymbolMap<'a>,
+    pub output: String,
+    pub indent: usize,
+
+    comments: &'a [Comment],
+    /// The index of the first comment not printed yet.
+    next_comment: usize,
+
+    blank_lines: &'a [u32],
+    /// The index of the first blank line not printed or skipped yet.
+    next_blank_line: usize,
+}
+
+impl Printer for ASTPrinter<'_> {
+    const INDENT_SIZE: usize = 4;
+
+    type Output = String;
+
+    fn finish(self) -> Self::Output {
+        self.output
+    }
+
+    fn get_output_mut(&mut self) -> &mut Self::Output {
+        &mut self.output
+    }
+
+    fn get_indent_mut(&mut self) -> &mut usize {
+        &mut self.indent
+    }
+
+    /// Add newline, with no indentation on an empty line.
+    fn newline(&mut self) {
+        let len = self.output.trim_end_matches(' ').len();
+        self.output.truncate(len);
+        self.print("\n");
+        let margin = self.indent * Self::INDENT_SIZE;
+        self.spaces(margin);
+    }
+}
+
+impl<'a> ASTPrinter<'a> {
+    fn new(map: &'a SymbolMap<'a>, comments: &'a [Comment], blank_lines: &'a [u32]) -> Self {
+        ASTPrinter {
+            map,
+            output: String::new(),
+            indent: 0,
+            comments,
+            next_comment: 0,
+            blank_lines,
+            next_blank_line: 0,
+        }
+    }
+
+    /// Returns the next comment if it starts before `pos`.
+    fn next_comment_before(&self, pos: u32) -> Option<&'a Comment> {
+        let comments = self.comments;
+        comments
+            .get(self.next_comment)
+            .filter(|c| c.span.data().lo < pos)
+    }
+
+    /// Print the comments before `pos` which follow code on their line at the end of the current line.
+    fn print_trailing_comments(&mut self, pos: u32) {
+        while let Some(comment) = self.next_comment_before(pos) {
+            if !comment.trailing {
+                break;
+            }
+            self.space_print(&comment.text);
+            self.next_comment += 1;
+        }
+    }
+
+    /// Print the comments before `pos`, each on a new line, keeping the blank lines in front of them.
+    fn print_comments(&mut self, pos: u32) {
+        while let Some(comment) = self.next_comment_before(pos) {
+            self.print_blank_line(comment.span.data().lo);
+            self.newline();
+            self.print(&comment.text);
+            self.next_comment += 1;
+        }
+    }
+
+    /// Print the comments before `pos` in front of the code on the next line.
+    fn print_comments_before(&mut self, pos: Option<u32>) {
+        if let Some(pos) = pos {
+            self.print_trailing_comments(pos);
+            self.print_comments(pos);
+        }
+    }
+
+    /// Print the comments before the closing delimiter at `hi`, dropping the blank lines before it.
+    fn print_closing_comments(&mut self, hi: Option<u32>) {
+        self.print_comments_before(hi);
+        if let Some(hi) = hi {
+            self.skip_blank_lines(hi);
+        }
+    }
+
+    /// Print an empty line if the source has a blank line before `pos` not printed yet,
+    /// unless the current line opens a block or arguments.
+    fn print_blank_line(&mut self, pos: u32) {
+        if self.skip_blank_lines(pos) && !self.output.trim_end().ends_with(['{', '(']) {
+            self.newline();
+        }
+    }
+
+    /// Skips the blank lines before `pos`, and returns `true` if there is any.
+    fn skip_blank_lines(&mut self, pos: u32) -> bool {
+        let start = self.next_blank_line;
+        while let Some(line) = self.blank_lines.get(self.next_blank_line) {
+            if *line >= pos {
+                break;
+            }
+            self.next_blank_line += 1;
+        }
+        self.next_blank_line > start
+    }
+
+    fn print_ident(&mut self, ident: &Ident) {
+        self.print(self.map.get(ident.name));
+    }
+
+    fn print_path(&mut self, path: &Path) {
+        self.separated(
+            path.segments.iter(),
+            |this| this.print("::"),
+            |this, segment| this.print_ident(segment),
+        );
+    }
+
+    fn print_ty(&mut self, ty: &Ty) {
+        match &ty.kind {
+            TyKind::Path(path) => self.print_path(path),
+            TyKind::Ref(mutbl, ty) => {
+                self.print("&");
+                self.print(mutbl.prefix_str());
+                self.print_ty(ty);
+            }
+            TyKind::Ptr(mutbl, ty) => {
+                self.print("*");
+                match mutbl {
+                    Mutability::Mut => self.print_space("mut"),
+                    Mutability::Not => self.print_space("const"),
+                }
+                self.print_ty(ty);
+            }
+            TyKind::TraitObject(path) => {
+                self.print_space("dyn");
+                self.print_path(path);
+            }
+            TyKind::BareFn(bare_fn) => {
+                self.print("fn");
+                self.list(bare_fn.inputs.iter(), Delim::Paren, |this, input| {
+                    this.print_ty(input);
+                });
+                self.print_output(&bare_fn.output);
+            }
+        }
+    }
+
+    /// Print a parameter, or the short form of `self`: `a: i32`, `mut self`, `&mut self`
+    fn print_param(&mut self, param: &Param) {
+        if param.ident.name == Kw::SelfLower.into() {
+            let self_ty = |ty: &Ty| match &ty.kind {
+                TyKind::Path(path) => {
+                    path.segments.len() == 1 && path.ident().name == Kw::SelfUpper.into()
+                }
+                _ => false,
+            };
+            match &param.ty.kind {
+                TyKind::Ref(mutbl, ty) if self_ty(ty) && param.mutbl == Mutability::Not => {
+                    self.print("&");
+                    self.print(mutbl.prefix_str());
+                    self.print("self");
+                    return;
+                }
+                _ if self_ty(&param.ty) => {
+                    self.print(param.mutbl.prefix_str());
+                    self.print("self");
+                    return;
+                }
+                _ => (),
+            }
+        }
+
+        self.print(param.mutbl.prefix_str());
+        self.print_ident(&param.ident);
+        self.colon();
+        self.space();
+        self.print_ty(&param.ty);
+    }
+
+    fn print_output(&mut self, output: &Option<Ty>) {
+        if let Some(output) = output {
+            self.space_print_space("->");
+            self.print_ty(output);
+        }
+    }
+}
+
+/// Returns the start of the span, or `None` for `DUMMY_SP`.
+fn span_lo(span: Span) -> Option<u32> {
+    if span.is_dummy() {
+        None
+    } else {
+        Some(span.data().lo)
+    }
+}
+
+/// Returns the end of the span, or `None` for `DUMMY_SP`.
+fn span_hi(span: Span) -> Option<u32> {
+    if span.is_dummy() {
+        None
+    } else {
+        Some(span.data().hi)
+    }
+}
@@ -0,0 +1,46 @@
+use super::ASTPrinter;
+use crate::*;
+
+use printer::{Delim, Printer};
+
+impl ASTPrinter<'_> {
+    pub fn print_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Local {
+                mutbl,
+                ident,
+                ty,
+                init,
+                ..
+            } => {
+                self.print_space("let");
+                self.print(mutbl.prefix_str());
+                self.print_ident(ident);
+                if let Some(ty) = ty {
+                    self.colon();
+                    self.space();
+                    self.print_ty(ty);
+                }
+                self.space();
+                self.eq();
+                self.space();
+                self.print_expr(init);
+                self.semi();
+            }
+            Stmt::Expr(e) => {
+                self.print_expr(e);
+            }
+            Stmt::Semi(e) => {
+                self.print_expr(e);
+                self.semi();
+            }
+            Stmt::Println(e) => {
+                self.print("println");
+                self.with_delim(Delim::Paren, false, |this| {
+                    this.print_expr(e);
+                });
+                self.semi();
+            }
+        }
+    }
+}
@@ -0,0 +1,343 @@
+use crate::*;
+
+use printer::Printer;
+use span::*;
+
+/// Prints the items as an indented tree of their nodes, with the spans of the nodes which have one.
+pub fn print_tree(map: &SymbolMap, items: &[&Item]) -> String {
+    let mut p = TreePrinter::new(map);
+
+    for item in items {
+        p.print_item(item);
+    }
+
+    // Every node starts on a new line, so the output starts with a newline.
+    p.finish().trim_start().to_string()
+}
+
+struct TreePrinter<'a> {
+    pub map: &'a SymbolMap<'a>,
+    pub output: String,
+    pub indent: usize,
+}
+
+impl Printer for TreePrinter<'_> {
+    const INDENT_SIZE: usize = 2;
+
+    type Output = String;
+
+    fn finish(self) -> Self::Output {
+        self.output
+    }
+
+    fn get_output_mut(&mut self) -> &mut Self::Output {
+        &mut self.output
+    }
+
+    fn get_indent_mut(&mut self) -> &mut usize {
+        &mut self.indent
+    }
+}
+
+impl<'a> TreePrinter<'a> {
+    fn new(map: &'a SymbolMap<'a>) -> Self {
+        TreePrinter {
+            map,
+            output: String::new(),
+            indent: 0,
+        }
+    }
+
+    /// Print a node on a new line: `Lit 1 @12..13`, and its children indented below it.
+    fn node<F: FnOnce(&mut Self)>(&mut self, label: &str, span: Span, children: F) {
+        self.newline();
+        self.print(label);
+        if !span.is_dummy() {
+            let data = span.data();
+            self.print(format!(" @{}..{}", data.lo, data.hi));
+        }
+
+        self.indent += 1;
+        children(self);
+        self.indent -= 1;
+    }
+
+    fn leaf(&mut self, label: &str, span: Span) {
+        self.node(label, span, |_| ());
+    }
+
+    fn name(&self, ident: &Ident) -> &'a str {
+        self.map.get(ident.name)
+    }
+
+    fn print_item(&mut self, item: &Item) {
+        let name = self.name(&item.ident);
+        let label = match &item.kind {
+            ItemKind::Fn(_) => format!("Fn {}", name),
+            ItemKind::Trait(_) => format!("Trait {}", name),
+            ItemKind::Impl(_) => "Impl".to_string(),
+            ItemKind::Mod(_) => format!("Mod {}", name),
+            ItemKind::Use(_) => "Use".to_string(),
+            ItemKind::Const(_) => format!("Const {}", name),
+            ItemKind::Static(s) => format!("Static {}{}", s.mutbl.prefix_str(), name),
+        };
+
+        self.node(&label, item.ident.span, |this| {
+            if item.vis == Visibility::Public {
+                this.leaf("Vis Public", DUMMY_SP);
+            }
+
+            match &item.kind {
+                ItemKind::Fn(fun) => this.print_fn(fun),
+                ItemKind::Trait(tr) => {
+                    for item in &tr.items {
+                        let label = format!("TraitItem {}", this.name(&item.ident));
+                        this.node(&label, item.ident.span, |this| {
+                            this.print_params(&item.inputs);
+                            this.print_output(&item.output);
+                        });
+                    }
+                }
+                ItemKind::Impl(imp) => {
+                    if let Some(path) = &imp.of_trait {
+                        this.node("Trait", DUMMY_SP, |this| this.print_path(path));
+                    }
+                    this.node("SelfTy", DUMMY_SP, |this| this.print_ty(&imp.self_ty));
+                    for item in &imp.items {
+                        this.print_item(item);
+                    }
+                }
+                ItemKind::Mod(module) => {
+                    for item in &module.items {
+                        this.print_item(item);
+                    }
+                }
+                ItemKind::Use(tree) => {
+                    this.print_path(&tree.prefix);
+                    match &tree.kind {
+                        UseTreeKind::Simple(None) => (),
+                        UseTreeKind::Simple(Some(alias)) => {
+                            let label = format!("Alias {}", this.name(alias));
+                            this.leaf(&label, alias.span);
+                        }
+                        UseTreeKind::Glob => this.leaf("Glob", DUMMY_SP),
+                    }
+                }
+                ItemKind::Const(c) => {
+                    this.print_ty(&c.ty);
+                    this.print_expr(&c.expr);
+                }
+                ItemKind::Static(s) => {
+                    this.print_ty(&s.ty);
+                    this.print_expr(&s.expr);
+                }
+            }
+        });
+    }
+
+    fn print_fn(&mut self, fun: &Fn) {
+        if fun.inline != InlineAttr::None {
+            self.leaf(&format!("Inline {:?}", fun.inline), DUMMY_SP);
+        }
+        for param in &fun.generics.params {
+            let label = format!("GenericParam {}", self.name(&param.ident));
+            self.node(&label, param.ident.span, |this| {
+                for bound in &param.bounds {
+                    this.print_path(bound);
+                }
+            });
+        }
+        self.print_params(&fun.inputs);
+        self.print_output(&fun.output);
+        self.print_block(&fun.body);
+    }
+
+    fn print_params(&mut self, params: &[Param]) {
+        for param in params {
+            let label = format!(
+                "Param {}{}",
+                param.mutbl.prefix_str(),
+                self.name(&param.ident)
+            );
+            self.node(&label, param.ident.span, |this| this.print_ty(&param.ty));
+        }
+    }
+
+    fn print_output(&mut self, output: &Option<Ty>) {
+        if let Some(output) = output {
+            self.node("Output", DUMMY_SP, |this| this.print_ty(output));
+        }
+    }
+
+    fn print_path(&mut self, path: &Path) {
+        let segments: Vec<_> = path.segments.iter().map(|s| self.name(s)).collect();
+        let first = path.segments.first().unwrap().span;
+        let last = path.ident().span;
+        let span = if first.is_dummy() || last.is_dummy() {
+            DUMMY_SP
+        } else {
+            Span::new(first.data().lo, last.data().hi)
+        };
+
+        self.leaf(&format!("Path {}", segments.join("::")), span);
+    }
+
+    fn print_ty(&mut self, ty: &Ty) {
+        match &ty.kind {
+            TyKind::Path(path) => self.print_path(path),
+            TyKind::Ref(mutbl, ty) => {
+                let label = format!("Ref{}", mut_suffix(*mutbl));
+                self.node(&label, DUMMY_SP, |this| this.print_ty(ty));
+            }
+            TyKind::Ptr(mutbl, ty) => {
+                let label = match mutbl {
+                    Mutability::Mut => "Ptr mut",
+                    Mutability::Not => "Ptr const",
+                };
+                self.node(label, DUMMY_SP, |this| this.print_ty(ty));
+            }
+            TyKind::TraitObject(path) => {
+                self.node("TraitObject", DUMMY_SP, |this| this.print_path(path));
+            }
+            TyKind::BareFn(bare_fn) => self.node("BareFn", DUMMY_SP, |this| {
+                for input in &bare_fn.inputs {
+                    this.print_ty(input);
+                }
+                this.print_output(&bare_fn.output);
+            }),
+        }
+    }
+
+    fn print_block(&mut self, block: &Block) {
+        self.node("Block", block.span, |this| {
+            for stmt in &block.stmts {
+                this.print_stmt(stmt);
+            }
+        });
+    }
+
+    fn print_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Local {
+                mutbl,
+                ident,
+                ty,
+                init,
+                ..
+            } => {
+                let label = format!("Local {}{}", mutbl.prefix_str(), self.name(ident));
+                self.node(&label, ident.span, |this| {
+                    if let Some(ty) = ty {
+                        this.print_ty(ty);
+                    }
+                    this.print_expr(init);
+                });
+            }
+            Stmt::Expr(e) => self.node("Expr", DUMMY_SP, |this| this.print_expr(e)),
+            Stmt::Semi(e) => self.node("Semi", DUMMY_SP, |this| this.print_expr(e)),
+            Stmt::Println(e) => self.node("Println", DUMMY_SP, |this| this.print_expr(e)),
+        }
+    }
+
+    fn print_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Call { fun, args, .. } => self.node("Call", DUMMY_SP, |this| {
+                this.print_expr(fun);
+                for arg in args {
+                    this.print_expr(arg);
+                }
+            }),
+            Expr::MethodCall {
+                receiver,
+                method,
+                args,
+                ..
+            } => {
+                let label = format!("MethodCall {}", self.name(method));
+                self.node(&label, method.span, |this| {
+                    this.print_expr(receiver);
+                    for arg in args {
+                        this.print_expr(arg);
+                    }
+                });
+            }
+            Expr::Binary { op, lhs, rhs } => {
+                self.node(&format!("Binary {}", op), DUMMY_SP, |this| {
+                    this.print_expr(lhs);
+                    this.print_expr(rhs);
+                });
+            }
+            Expr::Unary { op, expr } => {
+                self.node(&format!("Unary {}", op), DUMMY_SP, |this| {
+                    this.print_expr(expr)
+                });
+            }
+            Expr::AddrOf { mutbl, expr } => {
+                self.node(&format!("AddrOf{}", mut_suffix(*mutbl)), DUMMY_SP, |this| {
+                    this.print_expr(expr)
+                });
+            }
+            Expr::If {
+                cond,
+                then,
+                else_opt,
+            } => self.node("If", DUMMY_SP, |this| {
+                this.print_expr(cond);
+                this.print_block(then);
+                if let Some(else_) = else_opt {
+                    this.print_expr(else_);
+                }
+            }),
+            Expr::Loop { block } => self.node("Loop", DUMMY_SP, |this| this.print_block(block)),
+            Expr::Break { expr } => self.print_expr_jump("Break", expr.as_deref()),
+            Expr::Continue { expr } => self.print_expr_jump("Continue", expr.as_deref()),
+            Expr::Return { expr } => self.print_expr_jump("Return", expr.as_deref()),
+            Expr::Block { block } => self.print_block(block),
+            Expr::Assign { lhs, rhs } => self.node("Assign", DUMMY_SP, |this| {
+                this.print_expr(lhs);
+                this.print_expr(rhs);
+            }),
+            Expr::Lit { lit } => {
+                let label = match lit.kind {
+                    LitKind::Int(value) => format!("Lit {}", value),
+                    LitKind::Bool(value) => format!("Lit {}", value),
+                };
+                self.leaf(&label, lit.span);
+            }
+            Expr::Path(path) => self.print_path(path),
+            Expr::Closure {
+                capture_by,
+                inputs,
+                output,
+                body,
+                ..
+            } => {
+                let label = match capture_by {
+                    CaptureBy::Value => "Closure move",
+                    CaptureBy::Ref => "Closure",
+                };
+                self.node(label, DUMMY_SP, |this| {
+                    this.print_params(inputs);
+                    this.print_output(output);
+                    this.print_expr(body);
+                });
+            }
+        }
+    }
+
+    fn print_expr_jump(&mut self, label: &str, expr: Option<&Expr>) {
+        self.node(label, DUMMY_SP, |this| {
+            if let Some(expr) = expr {
+                this.print_expr(expr);
+            }
+        });
+    }
+}
+
+/// Returns `" mut"` or `""` to follow the label of a node.
+fn mut_suffix(mutbl: Mutability) -> &'static str {
+    match mutbl {
+        Mutability::Mut => " mut",
+        Mutability::Not => "",
+    }
+}
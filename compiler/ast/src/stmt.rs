@@ -1,10 +1,13 @@
-use crate::{expr::Expr, ty::Ty};
+use crate::{expr::Expr, ty::Ty, Mutability, NodeId, DUMMY_NODE_ID};
+use serde::Serialize;
 use span::*;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub enum Stmt {
-    /// Local represents a let statement: `let <ident> = <expr>;`
+    /// Local represents a let statement: `let <mut>? <ident> = <expr>;`
     Local {
+        id: NodeId,
+        mutbl: Mutability,
         ident: Ident,
         ty: Option<Ty>,
         init: Expr,
@@ -23,7 +26,23 @@ pub enum Stmt {
 
 impl Stmt {
     pub fn local(ident: Ident, ty: Option<Ty>, init: Expr) -> Stmt {
-        Stmt::Local { ident, ty, init }
+        Stmt::Local {
+            id: DUMMY_NODE_ID,
+            mutbl: Mutability::Not,
+            ident,
+            ty,
+            init,
+        }
+    }
+
+    pub fn local_mut(ident: Ident, ty: Option<Ty>, init: Expr) -> Stmt {
+        Stmt::Local {
+            id: DUMMY_NODE_ID,
+            mutbl: Mutability::Mut,
+            ident,
+            ty,
+            init,
+        }
     }
 
     pub fn expr(expr: Expr) -> Stmt {
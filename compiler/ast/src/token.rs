@@ -1,31 +1,35 @@
+use serde::Serialize;
 use span::*;
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
 pub enum BinOpToken {
     Plus,
     Minus,
     Star,
     Slash,
+    And,
+    Or,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
 pub enum DelimToken {
     Paren,
     Brace,
+    Bracket,
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize)]
 pub enum LitKind {
     Integer,
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Serialize)]
 pub struct Lit {
     pub kind: LitKind,
     pub symbol: Symbol,
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Serialize)]
 pub enum TokenKind {
     /// `=`
     Eq,
@@ -48,7 +52,7 @@ pub enum TokenKind {
     /// `>`
     Gt,
 
-    /// Binary operator: `+`, `-`, `*`, `/`
+    /// Binary operator: `+`, `-`, `*`, `/`, `&`, `|`
     BinOp(BinOpToken),
 
     /// `;`
@@ -57,6 +61,9 @@ pub enum TokenKind {
     /// `:`
     Colon,
 
+    /// `::`
+    PathSep,
+
     // `.`
     Dot,
 
@@ -66,10 +73,13 @@ pub enum TokenKind {
     /// `->`
     Arrow,
 
-    /// Open delimiter: `(`, `{`
+    /// `#`
+    Pound,
+
+    /// Open delimiter: `(`, `{`, `[`
     OpenDelim(DelimToken),
 
-    /// Close delimiter: `)`, `}`
+    /// Close delimiter: `)`, `}`, `]`
     CloseDelim(DelimToken),
 
     /// Literal: `bool`, `integer`
@@ -81,7 +91,7 @@ pub enum TokenKind {
     Eof,
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Serialize)]
 pub struct Token {
     pub kind: TokenKind,
     pub span: Span,
@@ -96,6 +106,9 @@ impl Token {
     pub fn can_begin_expr(&self) -> bool {
         match self.kind {
             TokenKind::BinOp(BinOpToken::Minus) // unary minus
+            | TokenKind::BinOp(BinOpToken::Star) // dereference
+            | TokenKind::BinOp(BinOpToken::And) // borrow
+            | TokenKind::BinOp(BinOpToken::Or) // closure
             | TokenKind::OpenDelim(_) // parensized expr, block
             | TokenKind::Literal(_) => true, // literal
             TokenKind::Ident(name) => ident_can_begin_expr(&name), // identifier
@@ -115,11 +128,23 @@ pub fn ident_can_begin_expr(name: &Symbol) -> bool {
             Kw::Break,
             Kw::Continue,
             Kw::Return,
+            Kw::Move,
         ]
         .map(|k| k.into())
         .contains(name)
 }
 
+/// A line comment, which the parser skips and the pretty-printer puts back: `// comment`
+#[derive(Clone, PartialEq, Debug)]
+pub struct Comment {
+    /// The text from `//` to the end of the line, without the trailing whitespace.
+    pub text: String,
+    pub span: Span,
+
+    /// `true` if the comment follows a token on the same line: `let a = 1; // comment`
+    pub trailing: bool,
+}
+
 pub struct Tokens<'a> {
     pub tokens: Vec<Token>,
     pub map: SymbolMap<'a>,
@@ -1,8 +1,9 @@
-use crate::Path;
+use crate::{Mutability, Path};
 
+use serde::Serialize;
 use span::*;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct Ty {
     pub kind: TyKind,
 }
@@ -10,14 +11,54 @@ pub struct Ty {
 impl Ty {
     pub fn path_with_dummy_span<S: Into<Symbol>>(name: S) -> Ty {
         Ty {
-            kind: TyKind::Path(Path {
-                ident: Ident::with_dummy_span(name),
-            }),
+            kind: TyKind::Path(Path::from_ident(Ident::with_dummy_span(name))),
+        }
+    }
+
+    pub fn ref_(mutbl: Mutability, ty: Ty) -> Ty {
+        Ty {
+            kind: TyKind::Ref(mutbl, Box::new(ty)),
+        }
+    }
+
+    pub fn ptr(mutbl: Mutability, ty: Ty) -> Ty {
+        Ty {
+            kind: TyKind::Ptr(mutbl, Box::new(ty)),
+        }
+    }
+
+    pub fn trait_object(path: Path) -> Ty {
+        Ty {
+            kind: TyKind::TraitObject(path),
+        }
+    }
+
+    pub fn bare_fn(inputs: Vec<Ty>, output: Option<Ty>) -> Ty {
+        Ty {
+            kind: TyKind::BareFn(Box::new(BareFnTy { inputs, output })),
         }
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub enum TyKind {
     Path(Path),
+
+    /// A reference type: `&T`, `&mut T`
+    Ref(Mutability, Box<Ty>),
+
+    /// A raw pointer type: `*const T`, `*mut T`
+    Ptr(Mutability, Box<Ty>),
+
+    /// A trait object type: `dyn Trait`
+    TraitObject(Path),
+
+    /// A function pointer type: `fn(i32) -> bool`
+    BareFn(Box<BareFnTy>),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct BareFnTy {
+    pub inputs: Vec<Ty>,
+    pub output: Option<Ty>,
 }
@@ -2,50 +2,234 @@ use ::ty::res::*;
 use ast::*;
 use span::*;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 #[allow(dead_code)]
 pub struct LoweringCtx {
-    name_res: HashMap<Span, Res>,
+    name_res: HashMap<NodeId, Res>,
+
+    /// The closures being lowered, the innermost last.
+    closures: Vec<ClosureScope>,
+
+    /// The item whose nodes are being lowered, and the local id of its next node.
+    owner: Option<(DefId, hir::ItemLocalId)>,
+
+    /// The HIR ids given to the AST nodes.
+    node_id_to_hir_id: HashMap<NodeId, hir::HirId>,
+}
+
+/// The locals defined in a closure and the locals it captures from the enclosing functions.
+#[derive(Default)]
+struct ClosureScope {
+    defined: HashSet<DefId>,
+    upvars: Vec<hir::Upvar>,
 }
 
 impl LoweringCtx {
-    pub fn new(name_res: HashMap<Span, Res>) -> Self {
-        LoweringCtx { name_res }
+    pub fn new(name_res: HashMap<NodeId, Res>) -> Self {
+        LoweringCtx {
+            name_res,
+            closures: Vec::new(),
+            owner: None,
+            node_id_to_hir_id: HashMap::new(),
+        }
+    }
+
+    /// Lowers the nodes of an item, which owns the nodes lowered in `f`.
+    pub fn with_hir_id_owner<T, F>(&mut self, def: DefId, f: F) -> T
+    where
+        F: FnOnce(&mut LoweringCtx) -> T,
+    {
+        let parent = self.owner.replace((def, 0));
+        let result = f(self);
+        self.owner = parent;
+        result
     }
 
+    /// Returns the HIR id of the node, which is numbered in the order the nodes of the owner are lowered.
+    fn lower_node_id(&mut self, id: NodeId) -> hir::HirId {
+        if let Some(hir_id) = self.node_id_to_hir_id.get(&id) {
+            return *hir_id;
+        }
+
+        let (owner, next) = self.owner.as_mut().expect("nodes are lowered in an item.");
+        let hir_id = hir::HirId {
+            owner: *owner,
+            local_id: *next,
+        };
+        *next += 1;
+        self.node_id_to_hir_id.insert(id, hir_id);
+
+        hir_id
+    }
+
+    fn define_local(&mut self, res: Res) {
+        if let Some(closure) = self.closures.last_mut() {
+            closure.defined.insert(res.def);
+        }
+    }
+
+    /// Records the local as captured by each enclosing closure which does not define it.
+    fn capture(&mut self, res: Res, ident: &Ident) {
+        for closure in self.closures.iter_mut().rev() {
+            if closure.defined.contains(&res.def) {
+                break;
+            }
+            if !closure.upvars.iter().any(|upvar| upvar.res == res) {
+                closure.upvars.push(hir::Upvar {
+                    res,
+                    name: ident.name,
+                    span: ident.span,
+                });
+            }
+        }
+    }
+
+    /// Lowers the items, flattening the modules.
+    /// The items of a module follow the items of the enclosing module,
+    /// so that the `main` function of the root module is lowered first.
+    /// Use declarations are only needed by name resolution and are dropped.
     pub fn lower_items(&mut self, items: &[Item]) -> Vec<hir::Item> {
-        items.iter().map(|item| self.lower_item(item)).collect()
+        let mut hir_items = Vec::new();
+        let mut modules = Vec::new();
+        for item in items {
+            match &item.kind {
+                ItemKind::Mod(module) => modules.push(module),
+                ItemKind::Use(_) => {}
+                _ => hir_items.push(self.lower_item(item)),
+            }
+        }
+
+        for module in modules {
+            let items = self.lower_items(&module.items);
+            hir_items.extend(items);
+        }
+
+        hir_items
     }
 
     pub fn lower_item(&mut self, item: &Item) -> hir::Item {
+        let res = self.name_res[&item.id];
+        self.with_hir_id_owner(res.def, |this| this.lower_item_kind(item, res))
+    }
+
+    fn lower_item_kind(&mut self, item: &Item, res: Res) -> hir::Item {
+        let hir_id = self.lower_node_id(item.id);
         let name = item.ident.name;
-        let res = self.name_res[&item.ident.span];
         let kind = match &item.kind {
             ItemKind::Fn(fun) => {
+                let generics = self.lower_generics(&fun.generics);
                 let inputs = fun.inputs.iter().map(|p| self.lower_param(p)).collect();
                 let body = self.lower_block(&fun.body);
                 hir::ItemKind::Fn(Box::new(hir::Fn {
+                    inline: fun.inline,
+                    generics,
                     inputs,
-                    output: fun.output.clone(),
+                    output: fun.output.as_ref().map(|ty| self.lower_ty(ty)),
                     body,
                 }))
             }
+            ItemKind::Trait(tr) => {
+                let items = tr
+                    .items
+                    .iter()
+                    .map(|item| hir::TraitItem {
+                        hir_id: self.lower_node_id(item.id),
+                        res: self.name_res[&item.id],
+                        name: item.ident.name,
+                        inputs: item.inputs.iter().map(|p| self.lower_param(p)).collect(),
+                        output: item.output.as_ref().map(|ty| self.lower_ty(ty)),
+                    })
+                    .collect();
+                hir::ItemKind::Trait(Box::new(hir::Trait { items }))
+            }
+            ItemKind::Impl(imp) => {
+                let of_trait = imp.of_trait.as_ref().map(|path| self.name_res[&path.id]);
+                let items = self.lower_items(&imp.items);
+                hir::ItemKind::Impl(Box::new(hir::Impl {
+                    of_trait,
+                    self_ty: self.lower_ty(&imp.self_ty),
+                    items,
+                }))
+            }
+            ItemKind::Const(c) => hir::ItemKind::Const(Box::new(hir::Const {
+                ty: self.lower_ty(&c.ty),
+                body: self.lower_expr(&c.expr),
+            })),
+            ItemKind::Static(s) => hir::ItemKind::Static(Box::new(hir::Static {
+                mutbl: s.mutbl,
+                ty: self.lower_ty(&s.ty),
+                body: self.lower_expr(&s.expr),
+            })),
+            ItemKind::Mod(_) | ItemKind::Use(_) => {
+                unreachable!("modules are flattened in `lower_items`.")
+            }
         };
 
-        hir::Item { res, name, kind }
+        hir::Item {
+            hir_id,
+            res,
+            name,
+            span: item.ident.span,
+            kind,
+        }
+    }
+
+    pub fn lower_generics(&mut self, generics: &Generics) -> hir::Generics {
+        let params = generics
+            .params
+            .iter()
+            .map(|param| hir::GenericParam {
+                hir_id: self.lower_node_id(param.id),
+                res: self.name_res[&param.id],
+                name: param.ident.name,
+                bounds: param
+                    .bounds
+                    .iter()
+                    .map(|path| self.name_res[&path.id])
+                    .collect(),
+            })
+            .collect();
+
+        hir::Generics { params }
     }
 
     pub fn lower_param(&mut self, param: &Param) -> hir::Param {
         let name = param.ident.name;
-        let res = self.name_res[&param.ident.span];
+        let res = self.name_res[&param.id];
+        self.define_local(res);
         hir::Param {
+            hir_id: self.lower_node_id(param.id),
             res,
             name,
-            ty: param.ty.clone(),
+            mutbl: param.mutbl,
+            ty: self.lower_ty(&param.ty),
         }
     }
 
+    pub fn lower_ty(&mut self, ty: &Ty) -> hir::Ty {
+        let kind = match &ty.kind {
+            TyKind::Path(path) if path.ident().name == Kw::SelfUpper.into() => hir::TyKind::SelfTy,
+            TyKind::Path(path) => hir::TyKind::Path {
+                res: self.name_res[&path.id],
+                name: path.ident().name,
+            },
+            TyKind::Ref(mutbl, ty) => hir::TyKind::Ref(*mutbl, Box::new(self.lower_ty(ty))),
+            TyKind::Ptr(mutbl, ty) => hir::TyKind::Ptr(*mutbl, Box::new(self.lower_ty(ty))),
+            TyKind::TraitObject(path) => hir::TyKind::TraitObject {
+                res: self.name_res[&path.id],
+                name: path.ident().name,
+            },
+            TyKind::BareFn(bare_fn) => {
+                let inputs = bare_fn.inputs.iter().map(|ty| self.lower_ty(ty)).collect();
+                let output = bare_fn.output.as_ref().map(|ty| self.lower_ty(ty));
+                hir::TyKind::BareFn(Box::new(hir::BareFnTy { inputs, output }))
+            }
+        };
+
+        hir::Ty { kind }
+    }
+
     pub fn lower_block(&mut self, body: &Block) -> hir::Block {
         let (stmts, expr) = self.lower_stmts(&body.stmts);
         hir::Block { stmts, expr }
@@ -57,9 +241,13 @@ impl LoweringCtx {
 
         while let [s, tail @ ..] = ast_stmts {
             match s {
-                Stmt::Local { ident, ty, init } => {
-                    stmts.push(self.lower_stmt_local(ident.clone(), ty.clone(), &init))
-                }
+                Stmt::Local {
+                    id,
+                    mutbl,
+                    ident,
+                    ty,
+                    init,
+                } => stmts.push(self.lower_stmt_local(*id, *mutbl, ident.clone(), ty, init)),
                 Stmt::Expr(e) => {
                     let e = self.lower_expr(e);
                     if tail.is_empty() {
@@ -77,26 +265,44 @@ impl LoweringCtx {
         (stmts, expr)
     }
 
-    fn lower_stmt_local(&mut self, ident: Ident, ty: Option<Ty>, init: &Expr) -> hir::Stmt {
+    fn lower_stmt_local(
+        &mut self,
+        id: NodeId,
+        mutbl: Mutability,
+        ident: Ident,
+        ty: &Option<Ty>,
+        init: &Expr,
+    ) -> hir::Stmt {
+        let init = self.lower_expr(init);
         let pat = {
-            let res = self.name_res[&ident.span];
+            let res = self.name_res[&id];
+            self.define_local(res);
             hir::Pat {
+                hir_id: self.lower_node_id(id),
                 kind: hir::PatKind::Binding {
                     res,
                     name: ident.name,
+                    mutbl,
                 },
             }
         };
-        let init = self.lower_expr(init);
 
+        let ty = ty.as_ref().map(|ty| self.lower_ty(ty));
         hir::Stmt::Local { pat, ty, init }
     }
 
     pub fn lower_expr(&mut self, expr: &Expr) -> hir::Expr {
         match expr {
-            Expr::Call { fun, args } => self.lower_expr_call(fun, args),
+            Expr::Call { fun, args, .. } => self.lower_expr_call(fun, args),
+            Expr::MethodCall {
+                receiver,
+                method,
+                args,
+                ..
+            } => self.lower_expr_method_call(receiver, method, args),
             Expr::Binary { op, lhs, rhs } => self.lower_expr_binary(*op, &lhs, &rhs),
             Expr::Unary { op, expr } => self.lower_expr_unary(*op, &expr),
+            Expr::AddrOf { mutbl, expr } => self.lower_expr_addr_of(*mutbl, &expr),
             Expr::If {
                 cond,
                 then,
@@ -112,6 +318,13 @@ impl LoweringCtx {
             Expr::Assign { lhs, rhs } => self.lower_expr_assign(lhs.as_ref(), rhs.as_ref()),
             Expr::Lit { lit } => self.lower_expr_lit(&lit),
             Expr::Path(path) => self.lower_expr_path(path),
+            Expr::Closure {
+                capture_by,
+                inputs,
+                output,
+                body,
+                id,
+            } => self.lower_expr_closure(*capture_by, inputs, output, body, *id),
         }
     }
 
@@ -125,6 +338,23 @@ impl LoweringCtx {
         }
     }
 
+    fn lower_expr_method_call(
+        &mut self,
+        receiver: &Expr,
+        method: &Ident,
+        args: &[Expr],
+    ) -> hir::Expr {
+        let receiver = self.lower_expr(receiver);
+        let args = args.iter().map(|arg| self.lower_expr(arg)).collect();
+
+        hir::Expr::MethodCall {
+            receiver: Box::new(receiver),
+            method: method.name,
+            span: method.span,
+            args,
+        }
+    }
+
     fn lower_expr_binary(&mut self, op: BinOp, lhs: &Expr, rhs: &Expr) -> hir::Expr {
         match op {
             BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div => {
@@ -159,6 +389,15 @@ impl LoweringCtx {
         }
     }
 
+    fn lower_expr_addr_of(&mut self, mutbl: Mutability, expr: &Expr) -> hir::Expr {
+        let expr = self.lower_expr(expr);
+
+        hir::Expr::AddrOf {
+            mutbl,
+            expr: Box::new(expr),
+        }
+    }
+
     fn lower_expr_if(
         &mut self,
         cond: &Expr,
@@ -204,6 +443,9 @@ impl LoweringCtx {
     fn lower_expr_assign(&mut self, lhs: &Expr, rhs: &Expr) -> hir::Expr {
         let lhs = match lhs {
             Expr::Path(path) => self.lower_expr_path(path),
+            Expr::Unary {
+                op: UnOp::Deref, ..
+            } => self.lower_expr(lhs),
             _ => panic!("error: invalid left-hand side of assignment."),
         };
         let rhs = self.lower_expr(rhs);
@@ -215,6 +457,7 @@ impl LoweringCtx {
     }
 
     fn lower_expr_lit(&mut self, lit: &Lit) -> hir::Expr {
+        let lit_span = lit.span;
         match lit.kind {
             LitKind::Int(value) => {
                 let lit = {
@@ -223,40 +466,88 @@ impl LoweringCtx {
                     hir::Lit::Int(lit_int)
                 };
 
-                hir::Expr::Lit { lit }
+                hir::Expr::Lit {
+                    lit,
+                    span: lit_span,
+                }
             }
             LitKind::Bool(value) => {
                 let lit = hir::Lit::Bool { value };
 
-                hir::Expr::Lit { lit }
+                hir::Expr::Lit {
+                    lit,
+                    span: lit_span,
+                }
             }
         }
     }
 
     fn lower_expr_path(&mut self, path: &Path) -> hir::Expr {
-        let ident = &path.ident;
-        let def = self.name_res[&ident.span];
-        let path = hir::Path { res: def };
+        let ident = path.ident();
+        let def = self.name_res[&path.id];
+        if def.kind == ResKind::Local {
+            self.capture(def, ident);
+        }
+        let path = hir::Path {
+            hir_id: self.lower_node_id(path.id),
+            res: def,
+            span: ident.span,
+        };
 
         hir::Expr::Path { path }
     }
+
+    fn lower_expr_closure(
+        &mut self,
+        capture_by: CaptureBy,
+        inputs: &[Param],
+        output: &Option<Ty>,
+        body: &Expr,
+        id: NodeId,
+    ) -> hir::Expr {
+        let hir_id = self.lower_node_id(id);
+        let res = self.name_res[&id];
+
+        self.closures.push(ClosureScope::default());
+        let inputs = inputs.iter().map(|p| self.lower_param(p)).collect();
+        let body = self.lower_expr(body);
+        let upvars = self.closures.pop().unwrap().upvars;
+
+        hir::Expr::Closure(Box::new(hir::Closure {
+            hir_id,
+            res,
+            capture_by,
+            inputs,
+            output: output.as_ref().map(|ty| self.lower_ty(ty)),
+            body,
+            upvars,
+        }))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ast::ty;
-    use parser::{parse_block_from_source_str, parse_expr_from_source_str};
+    use std::iter;
+
+    use parser::{parse_block_from_source_str, parse_expr_from_source_str, parse_items};
     use resolve::ASTNameResolver;
 
-    const I32_TY: ty::Ty = ty::Ty {
-        kind: ty::TyKind::Path(Path {
-            ident: Ident {
-                name: Kw::I32.as_symbol(),
-                span: DUMMY_SP,
+    /// The item which owns the nodes of the blocks lowered in the tests.
+    fn owner() -> DefId {
+        DefId::from_usize(100)
+    }
+
+    fn i32_ty() -> hir::Ty {
+        let kind = hir::TyKind::Path {
+            res: Res {
+                def: DefId::from_usize(0),
+                kind: ResKind::PrimTy(PrimTy::I32),
             },
-        }),
-    };
+            name: Kw::I32.into(),
+        };
+        hir::Ty { kind }
+    }
 
     #[test]
     fn lower_stmt_local() {
@@ -269,25 +560,36 @@ mod tests {
         let hir = hir::Block {
             stmts: vec![hir::Stmt::Local {
                 pat: hir::Pat {
+                    hir_id: hir::HirId {
+                        owner: owner(),
+                        local_id: 0,
+                    },
                     kind: hir::PatKind::Binding {
                         res: Res {
-                            def: DefId::from_usize(0),
+                            def: DefId::from_usize(2),
                             kind: ResKind::Local,
                         },
                         name: Symbol::ident_nth(0),
+                        mutbl: Mutability::Not,
                     },
                 },
-                ty: Some(I32_TY.clone()),
+                ty: Some(i32_ty()),
                 init: hir::Expr::Lit {
                     lit: hir::Lit::Int(hir::LitInt { value: 0 }),
+                    span: DUMMY_SP,
                 },
             }],
             expr: Some(hir::Expr::Path {
                 path: hir::Path {
+                    hir_id: hir::HirId {
+                        owner: owner(),
+                        local_id: 1,
+                    },
                     res: Res {
-                        def: DefId::from_usize(0),
+                        def: DefId::from_usize(2),
                         kind: ResKind::Local,
                     },
+                    span: DUMMY_SP,
                 },
             }),
         };
@@ -298,14 +600,20 @@ mod tests {
             resolver.finish()
         };
         let mut ctx = LoweringCtx::new(res);
-        assert_eq!(hir, ctx.lower_block(&ast));
+        assert_eq!(
+            hir,
+            ctx.with_hir_id_owner(owner(), |ctx| ctx.lower_block(&ast))
+        );
     }
 
     #[test]
     fn lower_expr_binary() {
         let hir_lit_int = |value| {
             let lit = hir::Lit::Int(hir::LitInt { value });
-            hir::Expr::Lit { lit }
+            hir::Expr::Lit {
+                lit,
+                span: DUMMY_SP,
+            }
         };
         let hir_bin = |op, lhs, rhs| {
             let lhs = hir_lit_int(lhs);
@@ -379,6 +687,7 @@ mod tests {
             op: UnOp::Neg,
             expr: Box::new(hir::Expr::Lit {
                 lit: hir::Lit::Int(hir::LitInt { value: 1 }),
+                span: DUMMY_SP,
             }),
         };
         let res = {
@@ -389,4 +698,89 @@ mod tests {
         let mut ctx = LoweringCtx::new(res);
         assert_eq!(hir, ctx.lower_expr(&ast));
     }
+
+    #[test]
+    fn closure_upvars() {
+        let src = r"
+{
+    let a: i32 = 1;
+    let b: i32 = 2;
+    let c: i32 = |x: i32| {
+        let y: i32 = x;
+        || a + y
+    };
+    b
+}";
+        let (ast, symbol_map) = parse_block_from_source_str(src).unwrap();
+        let res = {
+            let mut resolver = ASTNameResolver::new(&symbol_map);
+            resolver.resolve_block(&ast).unwrap();
+            resolver.finish()
+        };
+        let mut ctx = LoweringCtx::new(res);
+        let hir = ctx.with_hir_id_owner(owner(), |ctx| ctx.lower_block(&ast));
+
+        let upvar_names = |closure: &hir::Closure| -> Vec<Symbol> {
+            closure.upvars.iter().map(|upvar| upvar.name).collect()
+        };
+        let outer = match &hir.stmts[2] {
+            hir::Stmt::Local {
+                init: hir::Expr::Closure(closure),
+                ..
+            } => closure,
+            _ => panic!("expected closure"),
+        };
+        let inner = match &outer.body {
+            hir::Expr::Block { block } => match &block.expr {
+                Some(hir::Expr::Closure(closure)) => closure,
+                _ => panic!("expected closure"),
+            },
+            _ => panic!("expected block"),
+        };
+
+        // `x` and `y` are defined in the outer closure, so only the inner closure captures `y`.
+        assert_eq!(upvar_names(outer), [Symbol::ident_nth(0)]);
+        assert_eq!(
+            upvar_names(inner),
+            [Symbol::ident_nth(0), Symbol::ident_nth(6)]
+        );
+    }
+
+    #[test]
+    fn hir_id_owners() {
+        let src = r"
+fn f(a: i32) -> i32 { a }
+impl i32 {
+    fn g(self, b: i32) -> i32 { b }
+}";
+        let (ast, symbol_map) = parse_items(src).unwrap();
+        let res = resolve::resolve_items(&ast, &symbol_map).unwrap();
+        let hir = LoweringCtx::new(res).lower_items(&ast);
+
+        let local_ids = |item: &hir::Item| match &item.kind {
+            hir::ItemKind::Fn(fun) => {
+                let body = match &fun.body.expr {
+                    Some(hir::Expr::Path { path }) => path.hir_id,
+                    _ => panic!("expected path"),
+                };
+                let ids = fun.inputs.iter().map(|param| param.hir_id);
+                let ids = iter::once(item.hir_id).chain(ids).chain(iter::once(body));
+                ids.map(|hir_id| {
+                    assert_eq!(hir_id.owner, item.res.def);
+                    hir_id.local_id
+                })
+                .collect::<Vec<_>>()
+            }
+            _ => panic!("expected function"),
+        };
+
+        // Each item numbers its nodes from its own id, and a method is the owner of its nodes.
+        assert_eq!(local_ids(&hir[0]), [0, 1, 2]);
+        let method = match &hir[1].kind {
+            hir::ItemKind::Impl(imp) => &imp.items[0],
+            _ => panic!("expected impl"),
+        };
+        assert_eq!(hir[1].hir_id.local_id, 0);
+        assert_eq!(local_ids(method), [0, 1, 2, 3]);
+    }
 }
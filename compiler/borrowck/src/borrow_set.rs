@@ -0,0 +1,111 @@
+use mir::{stmt::*, *};
+
+use derive_more::{From, Into};
+use typed_index_collections::TiVec;
+
+use std::collections::HashMap;
+
+#[derive(Debug, From, Into, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub(crate) struct BorrowIndex(usize);
+
+#[derive(Debug)]
+pub(crate) struct BorrowData {
+    pub kind: BorrowKind,
+    pub borrowed_place: Place,
+    pub source_info: SourceInfo,
+}
+
+/// All borrows in the body, that is, every `RValue::Ref`.
+#[derive(Debug)]
+pub(crate) struct BorrowSet {
+    pub borrows: TiVec<BorrowIndex, BorrowData>,
+    pub location_map: HashMap<Location, BorrowIndex>,
+}
+
+impl BorrowSet {
+    pub(crate) fn build(body: &Body) -> Self {
+        let mut borrows = TiVec::new();
+        let mut location_map = HashMap::new();
+
+        for (block, data) in body.blocks.iter_enumerated() {
+            for (statement_index, stmt) in data.stmts.iter().enumerate() {
+                if let StatementKind::Assign(assign) = &stmt.kind {
+                    if let RValue::Ref(kind, place) = &assign.1 {
+                        let borrow = BorrowData {
+                            kind: *kind,
+                            borrowed_place: place.clone(),
+                            source_info: stmt.source_info,
+                        };
+                        let location = Location {
+                            block,
+                            statement_index,
+                        };
+                        location_map.insert(location, borrows.push_and_get_key(borrow));
+                    }
+                }
+            }
+        }
+
+        BorrowSet {
+            borrows,
+            location_map,
+        }
+    }
+}
+
+#[derive(Debug, From, Into, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub(crate) struct MoveIndex(usize);
+
+#[derive(Debug)]
+pub(crate) struct MoveOut {
+    pub local: LocalId,
+    pub source_info: SourceInfo,
+}
+
+/// All moves out of locals in the body.
+/// Moves out of a place behind a pointer are not recorded because they are rejected.
+#[derive(Debug)]
+pub(crate) struct MoveData {
+    pub moves: TiVec<MoveIndex, MoveOut>,
+    pub location_map: HashMap<Location, Vec<MoveIndex>>,
+}
+
+impl MoveData {
+    pub(crate) fn gather_moves(body: &Body) -> Self {
+        let mut moves = TiVec::new();
+        let mut location_map: HashMap<Location, Vec<MoveIndex>> = HashMap::new();
+
+        for (block, data) in body.blocks.iter_enumerated() {
+            let terminator = data.terminator.as_ref().into_iter();
+            let locations = data
+                .stmts
+                .iter()
+                .map(|stmt| (stmt.source_info, stmt.operands()))
+                .chain(terminator.map(|t| (t.source_info, t.operands())));
+
+            for (statement_index, (source_info, operands)) in locations.enumerate() {
+                let location = Location {
+                    block,
+                    statement_index,
+                };
+                for operand in operands {
+                    if let Operand::Move(place) = operand {
+                        if !place.is_indirect() {
+                            let move_out = MoveOut {
+                                local: place.local,
+                                source_info,
+                            };
+                            let index = moves.push_and_get_key(move_out);
+                            location_map.entry(location).or_default().push(index);
+                        }
+                    }
+                }
+            }
+        }
+
+        MoveData {
+            moves,
+            location_map,
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use span::Span;
+
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum BorrowckError {
+    #[error("use of moved value: `{name}`: value moved at ({moved:?}), used at ({used:?})")]
+    UseAfterMove {
+        name: String,
+        moved: Span,
+        used: Span,
+    },
+    #[error("used binding `{name}` is possibly-uninitialized at ({span:?})")]
+    UseOfUninitialized { name: String, span: Span },
+    #[error("function may return without a value at ({span:?})")]
+    MissingReturn { span: Span },
+    #[error("cannot move out of `{name}`, which is behind a reference at ({span:?})")]
+    MoveOutOfReference { name: String, span: Span },
+    #[error("cannot move out of `{name}` because it is borrowed: borrowed at ({borrow:?}), moved at ({moved:?})")]
+    MoveOutOfBorrow {
+        name: String,
+        borrow: Span,
+        moved: Span,
+    },
+    #[error("cannot borrow `{name}` as mutable more than once at a time: first borrow at ({first:?}), second borrow at ({second:?})")]
+    MutBorrowTwice {
+        name: String,
+        first: Span,
+        second: Span,
+    },
+    #[error("cannot borrow `{name}` as {new} because it is also borrowed as {old}: {old} borrow at ({first:?}), {new} borrow at ({second:?})")]
+    ConflictingBorrow {
+        name: String,
+        new: &'static str,
+        old: &'static str,
+        first: Span,
+        second: Span,
+    },
+    #[error("cannot assign to `{name}` because it is borrowed: borrowed at ({borrow:?}), assigned at ({assign:?})")]
+    AssignBorrowed {
+        name: String,
+        borrow: Span,
+        assign: Span,
+    },
+    #[error("cannot use `{name}` because it was mutably borrowed: borrowed at ({borrow:?}), used at ({used:?})")]
+    UseWhileMutBorrowed {
+        name: String,
+        borrow: Span,
+        used: Span,
+    },
+    #[error("cannot return reference to local variable `{name}`: borrowed at ({borrow:?})")]
+    ReturnReferenceToLocal { name: String, borrow: Span },
+}
@@ -0,0 +1,228 @@
+use crate::borrow_set::*;
+
+use mir::dataflow::{Analysis, BitSet, Direction};
+use mir::{stmt::*, terminator::*, *};
+use ty::{Ty, TyKind};
+
+use typed_index_collections::TiVec;
+
+use std::collections::BTreeSet;
+
+/// Returns `true` if a value of the type may contain a reference.
+fn may_hold_borrows(ty: Ty) -> bool {
+    match ty.kind() {
+        // a type parameter may be instantiated with a reference.
+        TyKind::Ref(..) | TyKind::Param(_) => true,
+        TyKind::Tuple(ts) => ts.iter().any(|ty| may_hold_borrows(*ty)),
+        TyKind::Closure(closure) => closure.upvars.iter().any(|ty| may_hold_borrows(*ty)),
+        _ => false,
+    }
+}
+
+/// Computes the locals which may not hold a value, because they have not been assigned on some
+/// path from the entry of the function. The arguments are initialized by the caller, and a
+/// zero-sized local has nothing to initialize.
+pub(crate) struct MaybeUninitializedLocals;
+
+impl MaybeUninitializedLocals {
+    fn apply(&self, state: &mut BitSet<LocalId>, assigned: Option<&Place>) {
+        if let Some(place) = assigned.filter(|place| !place.is_indirect()) {
+            state.remove(place.local);
+        }
+    }
+}
+
+impl<'tcx> Analysis<'tcx> for MaybeUninitializedLocals {
+    type Domain = BitSet<LocalId>;
+
+    const DIRECTION: Direction = Direction::Forward;
+
+    fn bottom_value(&self, body: &Body<'tcx>) -> Self::Domain {
+        BitSet::new_empty(body.local_decls.len())
+    }
+
+    fn initialize_start_block(&self, body: &Body<'tcx>, state: &mut Self::Domain) {
+        *state = BitSet::new_filled(body.local_decls.len());
+        for arg in body.id_args() {
+            state.remove(arg);
+        }
+        for (local, decl) in body.local_decls.iter_enumerated() {
+            if decl.ty.is_zst() {
+                state.remove(local);
+            }
+        }
+    }
+
+    fn apply_statement_effect(
+        &self,
+        state: &mut Self::Domain,
+        statement: &Statement<'tcx>,
+        _location: Location,
+    ) {
+        self.apply(state, statement.destination());
+    }
+
+    fn apply_terminator_effect(
+        &self,
+        state: &mut Self::Domain,
+        terminator: &Terminator<'tcx>,
+        _location: Location,
+    ) {
+        self.apply(state, terminator.destination());
+    }
+}
+
+/// Computes the borrows which the value of each local may hold.
+/// A borrow is alive while a local holding it is live.
+pub(crate) struct Borrows<'a, 'tcx> {
+    body: &'a Body<'tcx>,
+    borrow_set: &'a BorrowSet,
+}
+
+impl<'a, 'tcx> Borrows<'a, 'tcx> {
+    pub(crate) fn new(body: &'a Body<'tcx>, borrow_set: &'a BorrowSet) -> Self {
+        Borrows { body, borrow_set }
+    }
+
+    fn assign(
+        &self,
+        state: &mut TiVec<LocalId, BTreeSet<BorrowIndex>>,
+        place: &Place,
+        borrows: BTreeSet<BorrowIndex>,
+    ) {
+        if !may_hold_borrows(place.ty(self.body)) {
+            return;
+        }
+
+        // The value behind a pointer is not tracked separately, so it is attributed to the pointer.
+        if place.is_indirect() {
+            state[place.local].extend(borrows);
+        } else {
+            state[place.local] = borrows;
+        }
+    }
+}
+
+impl<'a, 'tcx> Analysis<'tcx> for Borrows<'a, 'tcx> {
+    type Domain = TiVec<LocalId, BTreeSet<BorrowIndex>>;
+
+    const DIRECTION: Direction = Direction::Forward;
+
+    fn bottom_value(&self, body: &Body<'tcx>) -> Self::Domain {
+        body.local_decls.iter().map(|_| BTreeSet::new()).collect()
+    }
+
+    fn apply_statement_effect(
+        &self,
+        state: &mut Self::Domain,
+        statement: &Statement<'tcx>,
+        location: Location,
+    ) {
+        if let StatementKind::Assign(assign) = &statement.kind {
+            let (place, rvalue) = assign.as_ref();
+            let mut borrows = BTreeSet::new();
+            match rvalue {
+                RValue::Ref(_, borrowed) => {
+                    borrows.insert(self.borrow_set.location_map[&location]);
+
+                    // A reborrow through a reference keeps the original borrows alive.
+                    if borrowed.is_indirect() {
+                        borrows.extend(state[borrowed.local].iter().copied());
+                    }
+                }
+                RValue::Use(operand) | RValue::Cast(_, operand, _) => {
+                    if let Some(used) = operand.place() {
+                        borrows.extend(state[used.local].iter().copied());
+                    }
+                }
+                // A closure holds the borrows of its captured variables.
+                RValue::Aggregate(_, operands) => {
+                    for used in operands.iter().filter_map(|operand| operand.place()) {
+                        borrows.extend(state[used.local].iter().copied());
+                    }
+                }
+                RValue::AddressOf(..)
+                | RValue::StaticRef(_)
+                | RValue::BinaryOp(..)
+                | RValue::UnaryOp(..) => {}
+            }
+
+            self.assign(state, place, borrows);
+        }
+    }
+
+    fn apply_terminator_effect(
+        &self,
+        state: &mut Self::Domain,
+        terminator: &Terminator<'tcx>,
+        _location: Location,
+    ) {
+        // The returned value may hold any borrow passed to the function.
+        if let TerminatorKind::Call {
+            args,
+            destination: Some((place, _)),
+            ..
+        } = &terminator.kind
+        {
+            let borrows = args
+                .iter()
+                .filter_map(|arg| arg.place())
+                .flat_map(|arg| state[arg.local].iter().copied())
+                .collect();
+
+            self.assign(state, place, borrows);
+        }
+    }
+}
+
+/// Computes the moves out of locals which may have happened.
+/// A move is cancelled by assigning a new value to the local.
+pub(crate) struct MaybeMovedLocals<'a> {
+    move_data: &'a MoveData,
+}
+
+impl<'a> MaybeMovedLocals<'a> {
+    pub(crate) fn new(move_data: &'a MoveData) -> Self {
+        MaybeMovedLocals { move_data }
+    }
+
+    fn apply(&self, state: &mut BTreeSet<MoveIndex>, assigned: Option<&Place>, location: Location) {
+        if let Some(moves) = self.move_data.location_map.get(&location) {
+            state.extend(moves.iter().copied());
+        }
+
+        if let Some(place) = assigned {
+            if !place.is_indirect() {
+                state.retain(|index| self.move_data.moves[*index].local != place.local);
+            }
+        }
+    }
+}
+
+impl<'a, 'tcx> Analysis<'tcx> for MaybeMovedLocals<'a> {
+    type Domain = BTreeSet<MoveIndex>;
+
+    const DIRECTION: Direction = Direction::Forward;
+
+    fn bottom_value(&self, _body: &Body<'tcx>) -> Self::Domain {
+        BTreeSet::new()
+    }
+
+    fn apply_statement_effect(
+        &self,
+        state: &mut Self::Domain,
+        statement: &Statement<'tcx>,
+        location: Location,
+    ) {
+        self.apply(state, statement.destination(), location);
+    }
+
+    fn apply_terminator_effect(
+        &self,
+        state: &mut Self::Domain,
+        terminator: &Terminator<'tcx>,
+        location: Location,
+    ) {
+        self.apply(state, terminator.destination(), location);
+    }
+}
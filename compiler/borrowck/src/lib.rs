@@ -0,0 +1,643 @@
+//! Borrow checker over MIR.
+//!
+//! A borrow is alive while a local holding the reference may be used later, so the region of
+//! a borrow is computed from two dataflow analyses: the borrows each local may hold, and the
+//! liveness of locals. Every access to a place is checked against the borrows alive there.
+//! A read of a local must also follow an assignment to it on every path, which is computed by
+//! the analysis of the maybe-uninitialized locals.
+
+mod borrow_set;
+pub mod error;
+mod impls;
+
+use crate::{borrow_set::*, error::BorrowckError, impls::*};
+
+use mir::dataflow::{impls::MaybeLiveLocals, iterate_to_fixpoint, ResultsCursor};
+use mir::{stmt::*, terminator::*, *};
+
+use anyhow::Result;
+
+use std::collections::BTreeSet;
+
+pub fn borrowck(body: &Body) -> Result<()> {
+    let borrow_set = BorrowSet::build(body);
+    let move_data = MoveData::gather_moves(body);
+
+    let mut checker = BorrowChecker {
+        body,
+        borrow_set: &borrow_set,
+        move_data: &move_data,
+        liveness: iterate_to_fixpoint(MaybeLiveLocals, body).into_results_cursor(body),
+        borrows: iterate_to_fixpoint(Borrows::new(body, &borrow_set), body)
+            .into_results_cursor(body),
+        moves: iterate_to_fixpoint(MaybeMovedLocals::new(&move_data), body)
+            .into_results_cursor(body),
+        uninit: iterate_to_fixpoint(MaybeUninitializedLocals, body).into_results_cursor(body),
+    };
+
+    checker.check()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AccessKind {
+    Read,
+    Move,
+    Write,
+    Borrow(BorrowKind),
+}
+
+struct BorrowChecker<'a, 'tcx> {
+    body: &'a Body<'tcx>,
+    borrow_set: &'a BorrowSet,
+    move_data: &'a MoveData,
+
+    liveness: ResultsCursor<'a, 'tcx, MaybeLiveLocals>,
+    borrows: ResultsCursor<'a, 'tcx, Borrows<'a, 'tcx>>,
+    moves: ResultsCursor<'a, 'tcx, MaybeMovedLocals<'a>>,
+    uninit: ResultsCursor<'a, 'tcx, MaybeUninitializedLocals>,
+}
+
+impl<'a, 'tcx> BorrowChecker<'a, 'tcx> {
+    fn check(&mut self) -> Result<()> {
+        for (block, data) in self.body.blocks.iter_enumerated() {
+            for (statement_index, stmt) in data.stmts.iter().enumerate() {
+                let location = Location {
+                    block,
+                    statement_index,
+                };
+                self.check_accesses(location, stmt.source_info, statement_accesses(stmt))?;
+            }
+
+            let terminator = data
+                .terminator
+                .as_ref()
+                .expect("The Terminator in the Block is None.");
+            let location = Location {
+                block,
+                statement_index: data.stmts.len(),
+            };
+            self.check_accesses(
+                location,
+                terminator.source_info,
+                terminator_accesses(terminator),
+            )?;
+
+            if let TerminatorKind::Return = terminator.kind {
+                self.check_return(location, terminator.source_info)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_accesses(
+        &mut self,
+        location: Location,
+        source_info: SourceInfo,
+        accesses: Vec<(Place, AccessKind)>,
+    ) -> Result<()> {
+        let span = source_info.span;
+        self.liveness.seek_before(location);
+        self.borrows.seek_before(location);
+        self.moves.seek_before(location);
+        self.uninit.seek_before(location);
+        let live = self.liveness.get();
+        let holders = self.borrows.get();
+        let moved = self.moves.get();
+        let uninit = self.uninit.get();
+
+        let alive: BTreeSet<BorrowIndex> = live
+            .iter()
+            .flat_map(|local| holders[local].iter().copied())
+            .collect();
+
+        for (place, access) in accesses {
+            if access == AccessKind::Move && place.is_indirect() {
+                let name = self.describe_place(&place);
+                return Err(BorrowckError::MoveOutOfReference { name, span }.into());
+            }
+
+            // Assigning to a local initializes it, while any other access needs its value.
+            if access != AccessKind::Write || place.is_indirect() {
+                if uninit.contains(place.local) {
+                    return Err(BorrowckError::UseOfUninitialized {
+                        name: self.describe_local(place.local),
+                        span,
+                    }
+                    .into());
+                }
+
+                let move_out = moved
+                    .iter()
+                    .map(|index| &self.move_data.moves[*index])
+                    .find(|move_out| move_out.local == place.local);
+                if let Some(move_out) = move_out {
+                    return Err(BorrowckError::UseAfterMove {
+                        name: self.describe_local(place.local),
+                        moved: move_out.source_info.span,
+                        used: span,
+                    }
+                    .into());
+                }
+            }
+
+            for index in &alive {
+                self.check_conflict(&self.borrow_set.borrows[*index], &place, access, span)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_conflict(
+        &self,
+        borrow: &BorrowData,
+        place: &Place,
+        access: AccessKind,
+        span: span::Span,
+    ) -> Result<()> {
+        let borrowed = &borrow.borrowed_place;
+        if borrowed.local != place.local {
+            return Ok(());
+        }
+
+        // Overwriting a pointer does not affect the borrows of the value behind it.
+        if access == AccessKind::Write && borrowed.projection.len() > place.projection.len() {
+            return Ok(());
+        }
+
+        let name = self.describe_place(place);
+        let (first, second) = (borrow.source_info.span, span);
+        let err = match (borrow.kind, access) {
+            (BorrowKind::Shared, AccessKind::Read | AccessKind::Borrow(BorrowKind::Shared)) => {
+                return Ok(())
+            }
+            (_, AccessKind::Move) => BorrowckError::MoveOutOfBorrow {
+                name,
+                borrow: first,
+                moved: second,
+            },
+            (_, AccessKind::Write) => BorrowckError::AssignBorrowed {
+                name,
+                borrow: first,
+                assign: second,
+            },
+            (BorrowKind::Mut, AccessKind::Borrow(BorrowKind::Mut)) => {
+                BorrowckError::MutBorrowTwice {
+                    name,
+                    first,
+                    second,
+                }
+            }
+            (BorrowKind::Shared, AccessKind::Borrow(BorrowKind::Mut)) => {
+                BorrowckError::ConflictingBorrow {
+                    name,
+                    new: "mutable",
+                    old: "immutable",
+                    first,
+                    second,
+                }
+            }
+            (BorrowKind::Mut, AccessKind::Borrow(BorrowKind::Shared)) => {
+                BorrowckError::ConflictingBorrow {
+                    name,
+                    new: "immutable",
+                    old: "mutable",
+                    first,
+                    second,
+                }
+            }
+            (BorrowKind::Mut, AccessKind::Read) => BorrowckError::UseWhileMutBorrowed {
+                name,
+                borrow: first,
+                used: second,
+            },
+        };
+
+        Err(err.into())
+    }
+
+    /// The return place must be assigned, and the returned value must not hold a borrow of a
+    /// local of the function.
+    fn check_return(&mut self, location: Location, source_info: SourceInfo) -> Result<()> {
+        self.uninit.seek_before(location);
+        if self.uninit.get().contains(self.body.id_return()) {
+            return Err(BorrowckError::MissingReturn {
+                span: source_info.span,
+            }
+            .into());
+        }
+
+        self.borrows.seek_before(location);
+        let holders = self.borrows.get();
+        for index in &holders[self.body.id_return()] {
+            let borrow = &self.borrow_set.borrows[*index];
+            if !borrow.borrowed_place.is_indirect() {
+                return Err(BorrowckError::ReturnReferenceToLocal {
+                    name: self.describe_place(&borrow.borrowed_place),
+                    borrow: borrow.source_info.span,
+                }
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn describe_local(&self, local: LocalId) -> String {
+        match &self.body.local_decls[local].name {
+            Some(name) => name.clone(),
+            None => format!("_{}", local.index()),
+        }
+    }
+
+    fn describe_place(&self, place: &Place) -> String {
+        let mut description = self.describe_local(place.local);
+        for (i, elem) in place.projection.iter().enumerate() {
+            description = match elem {
+                PlaceElem::Deref
+                    if matches!(place.projection.get(i + 1), Some(PlaceElem::Field(_))) =>
+                {
+                    format!("(*{})", description)
+                }
+                PlaceElem::Deref => format!("*{}", description),
+                PlaceElem::Field(idx) => format!("{}.{}", description, idx),
+            };
+        }
+
+        description
+    }
+}
+
+fn operand_access(operand: &Operand) -> Option<(Place, AccessKind)> {
+    match operand {
+        Operand::Copy(place) => Some((place.clone(), AccessKind::Read)),
+        Operand::Move(place) => Some((place.clone(), AccessKind::Move)),
+        Operand::Constant(_) => None,
+    }
+}
+
+fn statement_accesses(statement: &Statement) -> Vec<(Place, AccessKind)> {
+    let mut accesses: Vec<_> = statement
+        .operands()
+        .into_iter()
+        .filter_map(operand_access)
+        .collect();
+
+    if let StatementKind::Assign(assign) = &statement.kind {
+        let (place, rvalue) = assign.as_ref();
+        match rvalue {
+            RValue::Ref(kind, borrowed) => {
+                accesses.push((borrowed.clone(), AccessKind::Borrow(*kind)));
+            }
+            RValue::AddressOf(mutbl, borrowed) => {
+                let kind = match mutbl {
+                    ty::Mutability::Mut => BorrowKind::Mut,
+                    ty::Mutability::Not => BorrowKind::Shared,
+                };
+                accesses.push((borrowed.clone(), AccessKind::Borrow(kind)));
+            }
+            _ => {}
+        }
+        accesses.push((place.clone(), AccessKind::Write));
+    }
+
+    accesses
+}
+
+fn terminator_accesses(terminator: &Terminator) -> Vec<(Place, AccessKind)> {
+    let mut accesses: Vec<_> = terminator
+        .operands()
+        .into_iter()
+        .filter_map(operand_access)
+        .collect();
+
+    if let Some(place) = terminator.destination() {
+        accesses.push((place.clone(), AccessKind::Write));
+    }
+
+    accesses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use driver::{Options, Session};
+    use span::source_map::SourceMap;
+
+    fn check(src: &str) -> Option<BorrowckError> {
+        let source_map = SourceMap::new();
+        let sess = Session::from_source_str(
+            &source_map,
+            "main.elaina",
+            src.to_string(),
+            Options::default(),
+        )
+        .unwrap();
+        sess.with_mir_built(|_, bodies| {
+            let err = bodies.iter().find_map(|body| borrowck(body).err())?;
+            Some(err.downcast().unwrap())
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn mutable_references() {
+        let src = r"
+fn main() -> i32 {
+    let mut a: i32 = 1;
+    let mut b: i32 = 2;
+    swap(&mut a, &mut b);
+    return a;
+}
+
+fn swap(a: &mut i32, b: &mut i32) -> i32 {
+    let tmp: i32 = *a;
+    *a = *b;
+    *b = tmp;
+    return 0;
+}";
+        assert_eq!(check(src), None);
+    }
+
+    #[test]
+    fn borrow_ends_at_last_use() {
+        let src = r"
+fn main() -> i32 {
+    let mut x: i32 = 0;
+    let r: &mut i32 = &mut x;
+    *r = 1;
+    let s: &mut i32 = &mut x;
+    *s = 2;
+    return x;
+}";
+        assert_eq!(check(src), None);
+    }
+
+    #[test]
+    fn reborrow_arguments() {
+        let src = r"
+fn f(r: &mut i32) -> i32 {
+    g(r);
+    g(r);
+    return *r;
+}
+
+fn g(r: &mut i32) -> i32 {
+    *r = 1;
+    return 0;
+}";
+        assert_eq!(check(src), None);
+    }
+
+    #[test]
+    fn mut_borrow_twice() {
+        let src = r"
+fn main() -> i32 {
+    let mut x: i32 = 0;
+    f(&mut x, &mut x);
+    return x;
+}
+
+fn f(a: &mut i32, b: &mut i32) -> i32 {
+    return 0;
+}";
+        assert!(matches!(
+            check(src),
+            Some(BorrowckError::MutBorrowTwice { .. })
+        ));
+    }
+
+    #[test]
+    fn mut_borrow_through_reborrow() {
+        let src = r"
+fn main() -> i32 {
+    let mut x: i32 = 0;
+    let r: &mut i32 = &mut x;
+    let s: &mut i32 = r;
+    let t: &mut i32 = &mut x;
+    *s = 1;
+    return 0;
+}";
+        assert!(matches!(
+            check(src),
+            Some(BorrowckError::MutBorrowTwice { .. })
+        ));
+    }
+
+    #[test]
+    fn conflicting_borrow() {
+        let src = r"
+fn main() -> i32 {
+    let mut x: i32 = 0;
+    let r: &mut i32 = &mut x;
+    let s: &i32 = &x;
+    *r = 1;
+    return 0;
+}";
+        assert!(matches!(
+            check(src),
+            Some(BorrowckError::ConflictingBorrow { .. })
+        ));
+    }
+
+    #[test]
+    fn assign_borrowed() {
+        let src = r"
+fn main() -> i32 {
+    let mut x: i32 = 0;
+    let r: &i32 = &x;
+    x = 1;
+    return *r;
+}";
+        assert!(matches!(
+            check(src),
+            Some(BorrowckError::AssignBorrowed { .. })
+        ));
+    }
+
+    #[test]
+    fn use_while_mut_borrowed() {
+        let src = r"
+fn main() -> i32 {
+    let mut x: i32 = 0;
+    let r: &mut i32 = &mut x;
+    let y: i32 = x;
+    *r = 1;
+    return y;
+}";
+        assert!(matches!(
+            check(src),
+            Some(BorrowckError::UseWhileMutBorrowed { .. })
+        ));
+    }
+
+    #[test]
+    fn use_after_move() {
+        let src = r"
+fn main() -> i32 {
+    let mut x: i32 = 0;
+    let mut y: i32 = 0;
+    let r: &mut i32 = &mut x;
+    let mut s: &mut i32 = &mut y;
+    s = r;
+    *r = 1;
+    return 0;
+}";
+        assert!(matches!(
+            check(src),
+            Some(BorrowckError::UseAfterMove { .. })
+        ));
+    }
+
+    #[test]
+    fn generic_values_are_moved() {
+        let src = r"
+fn max<T>(a: T, b: T) -> T {
+    if a < b { b } else { a }
+}";
+        assert_eq!(check(src), None);
+
+        let src = r"
+fn dup<T>(a: T) -> T {
+    let b: T = a;
+    return a;
+}";
+        assert!(matches!(
+            check(src),
+            Some(BorrowckError::UseAfterMove { .. })
+        ));
+    }
+
+    #[test]
+    fn return_reference() {
+        let src = r"
+fn f(x: &mut i32) -> &i32 {
+    return &*x;
+}";
+        assert_eq!(check(src), None);
+
+        let src = r"
+fn f() -> &i32 {
+    let x: i32 = 1;
+    return &x;
+}";
+        assert!(matches!(
+            check(src),
+            Some(BorrowckError::ReturnReferenceToLocal { .. })
+        ));
+    }
+
+    #[test]
+    fn missing_return() {
+        let src = r"
+fn f(x: i32) -> i32 {
+    if x < 0 {
+        return 1;
+    }
+    println(x);
+}";
+        assert!(matches!(
+            check(src),
+            Some(BorrowckError::MissingReturn { .. })
+        ));
+
+        let src = r"
+fn f(x: i32) -> i32 {
+    loop {
+        if x < 0 {
+            return 1;
+        }
+    }
+    println(x);
+}
+
+fn g() {
+    println(1);
+}";
+        assert_eq!(check(src), None);
+    }
+
+    #[test]
+    fn tail_expression_is_coerced() {
+        let src = r"
+fn f(x: &i32) -> *const i32 {
+    x
+}";
+        assert_eq!(check(src), None);
+    }
+
+    #[test]
+    fn use_of_uninitialized() {
+        let src = r"
+fn f(b: bool) -> i32 {
+    let x: i32 = if b { 1 };
+    x
+}";
+        assert!(matches!(
+            check(src),
+            Some(BorrowckError::UseOfUninitialized { .. })
+        ));
+    }
+
+    #[test]
+    fn trait_object_keeps_borrow() {
+        let src = r"
+trait Shape {
+    fn area(&mut self) -> i32;
+}
+
+impl Shape for i32 {
+    fn area(&mut self) -> i32 { *self }
+}
+
+fn main() -> i32 {
+    let mut a: i32 = 1;
+    let s: &mut dyn Shape = &mut a;
+    let b: i32 = a;
+    s.area()
+}";
+        assert!(matches!(
+            check(src),
+            Some(BorrowckError::UseWhileMutBorrowed { .. })
+        ));
+    }
+
+    #[test]
+    fn closure_keeps_mutable_capture() {
+        let src = r"
+fn main() -> i32 {
+    let mut count: i32 = 0;
+    let mut incr = |n: i32| {
+        count = count + n;
+        count
+    };
+    incr(1);
+    let a: i32 = count;
+    incr(2)
+}";
+        assert!(matches!(
+            check(src),
+            Some(BorrowckError::UseWhileMutBorrowed { .. })
+        ));
+    }
+
+    #[test]
+    fn capture_ends_at_last_call() {
+        let src = r"
+fn main() -> i32 {
+    let mut count: i32 = 0;
+    let offset: i32 = 2;
+    let mut incr = |n: i32| {
+        count = count + n + offset;
+        count
+    };
+    incr(1);
+    incr(2);
+    count = count + offset;
+    count
+}";
+        assert_eq!(check(src), None);
+    }
+}
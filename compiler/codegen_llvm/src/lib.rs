@@ -1,8 +1,7 @@
 pub mod stmt;
 pub mod value;
 
-use mir::*;
-use span::SymbolMap;
+use mir::{stmt::*, *};
 use ty::{res::DefId, *};
 
 use anyhow;
@@ -11,7 +10,9 @@ use inkwell::{
     builder::Builder,
     context::Context,
     module::Module,
-    types::{BasicMetadataTypeEnum, BasicType},
+    types::{
+        BasicMetadataTypeEnum, BasicType, BasicTypeEnum, FunctionType, PointerType, StructType,
+    },
     values::*,
     AddressSpace, IntPredicate,
 };
@@ -19,54 +20,64 @@ use typed_index_collections::TiVec;
 
 use std::collections::HashMap;
 
-pub struct CodegenContext<'ctx, 'a> {
+pub struct CodegenContext<'ctx, 'a, 'tcx> {
     context: &'ctx Context,
     builder: Builder<'ctx>,
 
-    symbol_map: &'a SymbolMap<'a>,
+    tcx: &'a TyCtx<'a, 'tcx>,
     local_values: TiVec<LocalId, PointerValue<'ctx>>,
-    functions: HashMap<DefId, FunctionValue<'ctx>>,
+    functions: HashMap<Instance<'tcx>, FunctionValue<'ctx>>,
     blocks: HashMap<BlockId, BasicBlock<'ctx>>,
+
+    /// The vtable of each type for each trait it is coerced to a trait object of.
+    vtables: HashMap<(DefId, Ty<'tcx>), GlobalValue<'ctx>>,
+
+    /// The global variable of each static.
+    statics: HashMap<DefId, GlobalValue<'ctx>>,
 }
 
-impl<'ctx, 'a> CodegenContext<'ctx, 'a> {
-    pub fn new(context: &'ctx Context, symbol_map: &'a SymbolMap<'a>) -> Self {
+impl<'ctx, 'a, 'tcx> CodegenContext<'ctx, 'a, 'tcx> {
+    pub fn new(context: &'ctx Context, tcx: &'a TyCtx<'a, 'tcx>) -> Self {
         CodegenContext {
             context: context,
             builder: context.create_builder(),
             local_values: TiVec::new(),
-            symbol_map,
+            tcx,
             functions: HashMap::new(),
             blocks: HashMap::new(),
+            vtables: HashMap::new(),
+            statics: HashMap::new(),
         }
     }
 
-    pub fn codegen(&mut self, bodies: &[Body]) -> Module<'ctx> {
+    /// Generates a function for each body.
+    /// Generic functions must be monomorphized before this.
+    pub fn codegen(&mut self, bodies: &[Body<'tcx>]) -> Module<'ctx> {
         let module = self.context.create_module("main");
 
         // declare functions
         for body in bodies {
-            let ret_ty = {
-                let ty = &body.local_decls[body.id_return()].ty;
-                self.basic_type(ty)
-            };
+            let ret_ty = &body.local_decls[body.id_return()].ty;
             let input_types: Vec<_> = body
                 .id_args()
                 .map(|id| &body.local_decls[id].ty)
                 .map(|ty| self.basic_meta_data_type(ty))
                 .collect();
-            let fn_type = ret_ty.fn_type(input_types.as_slice(), false);
-            let fn_name = self.symbol_map.get(body.name);
-            let function = module.add_function(fn_name, fn_type, None);
-            self.functions.insert(body.def, function);
+            let fn_type = self.fn_type(input_types.as_slice(), ret_ty);
+            let fn_name = self.symbol_name(body);
+            let function = module.add_function(&fn_name, fn_type, None);
+            self.functions.insert(body.instance(), function);
         }
 
         // declare buildin functions
         self.declare_builtin_print(&module);
 
+        self.declare_vtables(&module, bodies);
+        self.declare_statics(&module);
+
         // codegen bodies
         for body in bodies {
-            let function = self.functions[&body.def];
+            let function = self.functions[&body.instance()];
             self.codegen_body(&module, function, body);
         }
 
@@ -77,7 +88,7 @@ impl<'ctx, 'a> CodegenContext<'ctx, 'a> {
         &mut self,
         module: &Module<'ctx>,
         function: FunctionValue<'ctx>,
-        body: &Body,
+        body: &Body<'tcx>,
     ) {
         for block_key in body.blocks.keys() {
             let block = self.context.append_basic_block(function, "");
@@ -105,10 +116,86 @@ impl<'ctx, 'a> CodegenContext<'ctx, 'a> {
             let block = &body.blocks[block_id];
 
             self.builder.position_at_end(self.blocks[&block_id]);
-            self.codegen_block(module, function, block);
+            self.codegen_block(module, function, body, block);
         }
     }
 
+    /// Generates a vtable for each pair of a type and a trait that the type is coerced to.
+    /// A vtable is a constant array of pointers to the methods in the impl of the trait for the
+    /// type, in the declaration order of the trait methods.
+    fn declare_vtables(&mut self, module: &Module<'ctx>, bodies: &[Body<'tcx>]) {
+        let i8_ptr_type = self.context.i8_type().ptr_type(AddressSpace::Generic);
+
+        for body in bodies {
+            for stmt in body.blocks.iter().flat_map(|block| block.stmts.iter()) {
+                let (operand, ty) = match &stmt.kind {
+                    StatementKind::Assign(assign) => match &assign.1 {
+                        RValue::Cast(CastKind::Unsize, operand, ty) => (operand, ty),
+                        _ => continue,
+                    },
+                    StatementKind::Println(_) => continue,
+                };
+                let self_ty = operand.ty(body).builtin_deref().unwrap().0;
+                let trait_def = match ty.builtin_deref().unwrap().0.kind() {
+                    TyKind::Dynamic(def) => *def,
+                    _ => unreachable!(),
+                };
+                if self.vtables.contains_key(&(trait_def, self_ty)) {
+                    continue;
+                }
+
+                let methods: Vec<_> = self
+                    .tcx
+                    .vtable_entries(trait_def, self_ty)
+                    .iter()
+                    .map(|instance| {
+                        self.functions[instance]
+                            .as_global_value()
+                            .as_pointer_value()
+                            .const_cast(i8_ptr_type)
+                    })
+                    .collect();
+                let trait_name = self
+                    .tcx
+                    .symbol_map
+                    .get(self.tcx.traits.trait_def(trait_def).name);
+                let name = format!("vtable.{}.{}", trait_name, self_ty);
+
+                let vtable =
+                    module.add_global(i8_ptr_type.array_type(methods.len() as u32), None, &name);
+                vtable.set_initializer(&i8_ptr_type.const_array(methods.as_slice()));
+                vtable.set_constant(true);
+                self.vtables.insert((trait_def, self_ty), vtable);
+            }
+        }
+    }
+
+    /// Generates a global variable for each static, initialized with its evaluated value.
+    /// An immutable static is a constant global.
+    fn declare_statics(&mut self, module: &Module<'ctx>) {
+        for def in &self.tcx.statics {
+            let ConstLit::Scalar(scalar) = &def.value.literal;
+            let name = self.tcx.symbol_map.get(def.name);
+
+            let global = module.add_global(self.basic_type(&def.value.ty), None, name);
+            global.set_initializer(&self.scalar_int(scalar));
+            global.set_constant(def.mutbl == Mutability::Not);
+            self.statics.insert(def.def, global);
+        }
+    }
+
+    /// Returns the name of the function generated for the body.
+    /// Instances of a generic function are distinguished by their type arguments: `max<i32>`
+    fn symbol_name(&self, body: &Body) -> String {
+        let name = self.tcx.symbol_map.get(body.name);
+        if body.substs.is_empty() {
+            return name.to_string();
+        }
+
+        let substs: Vec<_> = body.substs.iter().map(|ty| ty.to_string()).collect();
+        format!("{}<{}>", name, substs.join(", "))
+    }
+
     fn declare_local(&mut self, id: LocalId, decl: &LocalDecl) {
         let name = match &decl.name {
             Some(name) => name,
@@ -120,24 +207,31 @@ impl<'ctx, 'a> CodegenContext<'ctx, 'a> {
         self.local_values.insert(id, local_ptr);
     }
 
-    fn codegen_block(&self, module: &Module<'ctx>, function: FunctionValue, block: &Block) {
+    fn codegen_block(
+        &self,
+        module: &Module<'ctx>,
+        function: FunctionValue,
+        body: &Body<'tcx>,
+        block: &Block<'tcx>,
+    ) {
         // let basic_block = self.context.append_basic_block(function, "");
         // self.builder.position_at_end(basic_block);
 
         for stmt in &block.stmts {
-            self.codegen_stmt(module, function, stmt);
+            self.codegen_stmt(module, function, body, stmt);
         }
 
-        match block
+        match &block
             .terminator
             .as_ref()
             .expect("The Terminator in the Block is None.")
+            .kind
         {
-            terminator::Terminator::Goto { target } => {
+            terminator::TerminatorKind::Goto { target } => {
                 let target = self.blocks[target];
                 self.builder.build_unconditional_branch(target);
             }
-            terminator::Terminator::SwitchInt {
+            terminator::TerminatorKind::SwitchInt {
                 discr,
                 switch_ty: _,
                 targets,
@@ -153,43 +247,64 @@ impl<'ctx, 'a> CodegenContext<'ctx, 'a> {
                     todo!();
                 }
             }
-            terminator::Terminator::Call {
+            terminator::TerminatorKind::Call {
                 fun,
                 args,
                 destination,
             } => {
                 let (dest_place, dest_block) = destination.as_ref().unwrap();
-                let function = self.as_function_value(fun);
-                let args: Vec<_> = args
+                let mut arg_values: Vec<_> = args
                     .iter()
                     .map(|arg| self.basic_metadata_value(arg))
                     .collect();
+                let function = match self.vtable_index(fun) {
+                    Some(index) => {
+                        let ret_ty = dest_place.ty(body);
+                        let (function, data) = self.virtual_function(body, index, args, ret_ty);
+                        arg_values[0] = data.into();
+                        function
+                    }
+                    None => self.as_function_value(fun),
+                };
                 let call = self
                     .builder
-                    .build_call(function, args.as_slice(), "")
+                    .build_call(function, arg_values.as_slice(), "")
                     .try_as_basic_value()
-                    .left()
-                    .unwrap();
-                let dest_ptr = self.pointer_value(dest_place);
-                self.builder.build_store(dest_ptr, call);
+                    .left();
+                // a function returning `()` returns no value to store.
+                if let Some(value) = call {
+                    let dest_ptr = self.pointer_value(dest_place);
+                    self.builder.build_store(dest_ptr, value);
+                }
 
                 let target = self.blocks[dest_block];
                 self.builder.build_unconditional_branch(target);
             }
-            terminator::Terminator::Return => {
-                let ret_ptr = self.local_values.first().unwrap().clone();
-                let ret_val = self.builder.build_load(ret_ptr, "").into_int_value();
-                self.builder.build_return(Some(&ret_val));
+            terminator::TerminatorKind::Return => {
+                if body.local_decls[body.id_return()].ty.is_zst() {
+                    self.builder.build_return(None);
+                } else {
+                    let ret_ptr = self.local_values.first().unwrap().clone();
+                    let ret_val = self.builder.build_load(ret_ptr, "");
+                    self.builder.build_return(Some(&ret_val));
+                }
             }
         }
     }
 
-    fn basic_type(&self, ty: &Ty) -> impl BasicType<'ctx> {
+    fn basic_type(&self, ty: &Ty) -> BasicTypeEnum<'ctx> {
         match ty.kind() {
-            TyKind::Bool => self.context.bool_type(),
+            TyKind::Bool => self.context.bool_type().into(),
             TyKind::Int(int_ty) => match int_ty {
-                IntTy::I32 => self.context.i32_type(),
+                IntTy::I32 => self.context.i32_type().into(),
             },
+            _ if ty.is_zst() => self.unit_type().into(),
+            _ if ty.is_fat_ptr() => self.fat_ptr_type().into(),
+            TyKind::FnPtr(sig) => self.fn_ptr_type(sig).into(),
+            TyKind::Closure(closure) => self.closure_type(closure).into(),
+            TyKind::Ref(ty, _) | TyKind::RawPtr(ty, _) => {
+                self.basic_type(ty).ptr_type(AddressSpace::Generic).into()
+            }
             _ => {
                 panic!("error: tried to convert {:?} to BasicType", &ty.kind())
             }
@@ -202,6 +317,13 @@ impl<'ctx, 'a> CodegenContext<'ctx, 'a> {
             TyKind::Int(int_ty) => match int_ty {
                 IntTy::I32 => self.context.i32_type().into(),
             },
+            _ if ty.is_zst() => self.unit_type().into(),
+            _ if ty.is_fat_ptr() => self.fat_ptr_type().into(),
+            TyKind::FnPtr(sig) => self.fn_ptr_type(sig).into(),
+            TyKind::Closure(closure) => self.closure_type(closure).into(),
+            TyKind::Ref(ty, _) | TyKind::RawPtr(ty, _) => {
+                self.basic_type(ty).ptr_type(AddressSpace::Generic).into()
+            }
             _ => {
                 panic!(
                     "error: tried to convert {:?} to BasicMetadataTypeEnum",
@@ -211,6 +333,59 @@ impl<'ctx, 'a> CodegenContext<'ctx, 'a> {
         }
     }
 
+    /// Returns the type of the unit value, an empty struct: `{}`
+    /// A local of the unit type takes no space, and a function returning `()` returns `void`.
+    pub(crate) fn unit_type(&self) -> StructType<'ctx> {
+        self.context.struct_type(&[], false)
+    }
+
+    /// Returns the type of a function of the signature: `i32 (i32)`, `void (i32*)`
+    pub(crate) fn fn_type(
+        &self,
+        input_types: &[BasicMetadataTypeEnum<'ctx>],
+        output: &Ty,
+    ) -> FunctionType<'ctx> {
+        if output.is_zst() {
+            self.context.void_type().fn_type(input_types, false)
+        } else {
+            self.basic_type(output).fn_type(input_types, false)
+        }
+    }
+
+    /// Returns the type of a pointer to a trait object: `{ i8*, i8** }`
+    /// It holds the address of the value and the address of the vtable of its type.
+    fn fat_ptr_type(&self) -> StructType<'ctx> {
+        let i8_ptr_type = self.context.i8_type().ptr_type(AddressSpace::Generic);
+        let vtable_ptr_type = i8_ptr_type.ptr_type(AddressSpace::Generic);
+
+        self.context
+            .struct_type(&[i8_ptr_type.into(), vtable_ptr_type.into()], false)
+    }
+
+    /// Returns the type of the environment of a closure, a struct of the captured variables:
+    /// `{ i32*, i32 }`
+    fn closure_type(&self, closure: &ClosureTy) -> StructType<'ctx> {
+        let field_types: Vec<_> = closure
+            .upvars
+            .iter()
+            .map(|ty| self.basic_type(ty))
+            .collect();
+
+        self.context.struct_type(&field_types, false)
+    }
+
+    /// Returns the type of a pointer to a function of the signature: `i32 (i32)*`
+    fn fn_ptr_type(&self, sig: &FnSig) -> PointerType<'ctx> {
+        let input_types: Vec<_> = sig
+            .inputs
+            .iter()
+            .map(|ty| self.basic_meta_data_type(ty))
+            .collect();
+
+        self.fn_type(input_types.as_slice(), &sig.output)
+            .ptr_type(AddressSpace::Generic)
+    }
+
     fn declare_builtin_print(&self, module: &Module<'ctx>) {
         let i32_type = self.context.i32_type();
         let i8_ptr_type = self.context.i8_type().ptr_type(AddressSpace::Generic);
@@ -273,23 +448,26 @@ impl<'ctx, 'a> CodegenContext<'ctx, 'a> {
     }
 }
 
-fn with_context<F, V>(symbol_map: &SymbolMap, f: F) -> V
+fn with_context<'tcx, F, V>(tcx: &TyCtx<'_, 'tcx>, f: F) -> V
 where
-    F: FnOnce(&mut CodegenContext) -> V,
+    F: FnOnce(&mut CodegenContext<'_, '_, 'tcx>) -> V,
 {
     let context = Context::create();
-    return f(&mut CodegenContext::new(&context, symbol_map));
+    return f(&mut CodegenContext::new(&context, tcx));
 }
 
-pub fn codegen_string(bodies: &[Body], symbol_map: &SymbolMap) -> String {
-    with_context(symbol_map, |context| {
+pub fn codegen_string<'tcx>(tcx: &TyCtx<'_, 'tcx>, bodies: &[Body<'tcx>]) -> String {
+    with_context(tcx, |context| {
         let module = context.codegen(bodies);
         module.print_to_string().to_string()
     })
 }
 
-pub fn codegen_and_execute(bodies: &[Body], symbol_map: &SymbolMap) -> anyhow::Result<i32> {
-    with_context(symbol_map, |context| {
+pub fn codegen_and_execute<'tcx>(
+    tcx: &TyCtx<'_, 'tcx>,
+    bodies: &[Body<'tcx>],
+) -> anyhow::Result<i32> {
+    with_context(tcx, |context| {
         let module = context.codegen(bodies);
         let engine = module
             .create_jit_execution_engine(inkwell::OptimizationLevel::None)
@@ -301,3 +479,58 @@ pub fn codegen_and_execute(bodies: &[Body], symbol_map: &SymbolMap) -> anyhow::R
         Ok(result)
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use driver::{Options, Session};
+    use span::source_map::SourceMap;
+
+    /// Generates the LLVM IR of the program, and returns it with the result of `main`.
+    fn codegen(src: &str) -> (String, i32) {
+        let source_map = SourceMap::new();
+        let sess = Session::from_source_str(
+            &source_map,
+            "main.elaina",
+            src.to_string(),
+            Options::default(),
+        )
+        .unwrap();
+        sess.with_mir_built(|tcx, bodies| {
+            let bodies = sess.monomorphize(tcx, &bodies).unwrap();
+            let ir = codegen_string(tcx, &bodies);
+            let result = codegen_and_execute(tcx, &bodies).unwrap();
+
+            (ir, result)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn unit_return() {
+        let src = r"
+trait Area {
+    fn scale(&mut self, k: i32);
+}
+
+impl Area for i32 {
+    fn scale(&mut self, k: i32) {
+        *self = *self * k;
+    }
+}
+
+fn nothing() {}
+
+fn main() -> i32 {
+    let _unit = nothing();
+    let mut x: i32 = 3;
+    x.scale(2);
+    return x;
+}";
+        let (ir, result) = codegen(src);
+        assert!(ir.contains("define void @nothing()"));
+        assert!(ir.contains("define void @scale(i32* %0, i32 %1)"));
+        assert_eq!(result, 6);
+    }
+}
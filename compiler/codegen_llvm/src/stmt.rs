@@ -1,24 +1,38 @@
-use mir::stmt::*;
+use mir::{stmt::*, Body};
 
 use inkwell::{module::Module, values::*, IntPredicate};
 
 use crate::CodegenContext;
 
-impl<'ctx, 'a> CodegenContext<'ctx, 'a> {
+impl<'ctx, 'a, 'tcx> CodegenContext<'ctx, 'a, 'tcx> {
     pub(crate) fn codegen_stmt(
         &self,
         module: &Module<'ctx>,
         function: FunctionValue,
-        stmt: &Statement,
+        body: &Body<'tcx>,
+        stmt: &Statement<'tcx>,
     ) {
-        match stmt {
-            Statement::Assign(stmt) => {
+        match &stmt.kind {
+            StatementKind::Assign(stmt) => {
                 let (place, rvalue) = stmt.as_ref();
 
-                let place_ptr = self.local_values[place.local];
+                let place_ptr = self.pointer_value(place);
 
-                let value = match rvalue {
-                    RValue::Use(operand) => self.int_value(operand),
+                let value: BasicValueEnum = match rvalue {
+                    RValue::Use(operand) => self.basic_value(operand),
+                    RValue::Ref(_, place) | RValue::AddressOf(_, place) => {
+                        self.address_value(body, place)
+                    }
+                    RValue::StaticRef(def) => self.statics[def].as_pointer_value().into(),
+                    RValue::Cast(CastKind::Unsize, operand, ty) => {
+                        self.unsize_value(body, operand, *ty)
+                    }
+                    RValue::Cast(CastKind::ReifyFnPointer, operand, _) => {
+                        self.fn_ptr_value(operand)
+                    }
+                    RValue::Aggregate(kind, operands) => match kind.as_ref() {
+                        AggregateKind::Closure(ty) => self.closure_value(*ty, operands),
+                    },
                     RValue::BinaryOp(bin, operands) => {
                         let (lhs, rhs) = operands.as_ref();
                         let lhs_val = self.int_value(lhs);
@@ -67,7 +81,7 @@ impl<'ctx, 'a> CodegenContext<'ctx, 'a> {
                             ),
                         };
 
-                        result
+                        result.into()
                     }
                     RValue::UnaryOp(op, operand) => {
                         let operand_val = self.int_value(operand);
@@ -76,13 +90,13 @@ impl<'ctx, 'a> CodegenContext<'ctx, 'a> {
                             UnOp::Neg => self.builder.build_int_nsw_neg(operand_val, ""),
                         };
 
-                        result
+                        result.into()
                     }
                 };
 
                 self.builder.build_store(place_ptr, value);
             }
-            Statement::Println(operand) => {
+            StatementKind::Println(operand) => {
                 let operand_val = self.int_value(operand);
                 self.call_buildin_print(&module, function, operand_val);
             }
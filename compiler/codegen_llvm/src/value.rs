@@ -1,14 +1,16 @@
 use mir::{stmt::*, *};
 use ty;
 
-use inkwell::values::*;
+use inkwell::{types::BasicType, values::*, AddressSpace};
 
 use crate::CodegenContext;
 
-impl<'ctx, 'a> CodegenContext<'ctx, 'a> {
+use std::iter;
+
+impl<'ctx, 'a, 'tcx> CodegenContext<'ctx, 'a, 'tcx> {
     pub(crate) fn int_value(&self, operand: &Operand) -> IntValue {
         match operand {
-            Operand::Copy(place) => {
+            Operand::Copy(place) | Operand::Move(place) => {
                 let ptr = self.pointer_value(place);
                 self.builder.build_load(ptr, "").into_int_value()
             }
@@ -30,11 +32,21 @@ impl<'ctx, 'a> CodegenContext<'ctx, 'a> {
         }
     }
 
-    pub(crate) fn as_function_value(&self, operand: &Operand) -> FunctionValue {
+    /// Returns the function to call.
+    /// A place holds a pointer to the function, which is loaded and called indirectly.
+    pub(crate) fn as_function_value(&self, operand: &Operand) -> CallableValue {
         match operand {
-            Operand::Copy(_) => todo!(),
+            Operand::Copy(place) | Operand::Move(place) => {
+                let ptr = self.pointer_value(place);
+                let fn_ptr = self.builder.build_load(ptr, "").into_pointer_value();
+                CallableValue::try_from(fn_ptr).unwrap_or_else(|_| {
+                    panic!("Tried to call {:?}, which is not a function", place)
+                })
+            }
             Operand::Constant(constant) => match &constant.ty.kind() {
-                ty::TyKind::FnDef(def) => self.functions[def],
+                ty::TyKind::FnDef(def, substs) => {
+                    self.functions[&ty::Instance::new(*def, substs)].into()
+                }
                 _ => panic!(
                     "Tried to convert constant of {:?} to function value",
                     &constant
@@ -43,13 +55,169 @@ impl<'ctx, 'a> CodegenContext<'ctx, 'a> {
         }
     }
 
+    /// Returns the address of the function item, which is called through a function pointer.
+    pub(crate) fn fn_ptr_value(&self, operand: &Operand) -> BasicValueEnum {
+        match operand {
+            Operand::Constant(constant) => match constant.ty.kind() {
+                ty::TyKind::FnDef(def, substs) => self.functions[&ty::Instance::new(*def, substs)]
+                    .as_global_value()
+                    .as_pointer_value()
+                    .into(),
+                _ => panic!("Tried to convert {:?} to function pointer", &constant),
+            },
+            Operand::Copy(_) | Operand::Move(_) => {
+                panic!("Tried to convert {:?} to function pointer", operand)
+            }
+        }
+    }
+
+    /// Returns the position in the vtable if the operand is a trait method called on a trait object.
+    pub(crate) fn vtable_index(&self, operand: &Operand) -> Option<usize> {
+        match operand {
+            Operand::Constant(constant) => match constant.ty.kind() {
+                ty::TyKind::FnDef(def, substs) => self.tcx.traits.vtable_index(*def, substs),
+                _ => None,
+            },
+            Operand::Copy(_) | Operand::Move(_) => None,
+        }
+    }
+
+    /// Loads the method at `index` from the vtable of the receiver `args[0]`, a fat pointer.
+    /// Returns the method and the address of the receiver value, which is passed as `self`.
+    pub(crate) fn virtual_function(
+        &self,
+        body: &Body<'tcx>,
+        index: usize,
+        args: &[Operand<'tcx>],
+        ret_ty: ty::Ty<'tcx>,
+    ) -> (CallableValue, PointerValue) {
+        let receiver = self.basic_value(&args[0]).into_struct_value();
+        let data = self
+            .builder
+            .build_extract_value(receiver, 0, "")
+            .unwrap()
+            .into_pointer_value();
+        let vtable = self
+            .builder
+            .build_extract_value(receiver, 1, "")
+            .unwrap()
+            .into_pointer_value();
+
+        let index = self.context.i32_type().const_int(index as u64, false);
+        let slot = unsafe { self.builder.build_in_bounds_gep(vtable, &[index], "") };
+        let method = self.builder.build_load(slot, "").into_pointer_value();
+
+        // the method takes the untyped address in place of `self`.
+        let input_types: Vec<_> = iter::once(data.get_type().into())
+            .chain(
+                args[1..]
+                    .iter()
+                    .map(|arg| self.basic_meta_data_type(&arg.ty(body))),
+            )
+            .collect();
+        let fn_type = self.fn_type(input_types.as_slice(), &ret_ty);
+        let method =
+            self.builder
+                .build_pointer_cast(method, fn_type.ptr_type(AddressSpace::Generic), "");
+
+        (CallableValue::try_from(method).unwrap(), data)
+    }
+
+    /// Returns the address of the place.
+    /// A trait object is only reached through a fat pointer, which is the address itself.
+    pub(crate) fn address_value(&self, body: &Body<'tcx>, place: &Place) -> BasicValueEnum {
+        if place.ty(body).is_trait_object() {
+            let mut fat_ptr = place.clone();
+            fat_ptr.projection.pop();
+
+            return self.builder.build_load(self.pointer_value(&fat_ptr), "");
+        }
+
+        self.pointer_value(place).into()
+    }
+
+    /// Converts the pointer to a fat pointer to the trait object of type `ty`,
+    /// which holds the address of the value and the vtable of its type.
+    pub(crate) fn unsize_value(
+        &self,
+        body: &Body<'tcx>,
+        operand: &Operand,
+        ty: ty::Ty<'tcx>,
+    ) -> BasicValueEnum {
+        let self_ty = operand.ty(body).builtin_deref().unwrap().0;
+        let trait_def = match ty.builtin_deref().unwrap().0.kind() {
+            ty::TyKind::Dynamic(def) => *def,
+            _ => unreachable!(),
+        };
+        let data = self.basic_value(operand).into_pointer_value();
+        let vtable = self.vtables[&(trait_def, self_ty)];
+
+        let i8_ptr_type = self.context.i8_type().ptr_type(AddressSpace::Generic);
+        let data = self.builder.build_pointer_cast(data, i8_ptr_type, "");
+        let vtable = self.builder.build_pointer_cast(
+            vtable.as_pointer_value(),
+            i8_ptr_type.ptr_type(AddressSpace::Generic),
+            "",
+        );
+
+        let fat_ptr = self.fat_ptr_type().get_undef();
+        let fat_ptr = self
+            .builder
+            .build_insert_value(fat_ptr, data, 0, "")
+            .unwrap();
+        let fat_ptr = self
+            .builder
+            .build_insert_value(fat_ptr, vtable, 1, "")
+            .unwrap();
+
+        fat_ptr.into_struct_value().into()
+    }
+
+    pub(crate) fn basic_value(&self, operand: &Operand) -> BasicValueEnum {
+        match operand {
+            Operand::Copy(place) | Operand::Move(place) => {
+                let ptr = self.pointer_value(place);
+                self.builder.build_load(ptr, "")
+            }
+            Operand::Constant(constant) if constant.ty.is_zst() => {
+                self.context.const_struct(&[], false).into()
+            }
+            Operand::Constant(_) => self.int_value(operand).into(),
+        }
+    }
+
+    /// Returns the pointer to the place, loading through each deref projection.
     pub(crate) fn pointer_value(&self, place: &Place) -> PointerValue {
-        self.local_values[place.local]
+        place
+            .projection
+            .iter()
+            .fold(self.local_values[place.local], |ptr, elem| match elem {
+                PlaceElem::Deref => self.builder.build_load(ptr, "").into_pointer_value(),
+                PlaceElem::Field(idx) => self
+                    .builder
+                    .build_struct_gep(ptr, *idx as u32, "")
+                    .unwrap_or_else(|_| panic!("Tried to access field {} of {:?}", idx, place)),
+            })
+    }
+
+    /// Builds the environment of a closure from the values of the captured variables.
+    pub(crate) fn closure_value(&self, ty: ty::Ty<'tcx>, operands: &[Operand]) -> BasicValueEnum {
+        let mut env = self.basic_type(&ty).into_struct_type().get_undef();
+        for (idx, operand) in operands.iter().enumerate() {
+            let value = self.basic_value(operand);
+            env = self
+                .builder
+                .build_insert_value(env, value, idx as u32, "")
+                .unwrap()
+                .into_struct_value();
+        }
+
+        env.into()
     }
 
     pub(crate) fn basic_metadata_value(&self, operand: &Operand) -> BasicMetadataValueEnum {
         match operand {
-            Operand::Copy(place) => {
+            Operand::Copy(place) | Operand::Move(place) => {
                 let ptr = self.pointer_value(place);
                 self.builder.build_load(ptr, "").into()
             }
@@ -58,7 +226,18 @@ impl<'ctx, 'a> CodegenContext<'ctx, 'a> {
                     let ty::ConstLit::Scalar(scalar) = &constant.literal;
                     self.scalar_int(scalar).into()
                 }
-                ty::TyKind::Tuple(_) | ty::TyKind::FnDef(_) | ty::TyKind::Never => {
+                ty::TyKind::Tuple(_) if constant.ty.is_zst() => {
+                    self.context.const_struct(&[], false).into()
+                }
+                ty::TyKind::Tuple(_)
+                | ty::TyKind::FnDef(..)
+                | ty::TyKind::FnPtr(_)
+                | ty::TyKind::Closure(_)
+                | ty::TyKind::Param(_)
+                | ty::TyKind::Ref(..)
+                | ty::TyKind::RawPtr(..)
+                | ty::TyKind::Dynamic(_)
+                | ty::TyKind::Never => {
                     panic!(
                         "Tried to convert type of {:?} to BasicMetadataValueEnum",
                         &constant.ty
@@ -0,0 +1,679 @@
+//! Runs the phases of the compiler on a crate.
+//!
+//! A `Session` holds what lives for the whole compilation: the sources, the symbols, the AST
+//! and the reported warnings. The sources are in a `SourceMap` created beforehand,
+//! which the symbols borrow. `Session::run` lowers the crate phase by phase down to the
+//! monomorphized MIR, and calls the `Callbacks` with the output of each phase,
+//! which may print it, execute it or stop the compilation there.
+
+mod queries;
+
+pub use queries::MirQueries;
+
+use mir::{validate::validate, Body};
+use resolve::{resolve_items, unused::check_unused};
+use span::{source_map::SourceMap, SymbolMap};
+use ty::{TyArena, TyCtx};
+
+use anyhow::Result;
+use serde::Serialize;
+use std::cell::{Ref, RefCell};
+
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// Runs the optimization passes on the MIR.
+    pub optimize: bool,
+
+    /// Prints the warnings to stderr as they are reported, in addition to collecting them.
+    pub emit_warnings: bool,
+}
+
+/// Whether the compilation goes on after a callback.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Compilation {
+    Continue,
+    Stop,
+}
+
+/// Called by `Session::run` after each phase. All of them continue the compilation by default.
+pub trait Callbacks {
+    fn after_parsing(&mut self, _sess: &Session) -> Result<Compilation> {
+        Ok(Compilation::Continue)
+    }
+
+    fn after_hir_lowering(&mut self, _sess: &Session, _hir: &[hir::Item]) -> Result<Compilation> {
+        Ok(Compilation::Continue)
+    }
+
+    fn after_thir_lowering<'tcx>(
+        &mut self,
+        _sess: &Session,
+        _thir: &[thir::Item<'tcx>],
+    ) -> Result<Compilation> {
+        Ok(Compilation::Continue)
+    }
+
+    /// The bodies are validated, but not yet borrow checked.
+    fn after_mir_building<'tcx>(
+        &mut self,
+        _sess: &Session,
+        _tcx: &TyCtx<'_, 'tcx>,
+        _mir: &[Body<'tcx>],
+    ) -> Result<Compilation> {
+        Ok(Compilation::Continue)
+    }
+
+    /// Called with a body before and after each optimization pass: `before SimplifyCfg`
+    fn after_pass<'tcx>(&mut self, _sess: &Session, _pass: &str, _body: &Body<'tcx>) {}
+
+    /// The bodies are borrow checked, and optimized if `Options::optimize` is set.
+    fn after_optimization<'tcx>(
+        &mut self,
+        _sess: &Session,
+        _tcx: &TyCtx<'_, 'tcx>,
+        _mir: &[Body<'tcx>],
+    ) -> Result<Compilation> {
+        Ok(Compilation::Continue)
+    }
+
+    /// The bodies are ready for the interpreter or the code generation.
+    fn after_monomorphization<'tcx>(
+        &mut self,
+        _sess: &Session,
+        _tcx: &TyCtx<'_, 'tcx>,
+        _mir: &[Body<'tcx>],
+    ) -> Result<Compilation> {
+        Ok(Compilation::Continue)
+    }
+}
+
+/// Serializes the value as pretty-printed JSON, with the symbols as their strings in the map.
+pub fn to_json<T: Serialize>(symbol_map: &SymbolMap, value: &T) -> Result<String> {
+    let json = span::with_symbol_map(symbol_map, || serde_json::to_string_pretty(value))?;
+    Ok(json)
+}
+
+/// The warnings reported during a session.
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    emit: bool,
+    warnings: RefCell<Vec<String>>,
+}
+
+impl Diagnostics {
+    pub fn warn(&self, message: String) {
+        if self.emit {
+            eprintln!("warning: {}", message);
+        }
+        self.warnings.borrow_mut().push(message);
+    }
+
+    pub fn warnings(&self) -> Ref<'_, Vec<String>> {
+        self.warnings.borrow()
+    }
+}
+
+pub struct Session<'sm> {
+    pub options: Options,
+    pub source_map: &'sm SourceMap,
+    pub symbol_map: SymbolMap<'sm>,
+    pub diagnostics: Diagnostics,
+    ast: Vec<ast::Item>,
+}
+
+impl<'sm> Session<'sm> {
+    /// Parses the crate whose root module is in the file, adding its files to the source map.
+    pub fn new(source_map: &'sm SourceMap, filename: &str, options: Options) -> Result<Self> {
+        let src = std::fs::read_to_string(filename)?;
+        Session::from_source_str(source_map, filename, src, options)
+    }
+
+    /// Parses the crate whose root module is the source of the file,
+    /// whose modules are loaded from the files next to it.
+    pub fn from_source_str(
+        source_map: &'sm SourceMap,
+        filename: &str,
+        src: String,
+        options: Options,
+    ) -> Result<Self> {
+        let (ast, symbol_map) = parser::parse_crate_from_source_str(source_map, filename, src)?;
+        let diagnostics = Diagnostics {
+            emit: options.emit_warnings,
+            ..Diagnostics::default()
+        };
+
+        Ok(Session {
+            options,
+            source_map,
+            symbol_map,
+            diagnostics,
+            ast,
+        })
+    }
+
+    pub fn ast(&self) -> &[ast::Item] {
+        &self.ast
+    }
+
+    /// Resolves the names of the AST, reporting the unused ones as warnings, and lowers it to HIR.
+    pub fn lower_to_hir(&self) -> Result<Vec<hir::Item>> {
+        let res = resolve_items(&self.ast, &self.symbol_map)?;
+        for warning in check_unused(&self.ast, &res, &self.symbol_map) {
+            self.diagnostics.warn(warning.to_string());
+        }
+
+        Ok(ast_lowering::LoweringCtx::new(res).lower_items(&self.ast))
+    }
+
+    /// Calls `f` with a new type context, whose types live until `f` returns.
+    /// The names of the type parameters are displayed in `f`, like in the type errors.
+    pub fn enter<F, R>(&self, f: F) -> R
+    where
+        F: for<'a, 'tcx> FnOnce(TyCtx<'a, 'tcx>) -> R,
+    {
+        let arena = TyArena::new();
+        span::with_symbol_map(&self.symbol_map, || f(TyCtx::new(&arena, &self.symbol_map)))
+    }
+
+    /// Type checks the HIR and lowers it to THIR,
+    /// returning the context with the types of the items.
+    pub fn lower_to_thir<'a, 'tcx>(
+        &self,
+        tcx: TyCtx<'a, 'tcx>,
+        hir: &[hir::Item],
+    ) -> Result<(Vec<thir::Item<'tcx>>, TyCtx<'a, 'tcx>)> {
+        let mut hir_lowering_ctx = hir_lowering::HIRLoweringCtx::new(tcx);
+        let thir = hir_lowering_ctx.lower_items(hir)?;
+        Ok((thir, hir_lowering_ctx.finish()))
+    }
+
+    /// Returns the queries building the MIR of the functions in the THIR on demand,
+    /// which optimize it if `Options::optimize` is set.
+    pub fn mir_queries<'a, 'tcx>(
+        &self,
+        tcx: &'a TyCtx<'a, 'tcx>,
+        thir: Vec<thir::Item<'tcx>>,
+    ) -> MirQueries<'a, 'tcx> {
+        MirQueries::new(tcx, thir, self.options.optimize)
+    }
+
+    /// Lowers the crate down to MIR, and calls `f` with the type context and the bodies of
+    /// the functions, which are validated but not yet borrow checked.
+    pub fn with_mir_built<F, R>(&self, f: F) -> Result<R>
+    where
+        F: for<'a, 'tcx> FnOnce(&TyCtx<'a, 'tcx>, Vec<Body<'tcx>>) -> R,
+    {
+        let hir = self.lower_to_hir()?;
+        self.enter(|tcx| {
+            let (thir, tcx) = self.lower_to_thir(tcx, &hir)?;
+            let queries = self.mir_queries(&tcx, thir);
+            let mir = queries
+                .defs()
+                .iter()
+                .map(|def| queries.mir_built(*def))
+                .collect::<Result<Vec<_>>>()?;
+
+            Ok(f(&tcx, mir))
+        })
+    }
+
+    /// Instantiates the generic functions used by the bodies, and validates the result.
+    pub fn monomorphize<'tcx>(
+        &self,
+        tcx: &TyCtx<'_, 'tcx>,
+        mir: &[Body<'tcx>],
+    ) -> Result<Vec<Body<'tcx>>> {
+        let mir = monomorphize::monomorphize(tcx, mir);
+        for body in &mir {
+            validate(tcx, body)?;
+        }
+
+        Ok(mir)
+    }
+
+    /// Runs the phases in order until a callback stops the compilation.
+    pub fn run(&self, callbacks: &mut dyn Callbacks) -> Result<()> {
+        if callbacks.after_parsing(self)? == Compilation::Stop {
+            return Ok(());
+        }
+
+        let hir = self.lower_to_hir()?;
+        if callbacks.after_hir_lowering(self, &hir)? == Compilation::Stop {
+            return Ok(());
+        }
+
+        self.enter(|tcx| {
+            let (thir, tcx) = self.lower_to_thir(tcx, &hir)?;
+            if callbacks.after_thir_lowering(self, &thir)? == Compilation::Stop {
+                return Ok(());
+            }
+
+            let queries = self.mir_queries(&tcx, thir);
+            let mir = queries
+                .defs()
+                .iter()
+                .map(|def| queries.mir_built(*def))
+                .collect::<Result<Vec<_>>>()?;
+            if callbacks.after_mir_building(self, &tcx, &mir)? == Compilation::Stop {
+                return Ok(());
+            }
+
+            let mut dump = |pass: &str, body: &Body<'_>| callbacks.after_pass(self, pass, body);
+            let mir = queries
+                .defs()
+                .iter()
+                .map(|def| queries.optimized_mir(*def, &mut dump))
+                .collect::<Result<Vec<_>>>()?;
+            if callbacks.after_optimization(self, &tcx, &mir)? == Compilation::Stop {
+                return Ok(());
+            }
+
+            let mir = self.monomorphize(&tcx, &mir)?;
+            callbacks.after_monomorphization(self, &tcx, &mir)?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use mir_interpret::interpret_with_output;
+    use serde_json::{json, Value};
+
+    /// Records the phases reached, and runs the program with the interpreter at the end.
+    #[derive(Default)]
+    struct Recorder {
+        stop_after: Option<&'static str>,
+        phases: Vec<&'static str>,
+        passes: usize,
+        output: String,
+    }
+
+    impl Recorder {
+        fn reach(&mut self, phase: &'static str) -> Result<Compilation> {
+            self.phases.push(phase);
+            if self.stop_after == Some(phase) {
+                Ok(Compilation::Stop)
+            } else {
+                Ok(Compilation::Continue)
+            }
+        }
+    }
+
+    impl Callbacks for Recorder {
+        fn after_parsing(&mut self, _sess: &Session) -> Result<Compilation> {
+            self.reach("parsing")
+        }
+
+        fn after_hir_lowering(
+            &mut self,
+            _sess: &Session,
+            _hir: &[hir::Item],
+        ) -> Result<Compilation> {
+            self.reach("hir")
+        }
+
+        fn after_thir_lowering<'tcx>(
+            &mut self,
+            _sess: &Session,
+            _thir: &[thir::Item<'tcx>],
+        ) -> Result<Compilation> {
+            self.reach("thir")
+        }
+
+        fn after_mir_building<'tcx>(
+            &mut self,
+            _sess: &Session,
+            _tcx: &TyCtx<'_, 'tcx>,
+            _mir: &[Body<'tcx>],
+        ) -> Result<Compilation> {
+            self.reach("mir")
+        }
+
+        fn after_pass<'tcx>(&mut self, _sess: &Session, _pass: &str, _body: &Body<'tcx>) {
+            self.passes += 1;
+        }
+
+        fn after_optimization<'tcx>(
+            &mut self,
+            _sess: &Session,
+            _tcx: &TyCtx<'_, 'tcx>,
+            _mir: &[Body<'tcx>],
+        ) -> Result<Compilation> {
+            self.reach("optimization")
+        }
+
+        fn after_monomorphization<'tcx>(
+            &mut self,
+            _sess: &Session,
+            tcx: &TyCtx<'_, 'tcx>,
+            mir: &[Body<'tcx>],
+        ) -> Result<Compilation> {
+            let mut output = Vec::new();
+            interpret_with_output(tcx, mir, &mut output)?;
+            self.output = String::from_utf8(output)?;
+            self.reach("monomorphization")
+        }
+    }
+
+    fn session<'sm>(source_map: &'sm SourceMap, src: &str, options: Options) -> Session<'sm> {
+        Session::from_source_str(source_map, "main.elaina", src.to_string(), options).unwrap()
+    }
+
+    /// Compiles and runs the program, returning the message of the error it fails with.
+    fn run(src: &str) -> Result<(), String> {
+        let source_map = SourceMap::new();
+        let sess = session(&source_map, src, Options::default());
+        sess.run(&mut Recorder::default())
+            .map_err(|err| err.to_string())
+    }
+
+    const PROGRAM: &str = "
+fn id<T>(x: T) -> T { x }
+fn main() {
+    let unused = 1;
+    println(id(2) + 3);
+}";
+
+    #[test]
+    fn run_all_phases() {
+        let source_map = SourceMap::new();
+        let sess = session(&source_map, PROGRAM, Options::default());
+        let mut recorder = Recorder::default();
+        sess.run(&mut recorder).unwrap();
+
+        assert_eq!(
+            recorder.phases,
+            vec![
+                "parsing",
+                "hir",
+                "thir",
+                "mir",
+                "optimization",
+                "monomorphization"
+            ]
+        );
+        assert_eq!(recorder.passes, 0);
+        assert_eq!(recorder.output, "5\n");
+    }
+
+    #[test]
+    fn stop_after_phase() {
+        let source_map = SourceMap::new();
+        let sess = session(&source_map, PROGRAM, Options::default());
+        let mut recorder = Recorder {
+            stop_after: Some("thir"),
+            ..Recorder::default()
+        };
+        sess.run(&mut recorder).unwrap();
+
+        assert_eq!(recorder.phases, vec!["parsing", "hir", "thir"]);
+        assert_eq!(recorder.output, "");
+    }
+
+    #[test]
+    fn optimize() {
+        let options = Options {
+            optimize: true,
+            ..Options::default()
+        };
+        let source_map = SourceMap::new();
+        let sess = session(&source_map, PROGRAM, options);
+        let mut recorder = Recorder::default();
+        sess.run(&mut recorder).unwrap();
+
+        assert!(recorder.passes > 0);
+        assert_eq!(recorder.output, "5\n");
+    }
+
+    #[test]
+    fn collect_warnings() {
+        let source_map = SourceMap::new();
+        let sess = session(&source_map, PROGRAM, Options::default());
+        sess.run(&mut Recorder::default()).unwrap();
+
+        let warnings = sess.diagnostics.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("unused"), "{}", warnings[0]);
+    }
+
+    #[test]
+    fn memoize_optimized_mir() {
+        let options = Options {
+            optimize: true,
+            ..Options::default()
+        };
+        let source_map = SourceMap::new();
+        let sess = session(&source_map, PROGRAM, options);
+        let hir = sess.lower_to_hir().unwrap();
+        sess.enter(|tcx| {
+            let (thir, tcx) = sess.lower_to_thir(tcx, &hir).unwrap();
+            let queries = sess.mir_queries(&tcx, thir);
+            let main = queries.defs()[1];
+
+            let mut passes = 0;
+            let body = queries
+                .optimized_mir(main, &mut |_, _| passes += 1)
+                .unwrap();
+            assert!(passes > 0);
+
+            let memoized = queries
+                .optimized_mir(main, &mut |pass, _| panic!("`{}` runs again", pass))
+                .unwrap();
+            assert_eq!(body, memoized);
+        });
+    }
+
+    #[test]
+    fn const_cycle() {
+        let src = "
+const A: i32 = B + 1;
+const B: i32 = if true { A } else { 0 };
+fn main() {}";
+        let source_map = SourceMap::new();
+        let sess = session(&source_map, src, Options::default());
+        let err = sess.run(&mut Recorder::default()).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "cycle detected when evaluating the constant `A`: `A` -> `B` -> `A`"
+        );
+    }
+
+    #[test]
+    fn const_eval_error() {
+        assert_eq!(
+            run("const A: i32 = 1 / (B - 2); const B: i32 = 2; fn main() {}").unwrap_err(),
+            "evaluation of the constant `A` failed: attempt to divide by zero"
+        );
+        assert_eq!(
+            run("static S: i32 = 2147483647 + 1; fn main() {}").unwrap_err(),
+            "evaluation of the constant `S` failed: attempt to add with overflow"
+        );
+        assert_eq!(
+            run("const A: bool = 1; fn main() {}").unwrap_err(),
+            "mismatched types. expected `bool` found `i32`"
+        );
+    }
+
+    #[test]
+    fn mismatched_types() {
+        assert_eq!(
+            run("fn f() -> i32 { true } fn main() {}").unwrap_err(),
+            "mismatched types. expected `i32` found `bool`"
+        );
+        assert_eq!(
+            run("fn main() { let x: bool = 1; }").unwrap_err(),
+            "mismatched types. expected `bool` found `i32`"
+        );
+        // a diverging tail is accepted for any return type.
+        assert!(run("fn f() -> i32 { return 1 } fn main() { println(f()); }").is_ok());
+        let src = "
+fn f(c: bool) -> i32 {
+    if c {
+        return 1;
+    } else {
+        return 2;
+    }
+}
+fn g(c: bool) -> i32 { if c { return 3; } else { 4 } }
+fn main() { println(f(true) + g(false)); }";
+        assert!(run(src).is_ok());
+        assert_eq!(
+            run("fn f(c: bool) -> i32 { if c { return 1; } } fn main() {}").unwrap_err(),
+            "mismatched types. expected `i32` found `()`"
+        );
+    }
+
+    #[test]
+    fn type_errors() {
+        assert_eq!(
+            run("fn f<T>(a: T, b: T) {} fn main() { f(1, true); }").unwrap_err(),
+            "mismatched types. expected `i32` found `bool`"
+        );
+        assert_eq!(
+            run("trait T {} fn f<U: T>(u: U) {} fn g<V>(v: V) { f(v); } fn main() {}").unwrap_err(),
+            "the trait bound `V: T` is not satisfied"
+        );
+        assert_eq!(
+            run("fn f<T>() {} fn main() { f(); }").unwrap_err(),
+            "type annotations needed: cannot infer type for type parameter `T`"
+        );
+        assert_eq!(
+            run("fn f(a: i32) {} fn main() { f(1, 2); }").unwrap_err(),
+            "this function takes 1 arguments but 2 argument was supplied"
+        );
+        assert_eq!(
+            run("fn main() { let f = |x: i32| -> bool { x }; }").unwrap_err(),
+            "mismatched types. expected `bool` found `i32`"
+        );
+        assert_eq!(
+            run("trait T { fn f(&self); } fn main() { 1.f(); }").unwrap_err(),
+            "no method named `f` found for type `i32`"
+        );
+        assert_eq!(
+            run("trait T { fn f(&mut self); } impl T for i32 { fn f(&mut self) {} } fn main() { let x = 1; x.f(); }").unwrap_err(),
+            "cannot borrow `x` as mutable, as it is not declared as mutable"
+        );
+    }
+
+    fn to_value<T: Serialize>(symbol_map: &SymbolMap, value: &T) -> Result<Value> {
+        Ok(serde_json::from_str(&to_json(symbol_map, value)?)?)
+    }
+
+    /// Records the HIR, the THIR and the MIR as JSON, and stops once the MIR is built.
+    #[derive(Default)]
+    struct JsonRecorder {
+        hir: Value,
+        thir: Value,
+        mir: Value,
+    }
+
+    impl Callbacks for JsonRecorder {
+        fn after_hir_lowering(&mut self, sess: &Session, hir: &[hir::Item]) -> Result<Compilation> {
+            self.hir = to_value(&sess.symbol_map, &hir)?;
+            Ok(Compilation::Continue)
+        }
+
+        fn after_thir_lowering<'tcx>(
+            &mut self,
+            sess: &Session,
+            thir: &[thir::Item<'tcx>],
+        ) -> Result<Compilation> {
+            self.thir = to_value(&sess.symbol_map, &thir)?;
+            Ok(Compilation::Continue)
+        }
+
+        fn after_mir_building<'tcx>(
+            &mut self,
+            sess: &Session,
+            _tcx: &TyCtx<'_, 'tcx>,
+            mir: &[Body<'tcx>],
+        ) -> Result<Compilation> {
+            self.mir = to_value(&sess.symbol_map, &mir)?;
+            Ok(Compilation::Stop)
+        }
+    }
+
+    const ADD: &str = "fn add(a: i32, b: i32) -> i32 { a + b }";
+
+    fn json_of_phases(src: &str) -> JsonRecorder {
+        let source_map = SourceMap::new();
+        let sess = session(&source_map, src, Options::default());
+        let mut recorder = JsonRecorder::default();
+        sess.run(&mut recorder).unwrap();
+        recorder
+    }
+
+    #[test]
+    fn tokens_json() {
+        let tokens = parser::lexer::parse_all_token("let a = 1;");
+        let json = to_value(&tokens.map, &tokens.tokens).unwrap();
+
+        assert_eq!(json[1]["kind"], json!({ "Ident": "a" }));
+        assert_eq!(json[1]["span"], json!({ "lo": 4, "hi": 5 }));
+        assert_eq!(
+            json[3]["kind"],
+            json!({ "Literal": { "kind": "Integer", "symbol": "1" } })
+        );
+    }
+
+    #[test]
+    fn ast_json() {
+        let source_map = SourceMap::new();
+        let sess = session(&source_map, ADD, Options::default());
+        let json = to_value(&sess.symbol_map, &sess.ast()).unwrap();
+
+        assert_eq!(json[0]["ident"]["name"], "add");
+        let inputs = &json[0]["kind"]["Fn"]["inputs"];
+        assert_eq!(inputs[1]["ident"]["name"], "b");
+        assert_eq!(
+            inputs[1]["ty"]["kind"]["Path"]["segments"][0]["name"],
+            "i32"
+        );
+    }
+
+    #[test]
+    fn hir_json() {
+        let json = json_of_phases(ADD).hir;
+
+        assert_eq!(json[0]["name"], "add");
+        assert_eq!(json[0]["res"]["kind"], "Fn");
+        assert_eq!(json[0]["kind"]["Fn"]["inputs"][0]["res"]["kind"], "Local");
+    }
+
+    #[test]
+    fn thir_json() {
+        let json = json_of_phases(ADD).thir;
+
+        let header = &json[0]["kind"]["Fn"]["header"];
+        assert_eq!(header["name"], "add");
+        assert_eq!(header["inputs"][1]["name"], "b");
+        assert_eq!(header["inputs"][1]["ty"], "i32");
+        assert_eq!(header["output"], "i32");
+    }
+
+    #[test]
+    fn generic_thir_json() {
+        let src = "
+fn id<T>(x: T) -> T { x }
+fn twice<U>(u: U) -> U { id(u) }
+fn main() { println(twice(1)); }";
+        let json = json_of_phases(src).thir;
+
+        assert_eq!(json[0]["kind"]["Fn"]["header"]["output"], "T");
+        let call = &json[1]["kind"]["Fn"]["body"]["expr"]["Call"];
+        assert_eq!(call["fun"]["VarRef"]["ty"], "FnDef(%2<U>)");
+    }
+
+    #[test]
+    fn mir_json() {
+        let json = json_of_phases(ADD).mir;
+
+        assert_eq!(json[0]["name"], "add");
+        let assign = &json[0]["blocks"][0]["stmts"][0]["kind"]["Assign"];
+        assert_eq!(assign[1]["BinaryOp"][0], "Add");
+    }
+}
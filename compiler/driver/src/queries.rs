@@ -0,0 +1,130 @@
+use borrowck::borrowck;
+use mir::{validate::validate, Body};
+use mir_transform::Inline;
+use ty::{query::Query, res::DefId, TyCtx};
+
+use anyhow::Result;
+use std::cell::OnceCell;
+use std::collections::HashMap;
+
+/// The MIR of the functions and the closures of the crate, built and optimized on demand.
+pub struct MirQueries<'a, 'tcx> {
+    tcx: &'a TyCtx<'a, 'tcx>,
+    optimize: bool,
+
+    /// The functions and the closures, in the order of their items.
+    defs: Vec<DefId>,
+    thir: HashMap<DefId, thir::Fn<'tcx>>,
+
+    mir_built: Query<Body<'tcx>>,
+
+    /// The bodies after the borrow checker and the cleanup passes, which the inliner takes.
+    mir_cleaned: Query<Body<'tcx>>,
+
+    optimized_mir: Query<Body<'tcx>>,
+
+    /// The inliner, created from the cleaned up bodies of all functions on the first demand.
+    inline: OnceCell<Inline<'tcx>>,
+}
+
+impl<'a, 'tcx> MirQueries<'a, 'tcx> {
+    pub fn new(tcx: &'a TyCtx<'a, 'tcx>, thir: Vec<thir::Item<'tcx>>, optimize: bool) -> Self {
+        let mut defs = Vec::new();
+        let mut fns = HashMap::new();
+        for item in thir {
+            match item.kind {
+                thir::ItemKind::Fn(fun) => {
+                    defs.push(fun.header.def);
+                    fns.insert(fun.header.def, *fun);
+                }
+            }
+        }
+
+        MirQueries {
+            tcx,
+            optimize,
+            defs,
+            thir: fns,
+            mir_built: Query::new("mir_built"),
+            mir_cleaned: Query::new("mir_cleaned"),
+            optimized_mir: Query::new("optimized_mir"),
+            inline: OnceCell::new(),
+        }
+    }
+
+    pub fn defs(&self) -> &[DefId] {
+        &self.defs
+    }
+
+    /// Builds and validates the MIR of the function.
+    pub fn mir_built(&self, def: DefId) -> Result<Body<'tcx>> {
+        if let Some(body) = self.mir_built.start(def)? {
+            return Ok(body);
+        }
+
+        let fun = &self.thir[&def];
+        let mut ctx = thir_lowering::LoweringCtx::new(def, fun.header.name, self.tcx);
+        ctx.lower_item_fun(&fun.header, &fun.body);
+        let body = ctx.build();
+        validate(self.tcx, &body)?;
+
+        Ok(self.mir_built.complete(def, body))
+    }
+
+    /// Returns the MIR of the function once it passes the borrow checker,
+    /// after the optimization passes if they are enabled.
+    /// `dump` is called with a body before and after each pass run to answer the query.
+    pub fn optimized_mir<F>(&self, def: DefId, dump: &mut F) -> Result<Body<'tcx>>
+    where
+        F: FnMut(&str, &Body<'tcx>),
+    {
+        if let Some(body) = self.optimized_mir.start(def)? {
+            return Ok(body);
+        }
+
+        let body = if self.optimize {
+            let mut body = self.mir_cleaned(def, dump)?;
+            let inline = self.inliner(dump)?;
+            mir_transform::inline_callees(self.tcx, inline, &mut body, dump)?;
+            body
+        } else {
+            let body = self.mir_built(def)?;
+            borrowck(&body)?;
+            body
+        };
+
+        Ok(self.optimized_mir.complete(def, body))
+    }
+
+    fn mir_cleaned<F>(&self, def: DefId, dump: &mut F) -> Result<Body<'tcx>>
+    where
+        F: FnMut(&str, &Body<'tcx>),
+    {
+        if let Some(body) = self.mir_cleaned.start(def)? {
+            return Ok(body);
+        }
+
+        // the passes rely on what the borrow checker guarantees.
+        let mut body = self.mir_built(def)?;
+        borrowck(&body)?;
+        mir_transform::clean_up(self.tcx, &mut body, dump)?;
+
+        Ok(self.mir_cleaned.complete(def, body))
+    }
+
+    fn inliner<F>(&self, dump: &mut F) -> Result<&Inline<'tcx>>
+    where
+        F: FnMut(&str, &Body<'tcx>),
+    {
+        if self.inline.get().is_none() {
+            let bodies = self
+                .defs
+                .iter()
+                .map(|def| self.mir_cleaned(*def, dump))
+                .collect::<Result<Vec<_>>>()?;
+            let _ = self.inline.set(Inline::new(&bodies));
+        }
+
+        Ok(self.inline.get().unwrap())
+    }
+}
@@ -4,63 +4,184 @@ use ::ty::res::{DefId, Res};
 
 use ast::{
     op::{BinOp, UnOp},
-    ty,
+    CaptureBy, InlineAttr, Mutability,
 };
+use serde::Serialize;
 use span::*;
 
-#[derive(Debug, PartialEq, Clone)]
+/// Identifies a node of the HIR by the item which owns it and the position in the item.
+/// The ids in an item do not change when the other items change.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize)]
+pub struct HirId {
+    pub owner: DefId,
+    pub local_id: ItemLocalId,
+}
+
+/// The position of a node in its owner. The owner item itself is `0`.
+pub type ItemLocalId = u32;
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct Item {
+    pub hir_id: HirId,
     pub res: Res,
     pub name: Symbol,
+
+    /// The span of the name of the item.
+    pub span: Span,
     pub kind: ItemKind,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub enum ItemKind {
     Fn(Box<Fn>),
+    Trait(Box<Trait>),
+    Impl(Box<Impl>),
+
+    /// A constant item: `const N: i32 = 4 * 8;`
+    Const(Box<Const>),
+
+    /// A static item: `static mut COUNTER: i32 = 0;`
+    Static(Box<Static>),
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Const {
+    pub ty: Ty,
+    pub body: Expr,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Static {
+    pub mutbl: Mutability,
+    pub ty: Ty,
+    pub body: Expr,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct Fn {
+    pub inline: InlineAttr,
+    pub generics: Generics,
     pub inputs: Vec<Param>,
-    pub output: Option<ast::ty::Ty>,
+    pub output: Option<Ty>,
     pub body: Block,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Default, Serialize)]
+pub struct Generics {
+    pub params: Vec<GenericParam>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct GenericParam {
+    pub hir_id: HirId,
+    pub res: Res,
+    pub name: Symbol,
+
+    /// The traits that the type parameter is bound by.
+    pub bounds: Vec<Res>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Trait {
+    pub items: Vec<TraitItem>,
+}
+
+/// A method signature in a trait: `fn foo(&self) -> i32;`
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct TraitItem {
+    pub hir_id: HirId,
+    pub res: Res,
+    pub name: Symbol,
+    pub inputs: Vec<Param>,
+    pub output: Option<Ty>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Impl {
+    /// The implemented trait, or `None` for an inherent impl.
+    pub of_trait: Option<Res>,
+    pub self_ty: Ty,
+
+    /// Methods of the impl. These are always function items.
+    pub items: Vec<Item>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct Param {
+    pub hir_id: HirId,
     pub res: Res,
     pub name: Symbol,
-    pub ty: ast::ty::Ty,
+    pub mutbl: Mutability,
+    pub ty: Ty,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct Path {
+    pub hir_id: HirId,
     pub res: Res,
+    pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// A type whose names are resolved.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Ty {
+    pub kind: TyKind,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub enum TyKind {
+    /// A primitive type or a type parameter: `i32`, `T`
+    Path { res: Res, name: Symbol },
+
+    /// `Self` in a trait or an impl
+    SelfTy,
+
+    /// A reference type: `&T`, `&mut T`
+    Ref(Mutability, Box<Ty>),
+
+    /// A raw pointer type: `*const T`, `*mut T`
+    Ptr(Mutability, Box<Ty>),
+
+    /// A trait object type: `dyn Trait`
+    TraitObject { res: Res, name: Symbol },
+
+    /// A function pointer type: `fn(i32) -> bool`
+    BareFn(Box<BareFnTy>),
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct BareFnTy {
+    pub inputs: Vec<Ty>,
+    pub output: Option<Ty>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct Block {
     pub stmts: Vec<Stmt>,
     pub expr: Option<Expr>,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct Pat {
+    pub hir_id: HirId,
     pub kind: PatKind,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub enum PatKind {
-    Binding { res: Res, name: Symbol },
+    Binding {
+        res: Res,
+        name: Symbol,
+        mutbl: Mutability,
+    },
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub enum Stmt {
     /// Local represents a let statement: `let <ident> = <expr>;`
     Local {
         pat: Pat,
-        ty: Option<ast::ty::Ty>,
+        ty: Option<Ty>,
         init: Expr,
     },
 
@@ -75,11 +196,19 @@ pub enum Stmt {
     Println(Expr),
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub enum Expr {
     /// A function call: `foo(a, b)`
     Call { fun: Box<Expr>, args: Vec<Expr> },
 
+    /// A method call: `x.foo(a, b)`
+    MethodCall {
+        receiver: Box<Expr>,
+        method: Symbol,
+        span: Span,
+        args: Vec<Expr>,
+    },
+
     /// A binary operation: `a + b`, "a * b"
     Binary {
         op: BinOp,
@@ -87,9 +216,12 @@ pub enum Expr {
         rhs: Box<Expr>,
     },
 
-    /// A unary operation: `-x`
+    /// A unary operation: `-x`, `*x`
     Unary { op: UnOp, expr: Box<Expr> },
 
+    /// A referencing operation: `&a`, `&mut a`
+    AddrOf { mutbl: Mutability, expr: Box<Expr> },
+
     /// An if expression: `if <cond> { <then> } else { <else_opt> }`
     If {
         cond: Box<Expr>,
@@ -116,12 +248,39 @@ pub enum Expr {
     Assign { lhs: Box<Expr>, rhs: Box<Expr> },
 
     /// A literal in place of an expression: `1`
-    Lit { lit: Lit },
+    Lit { lit: Lit, span: Span },
 
     /// A path such as variables, functions, etx: `foo`, `bar`
     Path { path: Path },
+
+    /// A closure expression: `|x: i32| x + a`, `move || a`
+    Closure(Box<Closure>),
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Closure {
+    pub hir_id: HirId,
+    pub res: Res,
+    pub capture_by: CaptureBy,
+    pub inputs: Vec<Param>,
+    pub output: Option<Ty>,
+    pub body: Expr,
+
+    /// The locals of the enclosing functions used in the body, in order of first use.
+    pub upvars: Vec<Upvar>,
+}
+
+/// A local of an enclosing function captured by a closure.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Upvar {
+    pub res: Res,
+    pub name: Symbol,
+
+    /// The first use of the local in the closure.
+    pub span: Span,
 }
 
+pub const PREC_CLOSURE: i8 = -40;
 pub const PREC_JUMP: i8 = -30;
 
 // The range 2..=14 is reserved for AssocOp binary operator precedences.
@@ -136,17 +295,18 @@ impl Expr {
     pub fn precedence(&self) -> i8 {
         use Expr::*;
         match self {
+            Closure(_) => PREC_CLOSURE,
             Break { .. } | Continue { .. } | Return { .. } => PREC_JUMP,
             Binary { op, .. } => op.precedence() as i8,
             Assign { .. } => PREC_ASSIGN,
-            Unary { .. } => PREC_PREFIX,
-            Call { .. } => PREC_POSTFIX,
+            Unary { .. } | AddrOf { .. } => PREC_PREFIX,
+            Call { .. } | MethodCall { .. } => PREC_POSTFIX,
             Lit { .. } | Path { .. } | If { .. } | Loop { .. } | Block { .. } => PREC_PAREN,
         }
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub enum Lit {
     /// A boolean literal: `true`, `false`
     Bool { value: bool },
@@ -155,7 +315,7 @@ pub enum Lit {
     Int(LitInt),
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct LitInt {
     pub value: u128,
 }
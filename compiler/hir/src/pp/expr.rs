@@ -1,7 +1,10 @@
 use super::HIRPrinter;
 
 use crate::*;
-use ast::op::{BinOp, Fixity, UnOp};
+use ast::{
+    op::{BinOp, Fixity, UnOp},
+    CaptureBy, Mutability,
+};
 use printer::{Delim, Printer};
 
 impl HIRPrinter<'_> {
@@ -10,12 +13,23 @@ impl HIRPrinter<'_> {
             Expr::Call { fun, args } => {
                 self.print_expr_call(fun, args.as_slice());
             }
+            Expr::MethodCall {
+                receiver,
+                method,
+                args,
+                ..
+            } => {
+                self.print_expr_method_call(receiver, *method, args.as_slice());
+            }
             Expr::Binary { op, lhs, rhs } => {
                 self.print_expr_binary(op, lhs, rhs);
             }
             Expr::Unary { op, expr } => {
                 self.print_expr_unary(op, expr);
             }
+            Expr::AddrOf { mutbl, expr } => {
+                self.print_expr_addr_of(*mutbl, expr);
+            }
             Expr::If {
                 cond,
                 then,
@@ -63,7 +77,38 @@ impl HIRPrinter<'_> {
             Expr::Path { path } => {
                 self.print_def(&path.res.def);
             }
+            Expr::Closure(closure) => self.print_expr_closure(closure),
+        }
+    }
+
+    fn print_expr_closure(&mut self, closure: &Closure) {
+        if closure.capture_by == CaptureBy::Value {
+            self.print_space("move");
+        }
+
+        // print closure args: |arg1:ty1, arg2:ty2, ..|
+        self.print("|");
+        self.separated(
+            closure.inputs.iter(),
+            |this| {
+                this.comma();
+                this.space();
+            },
+            |this, param| {
+                this.print(param.mutbl.prefix_str());
+                this.print_ident(param.res, param.name);
+                this.colon();
+                this.print_ty(&param.ty);
+            },
+        );
+        self.print("|");
+
+        if let Some(output) = &closure.output {
+            self.space_print_space("->");
+            self.print_ty(output);
         }
+        self.space();
+        self.print_expr(&closure.body);
     }
 
     fn print_lit(&mut self, lit: &Lit) {
@@ -107,6 +152,12 @@ impl HIRPrinter<'_> {
         self.print_expr_maybe_paren(expr, crate::PREC_PREFIX);
     }
 
+    fn print_expr_addr_of(&mut self, mutbl: Mutability, expr: &Expr) {
+        self.print("&");
+        self.print(mutbl.prefix_str());
+        self.print_expr_maybe_paren(expr, crate::PREC_PREFIX);
+    }
+
     fn print_expr_if(&mut self, cond: &Expr, then: &Block, else_opt: Option<&Expr>) {
         self.print_space("if");
         self.print_expr_cond_paren(cond, false);
@@ -146,4 +197,13 @@ impl HIRPrinter<'_> {
             this.print_expr(arg);
         });
     }
+
+    fn print_expr_method_call(&mut self, receiver: &Expr, method: Symbol, args: &[Expr]) {
+        self.print_expr_maybe_paren(receiver, crate::PREC_POSTFIX);
+        self.dot();
+        self.print(self.map.get(method));
+        self.list(args.iter(), Delim::Paren, |this, arg| {
+            this.print_expr(arg);
+        });
+    }
 }
@@ -1,7 +1,6 @@
 use super::HIRPrinter;
 use crate::*;
 
-use ast::ty::Ty;
 use printer::{Delim, Printer};
 
 impl HIRPrinter<'_> {
@@ -20,37 +19,116 @@ impl HIRPrinter<'_> {
 
     pub fn print_item(&mut self, item: &Item) {
         match &item.kind {
-            ItemKind::Fn(fun) => {
-                self.print_item_fn(item.res, item.name, &fun.inputs, &fun.output, &fun.body)
+            ItemKind::Fn(fun) => self.print_item_fn(
+                item.res,
+                item.name,
+                &fun.generics,
+                &fun.inputs,
+                &fun.output,
+                &fun.body,
+            ),
+            ItemKind::Trait(tr) => self.print_item_trait(item.res, item.name, tr),
+            ItemKind::Impl(imp) => self.print_item_impl(imp),
+            ItemKind::Const(c) => {
+                self.print_space("const");
+                self.print_item_const(item.res, item.name, &c.ty, &c.body);
             }
+            ItemKind::Static(s) => {
+                self.print_space("static");
+                self.print(s.mutbl.prefix_str());
+                self.print_item_const(item.res, item.name, &s.ty, &s.body);
+            }
+        }
+    }
+
+    /// print the rest of a constant or a static: `N: i32 = 4 * 8;`
+    fn print_item_const(&mut self, res: Res, name: Symbol, ty: &Ty, body: &Expr) {
+        self.print_ident(res, name);
+        self.colon();
+        self.print_ty(ty);
+        self.space();
+        self.eq();
+        self.space();
+        self.print_expr(body);
+        self.semi();
+    }
+
+    fn print_item_trait(&mut self, res: Res, name: Symbol, tr: &Trait) {
+        self.print_space("trait");
+        self.print_ident(res, name);
+        self.space();
+        self.with_delim(Delim::Brace, true, |this| {
+            this.lines(tr.items.iter(), |this, item| {
+                this.print_space("fn");
+                this.print_ident(item.res, item.name);
+                this.space();
+                this.print_params(&item.inputs);
+                this.print_output(&item.output);
+                this.semi();
+            });
+        });
+    }
+
+    fn print_item_impl(&mut self, imp: &Impl) {
+        self.print_space("impl");
+        if let Some(res) = &imp.of_trait {
+            self.print_def(&res.def);
+            self.space_print_space("for");
         }
+        self.print_ty(&imp.self_ty);
+        self.space();
+        self.with_delim(Delim::Brace, true, |this| {
+            this.print_items(&imp.items);
+        });
     }
 
     pub fn print_item_fn(
         &mut self,
         res: Res,
         name: Symbol,
+        generics: &Generics,
         inputs: &Vec<Param>,
         output: &Option<Ty>,
         body: &Block,
     ) {
         self.print_space("fn");
         self.print_ident(res, name);
+        if !generics.params.is_empty() {
+            self.list(generics.params.iter(), Delim::Angle, |this, param| {
+                this.print(this.map.get(param.name));
+                if !param.bounds.is_empty() {
+                    this.colon();
+                    this.separated(
+                        param.bounds.iter(),
+                        |this| this.space_print_space("+"),
+                        |this, res| {
+                            this.print_def(&res.def);
+                        },
+                    );
+                }
+            });
+        }
+        self.space();
+        self.print_params(inputs);
+        self.print_output(output);
         self.space();
+        self.print_block(body);
+    }
 
-        // print fn args: (arg1:ty1, arg2:ty2, ..)
+    /// print fn args: (arg1:ty1, arg2:ty2, ..)
+    fn print_params(&mut self, inputs: &[Param]) {
         self.list(inputs.iter(), Delim::Paren, |this, param| {
+            this.print(param.mutbl.prefix_str());
             this.print_ident(param.res, param.name);
             this.colon();
             this.print_ty(&param.ty);
         });
+    }
 
+    fn print_output(&mut self, output: &Option<Ty>) {
         if let Some(output) = &output {
             self.space_print_space("->");
             self.print_ty(output);
         }
-
-        self.space();
-        self.print_block(body);
     }
 }
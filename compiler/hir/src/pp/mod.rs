@@ -57,7 +57,8 @@ impl<'a> HIRPrinter<'a> {
 
     fn print_pat(&mut self, pat: &Pat) {
         match pat.kind {
-            PatKind::Binding { res, name } => {
+            PatKind::Binding { res, name, mutbl } => {
+                self.print(mutbl.prefix_str());
                 self.print_ident(res, name);
             }
         }
@@ -76,11 +77,36 @@ impl<'a> HIRPrinter<'a> {
         self.print(def.to_string());
     }
 
-    fn print_ty(&mut self, ty: &ty::Ty) {
+    fn print_ty(&mut self, ty: &Ty) {
         match &ty.kind {
-            ty::TyKind::Path(path) => {
-                let name = self.map.get(path.ident.name);
-                self.print(name);
+            TyKind::Path { name, .. } => self.print(self.map.get(*name)),
+            TyKind::SelfTy => self.print("Self"),
+            TyKind::Ref(mutbl, ty) => {
+                self.print("&");
+                self.print(mutbl.prefix_str());
+                self.print_ty(ty);
+            }
+            TyKind::Ptr(mutbl, ty) => {
+                self.print("*");
+                match mutbl {
+                    ast::Mutability::Mut => self.print_space("mut"),
+                    ast::Mutability::Not => self.print_space("const"),
+                }
+                self.print_ty(ty);
+            }
+            TyKind::TraitObject { name, .. } => {
+                self.print_space("dyn");
+                self.print(self.map.get(*name));
+            }
+            TyKind::BareFn(bare_fn) => {
+                self.print("fn");
+                self.list(bare_fn.inputs.iter(), Delim::Paren, |this, input| {
+                    this.print_ty(input);
+                });
+                if let Some(output) = &bare_fn.output {
+                    self.space_print_space("->");
+                    self.print_ty(output);
+                }
             }
         }
     }
@@ -25,7 +25,7 @@ impl HIRPrinter<'_> {
         }
     }
 
-    fn print_local(&mut self, pat: &Pat, ty: &Option<ty::Ty>, init: &Expr) {
+    fn print_local(&mut self, pat: &Pat, ty: &Option<Ty>, init: &Expr) {
         self.print_space("let");
         self.print_pat(pat);
 
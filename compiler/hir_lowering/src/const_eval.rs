@@ -0,0 +1,146 @@
+use crate::error::ConstEvalError;
+
+use ast::op::{BinOp, UnOp};
+use thir::*;
+use ty::{Const, ConstLit, ConstValue, ScalarInt, TyCtx, TyKind};
+
+/// A value computed at compile time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Value {
+    Int(i32),
+    Bool(bool),
+}
+
+impl Value {
+    fn ty(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "i32",
+            Value::Bool(_) => "bool",
+        }
+    }
+}
+
+/// Evaluates the initializer of a constant or a static.
+/// The initializer may contain literals, other constants, arithmetic and comparison operators,
+/// `if` expressions and blocks without statements.
+pub fn eval_const<'tcx>(
+    tcx: &TyCtx<'_, 'tcx>,
+    expr: &Expr<'tcx>,
+) -> Result<Const<'tcx>, ConstEvalError> {
+    let value = match eval(expr)? {
+        Value::Int(value) => tcx.intern_const(ConstValue {
+            ty: expr.ty(),
+            literal: ConstLit::Scalar(ScalarInt::from_i32(value)),
+        }),
+        Value::Bool(true) => tcx.common_consts.true_,
+        Value::Bool(false) => tcx.common_consts.false_,
+    };
+
+    Ok(value)
+}
+
+fn eval(expr: &Expr) -> Result<Value, ConstEvalError> {
+    let value = match expr {
+        Expr::Lit { lit, .. } => match lit {
+            Lit::Bool { value } => Value::Bool(*value),
+            Lit::Int(lit) => match i32::try_from(lit.value) {
+                Ok(value) => Value::Int(value),
+                Err(_) => return Err(ConstEvalError::LitOutOfRange { value: lit.value }),
+            },
+        },
+        Expr::NamedConst { value, .. } => {
+            let ConstLit::Scalar(scalar) = &value.literal;
+            match value.ty.kind() {
+                TyKind::Bool => Value::Bool(scalar.data != 0),
+                _ => Value::Int(scalar.to_i32()),
+            }
+        }
+        Expr::Binary { op, lhs, rhs, .. } => eval_binary(*op, eval(lhs)?, eval(rhs)?)?,
+        Expr::Unary { op, expr, .. } => match (op, eval(expr)?) {
+            (UnOp::Neg, Value::Int(value)) => {
+                Value::Int(value.checked_neg().ok_or_else(|| overflow("negate"))?)
+            }
+            (op, value) => {
+                return Err(ConstEvalError::UnaryOp {
+                    op: op.to_string(),
+                    ty: value.ty(),
+                })
+            }
+        },
+        Expr::If {
+            cond,
+            then,
+            else_opt,
+            ..
+        } => match eval(cond)? {
+            Value::Bool(true) => eval_block(then)?,
+            Value::Bool(false) => match else_opt {
+                Some(else_expr) => eval(else_expr)?,
+                None => return Err(ConstEvalError::IfWithoutElse),
+            },
+            value => {
+                return Err(ConstEvalError::MismatchedTypes {
+                    expected: "bool",
+                    found: value.ty(),
+                })
+            }
+        },
+        Expr::Block { block } => eval_block(block)?,
+        Expr::Deref { expr, .. } if matches!(expr.as_ref(), Expr::StaticRef { .. }) => {
+            return Err(ConstEvalError::StaticRef)
+        }
+        _ => return Err(ConstEvalError::Unsupported),
+    };
+
+    Ok(value)
+}
+
+fn eval_block(block: &Block) -> Result<Value, ConstEvalError> {
+    if !block.stmts.is_empty() {
+        return Err(ConstEvalError::Stmt);
+    }
+    match &block.expr {
+        Some(expr) => eval(expr),
+        None => Err(ConstEvalError::BlockWithoutValue),
+    }
+}
+
+fn eval_binary(op: BinOp, lhs: Value, rhs: Value) -> Result<Value, ConstEvalError> {
+    let value = match (lhs, rhs) {
+        (Value::Int(lhs), Value::Int(rhs)) => match op {
+            BinOp::Add => Value::Int(lhs.checked_add(rhs).ok_or_else(|| overflow("add"))?),
+            BinOp::Sub => Value::Int(lhs.checked_sub(rhs).ok_or_else(|| overflow("subtract"))?),
+            BinOp::Mul => Value::Int(lhs.checked_mul(rhs).ok_or_else(|| overflow("multiply"))?),
+            BinOp::Div if rhs == 0 => return Err(ConstEvalError::DivideByZero),
+            BinOp::Div => Value::Int(lhs.checked_div(rhs).ok_or_else(|| overflow("divide"))?),
+            BinOp::Eq => Value::Bool(lhs == rhs),
+            BinOp::Lt => Value::Bool(lhs < rhs),
+            BinOp::Le => Value::Bool(lhs <= rhs),
+            BinOp::Ne => Value::Bool(lhs != rhs),
+            BinOp::Ge => Value::Bool(lhs >= rhs),
+            BinOp::Gt => Value::Bool(lhs > rhs),
+        },
+        (Value::Bool(lhs), Value::Bool(rhs)) => match op {
+            BinOp::Eq => Value::Bool(lhs == rhs),
+            BinOp::Ne => Value::Bool(lhs != rhs),
+            _ => {
+                return Err(ConstEvalError::BinaryOp {
+                    op: op.to_string(),
+                    ty: "bool",
+                })
+            }
+        },
+        (lhs, rhs) => {
+            return Err(ConstEvalError::MismatchedTypes {
+                expected: lhs.ty(),
+                found: rhs.ty(),
+            })
+        }
+    };
+
+    Ok(value)
+}
+
+fn overflow(op: &'static str) -> ConstEvalError {
+    ConstEvalError::Overflow { op }
+}
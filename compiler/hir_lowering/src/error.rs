@@ -0,0 +1,109 @@
+use thiserror::Error;
+use ty::query::CycleError;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum LoweringError {
+    #[error("cycle detected when evaluating the constant `{name}`: {cycle}")]
+    ConstCycle { name: String, cycle: String },
+
+    #[error(transparent)]
+    Cycle(#[from] CycleError),
+
+    #[error("evaluation of the constant `{name}` failed: {err}")]
+    ConstEval { name: String, err: ConstEvalError },
+
+    #[error("mismatched types. expected `{expected}` found `{found}`")]
+    MismatchedTypes { expected: String, found: String },
+
+    #[error("type annotations needed: cannot infer type for type parameter `{param}`")]
+    TypeAnnotationsNeeded { param: String },
+
+    #[error("the type `{name}` is not supported yet")]
+    UnsupportedTy { name: String },
+
+    #[error("the type `{ty}` of `{name}` is not supported in constants and statics yet")]
+    UnsupportedConstTy { ty: String, name: String },
+
+    #[error("`Self` is only available in traits and impls")]
+    SelfOutsideImpl,
+
+    #[error("generic methods are not supported in trait impls")]
+    GenericTraitImplMethod,
+
+    #[error("duplicate definitions with name `{name}`")]
+    DuplicateDefinition { name: String },
+
+    #[error("conflicting implementations of trait `{trait_name}` for type `{ty}`")]
+    ConflictingImpls { trait_name: String, ty: String },
+
+    #[error("method `{method}` is not a member of trait `{trait_name}`")]
+    NotTraitMember { method: String, trait_name: String },
+
+    #[error("method `{method}` has an incompatible type for trait `{trait_name}`")]
+    IncompatibleMethod { method: String, trait_name: String },
+
+    #[error("not all trait items implemented, missing: `{name}`")]
+    MissingTraitItem { name: String },
+
+    #[error("the trait bound `{ty}: {trait_name}` is not satisfied")]
+    UnsatisfiedTraitBound { ty: String, trait_name: String },
+
+    #[error("the trait `{trait_name}` cannot be made into an object")]
+    NotObjectSafe { trait_name: String },
+
+    #[error("no method named `{method}` found for type `{ty}`")]
+    NoMethod { method: String, ty: String },
+
+    #[error("multiple applicable items in scope: method `{method}` for type `{ty}`")]
+    AmbiguousMethod { method: String, ty: String },
+
+    #[error("cannot borrow `{name}` as mutable, as it is not declared as mutable")]
+    BorrowImmutableLocal { name: String },
+
+    #[error("cannot borrow data behind `{ty}` as mutable")]
+    BorrowBehindShared { ty: String },
+
+    #[error("cannot assign through `{ty}`, which is not a mutable reference or pointer")]
+    AssignThroughShared { ty: String },
+
+    #[error("type `{ty}` cannot be dereferenced")]
+    CannotDeref { ty: String },
+
+    #[error("expected function, found `{ty}`")]
+    NotCallable { ty: String },
+
+    #[error("this function takes {expected} arguments but {supplied} argument was supplied")]
+    ArgCount { expected: usize, supplied: usize },
+
+    #[error("the size for values of type `{ty}` cannot be known at compilation time")]
+    Unsized { ty: String },
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ConstEvalError {
+    #[error("literal `{value}` out of range for `i32`")]
+    LitOutOfRange { value: u128 },
+    #[error("attempt to {op} with overflow")]
+    Overflow { op: &'static str },
+    #[error("attempt to divide by zero")]
+    DivideByZero,
+    #[error("cannot apply unary operator `{op}` to `{ty}`")]
+    UnaryOp { op: String, ty: &'static str },
+    #[error("cannot apply binary operator `{op}` to `{ty}`")]
+    BinaryOp { op: String, ty: &'static str },
+    #[error("mismatched types. expected `{expected}` found `{found}`")]
+    MismatchedTypes {
+        expected: &'static str,
+        found: &'static str,
+    },
+    #[error("`if` without `else` has no value")]
+    IfWithoutElse,
+    #[error("constants cannot refer to statics")]
+    StaticRef,
+    #[error("statements are not allowed in constants")]
+    Stmt,
+    #[error("a block without a value cannot be evaluated")]
+    BlockWithoutValue,
+    #[error("this expression cannot be evaluated in a constant")]
+    Unsupported,
+}
@@ -1,21 +1,71 @@
+mod const_eval;
+pub mod error;
+
+use error::LoweringError;
+
 use ast::op::{BinOp, UnOp};
 use hir;
 use span::*;
 use thir::*;
-use ty::{res::*, *};
+use ty::{
+    query::{CycleError, Query},
+    res::*,
+    *,
+};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::iter;
 
 pub struct HIRLoweringCtx<'ast, 'tcx> {
     tcx: TyCtx<'ast, 'tcx>,
-    fn_headers: HashMap<DefId, FnHeader<'tcx>>,
+
+    /// The declarations of the functions and the methods, whose signatures are lowered on demand.
+    fn_decls: HashMap<DefId, FnDecl<'tcx>>,
+
+    /// Type parameters of the function being lowered.
+    ty_params: HashMap<DefId, Ty<'tcx>>,
+
+    /// Name and type parameters of the function being lowered, which its closures share.
+    fn_ident: Option<Ident>,
+    generics: Vec<ParamTy>,
+
+    /// Trait bounds on the type parameters of the function being lowered.
+    predicates: Vec<TraitRef<'tcx>>,
+
+    /// The `Self` type of the impl being lowered.
+    self_ty: Option<Ty<'tcx>>,
+
+    /// Names of the immutable locals in the function being lowered.
+    immutable_locals: HashMap<DefId, Symbol>,
+
+    /// The bodies of the closures, which are lowered to function items.
+    closure_items: Vec<Item<'tcx>>,
+
+    /// Names, types and initializers of the constants and the statics.
+    const_decls: HashMap<DefId, ConstDecl>,
+
+    /// The value of each constant and the initial value of each static, evaluated on demand.
+    const_value: Query<Const<'tcx>>,
+
+    /// Mutability of each static.
+    statics: HashMap<DefId, Mutability>,
 }
 
 impl<'ast, 'tcx> HIRLoweringCtx<'ast, 'tcx> {
     pub fn new(tcx: TyCtx<'ast, 'tcx>) -> HIRLoweringCtx<'ast, 'tcx> {
         HIRLoweringCtx {
             tcx,
-            fn_headers: HashMap::new(),
+            fn_decls: HashMap::new(),
+            ty_params: HashMap::new(),
+            fn_ident: None,
+            generics: Vec::new(),
+            predicates: Vec::new(),
+            self_ty: None,
+            immutable_locals: HashMap::new(),
+            closure_items: Vec::new(),
+            const_decls: HashMap::new(),
+            const_value: Query::new("const_value"),
+            statics: HashMap::new(),
         }
     }
 
@@ -23,80 +73,766 @@ impl<'ast, 'tcx> HIRLoweringCtx<'ast, 'tcx> {
         self.tcx
     }
 
-    fn insert_ty(&mut self, def: DefId, ty: Ty<'tcx>) {
-        self.tcx.def_map.insert(def, ty);
+    fn insert_ty(&self, def: DefId, ty: Ty<'tcx>) {
+        self.tcx.type_of.feed(def, ty);
     }
 
-    fn get_ty(&self, def: DefId) -> Ty<'tcx> {
-        self.tcx
-            .def_map
-            .get(&def)
-            .unwrap_or_else(|| {
-                panic!(
-                    "cannot found type of {:?}.\n defined types are:\n{:?}",
-                    def, self.tcx.def_map
-                )
-            })
-            .clone()
+    /// Returns the type of the definition. The types of the constants and the statics are lowered
+    /// on the first use, and the others are recorded when their definitions are lowered.
+    fn type_of(&self, def: DefId) -> Result<Ty<'tcx>, LoweringError> {
+        if let Some(ty) = self.tcx.type_of.start(def)? {
+            return Ok(ty);
+        }
+
+        let ty = self.lower_const_ty(def)?;
+        Ok(self.tcx.type_of.complete(def, ty))
+    }
+
+    /// Returns the signature of the function or the closure,
+    /// lowering it from the declaration of the function on the first use.
+    fn fn_sig(&self, def: DefId) -> Result<FnHeader<'tcx>, LoweringError> {
+        if let Some(header) = self.tcx.fn_sig.start(def)? {
+            return Ok(header);
+        }
+
+        let header = self.lower_fn_sig(def)?;
+        Ok(self.tcx.fn_sig.complete(def, header))
     }
 }
 
+/// The declaration of a function or a method, from which its signature is lowered.
+struct FnDecl<'tcx> {
+    ident: Ident,
+    generics: Vec<ParamTy>,
+    predicates: Vec<TraitRef<'tcx>>,
+    inline: InlineAttr,
+    inputs: Vec<hir::Param>,
+    output: Option<hir::Ty>,
+
+    /// The `Self` type of the trait or the impl declaring the method.
+    self_ty: Option<Ty<'tcx>>,
+}
+
+/// The declaration of a constant or a static.
+struct ConstDecl {
+    name: Symbol,
+    ty: hir::Ty,
+    body: hir::Expr,
+}
+
 impl<'ast, 'tcx> HIRLoweringCtx<'ast, 'tcx> {
-    pub fn lower_ty(&self, ty: &ast::Ty) -> Ty<'tcx> {
-        match &ty.kind {
-            ast::TyKind::Path(path) => self.tcx.common_type_from_name(path.ident.name),
+    pub fn lower_ty(&self, ty: &hir::Ty) -> Result<Ty<'tcx>, LoweringError> {
+        self.lower_ty_in(ty, &self.ty_params, self.self_ty)
+    }
+
+    /// Lowers the type with the type parameters and the `Self` type of an item
+    /// other than the one being lowered.
+    fn lower_ty_in(
+        &self,
+        ty: &hir::Ty,
+        ty_params: &HashMap<DefId, Ty<'tcx>>,
+        self_ty: Option<Ty<'tcx>>,
+    ) -> Result<Ty<'tcx>, LoweringError> {
+        let ty = match &ty.kind {
+            hir::TyKind::Path { res, name } => match res.kind {
+                ResKind::PrimTy(prim) => self.tcx.prim_ty(prim),
+                ResKind::TyParam => ty_params[&res.def],
+                _ => {
+                    return Err(LoweringError::UnsupportedTy {
+                        name: self.tcx.symbol_map.get(*name).to_string(),
+                    })
+                }
+            },
+            hir::TyKind::SelfTy => self_ty.ok_or(LoweringError::SelfOutsideImpl)?,
+            hir::TyKind::Ref(mutbl, ty) => {
+                let ty = self.lower_ty_in(ty, ty_params, self_ty)?;
+                self.tcx.intern_ref(ty, lower_mutability(*mutbl))
+            }
+            hir::TyKind::Ptr(mutbl, ty) => {
+                let ty = self.lower_ty_in(ty, ty_params, self_ty)?;
+                self.tcx.intern_ptr(ty, lower_mutability(*mutbl))
+            }
+            hir::TyKind::BareFn(bare_fn) => {
+                let inputs = bare_fn
+                    .inputs
+                    .iter()
+                    .map(|ty| self.lower_ty_in(ty, ty_params, self_ty))
+                    .collect::<Result<_, _>>()?;
+                let output = match &bare_fn.output {
+                    Some(ty) => self.lower_ty_in(ty, ty_params, self_ty)?,
+                    None => self.tcx.common_types.unit,
+                };
+                self.tcx.intern_fn_ptr(inputs, output)
+            }
+            hir::TyKind::TraitObject { res, .. } => self.tcx.intern_dynamic(res.def),
+        };
+
+        Ok(ty)
+    }
+
+    /// Brings the type parameters into scope for the types in the function.
+    fn set_ty_params(&mut self, generics: &[ParamTy]) {
+        self.ty_params = self.ty_params_of(generics);
+    }
+
+    fn ty_params_of(&self, generics: &[ParamTy]) -> HashMap<DefId, Ty<'tcx>> {
+        generics
+            .iter()
+            .map(|param| (param.def, self.tcx.intern(TyKind::Param(*param))))
+            .collect()
+    }
+
+    /// Returns the type parameters and their trait bounds.
+    fn lower_generics(&self, generics: &hir::Generics) -> (Vec<ParamTy>, Vec<TraitRef<'tcx>>) {
+        let mut params = Vec::new();
+        let mut predicates = Vec::new();
+        for (index, param) in generics.params.iter().enumerate() {
+            let param_ty = ParamTy {
+                index,
+                name: param.name,
+                def: param.res.def,
+            };
+            let self_ty = self.tcx.intern(TyKind::Param(param_ty));
+            predicates.extend(param.bounds.iter().map(|res| TraitRef {
+                def: res.def,
+                self_ty,
+            }));
+            params.push(param_ty);
         }
+
+        (params, predicates)
     }
 
-    pub fn lower_fun_header(
-        &mut self,
-        fn_def: DefId,
-        fn_name: Symbol,
-        hir_inputs: &Vec<hir::Param>,
-        hir_output: &Option<ast::ty::Ty>,
-    ) {
-        // insert function header.
-        let mut inputs = Vec::new();
-        for input in hir_inputs {
-            let ty = self.lower_ty(&input.ty);
+    /// Records the declaration of the function, whose signature is lowered on the first use.
+    fn declare_fn(&mut self, def: DefId, decl: FnDecl<'tcx>) {
+        self.fn_decls.insert(def, decl);
+        self.insert_ty(def, self.tcx.intern_fn_def(def, &[]));
+    }
+
+    /// Lowers the signature of the function, recording the types of its parameters.
+    fn lower_fn_sig(&self, def: DefId) -> Result<FnHeader<'tcx>, LoweringError> {
+        let decl = self
+            .fn_decls
+            .get(&def)
+            .unwrap_or_else(|| panic!("not found function header(defId:{}).", def));
+        let ty_params = self.ty_params_of(&decl.generics);
 
-            let param = Param {
+        let mut inputs = Vec::new();
+        for input in &decl.inputs {
+            let ty = self.lower_ty_in(&input.ty, &ty_params, decl.self_ty)?;
+            self.insert_ty(input.res.def, ty);
+            inputs.push(Param {
                 res: input.res,
                 name: input.name,
                 ty,
-            };
-
-            inputs.push(param);
+            });
         }
 
-        let output = match hir_output {
-            Some(ty) => self.lower_ty(ty),
+        let output = match &decl.output {
+            Some(ty) => self.lower_ty_in(ty, &ty_params, decl.self_ty)?,
             None => self.tcx.common_types.unit,
         };
 
-        let header = FnHeader {
-            def: fn_def,
-            name: fn_name,
+        Ok(FnHeader {
+            def,
+            name: decl.ident.name,
+            span: decl.ident.span,
+            generics: decl.generics.clone(),
+            predicates: decl.predicates.clone(),
+            inline: decl.inline,
             inputs,
             output,
+            upvars: Vec::new(),
+        })
+    }
+
+    /// Lowers the method signatures of the trait.
+    /// The trait methods take the `Self` type as their only type parameter.
+    fn lower_trait_header(&mut self, def: DefId, name: Symbol, tr: &hir::Trait) {
+        let self_param = ParamTy {
+            index: 0,
+            name: Kw::SelfUpper.into(),
+            def,
         };
+        let self_ty = self.tcx.intern(TyKind::Param(self_param));
+        let predicates = vec![TraitRef { def, self_ty }];
+
+        let mut methods = Vec::new();
+        for item in &tr.items {
+            let decl = FnDecl {
+                ident: Ident::with_dummy_span(item.name),
+                generics: vec![self_param],
+                predicates: predicates.clone(),
+                inline: InlineAttr::None,
+                inputs: item.inputs.clone(),
+                output: item.output.clone(),
+                self_ty: Some(self_ty),
+            };
+            self.declare_fn(item.res.def, decl);
+            methods.push((item.name, item.res.def));
+        }
+
+        self.tcx
+            .traits
+            .insert_trait(TraitDef { def, name, methods });
+    }
+
+    fn lower_impl_header(&mut self, imp: &hir::Impl) -> Result<(), LoweringError> {
+        self.set_ty_params(&[]);
+        let self_ty = self.lower_ty(&imp.self_ty)?;
+        let trait_def = imp.of_trait.map(|res| res.def);
+
+        let mut methods = Vec::new();
+        for item in &imp.items {
+            let fun = match &item.kind {
+                hir::ItemKind::Fn(fun) => fun,
+                hir::ItemKind::Trait(_)
+                | hir::ItemKind::Impl(_)
+                | hir::ItemKind::Const(_)
+                | hir::ItemKind::Static(_) => unreachable!(),
+            };
+            let (generics, predicates) = self.lower_generics(&fun.generics);
+            if trait_def.is_some() && !generics.is_empty() {
+                return Err(LoweringError::GenericTraitImplMethod);
+            }
+            if trait_def.is_none()
+                && self
+                    .tcx
+                    .traits
+                    .inherent_method(self_ty, item.name)
+                    .is_some()
+            {
+                return Err(LoweringError::DuplicateDefinition {
+                    name: self.tcx.symbol_map.get(item.name).to_string(),
+                });
+            }
+
+            let decl = FnDecl {
+                ident: Ident::new(item.name, item.span),
+                generics,
+                predicates,
+                inline: lower_inline(fun.inline),
+                inputs: fun.inputs.clone(),
+                output: fun.output.clone(),
+                self_ty: Some(self_ty),
+            };
+            self.declare_fn(item.res.def, decl);
+            methods.push((item.name, item.res.def));
+        }
+
+        self.tcx.traits.insert_impl(ImplDef {
+            trait_def,
+            self_ty,
+            methods,
+        });
+
+        Ok(())
+    }
+
+    /// Checks that each trait impl is the only impl of the trait for the type,
+    /// and that it implements exactly the methods of the trait with the same signatures.
+    fn check_impls(&self) -> Result<(), LoweringError> {
+        let impls = self.tcx.traits.impls();
+        for (i, imp) in impls.iter().enumerate() {
+            let trait_def = match imp.trait_def {
+                Some(def) => self.tcx.traits.trait_def(def),
+                None => continue,
+            };
+            let trait_name = self.tcx.symbol_map.get(trait_def.name);
 
-        self.fn_headers.insert(fn_def, header);
+            if impls[..i]
+                .iter()
+                .any(|other| other.trait_def == imp.trait_def && other.self_ty == imp.self_ty)
+            {
+                return Err(LoweringError::ConflictingImpls {
+                    trait_name: trait_name.to_string(),
+                    ty: imp.self_ty.to_string(),
+                });
+            }
+
+            let substs = self.tcx.intern_substs(vec![imp.self_ty]);
+            for (name, impl_method) in &imp.methods {
+                let method_name = self.tcx.symbol_map.get(*name);
+                let trait_method =
+                    trait_def
+                        .method(*name)
+                        .ok_or_else(|| LoweringError::NotTraitMember {
+                            method: method_name.to_string(),
+                            trait_name: trait_name.to_string(),
+                        })?;
 
-        // insert function definition type.
-        let fn_def_ty = self.tcx.intern(TyKind::FnDef(fn_def));
-        self.tcx.def_map.insert(fn_def, fn_def_ty);
+                let expected = self.fn_sig(trait_method)?;
+                let found = self.fn_sig(*impl_method)?;
+                let compatible =
+                    expected.inputs.len() == found.inputs.len()
+                        && expected.inputs.iter().zip(found.inputs.iter()).all(
+                            |(expected, found)| self.tcx.subst(expected.ty, substs) == found.ty,
+                        )
+                        && self.tcx.subst(expected.output, substs) == found.output;
+                if !compatible {
+                    return Err(LoweringError::IncompatibleMethod {
+                        method: method_name.to_string(),
+                        trait_name: trait_name.to_string(),
+                    });
+                }
+            }
 
-        // insert function parameter type.
-        for param in hir_inputs {
-            let ty = self.lower_ty(&param.ty);
-            self.tcx.def_map.insert(param.res.def, ty);
+            for (name, _) in &trait_def.methods {
+                if imp.method(*name).is_none() {
+                    return Err(LoweringError::MissingTraitItem {
+                        name: self.tcx.symbol_map.get(*name).to_string(),
+                    });
+                }
+            }
         }
+
+        Ok(())
     }
 }
 
 impl<'ast, 'tcx> HIRLoweringCtx<'ast, 'tcx> {
-    pub fn lower_lit(&self, lit: &hir::Lit) -> Expr<'tcx> {
+    /// Apply the implicit pointer coercions allowed at `let` initializers, assignments
+    /// and function arguments.
+    /// `&mut T` is reborrowed as `&T` or `&mut T` instead of being moved,
+    /// references are converted to raw pointers,
+    /// `&T` is unsized to `&dyn Trait` if `T` implements the trait,
+    /// and a function item is converted to a function pointer of the same signature.
+    fn coerce(&self, mut expr: Expr<'tcx>, target: Ty<'tcx>) -> Result<Expr<'tcx>, LoweringError> {
+        let source = expr.ty();
+        if source == target && (source.is_copy() || !expr.is_place()) {
+            return Ok(expr);
+        }
+
+        let expr = match (source.kind(), target.kind()) {
+            (TyKind::Ref(from, from_mutbl), TyKind::Ref(to, to_mutbl))
+                if !from.is_trait_object()
+                    && to.is_trait_object()
+                    && (*from_mutbl == Mutability::Mut || *to_mutbl == Mutability::Not) =>
+            {
+                let trait_def = match to.kind() {
+                    TyKind::Dynamic(def) => def,
+                    _ => unreachable!(),
+                };
+                self.check_object_safety(*trait_def)?;
+                if !self.implements(*trait_def, *from) {
+                    return Err(self.unsatisfied_bound(*from, *trait_def));
+                }
+
+                let expr = self.coerce(expr, self.tcx.intern_ref(*from, *to_mutbl))?;
+                Expr::Unsize {
+                    expr: Box::new(expr),
+                    ty: target,
+                }
+            }
+            (TyKind::Ref(from, Mutability::Mut), TyKind::Ref(to, to_mutbl)) if from == to => {
+                let place = Expr::Deref {
+                    expr: Box::new(expr),
+                    ty: *from,
+                };
+
+                Expr::Borrow {
+                    mutbl: *to_mutbl,
+                    expr: Box::new(place),
+                    ty: target,
+                }
+            }
+            (TyKind::Ref(from, from_mutbl), TyKind::RawPtr(to, to_mutbl))
+            | (TyKind::RawPtr(from, from_mutbl), TyKind::RawPtr(to, to_mutbl))
+                if from == to
+                    && (*from_mutbl == Mutability::Mut || *to_mutbl == Mutability::Not) =>
+            {
+                let place = Expr::Deref {
+                    expr: Box::new(expr),
+                    ty: *from,
+                };
+
+                Expr::AddressOf {
+                    mutbl: *to_mutbl,
+                    expr: Box::new(place),
+                    ty: target,
+                }
+            }
+            (TyKind::FnDef(def, substs), TyKind::FnPtr(sig)) => {
+                let header = self.fn_sig(*def)?;
+                let substs = if substs.is_empty() && !header.generics.is_empty() {
+                    // infer the type arguments of a generic function from the signature.
+                    let mut substs = vec![None; header.generics.len()];
+                    for (param, ty) in header.inputs.iter().zip(sig.inputs.iter()) {
+                        infer_ty(param.ty, *ty, &mut substs)?;
+                    }
+                    infer_ty(header.output, sig.output, &mut substs)?;
+                    self.unwrap_substs(&header, substs)?
+                } else {
+                    substs
+                };
+
+                let inputs: Vec<Ty> = header
+                    .inputs
+                    .iter()
+                    .map(|param| self.tcx.subst(param.ty, substs))
+                    .collect();
+                let output = self.tcx.subst(header.output, substs);
+                if inputs != sig.inputs || output != sig.output {
+                    return Ok(expr);
+                }
+                self.check_predicates(&header, substs)?;
+
+                if let Expr::VarRef { ty, .. } = &mut expr {
+                    *ty = self.tcx.intern_fn_def(*def, substs);
+                }
+                Expr::ReifyFnPointer {
+                    expr: Box::new(expr),
+                    ty: target,
+                }
+            }
+            _ => expr,
+        };
+
+        Ok(expr)
+    }
+
+    /// Infers the types substituted for the type parameters of the function from the arguments.
+    fn infer_substs(
+        &self,
+        header: &FnHeader<'tcx>,
+        args: &[Expr<'tcx>],
+    ) -> Result<SubstsRef<'tcx>, LoweringError> {
+        let mut substs = vec![None; header.generics.len()];
+        for (param, arg) in header.inputs.iter().zip(args) {
+            infer_ty(param.ty, arg.ty(), &mut substs)?;
+        }
+
+        self.unwrap_substs(header, substs)
+    }
+
+    /// Checks that every type parameter of the function is inferred.
+    fn unwrap_substs(
+        &self,
+        header: &FnHeader<'tcx>,
+        substs: Vec<Option<Ty<'tcx>>>,
+    ) -> Result<SubstsRef<'tcx>, LoweringError> {
+        let substs = substs
+            .into_iter()
+            .zip(header.generics.iter())
+            .map(|(ty, param)| {
+                ty.ok_or_else(|| LoweringError::TypeAnnotationsNeeded {
+                    param: self.tcx.symbol_map.get(param.name).to_string(),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(self.tcx.intern_substs(substs))
+    }
+
+    /// Checks that the type arguments satisfy the trait bounds of the function.
+    fn check_predicates(
+        &self,
+        header: &FnHeader<'tcx>,
+        substs: SubstsRef<'tcx>,
+    ) -> Result<(), LoweringError> {
+        for predicate in &header.predicates {
+            let self_ty = self.tcx.subst(predicate.self_ty, substs);
+            if !self.implements(predicate.def, self_ty) {
+                return Err(self.unsatisfied_bound(self_ty, predicate.def));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn unsatisfied_bound(&self, ty: Ty<'tcx>, trait_def: DefId) -> LoweringError {
+        let trait_def = self.tcx.traits.trait_def(trait_def);
+        LoweringError::UnsatisfiedTraitBound {
+            ty: ty.to_string(),
+            trait_name: self.tcx.symbol_map.get(trait_def.name).to_string(),
+        }
+    }
+
+    /// Returns `true` if the type implements the trait.
+    /// A type parameter implements the traits it is bound by,
+    /// and a trait object implements its trait.
+    fn implements(&self, trait_def: DefId, ty: Ty<'tcx>) -> bool {
+        match ty.kind() {
+            TyKind::Param(_) => self.predicates.contains(&TraitRef {
+                def: trait_def,
+                self_ty: ty,
+            }),
+            TyKind::Dynamic(def) => *def == trait_def,
+            _ => self.tcx.traits.find_impl(trait_def, ty).is_some(),
+        }
+    }
+
+    /// Checks that the trait can be made into a trait object.
+    /// Every method must take `&self` or `&mut self`, and must not use `Self` elsewhere,
+    /// so that it can be called through the vtable without knowing the `Self` type.
+    fn check_object_safety(&self, trait_def: DefId) -> Result<(), LoweringError> {
+        let trait_def = self.tcx.traits.trait_def(trait_def);
+        for (_, method) in &trait_def.methods {
+            let header = self.fn_sig(*method)?;
+            let by_ref = header.inputs.first().is_some_and(|param| {
+                param.name == Kw::SelfLower.into()
+                    && matches!(param.ty.kind(), TyKind::Ref(ty, _) if ty.has_params())
+            });
+            let object_safe = by_ref
+                && header.inputs[1..]
+                    .iter()
+                    .all(|param| !param.ty.has_params())
+                && !header.output.has_params();
+
+            if !object_safe {
+                return Err(LoweringError::NotObjectSafe {
+                    trait_name: self.tcx.symbol_map.get(trait_def.name).to_string(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Finds the method called on a receiver of type `receiver_ty`.
+    /// The receiver is dereferenced until a type having the method is found.
+    /// Returns the method, the types substituted for `Self` of a trait method
+    /// and the number of the dereferences.
+    fn lookup_method(
+        &self,
+        receiver_ty: Ty<'tcx>,
+        name: Symbol,
+    ) -> Result<(DefId, SubstsRef<'tcx>, usize), LoweringError> {
+        let mut ty = receiver_ty;
+        let mut autoderefs = 0;
+        loop {
+            if let Some((def, substs)) = self.probe_method(ty, name)? {
+                return Ok((def, substs, autoderefs));
+            }
+
+            match ty.kind() {
+                TyKind::Ref(inner, _) => {
+                    ty = *inner;
+                    autoderefs += 1;
+                }
+                _ => {
+                    return Err(LoweringError::NoMethod {
+                        method: self.tcx.symbol_map.get(name).to_string(),
+                        ty: receiver_ty.to_string(),
+                    })
+                }
+            }
+        }
+    }
+
+    /// Returns the method named `name` whose `Self` type is `self_ty`.
+    /// Methods in inherent impls take precedence over trait methods.
+    fn probe_method(
+        &self,
+        self_ty: Ty<'tcx>,
+        name: Symbol,
+    ) -> Result<Option<(DefId, SubstsRef<'tcx>)>, LoweringError> {
+        let traits = &self.tcx.traits;
+        if let Some(def) = traits.inherent_method(self_ty, name) {
+            if self.takes_self(def)? {
+                return Ok(Some((def, &[])));
+            }
+        }
+
+        let candidates: Vec<(DefId, SubstsRef)> = match self_ty.kind() {
+            TyKind::Param(_) => self
+                .predicates
+                .iter()
+                .filter(|predicate| predicate.self_ty == self_ty)
+                .filter_map(|predicate| traits.trait_def(predicate.def).method(name))
+                .map(|def| (def, self.tcx.intern_substs(vec![self_ty])))
+                .collect(),
+            TyKind::Dynamic(trait_def) => traits
+                .trait_def(*trait_def)
+                .method(name)
+                .map(|def| (def, self.tcx.intern_substs(vec![self_ty])))
+                .into_iter()
+                .collect(),
+            _ => traits
+                .trait_impl_methods(self_ty, name)
+                .into_iter()
+                .map(|def| (def, &[] as SubstsRef))
+                .collect(),
+        };
+        let mut methods = Vec::new();
+        for (def, substs) in candidates {
+            if self.takes_self(def)? && !methods.contains(&(def, substs)) {
+                methods.push((def, substs));
+            }
+        }
+
+        if methods.len() > 1 {
+            return Err(LoweringError::AmbiguousMethod {
+                method: self.tcx.symbol_map.get(name).to_string(),
+                ty: self_ty.to_string(),
+            });
+        }
+        Ok(methods.pop())
+    }
+
+    /// Returns `true` if the function takes `self` as the first parameter.
+    fn takes_self(&self, def: DefId) -> Result<bool, LoweringError> {
+        let header = self.fn_sig(def)?;
+        Ok(header
+            .inputs
+            .first()
+            .is_some_and(|param| param.name == Kw::SelfLower.into()))
+    }
+
+    /// Dereferences the receiver `autoderefs` times,
+    /// then borrows it if the method takes `&self` or `&mut self`.
+    fn adjust_receiver(
+        &self,
+        mut receiver: Expr<'tcx>,
+        autoderefs: usize,
+        self_ty: Ty<'tcx>,
+    ) -> Result<Expr<'tcx>, LoweringError> {
+        for _ in 0..autoderefs {
+            let (ty, _) = receiver.ty().builtin_deref().unwrap();
+            receiver = Expr::Deref {
+                expr: Box::new(receiver),
+                ty,
+            };
+        }
+
+        if receiver.ty() == self_ty {
+            return Ok(receiver);
+        }
+
+        match self_ty.kind() {
+            TyKind::Ref(ty, mutbl) if *ty == receiver.ty() => {
+                if *mutbl == Mutability::Mut {
+                    self.check_mutable_place(&receiver)?;
+                }
+
+                Ok(Expr::Borrow {
+                    mutbl: *mutbl,
+                    expr: Box::new(receiver),
+                    ty: self_ty,
+                })
+            }
+            _ => Err(mismatched_types(self_ty, receiver.ty())),
+        }
+    }
+
+    /// Checks that the place can be borrowed as mutable.
+    fn check_mutable_place(&self, place: &Expr<'tcx>) -> Result<(), LoweringError> {
+        match place {
+            Expr::VarRef { res, .. } => {
+                if let Some(name) = self.immutable_locals.get(&res.def) {
+                    return Err(LoweringError::BorrowImmutableLocal {
+                        name: self.tcx.symbol_map.get(*name).to_string(),
+                    });
+                }
+            }
+            Expr::Deref { expr, .. } => {
+                if let Some((_, Mutability::Not)) = expr.ty().builtin_deref() {
+                    return Err(LoweringError::BorrowBehindShared {
+                        ty: expr.ty().to_string(),
+                    });
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Type checks the call, inferring the type arguments of a generic function.
+    /// A function pointer is called with the types in its signature.
+    /// A closure is called directly, passing a reference to its environment as the first argument.
+    fn lower_call(
+        &mut self,
+        mut fun: Expr<'tcx>,
+        mut args: Vec<Expr<'tcx>>,
+    ) -> Result<Expr<'tcx>, LoweringError> {
+        let (inputs, output) = match *fun.ty() {
+            TyKind::FnDef(def, _) => {
+                let header = self.fn_sig(def)?;
+                check_arg_count(header.inputs.len(), args.len())?;
+
+                // substitute the inferred types for the type parameters of the function.
+                let substs = self.infer_substs(&header, &args)?;
+                let inputs: Vec<Ty> = header
+                    .inputs
+                    .iter()
+                    .map(|param| self.tcx.subst(param.ty, substs))
+                    .collect();
+                if let Expr::VarRef { ty, .. } = &mut fun {
+                    *ty = self.tcx.intern_fn_def(header.def, substs);
+                }
+                self.check_predicates(&header, substs)?;
+
+                (inputs, self.tcx.subst(header.output, substs))
+            }
+            TyKind::FnPtr(sig) => {
+                check_arg_count(sig.inputs.len(), args.len())?;
+                (sig.inputs.to_vec(), sig.output)
+            }
+            TyKind::Closure(closure) => {
+                let header = self.fn_sig(closure.def)?;
+                check_arg_count(header.inputs.len() - 1, args.len())?;
+
+                let inputs: Vec<Ty> = header
+                    .inputs
+                    .iter()
+                    .map(|param| self.tcx.subst(param.ty, closure.substs))
+                    .collect();
+                let output = self.tcx.subst(header.output, closure.substs);
+                let mutbl = match inputs[0].kind() {
+                    TyKind::Ref(_, mutbl) => *mutbl,
+                    _ => unreachable!(),
+                };
+                if mutbl == Mutability::Mut {
+                    self.check_mutable_place(&fun)?;
+                }
+
+                let span = fun.span().unwrap_or(DUMMY_SP);
+                let env = Expr::Borrow {
+                    mutbl,
+                    expr: Box::new(fun),
+                    ty: inputs[0],
+                };
+                args.insert(0, env);
+                fun = Expr::VarRef {
+                    res: Res {
+                        def: closure.def,
+                        kind: ResKind::Fn,
+                    },
+                    ty: self.tcx.intern_fn_def(closure.def, closure.substs),
+                    span,
+                };
+
+                (inputs, output)
+            }
+            _ => {
+                return Err(LoweringError::NotCallable {
+                    ty: fun.ty().to_string(),
+                })
+            }
+        };
+
+        let args: Vec<Expr> = args
+            .into_iter()
+            .zip(inputs.iter())
+            .map(|(arg, ty)| self.coerce(arg, *ty))
+            .collect::<Result<_, _>>()?;
+
+        for (supplied, taked) in args.iter().map(|arg| arg.ty()).zip(inputs.iter()) {
+            check_sized(supplied)?;
+            if supplied != *taked {
+                return Err(mismatched_types(*taked, supplied));
+            }
+        }
+
+        Ok(Expr::Call {
+            fun: Box::new(fun),
+            args,
+            ty: output,
+        })
+    }
+
+    pub fn lower_lit(&self, lit: &hir::Lit, span: Span) -> Expr<'tcx> {
         let ty = match &lit {
             hir::Lit::Bool { .. } => self.tcx.common_types.bool,
             hir::Lit::Int(_) => self.tcx.common_types.i32,
@@ -107,12 +843,12 @@ impl<'ast, 'tcx> HIRLoweringCtx<'ast, 'tcx> {
             hir::Lit::Int(lit) => Lit::Int(LitInt { value: lit.value }),
         };
 
-        Expr::Lit { lit, ty }
+        Expr::Lit { lit, ty, span }
     }
 
     pub fn lower_pat(&self, pat: &hir::Pat, ty: ty::Ty<'tcx>) -> Pat<'tcx> {
         let kind = match &pat.kind {
-            hir::PatKind::Binding { res, name } => PatKind::Binding {
+            hir::PatKind::Binding { res, name, .. } => PatKind::Binding {
                 res: *res,
                 name: *name,
                 ty,
@@ -124,54 +860,45 @@ impl<'ast, 'tcx> HIRLoweringCtx<'ast, 'tcx> {
         }
     }
 
-    pub fn lower_expr(&mut self, expr: &hir::Expr) -> Expr<'tcx> {
-        match expr {
+    pub fn lower_expr(&mut self, expr: &hir::Expr) -> Result<Expr<'tcx>, LoweringError> {
+        let expr = match expr {
             hir::Expr::Call { fun, args } => {
-                let fun = self.lower_expr(fun);
-                let args: Vec<Expr> = args.iter().map(|arg| self.lower_expr(arg)).collect();
-
-                match *fun.ty() {
-                    TyKind::FnDef(def) => {
-                        let header = self.fn_headers.get(&def).unwrap();
-                        if args.len() != header.inputs.len() {
-                            panic!(
-                                "this function takes {} arguments but {} argument was supplied.",
-                                header.inputs.len(),
-                                args.len()
-                            );
-                        }
+                let fun = self.lower_expr(fun)?;
+                let args: Vec<Expr> = args
+                    .iter()
+                    .map(|arg| self.lower_expr(arg))
+                    .collect::<Result<_, _>>()?;
 
-                        for (supplied, taked) in args
-                            .iter()
-                            .map(|arg| arg.ty())
-                            .zip(header.inputs.iter().map(|param| &param.ty))
-                        {
-                            if supplied != *taked {
-                                panic!(
-                                    "mismatched types. expected {:?} found {:?}",
-                                    taked.kind(),
-                                    supplied.kind()
-                                );
-                            }
-                        }
-                    }
-                    _ => panic!("Type of expression tried to call is not a function type."),
-                }
+                self.lower_call(fun, args)?
+            }
+            hir::Expr::MethodCall {
+                receiver,
+                method,
+                span,
+                args,
+            } => {
+                let receiver = self.lower_expr(receiver)?;
+                let (def, self_substs, autoderefs) = self.lookup_method(receiver.ty(), *method)?;
+                let self_ty = self.tcx.subst(self.fn_sig(def)?.inputs[0].ty, self_substs);
+                let receiver = self.adjust_receiver(receiver, autoderefs, self_ty)?;
 
-                let ty = match *fun.ty() {
-                    TyKind::FnDef(def) => self.fn_headers.get(&def).unwrap().output.clone(),
-                    _ => unreachable!(),
+                let fun = Expr::VarRef {
+                    res: Res {
+                        def,
+                        kind: ResKind::Fn,
+                    },
+                    ty: self.type_of(def)?,
+                    span: *span,
                 };
+                let args: Vec<Expr> = iter::once(Ok(receiver))
+                    .chain(args.iter().map(|arg| self.lower_expr(arg)))
+                    .collect::<Result<_, _>>()?;
 
-                Expr::Call {
-                    fun: Box::new(fun),
-                    args,
-                    ty,
-                }
+                self.lower_call(fun, args)?
             }
             hir::Expr::Binary { op, lhs, rhs } => {
-                let lhs = Box::new(self.lower_expr(lhs));
-                let rhs = Box::new(self.lower_expr(rhs));
+                let lhs = Box::new(self.lower_expr(lhs)?);
+                let rhs = Box::new(self.lower_expr(rhs)?);
 
                 let ty = match op {
                     BinOp::Mul | BinOp::Div | BinOp::Add | BinOp::Sub => self.tcx.common_types.i32,
@@ -187,22 +914,54 @@ impl<'ast, 'tcx> HIRLoweringCtx<'ast, 'tcx> {
                     ty,
                 }
             }
+            hir::Expr::Unary {
+                op: UnOp::Deref,
+                expr,
+            } => {
+                let expr = Box::new(self.lower_expr(expr)?);
+                let ty = match expr.ty().builtin_deref() {
+                    Some((ty, _)) => ty,
+                    None => {
+                        return Err(LoweringError::CannotDeref {
+                            ty: expr.ty().to_string(),
+                        })
+                    }
+                };
+
+                Expr::Deref { expr, ty }
+            }
             hir::Expr::Unary { op, expr } => {
-                let expr = Box::new(self.lower_expr(expr));
+                let expr = Box::new(self.lower_expr(expr)?);
                 let ty = match op {
                     UnOp::Neg => self.tcx.common_types.i32,
+                    UnOp::Deref => unreachable!(),
                 };
                 Expr::Unary { op: *op, expr, ty }
             }
+            hir::Expr::AddrOf { mutbl, expr } => {
+                let expr = Box::new(self.lower_expr(expr)?);
+                let mutbl = lower_mutability(*mutbl);
+                let ty = self.tcx.intern_ref(expr.ty(), mutbl);
+
+                Expr::Borrow { mutbl, expr, ty }
+            }
             hir::Expr::If {
                 cond,
                 then,
                 else_opt,
             } => {
-                let cond = Box::new(self.lower_expr(cond));
-                let then = Box::new(self.lower_block(then));
-                let else_opt = else_opt.as_ref().map(|e| Box::new(self.lower_expr(e)));
-                let ty = then.ty.clone();
+                let cond = Box::new(self.lower_expr(cond)?);
+                let then = Box::new(self.lower_block(then)?);
+                let else_opt = match else_opt {
+                    Some(e) => Some(Box::new(self.lower_expr(e)?)),
+                    None => None,
+                };
+                // a branch which diverges takes the type of the other branch.
+                let ty = match &else_opt {
+                    Some(else_expr) if then.ty.is_never() => else_expr.ty(),
+                    None if then.ty.is_never() => self.tcx.common_types.unit,
+                    _ => then.ty.clone(),
+                };
 
                 Expr::If {
                     cond,
@@ -212,120 +971,751 @@ impl<'ast, 'tcx> HIRLoweringCtx<'ast, 'tcx> {
                 }
             }
             hir::Expr::Loop { block } => {
-                let block = Box::new(self.lower_block(block));
+                let block = Box::new(self.lower_block(block)?);
 
                 Expr::Loop { block }
             }
             hir::Expr::Break { expr } => {
-                let expr = expr.as_ref().map(|e| Box::new(self.lower_expr(e)));
+                let expr = self.lower_expr_opt(expr)?;
                 let ty = self.tcx.common_types.never;
 
                 Expr::Break { expr, ty }
             }
             hir::Expr::Continue { expr } => {
-                let expr = expr.as_ref().map(|e| Box::new(self.lower_expr(e)));
+                let expr = self.lower_expr_opt(expr)?;
                 let ty = self.tcx.common_types.never;
 
                 Expr::Continue { expr, ty }
             }
             hir::Expr::Return { expr } => {
-                let expr = expr.as_ref().map(|e| Box::new(self.lower_expr(e)));
+                let expr = self.lower_expr_opt(expr)?;
                 let ty = self.tcx.common_types.never;
 
                 Expr::Return { expr, ty }
             }
             hir::Expr::Block { block } => {
-                let block = Box::new(self.lower_block(block));
+                let block = Box::new(self.lower_block(block)?);
 
                 Expr::Block { block }
             }
             hir::Expr::Assign { lhs, rhs } => {
-                let rhs = Box::new(self.lower_expr(rhs));
-                let lhs = Box::new(self.lower_expr(lhs));
+                let rhs = self.lower_expr(rhs)?;
+                let lhs = Box::new(self.lower_expr(lhs)?);
+                // a value of the same type is moved into the place rather than reborrowed.
+                let rhs = if rhs.ty() == lhs.ty() {
+                    rhs
+                } else {
+                    self.coerce(rhs, lhs.ty())?
+                };
+                let rhs = Box::new(rhs);
                 let ty = self.tcx.common_types.unit;
 
+                if let Expr::Deref { expr, .. } = lhs.as_ref() {
+                    if let Some((_, Mutability::Not)) = expr.ty().builtin_deref() {
+                        return Err(LoweringError::AssignThroughShared {
+                            ty: expr.ty().to_string(),
+                        });
+                    }
+                }
+
                 Expr::Assign { lhs, rhs, ty }
             }
-            hir::Expr::Lit { lit } => self.lower_lit(lit),
+            hir::Expr::Lit { lit, span } => self.lower_lit(lit, *span),
             hir::Expr::Path { path } => {
                 let def = path.res.def;
-                let ty = self.get_ty(def);
-                Expr::VarRef { res: path.res, ty }
+                match path.res.kind {
+                    ResKind::Const => Expr::NamedConst {
+                        def,
+                        value: self.const_value.get(def).unwrap_or_else(|| {
+                            panic!("the constant %{} is used before it is evaluated.", def)
+                        }),
+                        span: path.span,
+                    },
+                    ResKind::Static => self.lower_static_use(def, path.span)?,
+                    _ => Expr::VarRef {
+                        res: path.res,
+                        ty: self.type_of(def)?,
+                        span: path.span,
+                    },
+                }
+            }
+            hir::Expr::Closure(closure) => self.lower_closure(closure)?,
+        };
+
+        Ok(expr)
+    }
+
+    fn lower_expr_opt(
+        &mut self,
+        expr: &Option<Box<hir::Expr>>,
+    ) -> Result<Option<Box<Expr<'tcx>>>, LoweringError> {
+        match expr {
+            Some(expr) => Ok(Some(Box::new(self.lower_expr(expr)?))),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the place of the static, which is accessed through a pointer to it.
+    /// A mutable static is accessed through a raw pointer, so that its uses are not borrow checked.
+    fn lower_static_use(&self, def: DefId, span: Span) -> Result<Expr<'tcx>, LoweringError> {
+        let ty = self.type_of(def)?;
+        let ptr_ty = match self.statics[&def] {
+            Mutability::Mut => self.tcx.intern_ptr(ty, Mutability::Mut),
+            Mutability::Not => self.tcx.intern_ref(ty, Mutability::Not),
+        };
+
+        Ok(Expr::Deref {
+            expr: Box::new(Expr::StaticRef {
+                def,
+                ty: ptr_ty,
+                span,
+            }),
+            ty,
+        })
+    }
+
+    /// Returns the value of the constant or the initial value of the static,
+    /// evaluating its initializer on the first use.
+    /// The constants used in the initializer are evaluated before it is lowered,
+    /// so a constant referring to itself, directly or through other constants, is a cycle error.
+    fn const_value(&mut self, def: DefId) -> Result<Const<'tcx>, LoweringError> {
+        let ty = self.type_of(def)?;
+        let started = self.const_value.start(def);
+        if let Some(value) = started.map_err(|cycle| self.cycle_error(&cycle))? {
+            return Ok(value);
+        }
+
+        let body = self.const_decls[&def].body.clone();
+        let mut uses = Vec::new();
+        collect_const_uses(&body, &mut uses);
+        for used in uses {
+            self.const_value(used)?;
+        }
+
+        let expr = self.lower_expr(&body)?;
+        if expr.ty() != ty {
+            return Err(mismatched_types(ty, expr.ty()));
+        }
+        let value = const_eval::eval_const(&self.tcx, &expr).map_err(|err| {
+            let name = self.const_decls[&def].name;
+            LoweringError::ConstEval {
+                name: self.tcx.symbol_map.get(name).to_string(),
+                err,
             }
+        })?;
+
+        Ok(self.const_value.complete(def, value))
+    }
+
+    /// Lowers the type of the constant or the static.
+    fn lower_const_ty(&self, def: DefId) -> Result<Ty<'tcx>, LoweringError> {
+        let decl = self
+            .const_decls
+            .get(&def)
+            .unwrap_or_else(|| panic!("cannot found type of {:?}.", def));
+        let ty = self.lower_ty_in(&decl.ty, &HashMap::new(), None)?;
+        if !matches!(ty.kind(), TyKind::Int(_) | TyKind::Bool) {
+            return Err(LoweringError::UnsupportedConstTy {
+                ty: ty.to_string(),
+                name: self.tcx.symbol_map.get(decl.name).to_string(),
+            });
         }
+
+        Ok(ty)
     }
 
-    pub fn lower_stmt(&mut self, stmt: &hir::Stmt) -> Stmt<'tcx> {
-        match stmt {
+    /// Records the declaration of the constant or the static,
+    /// whose type and value are computed on the first use.
+    fn declare_const(&mut self, def: DefId, name: Symbol, ty: &hir::Ty, body: &hir::Expr) {
+        let decl = ConstDecl {
+            name,
+            ty: ty.clone(),
+            body: body.clone(),
+        };
+        self.const_decls.insert(def, decl);
+    }
+
+    /// Checks that `found` is `expected` or diverges.
+    fn check_ty(&self, expected: Ty<'tcx>, found: Ty<'tcx>) -> Result<(), LoweringError> {
+        if expected != found && !found.is_never() {
+            return Err(mismatched_types(expected, found));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the error for the first cycle of constants.
+    fn cycle_error(&self, cycle: &CycleError) -> LoweringError {
+        let name = |def| self.tcx.symbol_map.get(self.const_decls[def].name);
+        let names: Vec<String> = cycle
+            .cycle
+            .iter()
+            .map(|def| format!("`{}`", name(def)))
+            .collect();
+
+        LoweringError::ConstCycle {
+            name: name(&cycle.cycle[0]).to_string(),
+            cycle: names.join(" -> "),
+        }
+    }
+
+    /// Lowers the body of the closure to a function item taking a reference to the environment
+    /// as its first parameter, and returns the expression creating the environment.
+    /// A variable is captured by a mutable reference if the body assigns to it or borrows it
+    /// mutably, and by a shared reference otherwise. A `move` closure captures by value.
+    /// The environment is passed by a mutable reference if the body mutates any captured variable.
+    fn lower_closure(&mut self, closure: &hir::Closure) -> Result<Expr<'tcx>, LoweringError> {
+        let def = closure.res.def;
+        let mut inputs = Vec::new();
+        for param in &closure.inputs {
+            let ty = self.lower_ty(&param.ty)?;
+            check_sized(ty)?;
+            if param.mutbl == ast::Mutability::Not {
+                self.immutable_locals.insert(param.res.def, param.name);
+            }
+            self.insert_ty(param.res.def, ty);
+            inputs.push(Param {
+                res: param.res,
+                name: param.name,
+                ty,
+            });
+        }
+
+        // nested closures are placed after the closure.
+        let index = self.closure_items.len();
+        let body = match self.lower_expr(&closure.body)? {
+            Expr::Block { block } => *block,
+            expr => Block {
+                stmts: Vec::new(),
+                ty: expr.ty(),
+                expr: Some(expr),
+            },
+        };
+        let output = match &closure.output {
+            Some(ty) => {
+                let ty = self.lower_ty(ty)?;
+                self.check_ty(ty, body.ty)?;
+                ty
+            }
+            None => body.ty,
+        };
+
+        let mut mutated = HashSet::new();
+        collect_mutated_locals_block(&body, &mut mutated);
+        let upvars: Vec<Upvar> = closure
+            .upvars
+            .iter()
+            .map(|upvar| {
+                let mutbl = if mutated.contains(&upvar.res.def) {
+                    Mutability::Mut
+                } else {
+                    Mutability::Not
+                };
+                Ok(Upvar {
+                    res: upvar.res,
+                    name: upvar.name,
+                    by_ref: match closure.capture_by {
+                        ast::CaptureBy::Value => None,
+                        ast::CaptureBy::Ref => Some(mutbl),
+                    },
+                    ty: self.type_of(upvar.res.def)?,
+                    span: upvar.span,
+                })
+            })
+            .collect::<Result<_, LoweringError>>()?;
+
+        let field_tys = upvars
+            .iter()
+            .map(|upvar| match upvar.by_ref {
+                Some(mutbl) => self.tcx.intern_ref(upvar.ty, mutbl),
+                None => upvar.ty,
+            })
+            .collect();
+        let substs = self
+            .generics
+            .iter()
+            .map(|param| self.tcx.intern(TyKind::Param(*param)))
+            .collect();
+        let ty = self.tcx.intern_closure(def, substs, field_tys);
+
+        let env_mutbl = if upvars.iter().any(|upvar| mutated.contains(&upvar.res.def)) {
+            Mutability::Mut
+        } else {
+            Mutability::Not
+        };
+        let env = Param {
+            res: closure.res,
+            name: Kw::SelfLower.into(),
+            ty: self.tcx.intern_ref(ty, env_mutbl),
+        };
+        self.insert_ty(def, env.ty);
+        inputs.insert(0, env);
+
+        let captures = upvars
+            .iter()
+            .map(|upvar| {
+                let var = Expr::VarRef {
+                    res: upvar.res,
+                    ty: upvar.ty,
+                    span: upvar.span,
+                };
+                match upvar.by_ref {
+                    Some(mutbl) => Expr::Borrow {
+                        mutbl,
+                        expr: Box::new(var),
+                        ty: self.tcx.intern_ref(upvar.ty, mutbl),
+                    },
+                    None => var,
+                }
+            })
+            .collect();
+
+        let ident = self
+            .fn_ident
+            .clone()
+            .expect("closures are only allowed in functions.");
+        let header = FnHeader {
+            def,
+            name: ident.name,
+            span: ident.span,
+            generics: self.generics.clone(),
+            predicates: self.predicates.clone(),
+            inline: InlineAttr::None,
+            inputs,
+            output,
+            upvars,
+        };
+        self.tcx.fn_sig.feed(def, header.clone());
+        self.closure_items.insert(
+            index,
+            Item {
+                res: closure.res,
+                name: ident.name,
+                kind: ItemKind::Fn(Box::new(Fn { header, body })),
+            },
+        );
+
+        Ok(Expr::Closure {
+            upvars: captures,
+            ty,
+        })
+    }
+
+    pub fn lower_stmt(&mut self, stmt: &hir::Stmt) -> Result<Stmt<'tcx>, LoweringError> {
+        let stmt = match stmt {
             hir::Stmt::Local { pat, ty, init } => {
-                let init = self.lower_expr(init);
+                let init = self.lower_expr(init)?;
 
                 let res = match pat.kind {
-                    hir::PatKind::Binding { res, .. } => res,
+                    hir::PatKind::Binding { res, name, mutbl } => {
+                        if mutbl == ast::Mutability::Not {
+                            self.immutable_locals.insert(res.def, name);
+                        }
+                        res
+                    }
+                };
+                // a local without a type annotation takes the type of its initializer.
+                let ty = match ty {
+                    Some(ty) => self.lower_ty(ty)?,
+                    None => init.ty(),
                 };
-                let ty = ty
-                    .as_ref()
-                    .map(|ty| self.lower_ty(ty))
-                    .expect("Type annotation is requred.");
+                check_sized(ty)?;
                 self.insert_ty(res.def, ty.clone());
+                let init = self.coerce(init, ty)?;
+                self.check_ty(ty, init.ty())?;
 
                 let pat = self.lower_pat(pat, ty.clone());
 
                 Stmt::Local { pat, init }
             }
-            hir::Stmt::Expr(expr) | hir::Stmt::Semi(expr) => Stmt::Expr(self.lower_expr(expr)),
-            hir::Stmt::Println(expr) => Stmt::Println(self.lower_expr(expr)),
-        }
+            hir::Stmt::Expr(expr) | hir::Stmt::Semi(expr) => Stmt::Expr(self.lower_expr(expr)?),
+            hir::Stmt::Println(expr) => Stmt::Println(self.lower_expr(expr)?),
+        };
+
+        Ok(stmt)
     }
 
-    pub fn lower_block(&mut self, block: &hir::Block) -> Block<'tcx> {
-        let stmts = block.stmts.iter().map(|s| self.lower_stmt(s)).collect();
-        let expr = block.expr.as_ref().map(|e| self.lower_expr(e));
-        let ty = expr
-            .as_ref()
-            .map_or_else(|| self.tcx.common_types.unit, |e| e.ty());
+    /// A block without a tail has the type `()`, or `!` if one of its statements diverges.
+    pub fn lower_block(&mut self, block: &hir::Block) -> Result<Block<'tcx>, LoweringError> {
+        let stmts: Vec<_> = block
+            .stmts
+            .iter()
+            .map(|s| self.lower_stmt(s))
+            .collect::<Result<_, _>>()?;
+        let expr = match &block.expr {
+            Some(e) => Some(self.lower_expr(e)?),
+            None => None,
+        };
+        let ty = match &expr {
+            Some(expr) => expr.ty(),
+            None if stmts.iter().any(|stmt| stmt.diverges()) => self.tcx.common_types.never,
+            None => self.tcx.common_types.unit,
+        };
 
-        Block { stmts, expr, ty }
+        Ok(Block { stmts, expr, ty })
     }
 
-    pub fn lower_items(&mut self, items: &[hir::Item]) -> Vec<Item<'tcx>> {
+    pub fn lower_items(&mut self, items: &[hir::Item]) -> Result<Vec<Item<'tcx>>, LoweringError> {
         // lower item decl
         for item in items {
-            self.lower_item_header(item);
+            self.lower_item_header(item)?;
         }
+        self.check_impls()?;
 
-        items.iter().map(|item| self.lower_item(item)).collect()
+        // evaluate the constants and the statics before their uses in the functions.
+        self.set_ty_params(&[]);
+        for item in items {
+            match &item.kind {
+                hir::ItemKind::Const(_) => {
+                    self.const_value(item.res.def)?;
+                }
+                hir::ItemKind::Static(s) => {
+                    let value = self.const_value(item.res.def)?;
+                    self.tcx.statics.push(StaticDef {
+                        def: item.res.def,
+                        name: item.name,
+                        mutbl: lower_mutability(s.mutbl),
+                        value,
+                    });
+                }
+                hir::ItemKind::Fn(_) | hir::ItemKind::Trait(_) | hir::ItemKind::Impl(_) => {}
+            }
+        }
+
+        let mut fns = Vec::new();
+        for item in items {
+            fns.append(&mut self.lower_item(item)?);
+        }
+        fns.append(&mut self.closure_items);
+
+        Ok(fns)
     }
 
-    pub fn lower_item_header(&mut self, item: &hir::Item) {
+    pub fn lower_item_header(&mut self, item: &hir::Item) -> Result<(), LoweringError> {
         match &item.kind {
             hir::ItemKind::Fn(fun) => {
-                self.lower_fun_header(item.res.def, item.name, &fun.inputs, &fun.output);
+                let (generics, predicates) = self.lower_generics(&fun.generics);
+                let decl = FnDecl {
+                    ident: Ident::new(item.name, item.span),
+                    generics,
+                    predicates,
+                    inline: lower_inline(fun.inline),
+                    inputs: fun.inputs.clone(),
+                    output: fun.output.clone(),
+                    self_ty: None,
+                };
+                self.declare_fn(item.res.def, decl);
+            }
+            hir::ItemKind::Trait(tr) => self.lower_trait_header(item.res.def, item.name, tr),
+            hir::ItemKind::Impl(imp) => self.lower_impl_header(imp)?,
+            hir::ItemKind::Const(c) => self.declare_const(item.res.def, item.name, &c.ty, &c.body),
+            hir::ItemKind::Static(s) => {
+                self.statics.insert(item.res.def, lower_mutability(s.mutbl));
+                self.declare_const(item.res.def, item.name, &s.ty, &s.body);
             }
         }
+
+        Ok(())
     }
 
-    pub fn lower_item(&mut self, item: &hir::Item) -> Item<'tcx> {
-        let kind = match &item.kind {
-            hir::ItemKind::Fn(fun) => self.lower_fun(item.res.def, &fun.body),
+    /// Lowers the item to function items.
+    /// The methods of an impl become function items. A trait, a constant and a static have no
+    /// function items, since the values of constants and statics are evaluated in `lower_items`.
+    pub fn lower_item(&mut self, item: &hir::Item) -> Result<Vec<Item<'tcx>>, LoweringError> {
+        let items = match &item.kind {
+            hir::ItemKind::Fn(fun) => {
+                let kind = self.lower_fun(item.res.def, fun)?;
+
+                vec![Item {
+                    res: item.res,
+                    name: item.name,
+                    kind,
+                }]
+            }
+            hir::ItemKind::Trait(_) | hir::ItemKind::Const(_) | hir::ItemKind::Static(_) => {
+                Vec::new()
+            }
+            hir::ItemKind::Impl(imp) => {
+                self.set_ty_params(&[]);
+                self.self_ty = Some(self.lower_ty(&imp.self_ty)?);
+                let mut items = Vec::new();
+                for item in &imp.items {
+                    items.append(&mut self.lower_item(item)?);
+                }
+                self.self_ty = None;
+
+                items
+            }
         };
 
-        Item {
-            res: item.res,
-            name: item.name,
-            kind,
+        Ok(items)
+    }
+
+    fn lower_fun(&mut self, def: DefId, fun: &hir::Fn) -> Result<ItemKind<'tcx>, LoweringError> {
+        let header = self.fn_sig(def)?;
+
+        self.set_ty_params(&header.generics);
+        self.fn_ident = Some(Ident::new(header.name, header.span));
+        self.generics = header.generics.clone();
+        self.predicates = header.predicates.clone();
+        self.immutable_locals = fun
+            .inputs
+            .iter()
+            .filter(|param| param.mutbl == ast::Mutability::Not)
+            .map(|param| (param.res.def, param.name))
+            .collect();
+        let mut body = self.lower_block(&fun.body)?;
+
+        // the value of the tail expression is returned, so it is coerced to the return type.
+        if let Some(expr) = body.expr.take() {
+            let expr = if expr.ty() == header.output {
+                expr
+            } else {
+                self.coerce(expr, header.output)?
+            };
+            self.check_ty(header.output, expr.ty())?;
+            body.ty = expr.ty();
+            body.expr = Some(expr);
         }
+
+        Ok(ItemKind::Fn(Box::new(Fn { header, body })))
     }
+}
 
-    fn lower_fun(&mut self, def: DefId, body: &hir::Block) -> ItemKind<'tcx> {
-        let header = self
-            .fn_headers
-            .get(&def)
-            .unwrap_or_else(|| panic!("not found function header(defId:{}).", def))
-            .clone();
+/// Matches the parameter type `expected` against the argument type `found`,
+/// recording the types that the type parameters in `expected` stand for.
+fn infer_ty<'tcx>(
+    expected: Ty<'tcx>,
+    found: Ty<'tcx>,
+    substs: &mut [Option<Ty<'tcx>>],
+) -> Result<(), LoweringError> {
+    match (expected.kind(), found.kind()) {
+        // a diverging argument gives no information.
+        (_, TyKind::Never) => {}
+        (TyKind::Param(param), _) => match substs[param.index] {
+            Some(ty) if ty != found => return Err(mismatched_types(ty, found)),
+            _ => substs[param.index] = Some(found),
+        },
+        (TyKind::Ref(expected, _), TyKind::Ref(found, _))
+        | (TyKind::RawPtr(expected, _), TyKind::Ref(found, _) | TyKind::RawPtr(found, _)) => {
+            infer_ty(*expected, *found, substs)?
+        }
+        (TyKind::Tuple(expected), TyKind::Tuple(found)) if expected.len() == found.len() => {
+            for (expected, found) in expected.iter().zip(found.iter()) {
+                infer_ty(*expected, *found, substs)?;
+            }
+        }
+        (TyKind::FnPtr(expected), TyKind::FnPtr(found))
+            if expected.inputs.len() == found.inputs.len() =>
+        {
+            for (expected, found) in expected.inputs.iter().zip(found.inputs.iter()) {
+                infer_ty(*expected, *found, substs)?;
+            }
+            infer_ty(expected.output, found.output, substs)?;
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
 
-        let body = self.lower_block(body);
+/// Collects the locals that the block assigns to or borrows mutably.
+fn collect_mutated_locals_block(block: &Block, mutated: &mut HashSet<DefId>) {
+    for stmt in &block.stmts {
+        match stmt {
+            Stmt::Local { init: expr, .. } | Stmt::Expr(expr) | Stmt::Println(expr) => {
+                collect_mutated_locals(expr, mutated)
+            }
+        }
+    }
+    if let Some(expr) = &block.expr {
+        collect_mutated_locals(expr, mutated);
+    }
+}
+
+fn collect_mutated_locals(expr: &Expr, mutated: &mut HashSet<DefId>) {
+    match expr {
+        Expr::Assign { lhs, rhs, .. } => {
+            mutated.extend(mutated_local(lhs));
+            collect_mutated_locals(lhs, mutated);
+            collect_mutated_locals(rhs, mutated);
+        }
+        Expr::Borrow {
+            mutbl: Mutability::Mut,
+            expr,
+            ..
+        }
+        | Expr::AddressOf {
+            mutbl: Mutability::Mut,
+            expr,
+            ..
+        } => {
+            mutated.extend(mutated_local(expr));
+            collect_mutated_locals(expr, mutated);
+        }
+        Expr::Call { fun, args, .. } => {
+            collect_mutated_locals(fun, mutated);
+            for arg in args {
+                collect_mutated_locals(arg, mutated);
+            }
+        }
+        Expr::Binary { lhs, rhs, .. } => {
+            collect_mutated_locals(lhs, mutated);
+            collect_mutated_locals(rhs, mutated);
+        }
+        Expr::Unary { expr, .. }
+        | Expr::Deref { expr, .. }
+        | Expr::Borrow { expr, .. }
+        | Expr::AddressOf { expr, .. }
+        | Expr::Unsize { expr, .. }
+        | Expr::ReifyFnPointer { expr, .. } => collect_mutated_locals(expr, mutated),
+        Expr::Closure { upvars, .. } => {
+            for upvar in upvars {
+                collect_mutated_locals(upvar, mutated);
+            }
+        }
+        Expr::If {
+            cond,
+            then,
+            else_opt,
+            ..
+        } => {
+            collect_mutated_locals(cond, mutated);
+            collect_mutated_locals_block(then, mutated);
+            if let Some(expr) = else_opt {
+                collect_mutated_locals(expr, mutated);
+            }
+        }
+        Expr::Loop { block } | Expr::Block { block } => {
+            collect_mutated_locals_block(block, mutated)
+        }
+        Expr::Break { expr, .. } | Expr::Continue { expr, .. } | Expr::Return { expr, .. } => {
+            if let Some(expr) = expr {
+                collect_mutated_locals(expr, mutated);
+            }
+        }
+        Expr::Lit { .. }
+        | Expr::VarRef { .. }
+        | Expr::NamedConst { .. }
+        | Expr::StaticRef { .. } => {}
+    }
+}
+
+/// Collects the constants used in the expression, in order of use.
+fn collect_const_uses(expr: &hir::Expr, uses: &mut Vec<DefId>) {
+    match expr {
+        hir::Expr::Path { path } => {
+            if path.res.kind == ResKind::Const {
+                uses.push(path.res.def);
+            }
+        }
+        hir::Expr::Call { fun, args } => {
+            collect_const_uses(fun, uses);
+            for arg in args {
+                collect_const_uses(arg, uses);
+            }
+        }
+        hir::Expr::MethodCall { receiver, args, .. } => {
+            collect_const_uses(receiver, uses);
+            for arg in args {
+                collect_const_uses(arg, uses);
+            }
+        }
+        hir::Expr::Binary { lhs, rhs, .. } | hir::Expr::Assign { lhs, rhs } => {
+            collect_const_uses(lhs, uses);
+            collect_const_uses(rhs, uses);
+        }
+        hir::Expr::Unary { expr, .. } | hir::Expr::AddrOf { expr, .. } => {
+            collect_const_uses(expr, uses)
+        }
+        hir::Expr::If {
+            cond,
+            then,
+            else_opt,
+        } => {
+            collect_const_uses(cond, uses);
+            collect_const_uses_block(then, uses);
+            if let Some(expr) = else_opt {
+                collect_const_uses(expr, uses);
+            }
+        }
+        hir::Expr::Loop { block } | hir::Expr::Block { block } => {
+            collect_const_uses_block(block, uses)
+        }
+        hir::Expr::Break { expr } | hir::Expr::Continue { expr } | hir::Expr::Return { expr } => {
+            if let Some(expr) = expr {
+                collect_const_uses(expr, uses);
+            }
+        }
+        hir::Expr::Closure(closure) => collect_const_uses(&closure.body, uses),
+        hir::Expr::Lit { .. } => {}
+    }
+}
+
+fn collect_const_uses_block(block: &hir::Block, uses: &mut Vec<DefId>) {
+    for stmt in &block.stmts {
+        match stmt {
+            hir::Stmt::Local { init: expr, .. }
+            | hir::Stmt::Expr(expr)
+            | hir::Stmt::Semi(expr)
+            | hir::Stmt::Println(expr) => collect_const_uses(expr, uses),
+        }
+    }
+    if let Some(expr) = &block.expr {
+        collect_const_uses(expr, uses);
+    }
+}
+
+/// Returns the local that is mutated by writing to the place.
+/// A place behind a mutable reference mutates the local holding the reference,
+/// while a place behind a shared reference or a raw pointer mutates no local.
+fn mutated_local(place: &Expr) -> Option<DefId> {
+    match place {
+        Expr::VarRef { res, .. } => Some(res.def),
+        Expr::Deref { expr, .. } => match expr.ty().kind() {
+            TyKind::Ref(_, Mutability::Mut) => mutated_local(expr),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn check_arg_count(expected: usize, supplied: usize) -> Result<(), LoweringError> {
+    if supplied != expected {
+        return Err(LoweringError::ArgCount { expected, supplied });
+    }
+
+    Ok(())
+}
+
+/// Checks that values of the type can be held in locals and passed to functions.
+/// A trait object can only be used behind a pointer.
+fn check_sized(ty: Ty) -> Result<(), LoweringError> {
+    if ty.is_trait_object() {
+        return Err(LoweringError::Unsized { ty: ty.to_string() });
+    }
+
+    Ok(())
+}
+
+fn mismatched_types(expected: Ty, found: Ty) -> LoweringError {
+    LoweringError::MismatchedTypes {
+        expected: expected.to_string(),
+        found: found.to_string(),
+    }
+}
+
+fn lower_mutability(mutbl: ast::Mutability) -> Mutability {
+    match mutbl {
+        ast::Mutability::Mut => Mutability::Mut,
+        ast::Mutability::Not => Mutability::Not,
+    }
+}
 
-        ItemKind::Fn(Box::new(Fn { header, body }))
+fn lower_inline(inline: ast::InlineAttr) -> InlineAttr {
+    match inline {
+        ast::InlineAttr::None => InlineAttr::None,
+        ast::InlineAttr::Hint => InlineAttr::Hint,
+        ast::InlineAttr::Never => InlineAttr::Never,
     }
 }
@@ -109,13 +109,22 @@ impl Cursor<'_> {
             c if is_id_start(c) => self.ident(),
 
             ';' => TokenKind::Semi,
-            ':' => TokenKind::Colon,
+            ':' => match self.first() {
+                ':' => {
+                    self.bump();
+                    TokenKind::PathSep
+                }
+                _ => TokenKind::Colon,
+            },
             '.' => TokenKind::Dot,
             ',' => TokenKind::Comma,
             '(' => TokenKind::OpenParen,
             ')' => TokenKind::CloseParen,
             '{' => TokenKind::OpenBrace,
             '}' => TokenKind::CloseBrace,
+            '[' => TokenKind::OpenBracket,
+            ']' => TokenKind::CloseBracket,
+            '#' => TokenKind::Pound,
 
             '=' => match self.first() {
                 '=' => {
@@ -156,7 +165,12 @@ impl Cursor<'_> {
 
             '+' => TokenKind::Plus,
             '*' => TokenKind::Star,
-            '/' => TokenKind::Slash,
+            '/' => match self.first() {
+                '/' => self.line_comment(),
+                _ => TokenKind::Slash,
+            },
+            '&' => TokenKind::And,
+            '|' => TokenKind::Or,
 
             _ => TokenKind::Unknown,
         };
@@ -169,6 +183,11 @@ impl Cursor<'_> {
         TokenKind::Whitespace
     }
 
+    fn line_comment(&mut self) -> TokenKind {
+        self.eat_while(|c| c != '\n');
+        TokenKind::LineComment
+    }
+
     fn ident(&mut self) -> TokenKind {
         self.eat_while(is_id_continue);
         TokenKind::Ident
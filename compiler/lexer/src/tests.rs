@@ -15,18 +15,22 @@ fn paren() {
     test_lexer!(")", vec![Token::new(TokenKind::CloseParen, 1)]);
     test_lexer!("{", vec![Token::new(TokenKind::OpenBrace, 1)]);
     test_lexer!("}", vec![Token::new(TokenKind::CloseBrace, 1)]);
+    test_lexer!("[", vec![Token::new(TokenKind::OpenBracket, 1)]);
+    test_lexer!("]", vec![Token::new(TokenKind::CloseBracket, 1)]);
 }
 
 #[test]
 fn symbol() {
     test_lexer!(";", vec![Token::new(TokenKind::Semi, 1)]);
     test_lexer!(":", vec![Token::new(TokenKind::Colon, 1)]);
+    test_lexer!("::", vec![Token::new(TokenKind::PathSep, 2)]);
     test_lexer!(".", vec![Token::new(TokenKind::Dot, 1)]);
     test_lexer!(",", vec![Token::new(TokenKind::Comma, 1)]);
 
     test_lexer!("->", vec![Token::new(TokenKind::Arrow, 2)]);
     test_lexer!("=", vec![Token::new(TokenKind::Eq, 1)]);
     test_lexer!("!", vec![Token::new(TokenKind::Bang, 1)]);
+    test_lexer!("#", vec![Token::new(TokenKind::Pound, 1)]);
 
     test_lexer!("<", vec![Token::new(TokenKind::Lt, 1)]);
     test_lexer!("<=", vec![Token::new(TokenKind::Le, 2)]);
@@ -39,6 +43,8 @@ fn symbol() {
     test_lexer!("+", vec![Token::new(TokenKind::Plus, 1)]);
     test_lexer!("*", vec![Token::new(TokenKind::Star, 1)]);
     test_lexer!("/", vec![Token::new(TokenKind::Slash, 1)]);
+    test_lexer!("&", vec![Token::new(TokenKind::And, 1)]);
+    test_lexer!("|", vec![Token::new(TokenKind::Or, 1)]);
 }
 
 #[test]
@@ -54,6 +60,21 @@ fn whitespace() {
     );
 }
 
+#[test]
+fn line_comment() {
+    test_lexer!("// foo", vec![Token::new(TokenKind::LineComment, 6)]);
+    test_lexer!(
+        "; // foo\n;",
+        vec![
+            Token::new(TokenKind::Semi, 1),
+            Token::new(TokenKind::Whitespace, 1),
+            Token::new(TokenKind::LineComment, 6),
+            Token::new(TokenKind::Whitespace, 1),
+            Token::new(TokenKind::Semi, 1)
+        ]
+    );
+}
+
 #[test]
 fn ident() {
     test_lexer!("true", vec![Token::new(TokenKind::Ident, 4)]);
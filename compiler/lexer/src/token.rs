@@ -15,6 +15,9 @@ pub enum TokenKind {
     /// Any whitespace characters sequence.
     Whitespace,
 
+    /// `// comment`
+    LineComment,
+
     /// "ident" or "keyword"
     /// At this step keywords are considered identifiers.
     Ident,
@@ -30,6 +33,9 @@ pub enum TokenKind {
     /// `:`
     Colon,
 
+    /// `::`
+    PathSep,
+
     // `.`
     Dot,
 
@@ -48,6 +54,15 @@ pub enum TokenKind {
     /// `}`
     CloseBrace,
 
+    /// `[`
+    OpenBracket,
+
+    /// `]`
+    CloseBracket,
+
+    /// `#`
+    Pound,
+
     /// `->`
     Arrow,
 
@@ -87,6 +102,12 @@ pub enum TokenKind {
     /// `/`
     Slash,
 
+    /// `&`
+    And,
+
+    /// `|`
+    Or,
+
     /// Unknown token, not expected by the lexer.
     Unknown,
 }
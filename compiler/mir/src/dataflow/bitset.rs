@@ -0,0 +1,178 @@
+use crate::dataflow::JoinSemiLattice;
+
+use std::fmt;
+use std::marker::PhantomData;
+
+const WORD_BITS: usize = 64;
+
+/// Returns the number of words needed to hold `domain_size` bits.
+fn num_words(domain_size: usize) -> usize {
+    (domain_size + WORD_BITS - 1) >> WORD_BITS.trailing_zeros()
+}
+
+/// A set of indices below `domain_size`, one bit for each index: `{_1, _3}`
+#[derive(Clone, PartialEq, Eq)]
+pub struct BitSet<T> {
+    domain_size: usize,
+    words: Vec<u64>,
+    marker: PhantomData<T>,
+}
+
+impl<T: From<usize> + Into<usize>> BitSet<T> {
+    pub fn new_empty(domain_size: usize) -> Self {
+        BitSet {
+            domain_size,
+            words: vec![0; num_words(domain_size)],
+            marker: PhantomData,
+        }
+    }
+
+    pub fn new_filled(domain_size: usize) -> Self {
+        let mut set = BitSet::new_empty(domain_size);
+        for word in &mut set.words {
+            *word = !0;
+        }
+        set.clear_excess_bits();
+        set
+    }
+
+    pub fn domain_size(&self) -> usize {
+        self.domain_size
+    }
+
+    /// Inserts the element, and returns `true` if it was not in the set.
+    pub fn insert(&mut self, elem: T) -> bool {
+        let (index, mask) = self.word_index_and_mask(elem);
+        let changed = self.words[index] & mask == 0;
+        self.words[index] |= mask;
+        changed
+    }
+
+    /// Removes the element, and returns `true` if it was in the set.
+    pub fn remove(&mut self, elem: T) -> bool {
+        let (index, mask) = self.word_index_and_mask(elem);
+        let changed = self.words[index] & mask != 0;
+        self.words[index] &= !mask;
+        changed
+    }
+
+    pub fn contains(&self, elem: T) -> bool {
+        let (index, mask) = self.word_index_and_mask(elem);
+        self.words[index] & mask != 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|word| *word == 0)
+    }
+
+    /// Returns the elements in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        (0..self.domain_size)
+            .filter(|i| self.words[i / WORD_BITS] & (1 << (i % WORD_BITS)) != 0)
+            .map(T::from)
+    }
+
+    /// Adds the elements of `other`, and returns `true` if `self` is changed.
+    pub fn union(&mut self, other: &Self) -> bool {
+        self.zip_words(other, |word, other| word | other)
+    }
+
+    /// Keeps only the elements also in `other`, and returns `true` if `self` is changed.
+    pub fn intersect(&mut self, other: &Self) -> bool {
+        self.zip_words(other, |word, other| word & other)
+    }
+
+    /// Removes the elements of `other`, and returns `true` if `self` is changed.
+    pub fn subtract(&mut self, other: &Self) -> bool {
+        self.zip_words(other, |word, other| word & !other)
+    }
+
+    fn zip_words(&mut self, other: &Self, f: impl Fn(u64, u64) -> u64) -> bool {
+        assert_eq!(self.domain_size, other.domain_size);
+        let mut changed = false;
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            let new = f(*word, *other);
+            changed |= new != *word;
+            *word = new;
+        }
+        changed
+    }
+
+    fn word_index_and_mask(&self, elem: T) -> (usize, u64) {
+        let elem = elem.into();
+        assert!(
+            elem < self.domain_size,
+            "{} is out of the domain of size {}",
+            elem,
+            self.domain_size
+        );
+        (elem / WORD_BITS, 1 << (elem % WORD_BITS))
+    }
+
+    fn clear_excess_bits(&mut self) {
+        let used = self.domain_size % WORD_BITS;
+        if let (Some(last), true) = (self.words.last_mut(), used > 0) {
+            *last &= (1 << used) - 1;
+        }
+    }
+}
+
+impl<T: From<usize> + Into<usize> + Clone + PartialEq> JoinSemiLattice for BitSet<T> {
+    fn join(&mut self, other: &Self) -> bool {
+        self.union(other)
+    }
+}
+
+impl<T: From<usize> + Into<usize> + fmt::Display> fmt::Debug for BitSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let elems: Vec<_> = self.iter().map(|elem| elem.to_string()).collect();
+        write!(f, "{{{}}}", elems.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::LocalId;
+
+    #[test]
+    fn insert_and_remove() {
+        let mut set: BitSet<LocalId> = BitSet::new_empty(70);
+        assert!(set.is_empty());
+        assert!(set.insert(LocalId::from(3)));
+        assert!(set.insert(LocalId::from(65)));
+        assert!(!set.insert(LocalId::from(3)));
+        assert!(set.contains(LocalId::from(65)));
+        assert!(!set.contains(LocalId::from(64)));
+        assert_eq!(format!("{:?}", set), "{_3, _65}");
+
+        assert!(set.remove(LocalId::from(3)));
+        assert!(!set.remove(LocalId::from(3)));
+        assert_eq!(set.iter().collect::<Vec<_>>(), [LocalId::from(65)]);
+    }
+
+    #[test]
+    fn set_operations() {
+        let set = |elems: &[usize]| {
+            let mut set: BitSet<LocalId> = BitSet::new_empty(5);
+            for elem in elems {
+                set.insert(LocalId::from(*elem));
+            }
+            set
+        };
+
+        let mut a = set(&[0, 1]);
+        assert!(a.union(&set(&[1, 4])));
+        assert_eq!(a, set(&[0, 1, 4]));
+        assert!(!a.join(&set(&[4])));
+
+        assert!(a.intersect(&set(&[1, 2, 4])));
+        assert_eq!(a, set(&[1, 4]));
+
+        assert!(a.subtract(&set(&[4])));
+        assert_eq!(a, set(&[1]));
+
+        assert_eq!(BitSet::new_filled(5), set(&[0, 1, 2, 3, 4]));
+    }
+}
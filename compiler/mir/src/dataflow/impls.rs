@@ -0,0 +1,83 @@
+//! Dataflow analyses shared by the passes over MIR.
+
+use crate::dataflow::{Analysis, BitSet, Direction};
+use crate::{stmt::*, terminator::*, *};
+
+/// Computes the locals whose current value may be used later.
+pub struct MaybeLiveLocals;
+
+impl MaybeLiveLocals {
+    fn apply(
+        &self,
+        state: &mut BitSet<LocalId>,
+        assigned: Option<&Place>,
+        operands: Vec<&Operand>,
+        borrowed: Option<&Place>,
+    ) {
+        // A direct assignment defines the local, while an assignment through a pointer reads it.
+        if let Some(place) = assigned {
+            if place.is_indirect() {
+                state.insert(place.local);
+            } else {
+                state.remove(place.local);
+            }
+        }
+
+        let used = operands.into_iter().filter_map(|operand| operand.place());
+        for place in used.chain(borrowed) {
+            state.insert(place.local);
+        }
+    }
+}
+
+impl<'tcx> Analysis<'tcx> for MaybeLiveLocals {
+    type Domain = BitSet<LocalId>;
+
+    const DIRECTION: Direction = Direction::Backward;
+
+    fn bottom_value(&self, body: &Body<'tcx>) -> Self::Domain {
+        BitSet::new_empty(body.local_decls.len())
+    }
+
+    fn apply_statement_effect(
+        &self,
+        state: &mut Self::Domain,
+        statement: &Statement<'tcx>,
+        _location: Location,
+    ) {
+        let borrowed = match &statement.kind {
+            StatementKind::Assign(assign) => match &assign.1 {
+                RValue::Ref(_, place) | RValue::AddressOf(_, place) => Some(place),
+                _ => None,
+            },
+            StatementKind::Println(_) => None,
+        };
+
+        self.apply(
+            state,
+            statement.destination(),
+            statement.operands(),
+            borrowed,
+        );
+    }
+
+    fn apply_terminator_effect(
+        &self,
+        state: &mut Self::Domain,
+        terminator: &Terminator<'tcx>,
+        _location: Location,
+    ) {
+        let return_place = Place::new(LocalId(0));
+        let returned = match terminator.kind {
+            TerminatorKind::Return => Some(&return_place),
+            _ => None,
+        };
+
+        self.apply(
+            state,
+            terminator.destination(),
+            terminator.operands(),
+            returned,
+        );
+    }
+}
@@ -0,0 +1,503 @@
+//! Dataflow analysis over MIR.
+//!
+//! An analysis gives each program point a state from a join semilattice, and describes how each
+//! statement and terminator changes it. The engine propagates the states along the control flow
+//! graph, forward or backward, joining them where the paths meet, until they no longer change.
+//! Only the state on entry to each block is kept; a cursor recomputes the state at a statement
+//! from the entry of its block.
+
+mod bitset;
+pub mod impls;
+
+pub use bitset::BitSet;
+
+use crate::{stmt::Statement, terminator::Terminator, *};
+
+use typed_index_collections::TiVec;
+
+use std::collections::{BTreeSet, VecDeque};
+
+/// A domain whose values are merged at the join points of the control flow graph.
+pub trait JoinSemiLattice: Clone + PartialEq {
+    /// Merge `other` into `self`, and return `true` if `self` is changed.
+    fn join(&mut self, other: &Self) -> bool;
+}
+
+impl<T: Ord + Clone> JoinSemiLattice for BTreeSet<T> {
+    fn join(&mut self, other: &Self) -> bool {
+        let len = self.len();
+        self.extend(other.iter().cloned());
+        self.len() != len
+    }
+}
+
+impl<K, V: JoinSemiLattice> JoinSemiLattice for TiVec<K, V> {
+    fn join(&mut self, other: &Self) -> bool {
+        let mut changed = false;
+        for (value, other) in self.iter_mut().zip(other.iter()) {
+            changed |= value.join(other);
+        }
+        changed
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Forward,
+    Backward,
+}
+
+pub trait Analysis<'tcx> {
+    type Domain: JoinSemiLattice;
+
+    const DIRECTION: Direction;
+
+    /// The initial state of every block.
+    fn bottom_value(&self, body: &Body<'tcx>) -> Self::Domain;
+
+    /// Initialize the state on entry to the start block of a forward analysis.
+    fn initialize_start_block(&self, _body: &Body<'tcx>, _state: &mut Self::Domain) {}
+
+    fn apply_statement_effect(
+        &self,
+        state: &mut Self::Domain,
+        statement: &Statement<'tcx>,
+        location: Location,
+    );
+
+    fn apply_terminator_effect(
+        &self,
+        state: &mut Self::Domain,
+        terminator: &Terminator<'tcx>,
+        location: Location,
+    );
+}
+
+/// The fixpoint of an analysis.
+pub struct Results<'tcx, A: Analysis<'tcx>> {
+    pub analysis: A,
+
+    /// The state on entry to each block for a forward analysis,
+    /// and the state on exit from each block for a backward analysis.
+    entry_sets: TiVec<BlockId, A::Domain>,
+}
+
+impl<'tcx, A: Analysis<'tcx>> Results<'tcx, A> {
+    /// Returns the state where the analysis starts the block,
+    /// which is the exit of the block for a backward analysis.
+    pub fn entry_set(&self, block: BlockId) -> &A::Domain {
+        &self.entry_sets[block]
+    }
+
+    pub fn into_results_cursor<'a>(self, body: &'a Body<'tcx>) -> ResultsCursor<'a, 'tcx, A> {
+        ResultsCursor::new(body, self)
+    }
+}
+
+/// Run the analysis over the body until the state of every block converges.
+pub fn iterate_to_fixpoint<'tcx, A: Analysis<'tcx>>(
+    analysis: A,
+    body: &Body<'tcx>,
+) -> Results<'tcx, A> {
+    let predecessors = body.predecessors();
+
+    let bottom = analysis.bottom_value(body);
+    let mut entry_sets: TiVec<BlockId, A::Domain> =
+        body.blocks.iter().map(|_| bottom.clone()).collect();
+    if A::DIRECTION == Direction::Forward {
+        if let Some(start) = body.blocks.first_key() {
+            analysis.initialize_start_block(body, &mut entry_sets[start]);
+        }
+    }
+
+    let mut worklist: VecDeque<BlockId> = match A::DIRECTION {
+        Direction::Forward => body.blocks.keys().collect(),
+        Direction::Backward => body.blocks.keys().rev().collect(),
+    };
+    while let Some(id) = worklist.pop_front() {
+        let mut state = entry_sets[id].clone();
+        let len = effect_count(body, id);
+        for index in 0..len {
+            apply_effect(
+                &analysis,
+                body,
+                id,
+                effect_index(A::DIRECTION, len, index),
+                &mut state,
+            );
+        }
+
+        let targets = match A::DIRECTION {
+            Direction::Forward => body.successors(id),
+            Direction::Backward => predecessors[id].clone(),
+        };
+        for target in targets {
+            if entry_sets[target].join(&state) && !worklist.contains(&target) {
+                worklist.push_back(target);
+            }
+        }
+    }
+
+    Results {
+        analysis,
+        entry_sets,
+    }
+}
+
+/// Returns the number of statements and the terminator in the block.
+fn effect_count(body: &Body, block: BlockId) -> usize {
+    body.blocks[block].stmts.len() + 1
+}
+
+/// Returns the statement index of the `index`-th effect in the direction of the analysis.
+fn effect_index(direction: Direction, len: usize, index: usize) -> usize {
+    match direction {
+        Direction::Forward => index,
+        Direction::Backward => len - 1 - index,
+    }
+}
+
+fn apply_effect<'tcx, A: Analysis<'tcx>>(
+    analysis: &A,
+    body: &Body<'tcx>,
+    block: BlockId,
+    statement_index: usize,
+    state: &mut A::Domain,
+) {
+    let location = Location {
+        block,
+        statement_index,
+    };
+    let data = &body.blocks[block];
+    match data.stmts.get(statement_index) {
+        Some(stmt) => analysis.apply_statement_effect(state, stmt, location),
+        None => {
+            let terminator = data
+                .terminator
+                .as_ref()
+                .expect("The Terminator in the Block is None.");
+            analysis.apply_terminator_effect(state, terminator, location);
+        }
+    }
+}
+
+/// Queries the state of an analysis at any statement or terminator.
+/// Moving the cursor forward in the direction of the analysis within a block only applies the
+/// effects in between; any other move starts again from the entry of the block.
+pub struct ResultsCursor<'a, 'tcx, A: Analysis<'tcx>> {
+    body: &'a Body<'tcx>,
+    results: Results<'tcx, A>,
+    state: A::Domain,
+
+    /// The block of the state, and the number of its effects applied to the state.
+    position: Option<(BlockId, usize)>,
+}
+
+impl<'a, 'tcx, A: Analysis<'tcx>> ResultsCursor<'a, 'tcx, A> {
+    pub fn new(body: &'a Body<'tcx>, results: Results<'tcx, A>) -> Self {
+        let state = results.analysis.bottom_value(body);
+        ResultsCursor {
+            body,
+            results,
+            state,
+            position: None,
+        }
+    }
+
+    pub fn results(&self) -> &Results<'tcx, A> {
+        &self.results
+    }
+
+    /// Returns the state at the position of the last seek.
+    pub fn get(&self) -> &A::Domain {
+        &self.state
+    }
+
+    /// Returns the state right before the statement or the terminator is executed.
+    pub fn seek_before(&mut self, location: Location) -> &A::Domain {
+        let len = effect_count(self.body, location.block);
+        let applied = match A::DIRECTION {
+            Direction::Forward => location.statement_index,
+            Direction::Backward => len - location.statement_index,
+        };
+        self.seek(location.block, applied)
+    }
+
+    /// Returns the state right after the statement or the terminator is executed.
+    pub fn seek_after(&mut self, location: Location) -> &A::Domain {
+        let len = effect_count(self.body, location.block);
+        let applied = match A::DIRECTION {
+            Direction::Forward => location.statement_index + 1,
+            Direction::Backward => len - 1 - location.statement_index,
+        };
+        self.seek(location.block, applied)
+    }
+
+    fn seek(&mut self, block: BlockId, applied: usize) -> &A::Domain {
+        let reusable =
+            matches!(self.position, Some((current, done)) if current == block && done <= applied);
+        if !reusable {
+            self.state = self.results.entry_sets[block].clone();
+            self.position = Some((block, 0));
+        }
+
+        let len = effect_count(self.body, block);
+        let (_, done) = self.position.as_mut().unwrap();
+        while *done < applied {
+            let statement_index = effect_index(A::DIRECTION, len, *done);
+            apply_effect(
+                &self.results.analysis,
+                self.body,
+                block,
+                statement_index,
+                &mut self.state,
+            );
+            *done += 1;
+        }
+
+        &self.state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::stmt::*;
+    use crate::terminator::*;
+
+    use span::{SymbolMap, DUMMY_SP};
+    use ty::{res::DefId, ConstLit, ConstValue, ScalarInt, TyArena, TyCtx};
+
+    /// The locals which may have been assigned.
+    struct MaybeAssigned;
+
+    impl<'tcx> Analysis<'tcx> for MaybeAssigned {
+        type Domain = BitSet<LocalId>;
+
+        const DIRECTION: Direction = Direction::Forward;
+
+        fn bottom_value(&self, body: &Body<'tcx>) -> Self::Domain {
+            BitSet::new_empty(body.local_decls.len())
+        }
+
+        fn initialize_start_block(&self, body: &Body<'tcx>, state: &mut Self::Domain) {
+            for arg in body.id_args() {
+                state.insert(arg);
+            }
+        }
+
+        fn apply_statement_effect(
+            &self,
+            state: &mut Self::Domain,
+            statement: &Statement<'tcx>,
+            _location: Location,
+        ) {
+            if let StatementKind::Assign(assign) = &statement.kind {
+                state.insert(assign.0.local);
+            }
+        }
+
+        fn apply_terminator_effect(
+            &self,
+            _state: &mut Self::Domain,
+            _terminator: &Terminator<'tcx>,
+            _location: Location,
+        ) {
+        }
+    }
+
+    /// The locals whose value may be read later.
+    struct Liveness;
+
+    impl<'tcx> Analysis<'tcx> for Liveness {
+        type Domain = BitSet<LocalId>;
+
+        const DIRECTION: Direction = Direction::Backward;
+
+        fn bottom_value(&self, body: &Body<'tcx>) -> Self::Domain {
+            BitSet::new_empty(body.local_decls.len())
+        }
+
+        fn apply_statement_effect(
+            &self,
+            state: &mut Self::Domain,
+            statement: &Statement<'tcx>,
+            _location: Location,
+        ) {
+            if let StatementKind::Assign(assign) = &statement.kind {
+                let (place, rvalue) = assign.as_ref();
+                state.remove(place.local);
+                if let RValue::BinaryOp(_, operands) = rvalue {
+                    for place in [&operands.0, &operands.1]
+                        .iter()
+                        .filter_map(|op| op.place())
+                    {
+                        state.insert(place.local);
+                    }
+                }
+                if let RValue::Use(operand) = rvalue {
+                    if let Some(place) = operand.place() {
+                        state.insert(place.local);
+                    }
+                }
+            }
+        }
+
+        fn apply_terminator_effect(
+            &self,
+            state: &mut Self::Domain,
+            terminator: &Terminator<'tcx>,
+            _location: Location,
+        ) {
+            match &terminator.kind {
+                TerminatorKind::Return => {
+                    state.insert(LocalId::from(0));
+                }
+                TerminatorKind::SwitchInt { discr, .. } => {
+                    if let Some(place) = discr.place() {
+                        state.insert(place.local);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Runs `f` on the body of
+    /// ```text
+    /// fn f(_1: i32) -> i32 {
+    ///     b0: { _2 = 0; goto -> b1; }
+    ///     b1: { _3 = Lt(_2, _1); switchInt(_3) -> [0: b3, 1: b2]; }
+    ///     b2: { _2 = Add(_2, 1); goto -> b1; }
+    ///     b3: { _0 = _2; return; }
+    /// }
+    /// ```
+    fn with_body<F>(f: F)
+    where
+        F: for<'tcx> FnOnce(&Body<'tcx>),
+    {
+        let arena = TyArena::new();
+        let mut map = SymbolMap::new();
+        let name = map.insert("f");
+        let tcx = TyCtx::new(&arena, &map);
+
+        let int = |value| {
+            Operand::Constant(Box::new(tcx.intern_const(ConstValue {
+                ty: tcx.common_types.i32,
+                literal: ConstLit::Scalar(ScalarInt::from_i32(value)),
+            })))
+        };
+        let local = |id: usize| Place::new(LocalId::from(id));
+        let assign = |id, rvalue| {
+            Statement::new(
+                SourceInfo::new(DUMMY_SP),
+                StatementKind::Assign(Box::new((local(id), rvalue))),
+            )
+        };
+        let block = |stmts, kind| Block {
+            stmts,
+            terminator: Some(Terminator::new(SourceInfo::new(DUMMY_SP), kind)),
+        };
+
+        let mut body = Body::new(DefId::from_usize(0), name);
+        body.arg_count = 1;
+        for ty in [tcx.common_types.i32; 3]
+            .into_iter()
+            .chain([tcx.common_types.bool])
+        {
+            body.local_decls.push(LocalDecl::new(None, ty));
+        }
+        body.blocks.push(block(
+            vec![assign(2, RValue::Use(int(0)))],
+            TerminatorKind::Goto {
+                target: BlockId::from(1),
+            },
+        ));
+        body.blocks.push(block(
+            vec![assign(
+                3,
+                RValue::BinaryOp(
+                    BinOp::Lt,
+                    Box::new((Operand::Copy(local(2)), Operand::Copy(local(1)))),
+                ),
+            )],
+            TerminatorKind::SwitchInt {
+                discr: Operand::Copy(local(3)),
+                switch_ty: tcx.common_types.bool,
+                targets: SwitchTargets {
+                    values: vec![0, 1],
+                    targets: vec![BlockId::from(3), BlockId::from(2)],
+                },
+            },
+        ));
+        body.blocks.push(block(
+            vec![assign(
+                2,
+                RValue::BinaryOp(BinOp::Add, Box::new((Operand::Copy(local(2)), int(1)))),
+            )],
+            TerminatorKind::Goto {
+                target: BlockId::from(1),
+            },
+        ));
+        body.blocks.push(block(
+            vec![assign(0, RValue::Use(Operand::Copy(local(2))))],
+            TerminatorKind::Return,
+        ));
+
+        f(&body);
+    }
+
+    fn location(block: usize, statement_index: usize) -> Location {
+        Location {
+            block: BlockId::from(block),
+            statement_index,
+        }
+    }
+
+    fn locals(set: &BitSet<LocalId>) -> Vec<usize> {
+        set.iter().map(|local| local.index()).collect()
+    }
+
+    #[test]
+    fn predecessors() {
+        with_body(|body| {
+            let predecessors: Vec<Vec<usize>> = body
+                .predecessors()
+                .iter()
+                .map(|blocks| blocks.iter().map(|block| usize::from(*block)).collect())
+                .collect();
+            assert_eq!(predecessors, [vec![], vec![0, 2], vec![1], vec![1]]);
+        });
+    }
+
+    #[test]
+    fn forward_analysis() {
+        with_body(|body| {
+            let results = iterate_to_fixpoint(MaybeAssigned, body);
+            assert_eq!(locals(results.entry_set(BlockId::from(1))), [1, 2, 3]);
+
+            let mut cursor = results.into_results_cursor(body);
+            assert_eq!(locals(cursor.seek_before(location(0, 0))), [1]);
+            assert_eq!(locals(cursor.seek_after(location(0, 0))), [1, 2]);
+            assert_eq!(locals(cursor.seek_before(location(3, 0))), [1, 2, 3]);
+            assert_eq!(locals(cursor.seek_after(location(3, 0))), [0, 1, 2, 3]);
+            // moving backward starts again from the entry of the block.
+            assert_eq!(locals(cursor.seek_before(location(3, 0))), [1, 2, 3]);
+        });
+    }
+
+    #[test]
+    fn backward_analysis() {
+        with_body(|body| {
+            let mut cursor = iterate_to_fixpoint(Liveness, body).into_results_cursor(body);
+            assert_eq!(locals(cursor.seek_before(location(0, 0))), [1]);
+            assert_eq!(locals(cursor.seek_before(location(1, 0))), [1, 2]);
+            assert_eq!(locals(cursor.seek_after(location(1, 0))), [1, 2, 3]);
+            assert_eq!(locals(cursor.seek_before(location(1, 1))), [1, 2, 3]);
+            assert_eq!(locals(cursor.seek_before(location(2, 0))), [1, 2]);
+            assert_eq!(locals(cursor.seek_after(location(3, 0))), [0]);
+            assert_eq!(locals(cursor.seek_before(location(3, 0))), [2]);
+        });
+    }
+}
@@ -1,11 +1,14 @@
+pub mod dataflow;
 pub mod stmt;
 pub mod terminator;
+pub mod validate;
 
 pub mod pp;
 
 use crate::stmt::*;
 use crate::terminator::*;
-use span::Symbol;
+use serde::Serialize;
+use span::{Span, Symbol};
 use ty::{self, res::DefId};
 
 use std::fmt;
@@ -13,11 +16,17 @@ use std::fmt;
 use derive_more::{From, Into};
 use typed_index_collections::TiVec;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct Body<'tcx> {
     pub def: DefId,
     pub name: Symbol,
 
+    /// The types substituted for the type parameters of the function.
+    /// These are the type parameters themselves until the body is monomorphized.
+    pub substs: ty::SubstsRef<'tcx>,
+
+    pub inline: ty::InlineAttr,
+
     pub blocks: TiVec<BlockId, Block<'tcx>>,
 
     /// The first local is return value
@@ -30,12 +39,23 @@ impl<'tcx> Body<'tcx> {
         Body {
             def,
             name,
+            substs: &[],
+            inline: ty::InlineAttr::None,
             blocks: TiVec::new(),
             local_decls: TiVec::new(),
             arg_count: 0,
         }
     }
 
+    pub fn instance(&self) -> ty::Instance<'tcx> {
+        ty::Instance::new(self.def, self.substs)
+    }
+
+    /// Returns `true` if the body still contains type parameters.
+    pub fn is_polymorphic(&self) -> bool {
+        self.substs.iter().any(|ty| ty.has_params())
+    }
+
     pub fn id_return(&self) -> LocalId {
         LocalId(0)
     }
@@ -47,9 +67,31 @@ impl<'tcx> Body<'tcx> {
     pub fn id_locals(&self) -> impl Iterator<Item = LocalId> {
         ((self.arg_count + 1)..).map(|i| LocalId(i))
     }
+
+    /// Returns the blocks the terminator of the block may jump to.
+    pub fn successors(&self, block: BlockId) -> Vec<BlockId> {
+        self.blocks[block]
+            .terminator
+            .as_ref()
+            .expect("The Terminator in the Block is None.")
+            .successors()
+    }
+
+    /// Returns the blocks which may jump to each block.
+    pub fn predecessors(&self) -> TiVec<BlockId, Vec<BlockId>> {
+        let mut predecessors: TiVec<BlockId, Vec<BlockId>> =
+            self.blocks.iter().map(|_| Vec::new()).collect();
+        for block in self.blocks.keys() {
+            for target in self.successors(block) {
+                predecessors[target].push(block);
+            }
+        }
+
+        predecessors
+    }
 }
 
-#[derive(Debug, From, Into, PartialEq, Eq, Clone, Copy, Hash)]
+#[derive(Debug, From, Into, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Serialize)]
 pub struct BlockId(usize);
 
 impl BlockId {
@@ -58,7 +100,13 @@ impl BlockId {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+impl fmt::Display for BlockId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "b{}", self.0)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct Block<'tcx> {
     pub stmts: Vec<Statement<'tcx>>,
     pub terminator: Option<Terminator<'tcx>>,
@@ -73,22 +121,98 @@ impl<'tcx> Block<'tcx> {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// The location of a statement in the body.
+/// `statement_index` equal to the number of statements in the block refers to the terminator.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Serialize)]
+pub struct Location {
+    pub block: BlockId,
+    pub statement_index: usize,
+}
+
+/// A location is printed as the block and the index in it: `b1[2]`
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]", self.block, self.statement_index)
+    }
+}
+
+/// The source location of a statement or a terminator.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+pub struct SourceInfo {
+    pub span: Span,
+}
+
+impl SourceInfo {
+    pub fn new(span: Span) -> Self {
+        SourceInfo { span }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct Place {
     pub local: LocalId,
+
+    /// Projections applied to the local, from the innermost to the outermost.
+    pub projection: Vec<PlaceElem>,
 }
 
 impl Place {
     pub fn new(idx: LocalId) -> Self {
-        Place { local: idx }
+        Place {
+            local: idx,
+            projection: Vec::new(),
+        }
     }
 
     pub fn local(&self) -> LocalId {
         self.local
     }
+
+    /// Returns the place `*self`.
+    pub fn deref(mut self) -> Self {
+        self.projection.push(PlaceElem::Deref);
+        self
+    }
+
+    /// Returns the place `self.idx`.
+    pub fn field(mut self, idx: usize) -> Self {
+        self.projection.push(PlaceElem::Field(idx));
+        self
+    }
+
+    /// Returns `true` if this place accesses memory through a pointer.
+    pub fn is_indirect(&self) -> bool {
+        self.projection.contains(&PlaceElem::Deref)
+    }
+
+    pub fn ty<'tcx>(&self, body: &Body<'tcx>) -> ty::Ty<'tcx> {
+        let local_ty = body.local_decls[self.local].ty;
+        self.projection
+            .iter()
+            .fold(local_ty, |ty, elem| match elem {
+                PlaceElem::Deref => {
+                    ty.builtin_deref()
+                        .unwrap_or_else(|| panic!("type `{}` cannot be dereferenced", ty))
+                        .0
+                }
+                PlaceElem::Field(idx) => match ty.kind() {
+                    ty::TyKind::Closure(closure) => closure.upvars[*idx],
+                    _ => panic!("type `{}` has no fields", ty),
+                },
+            })
+    }
 }
 
-#[derive(Debug, From, Into, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+pub enum PlaceElem {
+    /// Dereference of a reference or a raw pointer: `*p`
+    Deref,
+
+    /// A field of a closure environment: `(*_1).0`
+    Field(usize),
+}
+
+#[derive(Debug, From, Into, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Serialize)]
 pub struct LocalId(usize);
 
 impl LocalId {
@@ -97,7 +221,13 @@ impl LocalId {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+impl fmt::Display for LocalId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "_{}", self.0)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct LocalDecl<'tcx> {
     pub name: Option<String>,
     pub ty: ty::Ty<'tcx>,
@@ -0,0 +1,216 @@
+use crate::*;
+
+use super::MIRPrinter;
+
+use printer::*;
+use span::*;
+
+/// Prints a DOT digraph of the control flow graph of each body.
+/// A node lists the statements and the terminator of a block. An edge is labelled with the jump:
+/// `goto`, a value of `switchInt`, `call` for the return of a call, or `return` to the exit node.
+pub fn print_cfgs(map: &SymbolMap, bodies: &[Body]) -> String {
+    let mut p = MIRPrinter::new(map);
+    p.separated(
+        bodies.iter(),
+        |this| {
+            this.newline();
+            this.newline();
+        },
+        |this, body| this.print_cfg(body),
+    );
+
+    p.finish()
+}
+
+impl MIRPrinter<'_> {
+    fn print_cfg(&mut self, body: &Body) {
+        let name = self.render(|this| this.print_ident(body.name, body.def));
+        self.print_space("digraph");
+        self.print_quoted(&name);
+        self.space();
+        self.with_delim(Delim::Brace, true, |this| {
+            this.print(r#"node [shape="box", fontname="monospace"];"#);
+
+            for (id, block) in body.blocks.iter_enumerated() {
+                let terminator = block
+                    .terminator
+                    .as_ref()
+                    .expect("The Terminator in the Block is None.");
+
+                let mut label = format!("{}:\n", id);
+                for stmt in &block.stmts {
+                    label += &this.render(|this| this.print_stmt(stmt));
+                    label += "\n";
+                }
+                label += &this.render(|this| this.print_terminator_head(terminator));
+                label += "\n";
+
+                this.newline();
+                this.print(id);
+                this.print(" [label=");
+                this.print_quoted(&label);
+                this.print("];");
+            }
+
+            let returns = body.blocks.iter().any(|block| {
+                matches!(
+                    block.terminator.as_ref().map(|t| &t.kind),
+                    Some(TerminatorKind::Return)
+                )
+            });
+            if returns {
+                this.newline();
+                this.print(r#"exit [shape="point"];"#);
+            }
+
+            for (id, block) in body.blocks.iter_enumerated() {
+                let terminator = block
+                    .terminator
+                    .as_ref()
+                    .expect("The Terminator in the Block is None.");
+                for (target, label) in edges(terminator) {
+                    this.newline();
+                    this.print(id);
+                    this.space_print_space("->");
+                    this.print(target);
+                    this.print(" [label=");
+                    this.print_quoted(&label);
+                    this.print("];");
+                }
+            }
+        });
+    }
+
+    /// Prints the terminator without its targets, which are the edges of the graph.
+    fn print_terminator_head(&mut self, terminator: &Terminator) {
+        match &terminator.kind {
+            TerminatorKind::Goto { .. } => self.print("goto"),
+            TerminatorKind::SwitchInt { discr, .. } => {
+                self.print("switchInt");
+                self.with_delim(Delim::Paren, false, |this| this.print_operand(discr));
+            }
+            TerminatorKind::Call {
+                fun,
+                args,
+                destination,
+            } => {
+                if let Some((place, _)) = destination {
+                    self.print_place(place.clone());
+                    self.space();
+                    self.eq();
+                    self.space();
+                }
+                self.print_operand(fun);
+                self.list(args.iter(), Delim::Paren, |this, arg| {
+                    this.print_operand(arg);
+                });
+            }
+            TerminatorKind::Return => self.print("return"),
+        }
+    }
+
+    /// Prints a DOT string, whose lines are left-justified.
+    fn print_quoted(&mut self, s: &str) {
+        let escaped = s
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\l");
+        self.print('"');
+        self.print(escaped);
+        self.print('"');
+    }
+
+    fn render<F: FnOnce(&mut Self)>(&self, f: F) -> String {
+        let mut p = MIRPrinter::new(self.map);
+        f(&mut p);
+        p.finish()
+    }
+}
+
+/// Returns the targets of the terminator with the labels of the edges to them.
+fn edges(terminator: &Terminator) -> Vec<(String, String)> {
+    match &terminator.kind {
+        TerminatorKind::Goto { target } => vec![(target.to_string(), "goto".to_string())],
+        TerminatorKind::SwitchInt { targets, .. } => targets
+            .values
+            .iter()
+            .zip(&targets.targets)
+            .map(|(value, target)| (target.to_string(), value.to_string()))
+            .collect(),
+        TerminatorKind::Call { destination, .. } => destination
+            .iter()
+            .map(|(_, target)| (target.to_string(), "call".to_string()))
+            .collect(),
+        TerminatorKind::Return => vec![("exit".to_string(), "return".to_string())],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ty::{res::DefId, ConstLit, ConstValue, ScalarInt, TyArena, TyCtx};
+
+    #[test]
+    fn cfg() {
+        let arena = TyArena::new();
+        let mut map = SymbolMap::new();
+        let name = map.insert("f");
+        let tcx = TyCtx::new(&arena, &map);
+
+        let source_info = SourceInfo::new(DUMMY_SP);
+        let mut body = Body::new(DefId::from_usize(0), name);
+        let ret = body
+            .local_decls
+            .push_and_get_key(LocalDecl::new(None, tcx.common_types.i32));
+        let cond = body
+            .local_decls
+            .push_and_get_key(LocalDecl::new(None, tcx.common_types.bool));
+
+        body.blocks.push(Block::new(Some(Terminator::new(
+            source_info,
+            TerminatorKind::SwitchInt {
+                discr: Operand::Copy(Place::new(cond)),
+                switch_ty: tcx.common_types.bool,
+                targets: SwitchTargets {
+                    values: vec![0, 1],
+                    targets: vec![BlockId::from(2), BlockId::from(1)],
+                },
+            },
+        ))));
+        body.blocks.push(Block::new(Some(Terminator::new(
+            source_info,
+            TerminatorKind::Goto {
+                target: BlockId::from(2),
+            },
+        ))));
+        let mut exit = Block::new(Some(Terminator::new(source_info, TerminatorKind::Return)));
+        let seven = tcx.intern_const(ConstValue {
+            ty: tcx.common_types.i32,
+            literal: ConstLit::Scalar(ScalarInt::from_i32(7)),
+        });
+        exit.stmts.push(Statement::new(
+            source_info,
+            StatementKind::Assign(Box::new((
+                Place::new(ret),
+                RValue::Use(Operand::Constant(Box::new(seven))),
+            ))),
+        ));
+        body.blocks.push(exit);
+
+        assert_eq!(
+            print_cfgs(&map, &[body]),
+            r#"digraph "f(%0)" {
+    node [shape="box", fontname="monospace"];
+    b0 [label="b0:\lswitchInt(_1)\l"];
+    b1 [label="b1:\lgoto\l"];
+    b2 [label="b2:\l_0 = 7;\lreturn\l"];
+    exit [shape="point"];
+    b0 -> b2 [label="0"];
+    b0 -> b1 [label="1"];
+    b1 -> b2 [label="goto"];
+    b2 -> exit [label="return"];
+}"#
+        );
+    }
+}
@@ -1,3 +1,4 @@
+pub mod graphviz;
 pub mod stmt;
 
 use crate::*;
@@ -61,6 +62,11 @@ impl<'a> MIRPrinter<'a> {
     fn print_body(&mut self, item: &Body) {
         self.print_space("fn");
         self.print_ident(item.name, item.def);
+        if !item.substs.is_empty() {
+            self.list(item.substs.iter(), Delim::Angle, |this, ty| {
+                this.print(ty);
+            });
+        }
 
         self.with_delim(Delim::Brace, true, |this| {
             // print named locals
@@ -122,14 +128,14 @@ impl<'a> MIRPrinter<'a> {
 
     fn print_terminator(&mut self, terminator: &Terminator) {
         let this = self;
-        match terminator {
-            Terminator::Goto { target } => {
+        match &terminator.kind {
+            TerminatorKind::Goto { target } => {
                 this.print("goto");
                 this.space_print_space("->");
                 this.print_block_id(*target);
                 this.semi();
             }
-            Terminator::SwitchInt {
+            TerminatorKind::SwitchInt {
                 discr,
                 switch_ty: _,
                 targets: SwitchTargets { values, targets },
@@ -152,7 +158,7 @@ impl<'a> MIRPrinter<'a> {
                 );
                 this.semi();
             }
-            Terminator::Call {
+            TerminatorKind::Call {
                 fun,
                 args,
                 destination,
@@ -175,7 +181,7 @@ impl<'a> MIRPrinter<'a> {
                 this.print_block_id(*dest_block);
                 this.semi();
             }
-            Terminator::Return => {
+            TerminatorKind::Return => {
                 this.print("return");
                 this.semi();
             }
@@ -191,7 +197,24 @@ impl<'a> MIRPrinter<'a> {
     }
 
     fn print_place(&mut self, place: Place) {
+        for elem in place.projection.iter().rev() {
+            match elem {
+                PlaceElem::Deref => self.print("(*"),
+                PlaceElem::Field(_) => {}
+            }
+        }
+
         self.print_local_id(place.local);
+
+        for elem in place.projection.iter() {
+            match elem {
+                PlaceElem::Deref => self.print(")"),
+                PlaceElem::Field(idx) => {
+                    self.print(".");
+                    self.print(idx);
+                }
+            }
+        }
     }
 
     fn print_local_id(&mut self, local: LocalId) {
@@ -7,8 +7,8 @@ use ty::*;
 
 impl MIRPrinter<'_> {
     pub fn print_stmt(&mut self, stmt: &Statement) {
-        match stmt {
-            Statement::Assign(assign) => {
+        match &stmt.kind {
+            StatementKind::Assign(assign) => {
                 let (lhs, rhs) = assign.as_ref();
                 self.print_place(lhs.clone());
                 self.space();
@@ -16,7 +16,7 @@ impl MIRPrinter<'_> {
                 self.space();
                 self.print_rvalue(rhs);
             }
-            Statement::Println(op) => {
+            StatementKind::Println(op) => {
                 self.print("println");
                 self.with_delim(Delim::Paren, false, |this| {
                     this.print_operand(op);
@@ -29,6 +29,22 @@ impl MIRPrinter<'_> {
     fn print_rvalue(&mut self, rvalue: &RValue) {
         match rvalue {
             RValue::Use(op) => self.print_operand(op),
+            RValue::Ref(kind, place) => {
+                self.print(kind);
+                self.print_place(place.clone());
+            }
+            RValue::AddressOf(mutbl, place) => {
+                self.print("&raw");
+                match mutbl {
+                    Mutability::Mut => self.space_print_space("mut"),
+                    Mutability::Not => self.space_print_space("const"),
+                }
+                self.print_place(place.clone());
+            }
+            RValue::StaticRef(def) => {
+                self.print("&static %");
+                self.print(def);
+            }
             RValue::BinaryOp(bin, operand) => {
                 self.print(bin);
                 self.list(
@@ -45,6 +61,24 @@ impl MIRPrinter<'_> {
                     this.print_operand(operand);
                 });
             }
+            RValue::Cast(kind, operand, ty) => {
+                self.print_operand(operand);
+                self.space_print_space("as");
+                self.print(ty);
+                self.space();
+                self.with_delim(Delim::Paren, false, |this| {
+                    this.print(kind);
+                });
+            }
+            RValue::Aggregate(kind, operands) => {
+                match kind.as_ref() {
+                    AggregateKind::Closure(ty) => self.print(ty),
+                }
+                self.space();
+                self.list(operands.iter(), Delim::Brace, |this, operand| {
+                    this.print_operand(operand);
+                });
+            }
         }
     }
 
@@ -53,14 +87,17 @@ impl MIRPrinter<'_> {
             Operand::Copy(place) => {
                 self.print_place(place.clone());
             }
+            Operand::Move(place) => {
+                self.print_space("move");
+                self.print_place(place.clone());
+            }
             Operand::Constant(constant) => {
-                if let TyKind::FnDef(def) = constant.ty.kind() {
-                    self.print("%");
-                    self.print(def);
+                if let TyKind::FnDef(def, substs) = constant.ty.kind() {
+                    self.print(Instance::new(*def, substs));
                 } else {
                     match &constant.literal {
                         ConstLit::Scalar(scalar) => {
-                            self.print(scalar.data);
+                            self.print(scalar);
                         }
                     }
                 }
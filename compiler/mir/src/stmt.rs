@@ -1,10 +1,45 @@
-use crate::Place;
+use crate::{Body, Place, SourceInfo};
+use serde::Serialize;
 use std::fmt;
 
-use ty::Const;
+use ty::{res::DefId, Const, Mutability, Ty, TyCtx};
 
-#[derive(Debug, PartialEq, Clone)]
-pub enum Statement<'tcx> {
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Statement<'tcx> {
+    pub source_info: SourceInfo,
+    pub kind: StatementKind<'tcx>,
+}
+
+impl<'tcx> Statement<'tcx> {
+    pub fn new(source_info: SourceInfo, kind: StatementKind<'tcx>) -> Self {
+        Statement { source_info, kind }
+    }
+
+    /// Returns the place assigned by the statement.
+    pub fn destination(&self) -> Option<&Place> {
+        match &self.kind {
+            StatementKind::Assign(assign) => Some(&assign.0),
+            StatementKind::Println(_) => None,
+        }
+    }
+
+    /// Returns the operands read by the statement.
+    pub fn operands(&self) -> Vec<&Operand<'tcx>> {
+        match &self.kind {
+            StatementKind::Assign(assign) => match &assign.1 {
+                RValue::Use(operand) | RValue::Cast(_, operand, _) => vec![operand],
+                RValue::UnaryOp(_, operand) => vec![operand.as_ref()],
+                RValue::BinaryOp(_, operands) => vec![&operands.0, &operands.1],
+                RValue::Aggregate(_, operands) => operands.iter().collect(),
+                RValue::Ref(..) | RValue::AddressOf(..) | RValue::StaticRef(_) => Vec::new(),
+            },
+            StatementKind::Println(operand) => vec![operand],
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub enum StatementKind<'tcx> {
     Assign(Box<(Place, RValue<'tcx>)>),
 
     /// Function call of `println`
@@ -12,14 +47,110 @@ pub enum Statement<'tcx> {
     Println(Operand<'tcx>),
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub enum RValue<'tcx> {
     Use(Operand<'tcx>),
+
+    /// Creates a reference to the place: `&_1`, `&mut _1`
+    Ref(BorrowKind, Place),
+
+    /// Creates a raw pointer to the place: `&raw const _1`, `&raw mut _1`
+    AddressOf(Mutability, Place),
+
+    /// Creates a pointer to the static: `&static %3`
+    /// The pointer is a raw pointer for a mutable static, and a shared reference otherwise.
+    StaticRef(DefId),
+
     BinaryOp(BinOp, Box<(Operand<'tcx>, Operand<'tcx>)>),
     UnaryOp(UnOp, Box<Operand<'tcx>>),
+
+    /// Converts the operand to the type: `_1 as &dyn %0 (Unsize)`
+    Cast(CastKind, Operand<'tcx>, Ty<'tcx>),
+
+    /// Creates a value from the operands: `{closure %6} {_3, _4}`
+    Aggregate(Box<AggregateKind<'tcx>>, Vec<Operand<'tcx>>),
+}
+
+impl<'tcx> RValue<'tcx> {
+    /// Returns the type of the value created by the rvalue.
+    pub fn ty(&self, tcx: &TyCtx<'_, 'tcx>, body: &Body<'tcx>) -> Ty<'tcx> {
+        match self {
+            RValue::Use(operand) => operand.ty(body),
+            RValue::Ref(BorrowKind::Shared, place) => {
+                tcx.intern_ref(place.ty(body), Mutability::Not)
+            }
+            RValue::Ref(BorrowKind::Mut, place) => tcx.intern_ref(place.ty(body), Mutability::Mut),
+            RValue::AddressOf(mutbl, place) => tcx.intern_ptr(place.ty(body), *mutbl),
+            RValue::StaticRef(def) => {
+                let def = tcx
+                    .statics
+                    .iter()
+                    .find(|static_def| static_def.def == *def)
+                    .unwrap_or_else(|| panic!("not found static %{}.", def));
+                match def.mutbl {
+                    Mutability::Mut => tcx.intern_ptr(def.value.ty, Mutability::Mut),
+                    Mutability::Not => tcx.intern_ref(def.value.ty, Mutability::Not),
+                }
+            }
+            RValue::BinaryOp(op, operands) => match op {
+                BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div => operands.0.ty(body),
+                BinOp::Eq | BinOp::Lt | BinOp::Le | BinOp::Ne | BinOp::Ge | BinOp::Gt => {
+                    tcx.common_types.bool
+                }
+            },
+            RValue::UnaryOp(_, operand) => operand.ty(body),
+            RValue::Cast(_, _, ty) => *ty,
+            RValue::Aggregate(kind, _) => match kind.as_ref() {
+                AggregateKind::Closure(ty) => *ty,
+            },
+        }
+    }
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub enum AggregateKind<'tcx> {
+    /// The environment of a closure, of the given closure type.
+    Closure(Ty<'tcx>),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+pub enum CastKind {
+    /// Converts a pointer to a sized type into a fat pointer to a trait object,
+    /// attaching the vtable of the type: `&T` to `&dyn Trait`
+    Unsize,
+
+    /// Converts a function item to a function pointer: `%3 as fn(i32) -> i32`
+    ReifyFnPointer,
+}
+
+impl fmt::Display for CastKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CastKind::Unsize => write!(f, "Unsize"),
+            CastKind::ReifyFnPointer => write!(f, "ReifyFnPointer"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+pub enum BorrowKind {
+    /// A shared borrow: `&_1`
+    Shared,
+
+    /// A mutable borrow: `&mut _1`
+    Mut,
+}
+
+impl fmt::Display for BorrowKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BorrowKind::Shared => write!(f, "&"),
+            BorrowKind::Mut => write!(f, "&mut "),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
 pub enum BinOp {
     Add,
     Sub,
@@ -50,7 +181,7 @@ impl fmt::Display for BinOp {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
 pub enum UnOp {
     Neg,
 }
@@ -63,8 +194,37 @@ impl fmt::Display for UnOp {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub enum Operand<'tcx> {
+    /// Copies the value of the place.
     Copy(Place),
+
+    /// Moves the value out of the place. The place is uninitialized afterwards.
+    Move(Place),
+
     Constant(Box<Const<'tcx>>),
 }
+
+impl<'tcx> Operand<'tcx> {
+    pub fn place(&self) -> Option<&Place> {
+        match self {
+            Operand::Copy(place) | Operand::Move(place) => Some(place),
+            Operand::Constant(_) => None,
+        }
+    }
+
+    pub fn ty(&self, body: &Body<'tcx>) -> Ty<'tcx> {
+        match self {
+            Operand::Copy(place) | Operand::Move(place) => place.ty(body),
+            Operand::Constant(constant) => constant.ty,
+        }
+    }
+
+    /// Returns the operand that reads the value without moving it.
+    pub fn to_copy(self) -> Self {
+        match self {
+            Operand::Move(place) => Operand::Copy(place),
+            operand => operand,
+        }
+    }
+}
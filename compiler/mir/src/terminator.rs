@@ -1,8 +1,63 @@
-use crate::{BlockId, Operand, Place};
+use crate::{BlockId, Operand, Place, SourceInfo};
+use serde::Serialize;
 use ty;
 
-#[derive(Debug, PartialEq, Clone)]
-pub enum Terminator<'tcx> {
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Terminator<'tcx> {
+    pub source_info: SourceInfo,
+    pub kind: TerminatorKind<'tcx>,
+}
+
+impl<'tcx> Terminator<'tcx> {
+    pub fn new(source_info: SourceInfo, kind: TerminatorKind<'tcx>) -> Self {
+        Terminator { source_info, kind }
+    }
+
+    pub fn successors(&self) -> Vec<BlockId> {
+        match &self.kind {
+            TerminatorKind::Goto { target } => vec![*target],
+            TerminatorKind::SwitchInt { targets, .. } => targets.targets.clone(),
+            TerminatorKind::Call { destination, .. } => {
+                destination.iter().map(|(_, block)| *block).collect()
+            }
+            TerminatorKind::Return => Vec::new(),
+        }
+    }
+
+    pub fn successors_mut(&mut self) -> Vec<&mut BlockId> {
+        match &mut self.kind {
+            TerminatorKind::Goto { target } => vec![target],
+            TerminatorKind::SwitchInt { targets, .. } => targets.targets.iter_mut().collect(),
+            TerminatorKind::Call { destination, .. } => {
+                destination.iter_mut().map(|(_, block)| block).collect()
+            }
+            TerminatorKind::Return => Vec::new(),
+        }
+    }
+
+    /// Returns the place assigned by the terminator.
+    pub fn destination(&self) -> Option<&Place> {
+        match &self.kind {
+            TerminatorKind::Call {
+                destination: Some((place, _)),
+                ..
+            } => Some(place),
+            _ => None,
+        }
+    }
+
+    /// Returns the operands read by the terminator.
+    pub fn operands(&self) -> Vec<&Operand<'tcx>> {
+        match &self.kind {
+            TerminatorKind::Call { fun, args, .. } => std::iter::once(fun).chain(args).collect(),
+            TerminatorKind::SwitchInt { discr, .. } => vec![discr],
+            TerminatorKind::Goto { .. } | TerminatorKind::Return => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub enum TerminatorKind<'tcx> {
     Goto {
         target: BlockId,
     },
@@ -22,7 +77,7 @@ pub enum Terminator<'tcx> {
     Return,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct SwitchTargets {
     pub values: Vec<u128>,
     pub targets: Vec<BlockId>,
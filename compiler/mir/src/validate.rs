@@ -0,0 +1,375 @@
+//! Validation of MIR.
+//!
+//! The validator checks the invariants that the later passes rely on: every block has a
+//! terminator, every block and local referred to exists, the value assigned to a place has the
+//! type of the place, and each value of a `switchInt` has a target. It runs after lowering and
+//! after each transformation, so a broken body is reported by name instead of crashing codegen.
+
+use crate::{stmt::*, terminator::*, *};
+use ty::TyCtx;
+
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum Violation {
+    #[error("{block} has no terminator")]
+    Unterminated { block: BlockId },
+    #[error("{location} jumps to {target}, which does not exist")]
+    BlockOutOfRange { location: Location, target: BlockId },
+    #[error("{location} uses {local}, which does not exist")]
+    LocalOutOfRange { location: Location, local: LocalId },
+    #[error("{location} assigns a value of type `{found}` to a place of type `{expected}`")]
+    TypeMismatch {
+        location: Location,
+        expected: String,
+        found: String,
+    },
+    #[error("{location} switches on `{found}`, which is not `{expected}`")]
+    SwitchTypeMismatch {
+        location: Location,
+        expected: String,
+        found: String,
+    },
+    #[error("{location} switches on {values} values with {targets} targets")]
+    SwitchTargetsMismatch {
+        location: Location,
+        values: usize,
+        targets: usize,
+    },
+}
+
+/// The violations found in a body, reported with the name of the body.
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("broken MIR in `{name}`: {}", join(.violations))]
+pub struct ValidationError {
+    pub name: String,
+    pub violations: Vec<Violation>,
+}
+
+fn join(violations: &[Violation]) -> String {
+    let violations: Vec<_> = violations.iter().map(|v| v.to_string()).collect();
+    violations.join(", ")
+}
+
+/// Checks the body, and returns all the violations found in it.
+pub fn validate<'tcx>(tcx: &TyCtx<'_, 'tcx>, body: &Body<'tcx>) -> Result<(), ValidationError> {
+    let mut validator = Validator {
+        tcx,
+        body,
+        violations: Vec::new(),
+    };
+    validator.validate_body();
+
+    if validator.violations.is_empty() {
+        Ok(())
+    } else {
+        Err(ValidationError {
+            name: tcx.symbol_map.get(body.name).to_string(),
+            violations: validator.violations,
+        })
+    }
+}
+
+struct Validator<'a, 'ast, 'tcx> {
+    tcx: &'a TyCtx<'ast, 'tcx>,
+    body: &'a Body<'tcx>,
+    violations: Vec<Violation>,
+}
+
+impl<'a, 'ast, 'tcx> Validator<'a, 'ast, 'tcx> {
+    fn validate_body(&mut self) {
+        for (block, data) in self.body.blocks.iter_enumerated() {
+            for (statement_index, stmt) in data.stmts.iter().enumerate() {
+                let location = Location {
+                    block,
+                    statement_index,
+                };
+                self.validate_stmt(location, stmt);
+            }
+
+            let location = Location {
+                block,
+                statement_index: data.stmts.len(),
+            };
+            match &data.terminator {
+                Some(terminator) => self.validate_terminator(location, terminator),
+                None => self.violations.push(Violation::Unterminated { block }),
+            }
+        }
+    }
+
+    fn validate_stmt(&mut self, location: Location, stmt: &Statement<'tcx>) {
+        match &stmt.kind {
+            StatementKind::Assign(assign) => {
+                let (place, rvalue) = assign.as_ref();
+                // the types are only known if the locals exist.
+                let place_valid = self.validate_place(location, place);
+                if !(self.validate_rvalue(location, rvalue) && place_valid) {
+                    return;
+                }
+
+                let expected = place.ty(self.body);
+                let found = rvalue.ty(self.tcx, self.body);
+                if expected != found {
+                    self.violations.push(Violation::TypeMismatch {
+                        location,
+                        expected: expected.to_string(),
+                        found: found.to_string(),
+                    });
+                }
+            }
+            StatementKind::Println(operand) => {
+                self.validate_operand(location, operand);
+            }
+        }
+    }
+
+    fn validate_terminator(&mut self, location: Location, terminator: &Terminator<'tcx>) {
+        for target in terminator.successors() {
+            if self.body.blocks.get(target).is_none() {
+                self.violations
+                    .push(Violation::BlockOutOfRange { location, target });
+            }
+        }
+
+        match &terminator.kind {
+            TerminatorKind::SwitchInt {
+                discr,
+                switch_ty,
+                targets,
+            } => {
+                if self.validate_operand(location, discr) && discr.ty(self.body) != *switch_ty {
+                    self.violations.push(Violation::SwitchTypeMismatch {
+                        location,
+                        expected: switch_ty.to_string(),
+                        found: discr.ty(self.body).to_string(),
+                    });
+                }
+                if targets.values.len() != targets.targets.len() {
+                    self.violations.push(Violation::SwitchTargetsMismatch {
+                        location,
+                        values: targets.values.len(),
+                        targets: targets.targets.len(),
+                    });
+                }
+            }
+            TerminatorKind::Call {
+                fun,
+                args,
+                destination,
+            } => {
+                self.validate_operand(location, fun);
+                for arg in args {
+                    self.validate_operand(location, arg);
+                }
+                if let Some((place, _)) = destination {
+                    self.validate_place(location, place);
+                }
+            }
+            TerminatorKind::Goto { .. } | TerminatorKind::Return => {}
+        }
+    }
+
+    /// Returns `true` if all the locals used by the rvalue exist.
+    fn validate_rvalue(&mut self, location: Location, rvalue: &RValue<'tcx>) -> bool {
+        match rvalue {
+            RValue::Use(operand) | RValue::Cast(_, operand, _) => {
+                self.validate_operand(location, operand)
+            }
+            RValue::UnaryOp(_, operand) => self.validate_operand(location, operand),
+            RValue::Ref(_, place) | RValue::AddressOf(_, place) => {
+                self.validate_place(location, place)
+            }
+            RValue::StaticRef(_) => true,
+            RValue::BinaryOp(_, operands) => {
+                let lhs = self.validate_operand(location, &operands.0);
+                self.validate_operand(location, &operands.1) && lhs
+            }
+            RValue::Aggregate(_, operands) => {
+                let mut valid = true;
+                for operand in operands {
+                    valid &= self.validate_operand(location, operand);
+                }
+                valid
+            }
+        }
+    }
+
+    fn validate_operand(&mut self, location: Location, operand: &Operand<'tcx>) -> bool {
+        match operand.place() {
+            Some(place) => self.validate_place(location, place),
+            None => true,
+        }
+    }
+
+    fn validate_place(&mut self, location: Location, place: &Place) -> bool {
+        if self.body.local_decls.get(place.local).is_some() {
+            return true;
+        }
+
+        self.violations.push(Violation::LocalOutOfRange {
+            location,
+            local: place.local,
+        });
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use span::{Symbol, SymbolMap, DUMMY_SP};
+    use ty::{res::DefId, ConstLit, ConstValue, ScalarInt, TyArena};
+
+    /// Returns the body of `fn f() -> i32 { if 1 < 2 { 3 } else { 3 } }`
+    fn body<'tcx>(tcx: &TyCtx<'_, 'tcx>, name: Symbol) -> Body<'tcx> {
+        let int = |value| {
+            Operand::Constant(Box::new(tcx.intern_const(ConstValue {
+                ty: tcx.common_types.i32,
+                literal: ConstLit::Scalar(ScalarInt::from_i32(value)),
+            })))
+        };
+        let source_info = SourceInfo::new(DUMMY_SP);
+
+        let mut body = Body::new(DefId::from_usize(0), name);
+        let ret = body
+            .local_decls
+            .push_and_get_key(LocalDecl::new(None, tcx.common_types.i32));
+        let cond = body
+            .local_decls
+            .push_and_get_key(LocalDecl::new(None, tcx.common_types.bool));
+
+        let mut entry = Block::new(Some(Terminator::new(
+            source_info,
+            TerminatorKind::SwitchInt {
+                discr: Operand::Copy(Place::new(cond)),
+                switch_ty: tcx.common_types.bool,
+                targets: SwitchTargets {
+                    values: vec![0, 1],
+                    targets: vec![BlockId::from(1), BlockId::from(1)],
+                },
+            },
+        )));
+        entry.stmts.push(Statement::new(
+            source_info,
+            StatementKind::Assign(Box::new((
+                Place::new(cond),
+                RValue::BinaryOp(BinOp::Lt, Box::new((int(1), int(2)))),
+            ))),
+        ));
+        body.blocks.push(entry);
+
+        let mut exit = Block::new(Some(Terminator::new(source_info, TerminatorKind::Return)));
+        exit.stmts.push(Statement::new(
+            source_info,
+            StatementKind::Assign(Box::new((Place::new(ret), RValue::Use(int(3))))),
+        ));
+        body.blocks.push(exit);
+
+        body
+    }
+
+    fn check<F>(f: F) -> Result<(), ValidationError>
+    where
+        F: for<'tcx> FnOnce(&TyCtx<'_, 'tcx>, &mut Body<'tcx>),
+    {
+        let arena = TyArena::new();
+        let mut map = SymbolMap::new();
+        let name = map.insert("f");
+        let tcx = TyCtx::new(&arena, &map);
+
+        let mut body = body(&tcx, name);
+        f(&tcx, &mut body);
+        validate(&tcx, &body)
+    }
+
+    fn violations(result: Result<(), ValidationError>) -> Vec<Violation> {
+        let err = result.unwrap_err();
+        assert_eq!(err.name, "f");
+        err.violations
+    }
+
+    #[test]
+    fn valid_body() {
+        assert_eq!(check(|_, _| {}), Ok(()));
+    }
+
+    #[test]
+    fn unterminated_block() {
+        let result = check(|_, body| body.blocks[BlockId::from(1)].terminator = None);
+        assert_eq!(
+            violations(result),
+            [Violation::Unterminated {
+                block: BlockId::from(1)
+            }]
+        );
+    }
+
+    #[test]
+    fn out_of_range() {
+        let result = check(|_, body| {
+            let entry = &mut body.blocks[BlockId::from(0)];
+            entry.terminator.as_mut().unwrap().kind = TerminatorKind::Goto {
+                target: BlockId::from(2),
+            };
+            if let StatementKind::Assign(assign) = &mut entry.stmts[0].kind {
+                assign.0 = Place::new(LocalId::from(5));
+            }
+        });
+        let location = Location {
+            block: BlockId::from(0),
+            statement_index: 0,
+        };
+        assert_eq!(
+            violations(result),
+            [
+                Violation::LocalOutOfRange {
+                    location,
+                    local: LocalId::from(5)
+                },
+                Violation::BlockOutOfRange {
+                    location: Location {
+                        statement_index: 1,
+                        ..location
+                    },
+                    target: BlockId::from(2)
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn type_mismatch() {
+        let result = check(|tcx, body| {
+            body.local_decls[LocalId::from(1)].ty = tcx.common_types.i32;
+        });
+        let err = result.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "broken MIR in `f`: b0[0] assigns a value of type `bool` to a place of type `i32`, \
+             b0[1] switches on `i32`, which is not `bool`"
+        );
+    }
+
+    #[test]
+    fn switch_targets_mismatch() {
+        let result = check(|_, body| {
+            let terminator = body.blocks[BlockId::from(0)].terminator.as_mut().unwrap();
+            if let TerminatorKind::SwitchInt { targets, .. } = &mut terminator.kind {
+                targets.values.push(2);
+            }
+        });
+        assert_eq!(
+            violations(result),
+            [Violation::SwitchTargetsMismatch {
+                location: Location {
+                    block: BlockId::from(0),
+                    statement_index: 1,
+                },
+                values: 3,
+                targets: 2,
+            }]
+        );
+    }
+}
@@ -0,0 +1,21 @@
+use span::Span;
+
+use thiserror::Error;
+
+/// An error that stops the execution of the program.
+/// Each of them is undefined behavior in the compiled program, except for `NoMain`.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum InterpError {
+    #[error("`main` function not found")]
+    NoMain,
+    #[error("attempt to divide by zero at ({span:?})")]
+    DivisionByZero { span: Span },
+    #[error("attempt to {op} with overflow at ({span:?})")]
+    Overflow { op: &'static str, span: Span },
+    #[error("using uninitialized data at ({span:?})")]
+    UninitRead { span: Span },
+    #[error("dereference of a dangling pointer at ({span:?})")]
+    DanglingPointer { span: Span },
+    #[error("reached the recursion limit of {limit} calls at ({span:?})")]
+    StackOverflow { limit: usize, span: Span },
+}
@@ -0,0 +1,665 @@
+//! Interpreter of MIR.
+//!
+//! The monomorphized bodies are executed directly, starting from `main`, without generating
+//! LLVM-IR. Each local of a call and each static is a separate allocation, which is freed when
+//! the call returns, and a pointer is an allocation with the path of the fields in it.
+//! The execution stops with an error where the compiled program would have undefined behavior:
+//! arithmetic overflow, division by zero, reading an uninitialized local and dereferencing a
+//! pointer to a local of a returned call.
+
+pub mod error;
+mod memory;
+
+use crate::{error::InterpError, memory::*};
+
+use mir::{stmt::*, terminator::*, *};
+use span::Span;
+use ty::{res::DefId, Const, ConstLit, Instance, TyCtx, TyKind};
+
+use anyhow::Result;
+use typed_index_collections::TiVec;
+
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    iter,
+};
+
+/// The maximum depth of calls, beyond which the execution stops as a stack overflow.
+pub const RECURSION_LIMIT: usize = 10_000;
+
+/// Executes `main` and returns its result, printing the output of `println` to stdout.
+/// Generic functions must be monomorphized before this.
+pub fn interpret<'tcx>(tcx: &TyCtx<'_, 'tcx>, bodies: &[Body<'tcx>]) -> Result<i32> {
+    interpret_with_output(tcx, bodies, &mut io::stdout().lock())
+}
+
+/// Executes `main` and returns its result, writing the output of `println` to `out`.
+pub fn interpret_with_output<'tcx, W: Write>(
+    tcx: &TyCtx<'_, 'tcx>,
+    bodies: &[Body<'tcx>],
+    out: &mut W,
+) -> Result<i32> {
+    // the bodies of closures share the name of their function, but have the type of their environment.
+    let main = bodies
+        .iter()
+        .find(|body| {
+            tcx.symbol_map.get(body.name) == "main"
+                && body.substs.is_empty()
+                && tcx
+                    .type_of
+                    .get(body.def)
+                    .is_some_and(|ty| matches!(ty.kind(), TyKind::FnDef(..)))
+        })
+        .ok_or(InterpError::NoMain)?;
+
+    match Interpreter::new(tcx, bodies, out).run(main)? {
+        Value::Int(result) => Ok(result),
+        Value::Unit => Ok(0),
+        value => panic!("`main` returned {:?}, which is not an exit code", value),
+    }
+}
+
+struct Interpreter<'a, 'tcx, W> {
+    tcx: &'a TyCtx<'a, 'tcx>,
+    bodies: HashMap<Instance<'tcx>, &'a Body<'tcx>>,
+    memory: Memory<'tcx>,
+
+    /// The allocation of each static, initialized with its evaluated value.
+    statics: HashMap<DefId, AllocId>,
+
+    /// The calls being executed. The last one is the current call.
+    stack: Vec<Frame<'a, 'tcx>>,
+    out: &'a mut W,
+}
+
+struct Frame<'a, 'tcx> {
+    body: &'a Body<'tcx>,
+    locals: TiVec<LocalId, AllocId>,
+
+    /// The statement or the terminator to execute next.
+    location: Location,
+
+    /// The place in the caller that receives the result, and the block to continue in.
+    /// It is `None` for `main`.
+    destination: Option<(Place, BlockId)>,
+}
+
+impl<'a, 'tcx, W: Write> Interpreter<'a, 'tcx, W> {
+    fn new(tcx: &'a TyCtx<'a, 'tcx>, bodies: &'a [Body<'tcx>], out: &'a mut W) -> Self {
+        let mut memory = Memory::default();
+        let statics = tcx
+            .statics
+            .iter()
+            .map(|def| (def.def, memory.allocate(const_value(def.value))))
+            .collect();
+
+        Interpreter {
+            tcx,
+            bodies: bodies.iter().map(|body| (body.instance(), body)).collect(),
+            memory,
+            statics,
+            stack: Vec::new(),
+            out,
+        }
+    }
+
+    /// Executes the body until it returns, and returns its result.
+    fn run(&mut self, body: &'a Body<'tcx>) -> Result<Value<'tcx>> {
+        self.push_frame(body, Vec::new(), None);
+
+        loop {
+            let frame = self.frame();
+            let (body, location) = (frame.body, frame.location);
+            let block = &body.blocks[location.block];
+
+            match block.stmts.get(location.statement_index) {
+                Some(stmt) => {
+                    self.eval_stmt(stmt)?;
+                    self.frame_mut().location.statement_index += 1;
+                }
+                None => {
+                    let terminator = block
+                        .terminator
+                        .as_ref()
+                        .expect("The Terminator in the Block is None.");
+                    if let Some(result) = self.eval_terminator(terminator)? {
+                        return Ok(result);
+                    }
+                }
+            }
+        }
+    }
+
+    fn frame(&self) -> &Frame<'a, 'tcx> {
+        self.stack.last().expect("no call is being executed.")
+    }
+
+    fn frame_mut(&mut self) -> &mut Frame<'a, 'tcx> {
+        self.stack.last_mut().expect("no call is being executed.")
+    }
+
+    /// Starts a call of the body, allocating its locals and storing the arguments.
+    fn push_frame(
+        &mut self,
+        body: &'a Body<'tcx>,
+        args: Vec<Value<'tcx>>,
+        destination: Option<(Place, BlockId)>,
+    ) {
+        // the arguments follow the return value.
+        let values = iter::once(Value::Uninit)
+            .chain(args)
+            .chain(iter::repeat(Value::Uninit));
+        let locals = body
+            .local_decls
+            .iter()
+            .zip(values)
+            .map(|(_, value)| self.memory.allocate(value))
+            .collect();
+
+        self.stack.push(Frame {
+            body,
+            locals,
+            location: Location {
+                block: body.blocks.first_key().unwrap(),
+                statement_index: 0,
+            },
+            destination,
+        });
+    }
+
+    fn eval_stmt(&mut self, stmt: &Statement<'tcx>) -> Result<()> {
+        let span = stmt.source_info.span;
+        match &stmt.kind {
+            StatementKind::Assign(assign) => {
+                let (place, rvalue) = assign.as_ref();
+                let value = self.eval_rvalue(rvalue, span)?;
+                let ptr = self.place_pointer(place, span)?;
+                self.memory.write(&ptr, value, span)?;
+            }
+            StatementKind::Println(operand) => match self.eval_operand(operand, span)? {
+                Value::Bool(value) => writeln!(self.out, "{}", value)?,
+                Value::Int(value) => writeln!(self.out, "{}", value)?,
+                value => panic!("Tried to print {:?}", value),
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Executes the terminator, and returns the result of `main` when it returns.
+    fn eval_terminator(&mut self, terminator: &Terminator<'tcx>) -> Result<Option<Value<'tcx>>> {
+        let span = terminator.source_info.span;
+        match &terminator.kind {
+            TerminatorKind::Goto { target } => self.goto(*target),
+            TerminatorKind::SwitchInt { discr, targets, .. } => {
+                let value = match self.eval_operand(discr, span)? {
+                    Value::Bool(value) => value as u128,
+                    Value::Int(value) => value as u32 as u128,
+                    value => panic!("Tried to switch on {:?}", value),
+                };
+                let index = targets
+                    .values
+                    .iter()
+                    .position(|target_value| *target_value == value)
+                    .unwrap_or_else(|| panic!("no target of switchInt for {}", value));
+                self.goto(targets.targets[index]);
+            }
+            TerminatorKind::Call {
+                fun,
+                args,
+                destination,
+            } => {
+                if self.stack.len() >= RECURSION_LIMIT {
+                    return Err(InterpError::StackOverflow {
+                        limit: RECURSION_LIMIT,
+                        span,
+                    }
+                    .into());
+                }
+
+                let mut args = args
+                    .iter()
+                    .map(|arg| self.eval_operand(arg, span))
+                    .collect::<Result<Vec<_>>>()?;
+                let instance = self.callee(fun, &mut args, span)?;
+                let body = *self
+                    .bodies
+                    .get(&instance)
+                    .unwrap_or_else(|| panic!("not found the body of function %{}.", instance.def));
+                self.push_frame(body, args, destination.clone());
+            }
+            TerminatorKind::Return => {
+                let frame = self.stack.pop().unwrap();
+                let ret_ptr = Pointer::new(frame.locals[frame.body.id_return()]);
+                let result = match self.memory.read(&ret_ptr, span)? {
+                    Value::Uninit if frame.body.local_decls[frame.body.id_return()].ty.is_zst() => {
+                        Value::Unit
+                    }
+                    Value::Uninit => return Err(InterpError::UninitRead { span }.into()),
+                    value => value.clone(),
+                };
+                for alloc in frame.locals {
+                    self.memory.deallocate(alloc);
+                }
+
+                match frame.destination {
+                    Some((place, target)) => {
+                        let ptr = self.place_pointer(&place, span)?;
+                        self.memory.write(&ptr, result, span)?;
+                        self.goto(target);
+                    }
+                    None => return Ok(Some(result)),
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn goto(&mut self, target: BlockId) {
+        self.frame_mut().location = Location {
+            block: target,
+            statement_index: 0,
+        };
+    }
+
+    /// Returns the function to call.
+    /// A trait method called on a trait object is looked up in the vtable of the receiver
+    /// `args[0]`, and the address of the receiver value is passed as `self`.
+    fn callee(
+        &self,
+        fun: &Operand<'tcx>,
+        args: &mut [Value<'tcx>],
+        span: Span,
+    ) -> Result<Instance<'tcx>> {
+        if let Operand::Constant(constant) = fun {
+            if let TyKind::FnDef(def, substs) = constant.ty.kind() {
+                if let Some(index) = self.tcx.traits.vtable_index(*def, substs) {
+                    let (data, trait_def, self_ty) = match &args[0] {
+                        Value::FatPtr(data, trait_def, self_ty) => {
+                            (data.clone(), *trait_def, *self_ty)
+                        }
+                        value => panic!("Tried to call a method of trait object {:?}", value),
+                    };
+                    args[0] = Value::Ptr(data);
+
+                    return Ok(self.tcx.vtable_entries(trait_def, self_ty)[index]);
+                }
+            }
+        }
+
+        match self.eval_operand(fun, span)? {
+            Value::Fn(instance) => Ok(instance),
+            value => panic!("Tried to call {:?}, which is not a function", value),
+        }
+    }
+
+    fn eval_rvalue(&self, rvalue: &RValue<'tcx>, span: Span) -> Result<Value<'tcx>> {
+        let body = self.frame().body;
+        let value = match rvalue {
+            RValue::Use(operand) => self.eval_operand(operand, span)?,
+            // a trait object is only reached through a fat pointer, which is the address itself.
+            RValue::Ref(_, place) | RValue::AddressOf(_, place)
+                if place.ty(body).is_trait_object() =>
+            {
+                let mut fat_ptr = place.clone();
+                fat_ptr.projection.pop();
+                self.eval_operand(&Operand::Copy(fat_ptr), span)?
+            }
+            RValue::Ref(_, place) | RValue::AddressOf(_, place) => {
+                Value::Ptr(self.place_pointer(place, span)?)
+            }
+            RValue::StaticRef(def) => Value::Ptr(Pointer::new(self.statics[def])),
+            RValue::BinaryOp(op, operands) => {
+                let (lhs, rhs) = operands.as_ref();
+                let lhs = self.eval_operand(lhs, span)?;
+                let rhs = self.eval_operand(rhs, span)?;
+                binary_op(*op, lhs, rhs, span)?
+            }
+            RValue::UnaryOp(UnOp::Neg, operand) => match self.eval_operand(operand, span)? {
+                Value::Int(value) => Value::Int(
+                    value
+                        .checked_neg()
+                        .ok_or(InterpError::Overflow { op: "negate", span })?,
+                ),
+                value => panic!("Tried to negate {:?}", value),
+            },
+            RValue::Cast(CastKind::Unsize, operand, ty) => {
+                let self_ty = operand.ty(body).builtin_deref().unwrap().0;
+                let trait_def = match ty.builtin_deref().unwrap().0.kind() {
+                    TyKind::Dynamic(def) => *def,
+                    _ => unreachable!(),
+                };
+                match self.eval_operand(operand, span)? {
+                    Value::Ptr(data) => Value::FatPtr(data, trait_def, self_ty),
+                    value => panic!("Tried to unsize {:?}", value),
+                }
+            }
+            RValue::Cast(CastKind::ReifyFnPointer, operand, _) => {
+                self.eval_operand(operand, span)?
+            }
+            RValue::Aggregate(kind, operands) => match kind.as_ref() {
+                AggregateKind::Closure(_) => Value::Closure(
+                    operands
+                        .iter()
+                        .map(|operand| self.eval_operand(operand, span))
+                        .collect::<Result<_>>()?,
+                ),
+            },
+        };
+
+        Ok(value)
+    }
+
+    fn eval_operand(&self, operand: &Operand<'tcx>, span: Span) -> Result<Value<'tcx>> {
+        match operand {
+            Operand::Copy(place) | Operand::Move(place) => {
+                let ptr = self.place_pointer(place, span)?;
+                match self.memory.read(&ptr, span)? {
+                    Value::Uninit => Err(InterpError::UninitRead { span }.into()),
+                    value => Ok(value.clone()),
+                }
+            }
+            Operand::Constant(constant) => Ok(const_value(**constant)),
+        }
+    }
+
+    /// Returns the address of the place in the current call, reading through each deref
+    /// projection.
+    fn place_pointer(&self, place: &Place, span: Span) -> Result<Pointer, InterpError> {
+        let mut ptr = Pointer::new(self.frame().locals[place.local]);
+        for elem in &place.projection {
+            match elem {
+                PlaceElem::Deref => {
+                    ptr = match self.memory.read(&ptr, span)? {
+                        Value::Ptr(ptr) | Value::FatPtr(ptr, ..) => ptr.clone(),
+                        Value::Uninit => return Err(InterpError::UninitRead { span }),
+                        value => panic!("Tried to dereference {:?}", value),
+                    }
+                }
+                PlaceElem::Field(idx) => ptr.fields.push(*idx),
+            }
+        }
+
+        Ok(ptr)
+    }
+}
+
+fn const_value(constant: Const) -> Value {
+    let ConstLit::Scalar(scalar) = &constant.literal;
+    match constant.ty.kind() {
+        TyKind::Bool => Value::Bool(scalar.data != 0),
+        TyKind::Int(_) => Value::Int(scalar.to_i32()),
+        TyKind::FnDef(def, substs) => Value::Fn(Instance::new(*def, substs)),
+        _ if constant.ty.is_zst() => Value::Unit,
+        _ => panic!("Tried to evaluate constant {:?}", constant),
+    }
+}
+
+fn binary_op<'tcx>(
+    op: BinOp,
+    lhs: Value<'tcx>,
+    rhs: Value<'tcx>,
+    span: Span,
+) -> Result<Value<'tcx>, InterpError> {
+    let overflow = |op| InterpError::Overflow { op, span };
+    let value = match (lhs, rhs) {
+        (Value::Int(lhs), Value::Int(rhs)) => match op {
+            BinOp::Add => Value::Int(lhs.checked_add(rhs).ok_or(overflow("add"))?),
+            BinOp::Sub => Value::Int(lhs.checked_sub(rhs).ok_or(overflow("subtract"))?),
+            BinOp::Mul => Value::Int(lhs.checked_mul(rhs).ok_or(overflow("multiply"))?),
+            BinOp::Div if rhs == 0 => return Err(InterpError::DivisionByZero { span }),
+            BinOp::Div => Value::Int(lhs.checked_div(rhs).ok_or(overflow("divide"))?),
+            _ => Value::Bool(compare(op, lhs, rhs)),
+        },
+        (Value::Bool(lhs), Value::Bool(rhs)) => Value::Bool(compare(op, lhs, rhs)),
+        (lhs, rhs) => panic!("Tried to apply {} to {:?} and {:?}", op, lhs, rhs),
+    };
+
+    Ok(value)
+}
+
+fn compare<T: PartialOrd>(op: BinOp, lhs: T, rhs: T) -> bool {
+    match op {
+        BinOp::Eq => lhs == rhs,
+        BinOp::Lt => lhs < rhs,
+        BinOp::Le => lhs <= rhs,
+        BinOp::Ne => lhs != rhs,
+        BinOp::Ge => lhs >= rhs,
+        BinOp::Gt => lhs > rhs,
+        BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div => {
+            panic!("{} is not a comparison", op)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use driver::{Options, Session};
+    use span::source_map::SourceMap;
+
+    /// Runs the program and returns the result of `main` and the printed output.
+    fn run(src: &str) -> (Result<i32, InterpError>, String) {
+        let source_map = SourceMap::new();
+        let sess = Session::from_source_str(
+            &source_map,
+            "main.elaina",
+            src.to_string(),
+            Options::default(),
+        )
+        .unwrap();
+        sess.with_mir_built(|tcx, bodies| {
+            let bodies = sess.monomorphize(tcx, &bodies).unwrap();
+
+            let mut out = Vec::new();
+            let result = interpret_with_output(tcx, bodies.as_slice(), &mut out)
+                .map_err(|err| err.downcast().unwrap());
+
+            (result, String::from_utf8(out).unwrap())
+        })
+        .unwrap()
+    }
+
+    /// Returns the rest of the line of the source from the start of the span.
+    fn line_from(src: &str, span: Span) -> &str {
+        src[span.data().lo as usize..].lines().next().unwrap()
+    }
+
+    #[test]
+    fn recursion_and_println() {
+        let src = r"
+fn main() -> i32 {
+    println(fib(10));
+    println(fib(3) < fib(4));
+    return fib(5);
+}
+
+fn fib(n: i32) -> i32 {
+    if n < 2 { n } else { fib(n - 1) + fib(n - 2) }
+}";
+        assert_eq!(run(src), (Ok(5), "55\ntrue\n".to_string()));
+    }
+
+    #[test]
+    fn loops_and_references() {
+        let src = r"
+fn main() -> i32 {
+    let mut a: i32 = 1;
+    let mut b: i32 = 2;
+    swap(&mut a, &mut b);
+    println(a);
+
+    let mut result: i32 = 1;
+    let mut i: i32 = b;
+    loop {
+        if i == 5 {
+            break;
+        }
+        result = result * 2;
+        i = i + 1;
+    }
+    return result;
+}
+
+fn swap(a: &mut i32, b: &mut i32) -> i32 {
+    let tmp: i32 = *a;
+    *a = *b;
+    *b = tmp;
+    return 0;
+}";
+        assert_eq!(run(src), (Ok(16), "2\n".to_string()));
+    }
+
+    #[test]
+    fn statics() {
+        let src = r"
+const N: i32 = 4 * 8;
+static mut COUNTER: i32 = N;
+
+fn bump() -> i32 {
+    COUNTER = COUNTER + 1;
+    let p = &mut COUNTER;
+    *p = *p + 1;
+    COUNTER
+}
+
+fn main() -> i32 {
+    println(bump());
+    bump()
+}";
+        assert_eq!(run(src), (Ok(36), "34\n".to_string()));
+    }
+
+    #[test]
+    fn closures_fn_pointers_and_trait_objects() {
+        let src = r"
+trait Shape {
+    fn area(&self) -> i32;
+}
+
+impl Shape for i32 {
+    fn area(&self) -> i32 { *self * *self }
+}
+
+fn total<T: Shape>(x: &T, y: &dyn Shape) -> i32 { x.area() + y.area() }
+
+fn apply(f: fn(i32) -> i32, x: i32) -> i32 { f(x) }
+
+fn double(x: i32) -> i32 { x * 2 }
+
+fn main() -> i32 {
+    let mut n: i32 = 1;
+    let mut add = |x: i32| -> i32 { n = n + x; n };
+    add(2);
+    add(3);
+    total(&3, &4) + apply(double, 5) + n
+}";
+        assert_eq!(run(src), (Ok(41), String::new()));
+    }
+
+    #[test]
+    fn division_by_zero() {
+        let src = r"
+fn main() -> i32 {
+    div(1, 0)
+}
+
+fn div(a: i32, b: i32) -> i32 {
+    a / b
+}";
+        assert!(matches!(
+            run(src).0,
+            Err(InterpError::DivisionByZero { .. })
+        ));
+    }
+
+    #[test]
+    fn overflow() {
+        let src = r"
+fn main() -> i32 {
+    let x: i32 = 65536;
+    println(x);
+    x * x
+}";
+        let (result, out) = run(src);
+        assert!(matches!(
+            result,
+            Err(InterpError::Overflow { op: "multiply", .. })
+        ));
+        assert_eq!(out, "65536\n");
+    }
+
+    #[test]
+    fn recursion_limit() {
+        let src = r"
+fn main() -> i32 {
+    f(0)
+}
+
+fn f(n: i32) -> i32 {
+    f(n + 1)
+}";
+        assert!(matches!(run(src).0, Err(InterpError::StackOverflow { .. })));
+    }
+
+    #[test]
+    fn dangling_pointer() {
+        let src = r"
+fn main() -> i32 {
+    let p: *const i32 = local();
+    *p
+}
+
+fn local() -> *const i32 {
+    let x: i32 = 1;
+    let p: *const i32 = &x;
+    p
+}";
+        assert!(matches!(
+            run(src).0,
+            Err(InterpError::DanglingPointer { .. })
+        ));
+    }
+
+    #[test]
+    fn error_spans() {
+        let src = r"
+fn main() -> i32 {
+    println(1);
+    1 / 0
+}";
+        match run(src).0 {
+            Err(InterpError::DivisionByZero { span }) => assert_eq!(line_from(src, span), "1 / 0"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        let src = r"
+fn main() -> i32 {
+    let x: i32 = 65536;
+    let y: i32 = -x;
+    y * x
+}";
+        match run(src).0 {
+            Err(InterpError::Overflow { span, .. }) => assert_eq!(line_from(src, span), "y * x"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        let src = r"
+fn main() -> i32 {
+    f(0)
+}
+
+fn f(n: i32) -> i32 {
+    f(n + 1)
+}";
+        match run(src).0 {
+            Err(InterpError::StackOverflow { span, .. }) => {
+                assert_eq!(line_from(src, span), "f(n + 1)")
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use crate::error::InterpError;
+
+use span::Span;
+use ty::{res::DefId, Instance, Ty};
+
+use derive_more::{From, Into};
+use typed_index_collections::TiVec;
+
+#[derive(Debug, From, Into, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct AllocId(usize);
+
+/// The address of a value: an allocation and the path of the fields to the value in it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Pointer {
+    pub alloc: AllocId,
+    pub fields: Vec<usize>,
+}
+
+impl Pointer {
+    pub fn new(alloc: AllocId) -> Self {
+        Pointer {
+            alloc,
+            fields: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value<'tcx> {
+    /// The value of a local that has not been assigned yet.
+    Uninit,
+
+    /// The value of a zero-sized type: `()`
+    Unit,
+
+    Bool(bool),
+    Int(i32),
+
+    /// A reference or a raw pointer.
+    Ptr(Pointer),
+
+    /// A pointer to a trait object. The trait and the type of the value select the vtable.
+    FatPtr(Pointer, DefId, Ty<'tcx>),
+
+    /// A function item or a function pointer.
+    Fn(Instance<'tcx>),
+
+    /// The environment of a closure, holding the captured variables.
+    Closure(Vec<Value<'tcx>>),
+}
+
+/// The memory of the interpreted program.
+/// Each local of a call and each static is an allocation holding one value.
+#[derive(Debug, Default)]
+pub struct Memory<'tcx> {
+    /// The allocations freed by returning from their call are `None`.
+    allocs: TiVec<AllocId, Option<Value<'tcx>>>,
+}
+
+impl<'tcx> Memory<'tcx> {
+    pub fn allocate(&mut self, value: Value<'tcx>) -> AllocId {
+        self.allocs.push_and_get_key(Some(value))
+    }
+
+    pub fn deallocate(&mut self, alloc: AllocId) {
+        self.allocs[alloc] = None;
+    }
+
+    /// Returns the value at the address.
+    pub fn read(&self, ptr: &Pointer, span: Span) -> Result<&Value<'tcx>, InterpError> {
+        let value = self.allocs[ptr.alloc]
+            .as_ref()
+            .ok_or(InterpError::DanglingPointer { span })?;
+
+        Ok(ptr.fields.iter().fold(value, |value, idx| match value {
+            Value::Closure(fields) => &fields[*idx],
+            _ => panic!("Tried to access field {} of {:?}", idx, value),
+        }))
+    }
+
+    /// Overwrites the value at the address.
+    pub fn write(
+        &mut self,
+        ptr: &Pointer,
+        value: Value<'tcx>,
+        span: Span,
+    ) -> Result<(), InterpError> {
+        let mut place = self.allocs[ptr.alloc]
+            .as_mut()
+            .ok_or(InterpError::DanglingPointer { span })?;
+
+        for idx in &ptr.fields {
+            place = match place {
+                Value::Closure(fields) => &mut fields[*idx],
+                _ => panic!("Tried to access field {} of {:?}", idx, place),
+            };
+        }
+        *place = value;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+use crate::visit::{local_uses, visit_operands_mut};
+use crate::MirPass;
+
+use mir::{stmt::*, terminator::*, *};
+use ty::{Const, ConstLit, ConstValue, ScalarInt, TyCtx, TyKind};
+
+use std::collections::HashMap;
+
+/// Propagates constants and folds the operations on them.
+/// A local assigned only once with a constant is replaced by the constant where it is read,
+/// an operation on constants is replaced by its result, and a `switchInt` on a constant is
+/// replaced by a `goto` to the chosen target.
+/// An operation which overflows or divides by zero is left to fail at run time.
+pub struct ConstProp;
+
+impl<'tcx> MirPass<'tcx> for ConstProp {
+    fn name(&self) -> &'static str {
+        "ConstProp"
+    }
+
+    fn run_pass(&self, tcx: &TyCtx<'_, 'tcx>, body: &mut Body<'tcx>) {
+        // folding an operation may assign a constant to another local.
+        loop {
+            let consts = collect_consts(body);
+            let mut changed = false;
+            visit_operands_mut(body, |operand| {
+                let place = operand.place().filter(|place| place.projection.is_empty());
+                if let Some(constant) = place.and_then(|place| consts.get(&place.local)) {
+                    *operand = Operand::Constant(Box::new(*constant));
+                    changed = true;
+                }
+            });
+
+            for block in body.blocks.iter_mut() {
+                for stmt in &mut block.stmts {
+                    if let StatementKind::Assign(assign) = &mut stmt.kind {
+                        if let Some(constant) = fold_rvalue(tcx, &assign.1) {
+                            assign.1 = RValue::Use(Operand::Constant(Box::new(constant)));
+                            changed = true;
+                        }
+                    }
+                }
+
+                let terminator = block
+                    .terminator
+                    .as_mut()
+                    .expect("The Terminator in the Block is None.");
+                if let Some(target) = fold_switch(terminator) {
+                    terminator.kind = TerminatorKind::Goto { target };
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+    }
+}
+
+/// Returns the locals which always hold a constant.
+fn collect_consts<'tcx>(body: &Body<'tcx>) -> HashMap<LocalId, Const<'tcx>> {
+    let uses = local_uses(body);
+    let mut consts = HashMap::new();
+    for block in body.blocks.iter() {
+        for stmt in &block.stmts {
+            if let StatementKind::Assign(assign) = &stmt.kind {
+                if let (place, RValue::Use(Operand::Constant(constant))) = assign.as_ref() {
+                    if place.projection.is_empty() && uses[place.local].is_single_def() {
+                        consts.insert(place.local, **constant);
+                    }
+                }
+            }
+        }
+    }
+
+    consts
+}
+
+/// A constant of a scalar type.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+enum Scalar {
+    Int(i32),
+    Bool(bool),
+}
+
+fn to_scalar(operand: &Operand) -> Option<Scalar> {
+    let constant = match operand {
+        Operand::Constant(constant) => constant,
+        Operand::Copy(_) | Operand::Move(_) => return None,
+    };
+
+    let ConstLit::Scalar(scalar) = &constant.literal;
+    match constant.ty.kind() {
+        TyKind::Int(_) => Some(Scalar::Int(scalar.to_i32())),
+        TyKind::Bool => Some(Scalar::Bool(scalar.data != 0)),
+        _ => None,
+    }
+}
+
+fn from_scalar<'tcx>(tcx: &TyCtx<'_, 'tcx>, scalar: Scalar) -> Const<'tcx> {
+    match scalar {
+        Scalar::Int(value) => tcx.intern_const(ConstValue {
+            ty: tcx.common_types.i32,
+            literal: ConstLit::Scalar(ScalarInt::from_i32(value)),
+        }),
+        Scalar::Bool(true) => tcx.common_consts.true_,
+        Scalar::Bool(false) => tcx.common_consts.false_,
+    }
+}
+
+fn fold_rvalue<'tcx>(tcx: &TyCtx<'_, 'tcx>, rvalue: &RValue<'tcx>) -> Option<Const<'tcx>> {
+    let scalar = match rvalue {
+        RValue::BinaryOp(op, operands) => {
+            let (lhs, rhs) = (to_scalar(&operands.0)?, to_scalar(&operands.1)?);
+            fold_binary(*op, lhs, rhs)?
+        }
+        RValue::UnaryOp(UnOp::Neg, operand) => match to_scalar(operand)? {
+            Scalar::Int(value) => Scalar::Int(value.checked_neg()?),
+            Scalar::Bool(_) => return None,
+        },
+        _ => return None,
+    };
+
+    Some(from_scalar(tcx, scalar))
+}
+
+fn fold_binary(op: BinOp, lhs: Scalar, rhs: Scalar) -> Option<Scalar> {
+    let value = match (op, lhs, rhs) {
+        (BinOp::Add, Scalar::Int(lhs), Scalar::Int(rhs)) => Scalar::Int(lhs.checked_add(rhs)?),
+        (BinOp::Sub, Scalar::Int(lhs), Scalar::Int(rhs)) => Scalar::Int(lhs.checked_sub(rhs)?),
+        (BinOp::Mul, Scalar::Int(lhs), Scalar::Int(rhs)) => Scalar::Int(lhs.checked_mul(rhs)?),
+        (BinOp::Div, Scalar::Int(lhs), Scalar::Int(rhs)) => Scalar::Int(lhs.checked_div(rhs)?),
+        (BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div, ..) => return None,
+        (BinOp::Eq, ..) => Scalar::Bool(lhs == rhs),
+        (BinOp::Lt, ..) => Scalar::Bool(lhs < rhs),
+        (BinOp::Le, ..) => Scalar::Bool(lhs <= rhs),
+        (BinOp::Ne, ..) => Scalar::Bool(lhs != rhs),
+        (BinOp::Ge, ..) => Scalar::Bool(lhs >= rhs),
+        (BinOp::Gt, ..) => Scalar::Bool(lhs > rhs),
+    };
+
+    Some(value)
+}
+
+/// Returns the target chosen by a `switchInt` on a constant.
+fn fold_switch(terminator: &Terminator) -> Option<BlockId> {
+    if let TerminatorKind::SwitchInt {
+        discr: Operand::Constant(constant),
+        targets,
+        ..
+    } = &terminator.kind
+    {
+        let ConstLit::Scalar(scalar) = &constant.literal;
+        let index = targets
+            .values
+            .iter()
+            .position(|value| *value == scalar.data)?;
+        return Some(targets.targets[index]);
+    }
+
+    None
+}
@@ -0,0 +1,58 @@
+use crate::visit::{local_uses, visit_places_mut};
+use crate::MirPass;
+
+use mir::{stmt::*, *};
+use ty::TyCtx;
+
+use std::collections::HashMap;
+
+/// Propagates copies between locals.
+/// For a copy `_2 = _1` where both locals are assigned only once and never borrowed, `_2` always
+/// holds the value of `_1`, so the copy is removed and `_2` is replaced by `_1` everywhere.
+pub struct CopyProp;
+
+impl<'tcx> MirPass<'tcx> for CopyProp {
+    fn name(&self) -> &'static str {
+        "CopyProp"
+    }
+
+    fn run_pass(&self, _tcx: &TyCtx<'_, 'tcx>, body: &mut Body<'tcx>) {
+        let uses = local_uses(body);
+        let return_place = body.id_return();
+        let mut copies = HashMap::new();
+        for block in body.blocks.iter_mut() {
+            block.stmts.retain(|stmt| match copied_locals(stmt) {
+                // the return value is read by `return`, so it is left assigned.
+                Some((dest, src))
+                    if dest != return_place
+                        && uses[dest].is_single_def()
+                        && uses[src].is_single_def() =>
+                {
+                    copies.insert(dest, src);
+                    false
+                }
+                _ => true,
+            });
+        }
+
+        visit_places_mut(body, |place| {
+            // the source of a copy may be the destination of another.
+            while let Some(src) = copies.get(&place.local) {
+                place.local = *src;
+            }
+        });
+    }
+}
+
+/// Returns the destination and the source if the statement copies a local to another.
+fn copied_locals(stmt: &Statement) -> Option<(LocalId, LocalId)> {
+    if let StatementKind::Assign(assign) = &stmt.kind {
+        if let (dest, RValue::Use(Operand::Copy(src) | Operand::Move(src))) = assign.as_ref() {
+            if dest.projection.is_empty() && src.projection.is_empty() && dest.local != src.local {
+                return Some((dest.local, src.local));
+            }
+        }
+    }
+
+    None
+}
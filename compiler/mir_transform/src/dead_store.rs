@@ -0,0 +1,83 @@
+use crate::visit::local_uses;
+use crate::MirPass;
+
+use mir::dataflow::{impls::MaybeLiveLocals, iterate_to_fixpoint};
+use mir::{stmt::*, *};
+use ty::TyCtx;
+
+/// Removes the assignments to locals which are not read afterwards.
+/// Only the locals which are never borrowed are considered, and an arithmetic operation is kept
+/// since it may fail at run time.
+pub struct DeadStoreElimination;
+
+impl<'tcx> MirPass<'tcx> for DeadStoreElimination {
+    fn name(&self) -> &'static str {
+        "DeadStoreElimination"
+    }
+
+    fn run_pass(&self, _tcx: &TyCtx<'_, 'tcx>, body: &mut Body<'tcx>) {
+        // removing a store may leave the operands it read dead as well.
+        loop {
+            let dead_stores = find_dead_stores(body);
+            if dead_stores.is_empty() {
+                break;
+            }
+
+            // the statements are removed from the last, so the indices of the others are kept.
+            for location in dead_stores.into_iter().rev() {
+                body.blocks[location.block]
+                    .stmts
+                    .remove(location.statement_index);
+            }
+        }
+    }
+}
+
+/// Returns the locations of the dead stores, in order.
+fn find_dead_stores(body: &Body) -> Vec<Location> {
+    let uses = local_uses(body);
+    let mut dead_stores = Vec::new();
+    let mut liveness = iterate_to_fixpoint(MaybeLiveLocals, body).into_results_cursor(body);
+    for (block, data) in body.blocks.iter_enumerated() {
+        for (statement_index, stmt) in data.stmts.iter().enumerate() {
+            let assign = match &stmt.kind {
+                StatementKind::Assign(assign) => assign,
+                StatementKind::Println(_) => continue,
+            };
+
+            let (place, rvalue) = assign.as_ref();
+            if !place.projection.is_empty()
+                || place.local == body.id_return()
+                || uses[place.local].borrowed
+                || !is_pure(rvalue)
+            {
+                continue;
+            }
+
+            let location = Location {
+                block,
+                statement_index,
+            };
+            liveness.seek_after(location);
+            if !liveness.get().contains(place.local) {
+                dead_stores.push(location);
+            }
+        }
+    }
+
+    dead_stores
+}
+
+/// Returns `true` if evaluating the rvalue has no effect other than its value.
+fn is_pure(rvalue: &RValue) -> bool {
+    match rvalue {
+        RValue::BinaryOp(op, _) => !matches!(op, BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div),
+        RValue::UnaryOp(UnOp::Neg, _) => false,
+        RValue::Use(_)
+        | RValue::Ref(..)
+        | RValue::AddressOf(..)
+        | RValue::StaticRef(_)
+        | RValue::Cast(..)
+        | RValue::Aggregate(..) => true,
+    }
+}
@@ -0,0 +1,197 @@
+use crate::visit::visit_places_mut;
+use crate::MirPass;
+
+use mir::{stmt::*, terminator::*, *};
+use ty::{res::DefId, InlineAttr, TyCtx, TyKind};
+
+use typed_index_collections::TiVec;
+
+use std::collections::{HashMap, HashSet};
+
+/// The size of the largest function inlined without `#[inline]`,
+/// counted in statements and terminators.
+const INLINE_THRESHOLD: usize = 30;
+
+/// Replaces the calls of functions with their bodies.
+/// A function is inlined if it has `#[inline]` or is small enough, unless it has `#[inline(never)]`
+/// or may call the caller back. Generic functions and the methods called through a trait are
+/// not inlined.
+pub struct Inline<'tcx> {
+    /// The bodies of the functions which may be inlined, as they were when the pass was created.
+    callees: HashMap<DefId, Body<'tcx>>,
+
+    /// The functions called by each function, directly or not.
+    reachable: HashMap<DefId, HashSet<DefId>>,
+}
+
+impl<'tcx> Inline<'tcx> {
+    pub fn new(bodies: &[Body<'tcx>]) -> Self {
+        let calls: HashMap<_, _> = bodies
+            .iter()
+            .map(|body| (body.def, called_functions(body)))
+            .collect();
+        let reachable = calls
+            .keys()
+            .map(|def| {
+                let mut reachable = HashSet::new();
+                let mut worklist = calls[def].clone();
+                while let Some(callee) = worklist.pop() {
+                    if reachable.insert(callee) {
+                        worklist.extend(calls.get(&callee).into_iter().flatten());
+                    }
+                }
+                (*def, reachable)
+            })
+            .collect();
+
+        let callees = bodies
+            .iter()
+            .filter(|body| body.substs.is_empty())
+            .map(|body| (body.def, body.clone()))
+            .collect();
+
+        Inline { callees, reachable }
+    }
+
+    /// Returns the body of the function called by `fun` if it is inlined into `caller`.
+    fn inlined_callee(&self, caller: &Body<'tcx>, fun: &Operand<'tcx>) -> Option<&Body<'tcx>> {
+        let def = match fun {
+            Operand::Constant(constant) => match constant.ty.kind() {
+                TyKind::FnDef(def, []) => *def,
+                _ => return None,
+            },
+            Operand::Copy(_) | Operand::Move(_) => return None,
+        };
+        let callee = self.callees.get(&def)?;
+
+        // a recursive function would be inlined into itself again and again.
+        if def == caller.def || self.reachable[&def].contains(&caller.def) {
+            return None;
+        }
+
+        match callee.inline {
+            InlineAttr::Never => None,
+            InlineAttr::Hint => Some(callee),
+            InlineAttr::None if body_size(callee) <= INLINE_THRESHOLD => Some(callee),
+            InlineAttr::None => None,
+        }
+    }
+}
+
+impl<'tcx> MirPass<'tcx> for Inline<'tcx> {
+    fn name(&self) -> &'static str {
+        "Inline"
+    }
+
+    fn run_pass(&self, tcx: &TyCtx<'_, 'tcx>, body: &mut Body<'tcx>) {
+        // the inlined blocks are appended, so the calls in them are not inlined again.
+        for block in body.blocks.keys().collect::<Vec<_>>() {
+            let terminator = body.blocks[block]
+                .terminator
+                .as_ref()
+                .expect("The Terminator in the Block is None.");
+            let (callee, args, destination) = match &terminator.kind {
+                TerminatorKind::Call {
+                    fun,
+                    args,
+                    destination: Some(destination),
+                } => match self.inlined_callee(body, fun) {
+                    Some(callee) => (callee, args.clone(), destination.clone()),
+                    None => continue,
+                },
+                _ => continue,
+            };
+
+            inline_call(tcx, body, block, callee, args, destination);
+        }
+    }
+}
+
+/// Returns the functions called directly in the body.
+fn called_functions(body: &Body) -> Vec<DefId> {
+    body.blocks
+        .iter()
+        .filter_map(|block| match &block.terminator.as_ref()?.kind {
+            TerminatorKind::Call {
+                fun: Operand::Constant(constant),
+                ..
+            } => match constant.ty.kind() {
+                TyKind::FnDef(def, _) => Some(*def),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+fn body_size(body: &Body) -> usize {
+    body.blocks.iter().map(|block| block.stmts.len() + 1).sum()
+}
+
+/// Replaces the call terminating the block with a jump to a copy of the blocks of the callee.
+/// The locals of the callee, with its return place and its arguments, become temporaries of
+/// the caller, and each `return` of the callee moves the return value to the destination.
+fn inline_call<'tcx>(
+    tcx: &TyCtx<'_, 'tcx>,
+    caller: &mut Body<'tcx>,
+    block: BlockId,
+    callee: &Body<'tcx>,
+    args: Vec<Operand<'tcx>>,
+    (destination, target): (Place, BlockId),
+) {
+    let locals: TiVec<LocalId, LocalId> = callee
+        .local_decls
+        .iter()
+        .map(|decl| caller.local_decls.push_and_get_key(decl.clone()))
+        .collect();
+    let block_offset = caller.blocks.len();
+    let offset = |block: BlockId| BlockId::from(usize::from(block) + block_offset);
+
+    let mut inlined = callee.clone();
+    visit_places_mut(&mut inlined, |place| place.local = locals[place.local]);
+
+    let return_ty = callee.local_decls[callee.id_return()].ty;
+    let return_value = if return_ty.is_zst() {
+        Operand::Constant(Box::new(tcx.intern_const_zst(return_ty)))
+    } else {
+        Operand::Move(Place::new(locals[callee.id_return()]))
+    };
+    for mut data in inlined.blocks {
+        let terminator = data
+            .terminator
+            .as_mut()
+            .expect("The Terminator in the Block is None.");
+        for successor in terminator.successors_mut() {
+            *successor = offset(*successor);
+        }
+
+        if let TerminatorKind::Return = terminator.kind {
+            terminator.kind = TerminatorKind::Goto { target };
+            let source_info = terminator.source_info;
+            data.stmts.push(Statement::new(
+                source_info,
+                StatementKind::Assign(Box::new((
+                    destination.clone(),
+                    RValue::Use(return_value.clone()),
+                ))),
+            ));
+        }
+        caller.blocks.push(data);
+    }
+
+    let data = &mut caller.blocks[block];
+    let terminator = data
+        .terminator
+        .as_mut()
+        .expect("The Terminator in the Block is None.");
+    let source_info = terminator.source_info;
+    terminator.kind = TerminatorKind::Goto {
+        target: offset(BlockId::from(0)),
+    };
+    for (arg, operand) in callee.id_args().zip(args) {
+        data.stmts.push(Statement::new(
+            source_info,
+            StatementKind::Assign(Box::new((Place::new(locals[arg]), RValue::Use(operand)))),
+        ));
+    }
+}
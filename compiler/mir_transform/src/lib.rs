@@ -0,0 +1,386 @@
+//! Optimizations of MIR.
+//!
+//! Each pass rewrites a body in place, and the body is validated after each pass.
+//! The passes run on the bodies which passed the borrow checker, before monomorphization, and
+//! rely on what it guarantees: every local is assigned before it is read, so a local assigned
+//! once holds the same value wherever it is read.
+//!
+//! The bodies are cleaned up first, then the small functions are inlined into their callers,
+//! which are cleaned up again.
+
+mod const_prop;
+mod copy_prop;
+mod dead_store;
+mod inline;
+mod simplify;
+mod visit;
+
+pub use const_prop::ConstProp;
+pub use copy_prop::CopyProp;
+pub use dead_store::DeadStoreElimination;
+pub use inline::Inline;
+pub use simplify::SimplifyCfg;
+
+use mir::{validate::validate, validate::ValidationError, Body};
+use ty::TyCtx;
+
+pub trait MirPass<'tcx> {
+    fn name(&self) -> &'static str;
+
+    fn run_pass(&self, tcx: &TyCtx<'_, 'tcx>, body: &mut Body<'tcx>);
+}
+
+/// Returns the passes which clean up a body, run before and after inlining.
+pub fn optimization_passes<'tcx>() -> Vec<Box<dyn MirPass<'tcx>>> {
+    vec![
+        Box::new(SimplifyCfg),
+        Box::new(ConstProp),
+        Box::new(CopyProp),
+        Box::new(DeadStoreElimination),
+        // the branches on constants are removed by `ConstProp`.
+        Box::new(SimplifyCfg),
+    ]
+}
+
+/// Runs the cleanup passes on the body.
+pub fn clean_up<'tcx, F>(
+    tcx: &TyCtx<'_, 'tcx>,
+    body: &mut Body<'tcx>,
+    dump: &mut F,
+) -> Result<(), ValidationError>
+where
+    F: FnMut(&str, &Body<'tcx>),
+{
+    for pass in &optimization_passes() {
+        run_pass(tcx, pass.as_ref(), body, dump)?;
+    }
+
+    Ok(())
+}
+
+/// Inlines the callees into the cleaned up body, then cleans it up again.
+pub fn inline_callees<'tcx, F>(
+    tcx: &TyCtx<'_, 'tcx>,
+    inline: &Inline<'tcx>,
+    body: &mut Body<'tcx>,
+    dump: &mut F,
+) -> Result<(), ValidationError>
+where
+    F: FnMut(&str, &Body<'tcx>),
+{
+    run_pass(tcx, inline, body, dump)?;
+    clean_up(tcx, body, dump)
+}
+
+fn run_pass<'tcx, F>(
+    tcx: &TyCtx<'_, 'tcx>,
+    pass: &dyn MirPass<'tcx>,
+    body: &mut Body<'tcx>,
+    dump: &mut F,
+) -> Result<(), ValidationError>
+where
+    F: FnMut(&str, &Body<'tcx>),
+{
+    dump(&format!("before {}", pass.name()), body);
+    pass.run_pass(tcx, body);
+    dump(&format!("after {}", pass.name()), body);
+    validate(tcx, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use driver::{Options, Session};
+    use mir::{stmt::*, terminator::*, *};
+    use span::source_map::SourceMap;
+
+    /// Lowers the program to MIR, checks it, and passes the bodies to `f`.
+    fn with_bodies<F>(src: &str, f: F)
+    where
+        F: for<'tcx> FnOnce(&TyCtx<'_, 'tcx>, Vec<Body<'tcx>>),
+    {
+        let source_map = SourceMap::new();
+        let sess = Session::from_source_str(
+            &source_map,
+            "main.elaina",
+            src.to_string(),
+            Options::default(),
+        )
+        .unwrap();
+        sess.with_mir_built(|tcx, bodies| {
+            for body in &bodies {
+                borrowck::borrowck(body).unwrap();
+            }
+
+            f(tcx, bodies)
+        })
+        .unwrap();
+    }
+
+    /// Lowers the program to MIR, and passes the bodies answered by the `optimized_mir` query
+    /// of the driver to `f`, which runs the passes on them if `optimize` is set.
+    fn with_optimized_bodies<F>(src: &str, optimize: bool, f: F)
+    where
+        F: for<'tcx> FnOnce(&TyCtx<'_, 'tcx>, Vec<Body<'tcx>>),
+    {
+        let source_map = SourceMap::new();
+        let options = Options {
+            optimize,
+            ..Options::default()
+        };
+        let sess =
+            Session::from_source_str(&source_map, "main.elaina", src.to_string(), options).unwrap();
+        let hir = sess.lower_to_hir().unwrap();
+        sess.enter(|tcx| {
+            let (thir, tcx) = sess.lower_to_thir(tcx, &hir).unwrap();
+            let queries = sess.mir_queries(&tcx, thir);
+            let bodies = queries
+                .defs()
+                .iter()
+                .map(|def| queries.optimized_mir(*def, &mut |_, _| {}).unwrap())
+                .collect();
+
+            f(&tcx, bodies)
+        });
+    }
+
+    /// Returns the body of the only function after running the passes on it.
+    fn optimized<'tcx>(
+        tcx: &TyCtx<'_, 'tcx>,
+        mut bodies: Vec<Body<'tcx>>,
+        passes: &[&dyn MirPass<'tcx>],
+    ) -> Body<'tcx> {
+        let mut body = bodies.remove(0);
+        for pass in passes {
+            pass.run_pass(tcx, &mut body);
+            validate(tcx, &body).unwrap();
+        }
+
+        body
+    }
+
+    fn statements<'a, 'tcx>(body: &'a Body<'tcx>) -> Vec<&'a StatementKind<'tcx>> {
+        body.blocks
+            .iter()
+            .flat_map(|block| block.stmts.iter().map(|stmt| &stmt.kind))
+            .collect()
+    }
+
+    fn assign<'tcx>(place: LocalId, rvalue: RValue<'tcx>) -> StatementKind<'tcx> {
+        StatementKind::Assign(Box::new((Place::new(place), rvalue)))
+    }
+
+    /// Returns the names of the functions called by the function `name`.
+    fn calls<'ast>(tcx: &TyCtx<'ast, '_>, bodies: &[Body], name: &str) -> Vec<&'ast str> {
+        let name_of = |def| {
+            let body = bodies.iter().find(|body| body.def == def).unwrap();
+            tcx.symbol_map.get(body.name)
+        };
+        let body = bodies
+            .iter()
+            .find(|body| tcx.symbol_map.get(body.name) == name)
+            .unwrap();
+
+        body.blocks
+            .iter()
+            .filter_map(|block| match &block.terminator.as_ref().unwrap().kind {
+                TerminatorKind::Call {
+                    fun: Operand::Constant(constant),
+                    ..
+                } => match constant.ty.kind() {
+                    ty::TyKind::FnDef(def, _) => Some(name_of(*def)),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn simplify_cfg() {
+        let src = r"
+fn f(b: bool) -> i32 {
+    let x: i32 = { { 1 } };
+    if b { x } else { { 2 } }
+}";
+        with_bodies(src, |tcx, bodies| {
+            let body = optimized(tcx, bodies, &[&SimplifyCfg]);
+
+            // the entry with the `switchInt`, the two branches and the join.
+            assert_eq!(body.blocks.len(), 4);
+            let entry = body.blocks[BlockId::from(0)].terminator.as_ref().unwrap();
+            assert!(matches!(entry.kind, TerminatorKind::SwitchInt { .. }));
+        });
+    }
+
+    #[test]
+    fn const_prop() {
+        let src = r"
+fn f() -> i32 {
+    let a: i32 = 2 * 3;
+    if a < 10 { a + 1 } else { 0 }
+}";
+        with_bodies(src, |tcx, bodies| {
+            let body = optimized(tcx, bodies, &[&ConstProp, &SimplifyCfg]);
+
+            assert_eq!(body.blocks.len(), 1);
+            let constant = |value| {
+                Operand::Constant(Box::new(tcx.intern_const(ty::ConstValue {
+                    ty: tcx.common_types.i32,
+                    literal: ty::ConstLit::Scalar(ty::ScalarInt::from_i32(value)),
+                })))
+            };
+            // `a + 1` is folded, the `else` branch is removed with the `switchInt`.
+            assert!(statements(&body).iter().any(|stmt| matches!(
+                stmt,
+                StatementKind::Assign(assign) if assign.1 == RValue::Use(constant(7))
+            )));
+        });
+    }
+
+    #[test]
+    fn overflow_is_not_folded() {
+        let src = r"
+fn f() -> i32 {
+    let a: i32 = 2147483647;
+    a + 1
+}";
+        with_bodies(src, |tcx, bodies| {
+            let body = optimized(tcx, bodies, &[&ConstProp]);
+
+            assert!(statements(&body).iter().any(|stmt| matches!(
+                stmt,
+                StatementKind::Assign(assign) if matches!(assign.1, RValue::BinaryOp(BinOp::Add, _))
+            )));
+        });
+    }
+
+    #[test]
+    fn copy_prop_and_dead_stores() {
+        let src = r"
+fn f(x: i32) -> i32 {
+    let a: i32 = x;
+    let b: i32 = a;
+    let c: bool = b < 0;
+    b
+}";
+        with_bodies(src, |tcx, bodies| {
+            let body = optimized(tcx, bodies, &[&CopyProp, &DeadStoreElimination]);
+
+            let x = Operand::Copy(Place::new(LocalId::from(1)));
+            assert_eq!(
+                statements(&body),
+                [&assign(LocalId::from(0), RValue::Use(x))]
+            );
+        });
+    }
+
+    #[test]
+    fn borrowed_locals_are_kept() {
+        let src = r"
+fn f() -> i32 {
+    let mut x: i32 = 1;
+    let r: &mut i32 = &mut x;
+    *r = 2;
+    x
+}";
+        with_bodies(src, |tcx, bodies| {
+            let body = optimized(tcx, bodies, &[&ConstProp, &CopyProp, &DeadStoreElimination]);
+
+            // `x` is assigned through `r`, so it is not replaced by `1`.
+            let one = statements(&body).iter().any(|stmt| {
+                matches!(stmt, StatementKind::Assign(assign)
+                    if assign.0 == Place::new(LocalId::from(0))
+                        && matches!(assign.1, RValue::Use(Operand::Constant(_))))
+            });
+            assert!(!one);
+        });
+    }
+
+    #[test]
+    fn inline() {
+        let src = r"
+fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[inline(never)]
+fn sub(a: i32, b: i32) -> i32 {
+    a - b
+}
+
+fn fact(n: i32) -> i32 {
+    if n == 0 { 1 } else { n * fact(n - 1) }
+}
+
+fn even(n: i32) -> bool {
+    if n == 0 { true } else { odd(n - 1) }
+}
+
+fn odd(n: i32) -> bool {
+    if n == 0 { false } else { even(n - 1) }
+}
+
+fn main() -> i32 {
+    sub(fact(4), add(2, 3))
+}";
+        with_optimized_bodies(src, true, |tcx, bodies| {
+            // `fact` is inlined into `main`, but not into itself.
+            assert_eq!(calls(tcx, &bodies, "main"), ["fact", "sub"]);
+            assert_eq!(calls(tcx, &bodies, "fact"), ["fact"]);
+            assert_eq!(calls(tcx, &bodies, "even"), ["odd"]);
+            assert_eq!(calls(tcx, &bodies, "odd"), ["even"]);
+        });
+    }
+
+    #[test]
+    fn inline_threshold() {
+        let stmts = "println(1);\n".repeat(40);
+        let src = format!(
+            "fn large() {{ {stmts} }}\n#[inline]\nfn hinted() {{ {stmts} }}\nfn main() {{ large(); hinted(); }}",
+            stmts = stmts
+        );
+        with_optimized_bodies(&src, true, |tcx, bodies| {
+            assert_eq!(calls(tcx, &bodies, "main"), ["large"]);
+        });
+    }
+
+    #[test]
+    fn optimized_program_has_same_result() {
+        let src = r"
+fn square(x: i32) -> i32 {
+    x * x
+}
+
+fn main() -> i32 {
+    let n: i32 = 3 + 2;
+    let mut i: i32 = 0;
+    let mut sum: i32 = 0;
+    loop {
+        if i == n {
+            break;
+        }
+        let sq: i32 = square(i);
+        println(sq);
+        sum = sum + sq;
+        i = i + 1;
+    }
+    let add = |x: i32| x + sum;
+    add(n)
+}";
+        let run = |optimize: bool| {
+            let mut result = None;
+            with_optimized_bodies(src, optimize, |tcx, bodies| {
+                let bodies = monomorphize::monomorphize(tcx, bodies.as_slice());
+                let mut out = Vec::new();
+                let value = mir_interpret::interpret_with_output(tcx, &bodies, &mut out).unwrap();
+                result = Some((value, String::from_utf8(out).unwrap()));
+            });
+            result.unwrap()
+        };
+
+        assert_eq!(run(true), (35, "0\n1\n4\n9\n16\n".to_string()));
+        assert_eq!(run(true), run(false));
+    }
+}
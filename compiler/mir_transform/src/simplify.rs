@@ -0,0 +1,161 @@
+use crate::MirPass;
+
+use mir::{terminator::*, *};
+use ty::TyCtx;
+
+use typed_index_collections::TiVec;
+
+use std::collections::HashSet;
+
+/// Simplifies the control flow graph.
+/// A jump to an empty block is redirected to where the empty block jumps, a block with only one
+/// predecessor is merged into it, and the blocks unreachable from the entry are removed.
+pub struct SimplifyCfg;
+
+impl<'tcx> MirPass<'tcx> for SimplifyCfg {
+    fn name(&self) -> &'static str {
+        "SimplifyCfg"
+    }
+
+    fn run_pass(&self, _tcx: &TyCtx<'_, 'tcx>, body: &mut Body<'tcx>) {
+        simplify_branches(body);
+        collapse_goto_chains(body);
+        // the blocks skipped by the jumps would still count as predecessors.
+        remove_unreachable_blocks(body);
+        merge_blocks(body);
+        remove_unreachable_blocks(body);
+    }
+}
+
+fn entry_block() -> BlockId {
+    BlockId::from(0)
+}
+
+/// Replaces a `switchInt` whose targets are all the same block with a `goto`.
+fn simplify_branches(body: &mut Body) {
+    for block in body.blocks.iter_mut() {
+        let terminator = block
+            .terminator
+            .as_mut()
+            .expect("The Terminator in the Block is None.");
+        if let TerminatorKind::SwitchInt { targets, .. } = &terminator.kind {
+            if let Some((first, rest)) = targets.targets.split_first() {
+                if rest.iter().all(|target| target == first) {
+                    terminator.kind = TerminatorKind::Goto { target: *first };
+                }
+            }
+        }
+    }
+}
+
+/// Returns the block reached from the block by following the jumps of empty blocks.
+fn skip_empty_blocks(body: &Body, mut block: BlockId) -> BlockId {
+    let mut visited = HashSet::new();
+    while visited.insert(block) {
+        let data = &body.blocks[block];
+        match &data
+            .terminator
+            .as_ref()
+            .expect("The Terminator in the Block is None.")
+            .kind
+        {
+            TerminatorKind::Goto { target } if data.stmts.is_empty() => block = *target,
+            _ => break,
+        }
+    }
+
+    block
+}
+
+fn collapse_goto_chains(body: &mut Body) {
+    for block in body.blocks.keys().collect::<Vec<_>>() {
+        let targets: Vec<_> = body
+            .successors(block)
+            .into_iter()
+            .map(|target| skip_empty_blocks(body, target))
+            .collect();
+
+        let terminator = body.blocks[block]
+            .terminator
+            .as_mut()
+            .expect("The Terminator in the Block is None.");
+        for (successor, target) in terminator.successors_mut().into_iter().zip(targets) {
+            *successor = target;
+        }
+    }
+}
+
+/// Merges each block jumping to a block with no other predecessors with its target.
+fn merge_blocks(body: &mut Body) {
+    let mut predecessors = body.predecessors();
+    for block in body.blocks.keys().collect::<Vec<_>>() {
+        while let TerminatorKind::Goto { target } = body.blocks[block]
+            .terminator
+            .as_ref()
+            .expect("The Terminator in the Block is None.")
+            .kind
+        {
+            if target == block || target == entry_block() || predecessors[target].len() != 1 {
+                break;
+            }
+
+            // the target is left unreachable, jumping to itself.
+            let terminator = Terminator::new(
+                body.blocks[target]
+                    .terminator
+                    .as_ref()
+                    .expect("The Terminator in the Block is None.")
+                    .source_info,
+                TerminatorKind::Goto { target },
+            );
+            let merged = std::mem::replace(&mut body.blocks[target], Block::new(Some(terminator)));
+            predecessors[target].clear();
+            for successor in merged
+                .terminator
+                .as_ref()
+                .expect("The Terminator in the Block is None.")
+                .successors()
+            {
+                for predecessor in predecessors[successor].iter_mut() {
+                    if *predecessor == target {
+                        *predecessor = block;
+                    }
+                }
+            }
+
+            let data = &mut body.blocks[block];
+            data.stmts.extend(merged.stmts);
+            data.terminator = merged.terminator;
+        }
+    }
+}
+
+fn remove_unreachable_blocks(body: &mut Body) {
+    let mut reachable = HashSet::new();
+    let mut worklist = vec![entry_block()];
+    while let Some(block) = worklist.pop() {
+        if reachable.insert(block) {
+            worklist.extend(body.successors(block));
+        }
+    }
+
+    // the remaining blocks keep their order, so the entry block stays first.
+    let blocks = std::mem::take(&mut body.blocks);
+    let mut new_ids: TiVec<BlockId, Option<BlockId>> = blocks.iter().map(|_| None).collect();
+    for (block, data) in blocks.into_iter_enumerated() {
+        if reachable.contains(&block) {
+            new_ids[block] = Some(body.blocks.push_and_get_key(data));
+        }
+    }
+
+    for data in body.blocks.iter_mut() {
+        for target in data
+            .terminator
+            .as_mut()
+            .expect("The Terminator in the Block is None.")
+            .successors_mut()
+        {
+            *target = new_ids[*target].unwrap();
+        }
+    }
+}
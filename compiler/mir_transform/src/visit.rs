@@ -0,0 +1,123 @@
+use mir::{stmt::*, terminator::*, *};
+
+use typed_index_collections::TiVec;
+
+/// How a local is used in a body.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct LocalUse {
+    /// The number of assignments to the local, counting the argument passed by the caller.
+    pub defs: usize,
+
+    /// `true` if the local is borrowed, so it may be read or assigned through a pointer.
+    pub borrowed: bool,
+}
+
+impl LocalUse {
+    /// Returns `true` if the local always holds the value of its only assignment.
+    pub(crate) fn is_single_def(&self) -> bool {
+        self.defs == 1 && !self.borrowed
+    }
+}
+
+pub(crate) fn local_uses(body: &Body) -> TiVec<LocalId, LocalUse> {
+    let mut uses: TiVec<LocalId, LocalUse> = body
+        .local_decls
+        .iter()
+        .map(|_| LocalUse::default())
+        .collect();
+    for arg in body.id_args() {
+        uses[arg].defs += 1;
+    }
+
+    for block in body.blocks.iter() {
+        let destinations = block.stmts.iter().map(|stmt| stmt.destination());
+        let terminator = block.terminator.as_ref().map(|t| t.destination());
+        for place in destinations.chain(terminator).flatten() {
+            if !place.is_indirect() {
+                uses[place.local].defs += 1;
+            }
+        }
+
+        for stmt in &block.stmts {
+            if let StatementKind::Assign(assign) = &stmt.kind {
+                if let RValue::Ref(_, place) | RValue::AddressOf(_, place) = &assign.1 {
+                    if !place.is_indirect() {
+                        uses[place.local].borrowed = true;
+                    }
+                }
+            }
+        }
+    }
+
+    uses
+}
+
+/// Calls `f` with each operand in the body.
+pub(crate) fn visit_operands_mut<'tcx, F>(body: &mut Body<'tcx>, mut f: F)
+where
+    F: FnMut(&mut Operand<'tcx>),
+{
+    for block in body.blocks.iter_mut() {
+        for stmt in &mut block.stmts {
+            match &mut stmt.kind {
+                StatementKind::Assign(assign) => match &mut assign.1 {
+                    RValue::Use(operand) | RValue::Cast(_, operand, _) => f(operand),
+                    RValue::UnaryOp(_, operand) => f(operand),
+                    RValue::BinaryOp(_, operands) => {
+                        f(&mut operands.0);
+                        f(&mut operands.1);
+                    }
+                    RValue::Aggregate(_, operands) => operands.iter_mut().for_each(&mut f),
+                    RValue::Ref(..) | RValue::AddressOf(..) | RValue::StaticRef(_) => {}
+                },
+                StatementKind::Println(operand) => f(operand),
+            }
+        }
+
+        if let Some(terminator) = &mut block.terminator {
+            match &mut terminator.kind {
+                TerminatorKind::Call { fun, args, .. } => {
+                    f(fun);
+                    args.iter_mut().for_each(&mut f);
+                }
+                TerminatorKind::SwitchInt { discr, .. } => f(discr),
+                TerminatorKind::Goto { .. } | TerminatorKind::Return => {}
+            }
+        }
+    }
+}
+
+/// Calls `f` with each place in the body, read or assigned.
+pub(crate) fn visit_places_mut<F>(body: &mut Body, mut f: F)
+where
+    F: FnMut(&mut Place),
+{
+    visit_operands_mut(body, |operand| match operand {
+        Operand::Copy(place) | Operand::Move(place) => f(place),
+        Operand::Constant(_) => {}
+    });
+
+    for block in body.blocks.iter_mut() {
+        for stmt in &mut block.stmts {
+            if let StatementKind::Assign(assign) = &mut stmt.kind {
+                let (place, rvalue) = assign.as_mut();
+                f(place);
+                if let RValue::Ref(_, place) | RValue::AddressOf(_, place) = rvalue {
+                    f(place);
+                }
+            }
+        }
+
+        if let Some(Terminator {
+            kind:
+                TerminatorKind::Call {
+                    destination: Some((place, _)),
+                    ..
+                },
+            ..
+        }) = &mut block.terminator
+        {
+            f(place);
+        }
+    }
+}
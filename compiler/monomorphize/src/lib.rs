@@ -0,0 +1,413 @@
+//! Monomorphization of generic functions.
+//!
+//! The collector starts from the functions without type parameters, `main` among them, and
+//! follows the calls in their MIR. Each instance of a generic function that is reached gets its
+//! own copy of the body with the type arguments substituted for the type parameters.
+//! Calls of trait methods are resolved to the methods in the impls of the substituted types.
+//! Calls of trait methods on trait objects are left to the vtables, and the methods in the
+//! vtables of the types coerced to trait objects are collected instead.
+//! Functions converted to function pointers are collected as well, since they may be called
+//! indirectly.
+
+use mir::{stmt::*, terminator::*, *};
+use ty::{ConstValue, Instance, SubstsRef, TyCtx, TyKind};
+
+use std::collections::{HashMap, HashSet};
+
+/// Returns one body for each function instance reachable from the non-generic functions.
+/// No type parameters are left in the returned bodies.
+pub fn monomorphize<'tcx>(tcx: &TyCtx<'_, 'tcx>, bodies: &[Body<'tcx>]) -> Vec<Body<'tcx>> {
+    let generic_bodies: HashMap<_, _> = bodies.iter().map(|body| (body.def, body)).collect();
+
+    let mut worklist: Vec<Instance> = bodies
+        .iter()
+        .filter(|body| !body.is_polymorphic())
+        .map(|body| body.instance())
+        .rev()
+        .collect();
+    let mut visited: HashSet<Instance> = worklist.iter().copied().collect();
+
+    let mut mono_bodies = Vec::new();
+    while let Some(instance) = worklist.pop() {
+        let body = generic_bodies
+            .get(&instance.def)
+            .unwrap_or_else(|| panic!("not found the body of function %{}.", instance.def));
+        let mut body = instantiate(tcx, body, instance.substs);
+        resolve_calls(tcx, &mut body);
+
+        // callees are visited in the order of the calls.
+        let callees: Vec<_> = collect_callees(tcx, &body)
+            .into_iter()
+            .filter(|callee| visited.insert(*callee))
+            .collect();
+        worklist.extend(callees.into_iter().rev());
+
+        mono_bodies.push(body);
+    }
+
+    mono_bodies
+}
+
+/// Returns the functions called in the body, the functions in the vtables it creates,
+/// and the functions it converts to function pointers.
+fn collect_callees<'tcx>(tcx: &TyCtx<'_, 'tcx>, body: &Body<'tcx>) -> Vec<Instance<'tcx>> {
+    let mut callees = Vec::new();
+    for block in body.blocks.iter() {
+        for stmt in &block.stmts {
+            if let StatementKind::Assign(assign) = &stmt.kind {
+                match &assign.1 {
+                    RValue::Cast(CastKind::Unsize, operand, ty) => {
+                        let source = operand.ty(body).builtin_deref().unwrap().0;
+                        let target = ty.builtin_deref().unwrap().0;
+                        if let TyKind::Dynamic(trait_def) = target.kind() {
+                            callees.extend(tcx.vtable_entries(*trait_def, source));
+                        }
+                    }
+                    RValue::Cast(CastKind::ReifyFnPointer, Operand::Constant(constant), _) => {
+                        if let TyKind::FnDef(def, substs) = constant.ty.kind() {
+                            callees.push(Instance::new(*def, substs));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if let Some(Terminator {
+            kind:
+                TerminatorKind::Call {
+                    fun: Operand::Constant(constant),
+                    ..
+                },
+            ..
+        }) = &block.terminator
+        {
+            if let TyKind::FnDef(def, substs) = constant.ty.kind() {
+                if tcx.traits.vtable_index(*def, substs).is_none() {
+                    callees.push(Instance::new(*def, substs));
+                }
+            }
+        }
+    }
+
+    callees
+}
+
+/// Returns a copy of the body in which the type parameters are replaced with `substs`.
+fn instantiate<'tcx>(
+    tcx: &TyCtx<'_, 'tcx>,
+    body: &Body<'tcx>,
+    substs: SubstsRef<'tcx>,
+) -> Body<'tcx> {
+    let mut body = body.clone();
+    body.substs = substs;
+    if substs.is_empty() {
+        return body;
+    }
+
+    for decl in body.local_decls.iter_mut() {
+        decl.ty = tcx.subst(decl.ty, substs);
+    }
+
+    let subst_operand = |operand: &mut Operand<'tcx>| {
+        if let Operand::Constant(constant) = operand {
+            **constant = tcx.subst_const(**constant, substs);
+        }
+    };
+
+    for block in body.blocks.iter_mut() {
+        for stmt in block.stmts.iter_mut() {
+            match &mut stmt.kind {
+                StatementKind::Assign(assign) => match &mut assign.1 {
+                    RValue::Use(operand) => subst_operand(operand),
+                    RValue::UnaryOp(_, operand) => subst_operand(operand),
+                    RValue::BinaryOp(_, operands) => {
+                        subst_operand(&mut operands.0);
+                        subst_operand(&mut operands.1);
+                    }
+                    RValue::Cast(_, operand, ty) => {
+                        subst_operand(operand);
+                        *ty = tcx.subst(*ty, substs);
+                    }
+                    RValue::Aggregate(kind, operands) => {
+                        match kind.as_mut() {
+                            AggregateKind::Closure(ty) => *ty = tcx.subst(*ty, substs),
+                        }
+                        operands.iter_mut().for_each(subst_operand);
+                    }
+                    RValue::Ref(..) | RValue::AddressOf(..) | RValue::StaticRef(_) => {}
+                },
+                StatementKind::Println(operand) => subst_operand(operand),
+            }
+        }
+
+        if let Some(terminator) = &mut block.terminator {
+            match &mut terminator.kind {
+                TerminatorKind::SwitchInt {
+                    discr, switch_ty, ..
+                } => {
+                    subst_operand(discr);
+                    *switch_ty = tcx.subst(*switch_ty, substs);
+                }
+                TerminatorKind::Call { fun, args, .. } => {
+                    subst_operand(fun);
+                    for arg in args {
+                        subst_operand(arg);
+                    }
+                }
+                TerminatorKind::Goto { .. } | TerminatorKind::Return => {}
+            }
+        }
+    }
+
+    body
+}
+
+/// Replaces the called trait methods with the methods in the impls.
+fn resolve_calls<'tcx>(tcx: &TyCtx<'_, 'tcx>, body: &mut Body<'tcx>) {
+    for block in body.blocks.iter_mut() {
+        let constant = match &mut block.terminator {
+            Some(Terminator {
+                kind:
+                    TerminatorKind::Call {
+                        fun: Operand::Constant(constant),
+                        ..
+                    },
+                ..
+            }) => constant,
+            _ => continue,
+        };
+
+        if let TyKind::FnDef(def, substs) = constant.ty.kind() {
+            let instance = tcx.traits.resolve_instance(*def, substs);
+            if instance.def != *def {
+                **constant = tcx.intern_const(ConstValue {
+                    ty: tcx.intern_fn_def(instance.def, instance.substs),
+                    literal: constant.literal.clone(),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use driver::{Options, Session};
+    use span::{source_map::SourceMap, SymbolMap};
+
+    fn with_mono_bodies<F>(src: &str, f: F)
+    where
+        F: for<'ast, 'tcx> FnOnce(&TyCtx<'ast, 'tcx>, &[Body<'tcx>]),
+    {
+        let source_map = SourceMap::new();
+        let sess = Session::from_source_str(
+            &source_map,
+            "main.elaina",
+            src.to_string(),
+            Options::default(),
+        )
+        .unwrap();
+        sess.with_mir_built(|tcx, bodies| f(tcx, monomorphize(tcx, &bodies).as_slice()))
+            .unwrap();
+    }
+
+    /// Returns the names of the bodies with their type arguments: `max<i32>`
+    fn instance_names(map: &SymbolMap, bodies: &[Body]) -> Vec<String> {
+        bodies
+            .iter()
+            .map(|body| {
+                let substs: Vec<_> = body.substs.iter().map(|ty| ty.to_string()).collect();
+                format!("{}<{}>", map.get(body.name), substs.join(", "))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn instance_per_type_arguments() {
+        let src = r"
+fn main() -> i32 {
+    let a: i32 = max(1, 2);
+    let b: i32 = max(3, 4);
+    let c: bool = max(true, false);
+    return a;
+}
+
+fn max<T>(a: T, b: T) -> T {
+    if a < b { b } else { a }
+}";
+        with_mono_bodies(src, |tcx, bodies| {
+            assert_eq!(
+                instance_names(tcx.symbol_map, bodies),
+                ["main<>", "max<i32>", "max<bool>"]
+            );
+            assert!(bodies
+                .iter()
+                .flat_map(|body| body.local_decls.iter())
+                .all(|decl| !decl.ty.has_params()));
+        });
+    }
+
+    #[test]
+    fn instances_called_from_generic_functions() {
+        let src = r"
+fn main() -> i32 {
+    let a: i32 = 1;
+    let r: &i32 = first(&a, &a);
+    return *r;
+}
+
+fn first<T>(a: T, b: T) -> T {
+    id(a)
+}
+
+fn id<U>(x: U) -> U {
+    x
+}
+
+fn unused<T>(x: T) -> T {
+    x
+}";
+        with_mono_bodies(src, |tcx, bodies| {
+            assert_eq!(
+                instance_names(tcx.symbol_map, bodies),
+                ["main<>", "first<&i32>", "id<&i32>"]
+            );
+        });
+    }
+
+    #[test]
+    fn trait_methods_resolved_to_impls() {
+        let src = r"
+trait Double {
+    fn double(&self) -> i32;
+}
+
+impl Double for i32 {
+    fn double(&self) -> i32 { *self * 2 }
+}
+
+impl Double for bool {
+    fn double(&self) -> i32 { 0 }
+}
+
+fn main() -> i32 {
+    let a: i32 = twice(1);
+    twice(true)
+}
+
+fn twice<T: Double>(x: T) -> i32 {
+    x.double()
+}";
+        with_mono_bodies(src, |tcx, bodies| {
+            assert_eq!(
+                instance_names(tcx.symbol_map, bodies),
+                [
+                    "double<>",
+                    "double<>",
+                    "main<>",
+                    "twice<i32>",
+                    "twice<bool>"
+                ]
+            );
+
+            // each instance of `twice` calls the method of the impl for its type argument.
+            let callee = |body| {
+                let calls = collect_callees(tcx, body);
+                assert_eq!(calls.len(), 1);
+                calls[0].def
+            };
+            assert_eq!(callee(&bodies[3]), bodies[0].def);
+            assert_eq!(callee(&bodies[4]), bodies[1].def);
+        });
+    }
+
+    #[test]
+    fn trait_object_methods_collected_from_vtables() {
+        let src = r"
+trait Double {
+    fn double(&self) -> i32;
+}
+
+impl Double for i32 {
+    fn double(&self) -> i32 { *self * 2 }
+}
+
+fn main() -> i32 {
+    let a: i32 = 1;
+    call(&a)
+}
+
+fn call(x: &dyn Double) -> i32 {
+    x.double()
+}";
+        with_mono_bodies(src, |tcx, bodies| {
+            assert_eq!(
+                instance_names(tcx.symbol_map, bodies),
+                ["double<>", "main<>", "call<>"]
+            );
+
+            // the coercion in `main` puts the method of the impl in the vtable.
+            let callees: Vec<_> = collect_callees(tcx, &bodies[1])
+                .iter()
+                .map(|callee| callee.def)
+                .collect();
+            assert_eq!(callees, [bodies[0].def, bodies[2].def]);
+
+            // the call on the trait object is dispatched through the vtable.
+            assert!(collect_callees(tcx, &bodies[2]).is_empty());
+        });
+    }
+
+    #[test]
+    fn reified_functions_collected() {
+        let src = r"
+fn main() -> i32 {
+    let f: fn(i32) -> i32 = id;
+    apply(f, 1)
+}
+
+fn apply(f: fn(i32) -> i32, x: i32) -> i32 {
+    f(x)
+}
+
+fn id<T>(x: T) -> T {
+    x
+}";
+        with_mono_bodies(src, |tcx, bodies| {
+            assert_eq!(
+                instance_names(tcx.symbol_map, bodies),
+                ["main<>", "id<i32>", "apply<>"]
+            );
+
+            // the indirect call in `apply` has no known callee.
+            assert!(collect_callees(tcx, &bodies[2]).is_empty());
+        });
+    }
+
+    #[test]
+    fn closures_instantiated_with_enclosing_function() {
+        let src = r"
+fn main() -> i32 {
+    twice(1, 2)
+}
+
+fn twice<T>(x: T, n: i32) -> i32 {
+    let f = move |m: i32| n * m;
+    f(2)
+}";
+        with_mono_bodies(src, |tcx, bodies| {
+            assert_eq!(
+                instance_names(tcx.symbol_map, bodies),
+                ["main<>", "twice<i32>", "twice<i32>"]
+            );
+
+            // the closure body takes the environment of the instantiated closure type.
+            let closure_ty = bodies[1].local_decls[LocalId::from(3)].ty;
+            let env_ty = bodies[2].local_decls[LocalId::from(1)].ty;
+            assert!(!closure_ty.has_params());
+            assert_eq!(env_ty.builtin_deref().unwrap().0, closure_ty);
+        });
+    }
+}
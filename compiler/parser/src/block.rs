@@ -1,19 +1,26 @@
 use crate::Parser;
 use ast::{token::*, *};
+use span::*;
 
 use anyhow::Result;
 
 impl Parser<'_> {
     pub fn parse_block(&mut self) -> Result<Block> {
+        let lo = self.token.span;
         self.expect(&TokenKind::OpenDelim(DelimToken::Brace))?;
 
         let mut stmts = Vec::new();
-        while !self.consume(&TokenKind::CloseDelim(DelimToken::Brace)) {
+        while self.token.kind != TokenKind::CloseDelim(DelimToken::Brace) {
             let stmt = self.parse_stmt()?;
             stmts.push(stmt);
         }
+        let hi = self.token.span;
+        self.bump();
 
-        Ok(Block { stmts })
+        Ok(Block {
+            stmts,
+            span: Span::new(lo.data().lo, hi.data().hi),
+        })
     }
 }
 
@@ -25,7 +32,7 @@ mod tests {
     macro_rules! test_block {
         ($input: expr, $expected: expr) => {
             let tokens = parse_all_token($input);
-            let result = Parser::new(&tokens).parse_block().unwrap();
+            let result = Parser::without_node_ids(&tokens).parse_block().unwrap();
 
             assert_eq!(result, $expected);
         };
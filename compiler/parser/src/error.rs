@@ -12,4 +12,19 @@ pub enum ParseError {
 
     #[error("SyntaxError: expected Identifier, found {found:?}")]
     NotFoundIdent { found: TokenKind },
+
+    #[error("SyntaxError: expected a trait name before `for`")]
+    ExpectedTraitPath,
+
+    #[error("SyntaxError: unknown attribute `{name}`")]
+    UnknownAttribute { name: String },
+
+    #[error("SyntaxError: expected `inline` or `inline(never)`, found `inline({arg})`")]
+    MalformedInlineAttr { arg: String },
+
+    #[error("SyntaxError: attribute `inline` is only allowed on functions")]
+    MisplacedInlineAttr,
+
+    #[error("file not found for module `{name}`: {path}")]
+    ModuleFileNotFound { name: String, path: String },
 }
@@ -1,4 +1,4 @@
-use crate::{error::ParseError, Parser};
+use crate::Parser;
 
 use ast::{
     token::{self, BinOpToken, DelimToken, TokenKind},
@@ -60,19 +60,17 @@ impl Parser<'_> {
         None
     }
 
+    /// Parse path: `foo`, `foo::bar`, `crate::foo::bar`
     pub fn parse_path(&mut self) -> Result<Path> {
-        if let TokenKind::Ident(name) = self.token.kind {
-            let span = self.token.span;
-            self.bump();
-            return Ok(Path {
-                ident: Ident { name, span },
-            });
+        let mut segments = vec![self.expect_ident()?];
+        while self.consume(&TokenKind::PathSep) {
+            segments.push(self.expect_ident()?);
         }
 
-        Err(ParseError::NotFoundIdent {
-            found: self.token.kind.clone(),
-        }
-        .into())
+        Ok(Path {
+            segments,
+            id: self.new_node_id(),
+        })
     }
 
     pub fn parse_expr(&mut self) -> Result<Expr> {
@@ -107,9 +105,62 @@ impl Parser<'_> {
             return Ok(Expr::Return { expr });
         }
 
+        // Try to parse closure expression
+        if self.token.kind == TokenKind::BinOp(BinOpToken::Or)
+            || self.token.kind == TokenKind::Ident(Kw::Move.into())
+        {
+            return self.parse_closure_expr();
+        }
+
         self.parse_operator_expr()
     }
 
+    /// Parse closure expression: `|x: i32| x + a`, `move || a`, `|x: i32| -> i32 { x }`
+    fn parse_closure_expr(&mut self) -> Result<Expr> {
+        let capture_by = if self.consume_keyword(Kw::Move) {
+            CaptureBy::Value
+        } else {
+            CaptureBy::Ref
+        };
+
+        self.expect(&TokenKind::BinOp(BinOpToken::Or))?;
+        let mut inputs = Vec::new();
+        while !self.consume(&TokenKind::BinOp(BinOpToken::Or)) {
+            let mutbl = self.parse_mutability();
+            let ident = self.expect_ident()?;
+            self.expect(&TokenKind::Colon)?;
+            let ty = self.parse_ty()?;
+            inputs.push(Param {
+                id: self.new_node_id(),
+                mutbl,
+                ty,
+                ident,
+            });
+
+            if self.consume(&TokenKind::BinOp(BinOpToken::Or)) {
+                break;
+            }
+
+            self.expect(&TokenKind::Comma)?;
+        }
+
+        // the body must be a block if the return type is written.
+        let (output, body) = if self.consume(&TokenKind::Arrow) {
+            let output = self.parse_ty()?;
+            (Some(output), self.parse_block_expr()?)
+        } else {
+            (None, self.parse_expr()?)
+        };
+
+        Ok(Expr::Closure {
+            capture_by,
+            inputs,
+            output,
+            body: Box::new(body),
+            id: self.new_node_id(),
+        })
+    }
+
     pub fn parse_expr_with_block(&mut self) -> Result<Option<Expr>> {
         // Try to parse block expression
         if matches!(
@@ -317,39 +368,80 @@ impl Parser<'_> {
 
     fn parse_expr_unary(&mut self) -> Result<Expr> {
         if self.consume(&TokenKind::BinOp(BinOpToken::Minus)) {
-            let expr = self.parse_expr_call()?;
+            let expr = self.parse_expr_unary()?;
             return Ok(Expr::Unary {
                 op: UnOp::Neg,
                 expr: Box::new(expr),
             });
         }
 
+        if self.consume(&TokenKind::BinOp(BinOpToken::Star)) {
+            let expr = self.parse_expr_unary()?;
+            return Ok(Expr::Unary {
+                op: UnOp::Deref,
+                expr: Box::new(expr),
+            });
+        }
+
+        if self.consume(&TokenKind::BinOp(BinOpToken::And)) {
+            let mutbl = self.parse_mutability();
+            let expr = self.parse_expr_unary()?;
+            return Ok(Expr::AddrOf {
+                mutbl,
+                expr: Box::new(expr),
+            });
+        }
+
         self.parse_expr_call()
     }
 
+    /// Parse function calls and method calls: `f(a)`, `x.foo(a)`, `x.foo().bar()`
     fn parse_expr_call(&mut self) -> Result<Expr> {
-        let lhs = self.parse_expr_primary()?;
-
-        if !self.consume(&TokenKind::OpenDelim(DelimToken::Paren)) {
-            return Ok(lhs);
+        let mut expr = self.parse_expr_primary()?;
+
+        loop {
+            if self.token.kind == TokenKind::OpenDelim(DelimToken::Paren) {
+                let (args, span) = self.parse_call_args()?;
+                expr = Expr::Call {
+                    fun: Box::new(expr),
+                    args,
+                    span,
+                };
+            } else if self.consume(&TokenKind::Dot) {
+                let method = self.expect_ident()?;
+                let (args, span) = self.parse_call_args()?;
+                expr = Expr::MethodCall {
+                    receiver: Box::new(expr),
+                    method,
+                    args,
+                    span,
+                };
+            } else {
+                return Ok(expr);
+            }
         }
+    }
+
+    /// Parse arguments of a call and their span: `(a, b)`
+    fn parse_call_args(&mut self) -> Result<(Vec<Expr>, Span)> {
+        let lo = self.token.span;
+        self.expect(&TokenKind::OpenDelim(DelimToken::Paren))?;
 
         let mut args = Vec::new();
-        while !self.consume(&TokenKind::CloseDelim(DelimToken::Paren)) {
+        while self.token.kind != TokenKind::CloseDelim(DelimToken::Paren) {
             let arg = self.parse_expr()?;
             args.push(arg);
 
-            if self.consume(&TokenKind::CloseDelim(DelimToken::Paren)) {
+            if self.token.kind == TokenKind::CloseDelim(DelimToken::Paren) {
                 break;
             }
 
             self.expect(&TokenKind::Comma)?;
         }
+        let hi = self.token.span;
+        self.bump();
 
-        Ok(Expr::Call {
-            fun: Box::new(lhs),
-            args,
-        })
+        Ok((args, Span::new(lo.data().lo, hi.data().hi)))
     }
 
     fn parse_expr_primary(&mut self) -> Result<Expr> {
@@ -380,7 +472,7 @@ mod tests {
     macro_rules! test_lit {
         ($input: expr, $expected: expr) => {
             let tokens = parse_all_token($input);
-            let result = Parser::new(&tokens).parse_lit_opt().unwrap();
+            let result = Parser::without_node_ids(&tokens).parse_lit_opt().unwrap();
 
             assert_eq!(result, $expected);
         };
@@ -389,7 +481,7 @@ mod tests {
     macro_rules! test_expr {
         ($input: expr, $expected: expr) => {
             let tokens = parse_all_token($input);
-            let result = Parser::new(&tokens).parse_expr().unwrap();
+            let result = Parser::without_node_ids(&tokens).parse_expr().unwrap();
 
             assert_eq!(result, $expected);
         };
@@ -798,6 +890,65 @@ mod tests {
         test_expr!("1", Expr::lit_from_value_dummy(1));
     }
 
+    #[test]
+    fn test_parse_deref() {
+        test_expr!(
+            "*a",
+            Expr::unary(UnOp::Deref, Expr::path_dummy(Symbol::ident_nth(0)))
+        );
+        test_expr!(
+            "**a",
+            Expr::unary(
+                UnOp::Deref,
+                Expr::unary(UnOp::Deref, Expr::path_dummy(Symbol::ident_nth(0)))
+            )
+        );
+        test_expr!(
+            "a * *b",
+            Expr::binary(
+                BinOp::Mul,
+                Expr::path_dummy(Symbol::ident_nth(0)),
+                Expr::unary(UnOp::Deref, Expr::path_dummy(Symbol::ident_nth(1)))
+            )
+        );
+        test_expr!(
+            "*a = 1",
+            Expr::assign(
+                Expr::unary(UnOp::Deref, Expr::path_dummy(Symbol::ident_nth(0))),
+                Expr::lit_from_value_dummy(1)
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_addr_of() {
+        test_expr!(
+            "&a",
+            Expr::addr_of(Mutability::Not, Expr::path_dummy(Symbol::ident_nth(0)))
+        );
+        test_expr!(
+            "&mut a",
+            Expr::addr_of(Mutability::Mut, Expr::path_dummy(Symbol::ident_nth(0)))
+        );
+        test_expr!(
+            "&*a",
+            Expr::addr_of(
+                Mutability::Not,
+                Expr::unary(UnOp::Deref, Expr::path_dummy(Symbol::ident_nth(0)))
+            )
+        );
+        test_expr!(
+            "f(&mut a)",
+            Expr::call(
+                Expr::path_dummy(Symbol::ident_nth(0)),
+                [Expr::addr_of(
+                    Mutability::Mut,
+                    Expr::path_dummy(Symbol::ident_nth(1))
+                )]
+            )
+        );
+    }
+
     #[test]
     fn test_parse_primary() {
         test_expr!("1", Expr::lit_from_value_dummy(1));
@@ -823,6 +974,20 @@ mod tests {
                 Expr::lit_from_value_dummy(1)
             )
         );
+        test_expr!(
+            "crate::a::b()",
+            Expr::call(
+                Expr::Path(Path {
+                    segments: vec![
+                        Ident::with_dummy_span(Kw::Crate),
+                        Ident::with_dummy_span(Symbol::ident_nth(0)),
+                        Ident::with_dummy_span(Symbol::ident_nth(1)),
+                    ],
+                    id: DUMMY_NODE_ID,
+                }),
+                []
+            )
+        );
     }
 
     #[test]
@@ -893,4 +1058,100 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn method_call() {
+        test_expr!(
+            "x.f()",
+            Expr::method_call(
+                Expr::path_dummy(Symbol::ident_nth(0)),
+                Symbol::ident_nth(1),
+                []
+            )
+        );
+
+        test_expr!(
+            "x.f(1).g(2, 3) + 1",
+            Expr::binary(
+                BinOp::Add,
+                Expr::method_call(
+                    Expr::method_call(
+                        Expr::path_dummy(Symbol::ident_nth(0)),
+                        Symbol::ident_nth(1),
+                        [Expr::lit_from_value_dummy(1)]
+                    ),
+                    Symbol::ident_nth(3),
+                    [Expr::lit_from_value_dummy(2), Expr::lit_from_value_dummy(3)]
+                ),
+                Expr::lit_from_value_dummy(1)
+            )
+        );
+
+        test_expr!(
+            "-(1).f()",
+            Expr::unary(
+                UnOp::Neg,
+                Expr::method_call(Expr::lit_from_value_dummy(1), Symbol::ident_nth(1), [])
+            )
+        );
+    }
+
+    #[test]
+    fn closure() {
+        test_expr!(
+            "|x: i32| x + a",
+            Expr::closure_dummy(
+                CaptureBy::Ref,
+                [Param::new_dummy(
+                    Ty::path_with_dummy_span(Kw::I32),
+                    Symbol::ident_nth(0)
+                )],
+                None,
+                Expr::binary(
+                    BinOp::Add,
+                    Expr::path_dummy(Symbol::ident_nth(0)),
+                    Expr::path_dummy(Symbol::ident_nth(1))
+                )
+            )
+        );
+
+        test_expr!(
+            "move || a",
+            Expr::closure_dummy(
+                CaptureBy::Value,
+                [],
+                None,
+                Expr::path_dummy(Symbol::ident_nth(0))
+            )
+        );
+
+        test_expr!(
+            "|| -> i32 { 1 }",
+            Expr::closure_dummy(
+                CaptureBy::Ref,
+                [],
+                Some(Ty::path_with_dummy_span(Kw::I32)),
+                Expr::block_from([Stmt::Expr(Expr::lit_from_value_dummy(1))])
+            )
+        );
+
+        test_expr!(
+            "f(|x: i32| x, 1)",
+            Expr::call(
+                Expr::path_dummy(Symbol::ident_nth(0)),
+                [
+                    Expr::closure_dummy(
+                        CaptureBy::Ref,
+                        [Param::new_dummy(
+                            Ty::path_with_dummy_span(Kw::I32),
+                            Symbol::ident_nth(1)
+                        )],
+                        None,
+                        Expr::path_dummy(Symbol::ident_nth(1))
+                    ),
+                    Expr::lit_from_value_dummy(1)
+                ]
+            )
+        );
+    }
 }
@@ -1,7 +1,7 @@
 use crate::{error::ParseError, Parser};
 
 use ast::{
-    token::{DelimToken, TokenKind},
+    token::{BinOpToken, DelimToken, TokenKind},
     *,
 };
 use span::*;
@@ -21,25 +21,206 @@ impl Parser<'_> {
     }
 
     pub fn parse_item(&mut self) -> Result<Item> {
+        let inline = self.parse_inline_attr()?;
+        let vis = self.parse_visibility();
+
         if self.consume_keyword(Kw::Fn) {
+            let (ident, kind) = self.parse_item_fn(inline)?;
+            let id = self.new_node_id();
+            return Ok(Item {
+                id,
+                ident,
+                vis,
+                kind,
+            });
+        }
+
+        if inline != InlineAttr::None {
+            return Err(ParseError::MisplacedInlineAttr.into());
+        }
+
+        if self.consume_keyword(Kw::Trait) {
             let ident = self
                 .expect_ident()
-                .with_context(|| format!("Parsing name of function"))?;
+                .with_context(|| format!("Parsing name of trait"))?;
             let kind = self
-                .parse_fn()
-                .with_context(|| format!("Parsing function"))?;
+                .parse_trait()
+                .with_context(|| format!("Parsing trait"))?;
+
+            let id = self.new_node_id();
+            return Ok(Item {
+                id,
+                ident,
+                vis,
+                kind,
+            });
+        }
+
+        let span = self.token.span;
+        if self.consume_keyword(Kw::Impl) {
+            let ident = Ident {
+                name: Kw::Impl.into(),
+                span,
+            };
+            let kind = self.parse_impl().with_context(|| format!("Parsing impl"))?;
 
-            return Ok(Item { ident, kind });
+            let id = self.new_node_id();
+            return Ok(Item {
+                id,
+                ident,
+                vis,
+                kind,
+            });
+        }
+
+        if self.consume_keyword(Kw::Mod) {
+            let ident = self
+                .expect_ident()
+                .with_context(|| format!("Parsing name of module"))?;
+            let kind = self
+                .parse_mod()
+                .with_context(|| format!("Parsing module"))?;
+
+            let id = self.new_node_id();
+            return Ok(Item {
+                id,
+                ident,
+                vis,
+                kind,
+            });
+        }
+
+        if self.consume_keyword(Kw::Use) {
+            let ident = Ident {
+                name: Kw::Use.into(),
+                span,
+            };
+            let kind = self
+                .parse_use_tree()
+                .with_context(|| format!("Parsing use declaration"))?;
+
+            let id = self.new_node_id();
+            return Ok(Item {
+                id,
+                ident,
+                vis,
+                kind,
+            });
+        }
+
+        if self.consume_keyword(Kw::Const) {
+            let (ident, ty, expr) = self
+                .parse_item_const()
+                .with_context(|| "Parsing constant")?;
+            let kind = ItemKind::Const(Box::new(Const { ty, expr }));
+
+            let id = self.new_node_id();
+            return Ok(Item {
+                id,
+                ident,
+                vis,
+                kind,
+            });
+        }
+
+        if self.consume_keyword(Kw::Static) {
+            let mutbl = self.parse_mutability();
+            let (ident, ty, expr) = self.parse_item_const().with_context(|| "Parsing static")?;
+            let kind = ItemKind::Static(Box::new(Static { mutbl, ty, expr }));
+
+            let id = self.new_node_id();
+            return Ok(Item {
+                id,
+                ident,
+                vis,
+                kind,
+            });
         }
 
         Err(ParseError::UnexpectedToken {
-            expected: vec![TokenKind::Ident(Kw::Fn.into())],
+            expected: vec![
+                TokenKind::Ident(Kw::Fn.into()),
+                TokenKind::Ident(Kw::Trait.into()),
+                TokenKind::Ident(Kw::Impl.into()),
+                TokenKind::Ident(Kw::Mod.into()),
+                TokenKind::Ident(Kw::Use.into()),
+                TokenKind::Ident(Kw::Const.into()),
+                TokenKind::Ident(Kw::Static.into()),
+            ],
             found: self.token.kind.clone(),
         }
         .into())
     }
 
-    fn parse_fn(&mut self) -> Result<ItemKind> {
+    /// Parse optional inlining attribute: `#[inline]`, `#[inline(never)]`
+    fn parse_inline_attr(&mut self) -> Result<InlineAttr> {
+        if !self.consume(&TokenKind::Pound) {
+            return Ok(InlineAttr::None);
+        }
+
+        self.expect(&TokenKind::OpenDelim(DelimToken::Bracket))?;
+        let name = self.expect_ident()?;
+        if self.symbol_map.get(name.name) != "inline" {
+            let name = self.symbol_map.get(name.name).to_string();
+            return Err(ParseError::UnknownAttribute { name }.into());
+        }
+
+        let inline = if self.consume(&TokenKind::OpenDelim(DelimToken::Paren)) {
+            let arg = self.expect_ident()?;
+            if self.symbol_map.get(arg.name) != "never" {
+                let arg = self.symbol_map.get(arg.name).to_string();
+                return Err(ParseError::MalformedInlineAttr { arg }.into());
+            }
+            self.expect(&TokenKind::CloseDelim(DelimToken::Paren))?;
+
+            InlineAttr::Never
+        } else {
+            InlineAttr::Hint
+        };
+        self.expect(&TokenKind::CloseDelim(DelimToken::Bracket))?;
+
+        Ok(inline)
+    }
+
+    /// Parse optional `pub` keyword.
+    fn parse_visibility(&mut self) -> Visibility {
+        if self.consume_keyword(Kw::Pub) {
+            Visibility::Public
+        } else {
+            Visibility::Inherited
+        }
+    }
+
+    /// Parse function item.
+    /// Expect `fn` keyword is already parsed.
+    fn parse_item_fn(&mut self, inline: InlineAttr) -> Result<(Ident, ItemKind)> {
+        let ident = self
+            .expect_ident()
+            .with_context(|| format!("Parsing name of function"))?;
+        let kind = self
+            .parse_fn(inline)
+            .with_context(|| format!("Parsing function"))?;
+
+        Ok((ident, kind))
+    }
+
+    fn parse_fn(&mut self, inline: InlineAttr) -> Result<ItemKind> {
+        let generics = self.parse_generics()?;
+        let inputs = self.parse_fn_params()?;
+        let output = self.parse_fn_output()?;
+
+        let body = self.parse_block()?;
+        Ok(ItemKind::Fn(Box::new(Fn {
+            inline,
+            generics,
+            inputs,
+            output,
+            body,
+        })))
+    }
+
+    /// Parse function parameters: `(a: i32, b: bool)`
+    fn parse_fn_params(&mut self) -> Result<Vec<Param>> {
         self.expect(&TokenKind::OpenDelim(DelimToken::Paren))?;
 
         let mut inputs = Vec::new();
@@ -54,28 +235,247 @@ impl Parser<'_> {
             self.expect(&TokenKind::Comma)?;
         }
 
-        let output = if self.consume(&TokenKind::Arrow) {
-            Some(self.parse_ty()?)
+        Ok(inputs)
+    }
+
+    /// Parse optional return type: `-> i32`
+    fn parse_fn_output(&mut self) -> Result<Option<Ty>> {
+        if self.consume(&TokenKind::Arrow) {
+            Ok(Some(self.parse_ty()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Parse trait body: `{ fn foo(&self) -> i32; }`
+    /// Expect `trait` keyword and the name are already parsed.
+    fn parse_trait(&mut self) -> Result<ItemKind> {
+        let lo = self.token.span;
+        self.expect(&TokenKind::OpenDelim(DelimToken::Brace))?;
+
+        let mut items = Vec::new();
+        while self.token.kind != TokenKind::CloseDelim(DelimToken::Brace) {
+            self.expect(&TokenKind::Ident(Kw::Fn.into()))?;
+            let ident = self
+                .expect_ident()
+                .with_context(|| format!("Parsing name of trait method"))?;
+            let inputs = self.parse_fn_params()?;
+            let output = self.parse_fn_output()?;
+            self.expect(&TokenKind::Semi)?;
+
+            items.push(TraitItem {
+                id: self.new_node_id(),
+                ident,
+                inputs,
+                output,
+            });
+        }
+        let hi = self.token.span;
+        self.bump();
+
+        Ok(ItemKind::Trait(Box::new(Trait {
+            items,
+            span: Span::new(lo.data().lo, hi.data().hi),
+        })))
+    }
+
+    /// Parse impl: `Foo for i32 { .. }`, `i32 { .. }`
+    /// Expect `impl` keyword is already parsed.
+    fn parse_impl(&mut self) -> Result<ItemKind> {
+        let ty = self.parse_ty()?;
+
+        let (of_trait, self_ty) = if self.consume_keyword(Kw::For) {
+            let path = match ty.kind {
+                TyKind::Path(path) => path,
+                _ => return Err(ParseError::ExpectedTraitPath.into()),
+            };
+            (Some(path), self.parse_ty()?)
         } else {
-            None
+            (None, ty)
         };
 
-        let body = self.parse_block()?;
-        Ok(ItemKind::Fn(Box::new(Fn {
-            inputs,
-            output,
-            body,
+        let lo = self.token.span;
+        self.expect(&TokenKind::OpenDelim(DelimToken::Brace))?;
+
+        let mut items = Vec::new();
+        while self.token.kind != TokenKind::CloseDelim(DelimToken::Brace) {
+            let inline = self.parse_inline_attr()?;
+            let vis = self.parse_visibility();
+            self.expect(&TokenKind::Ident(Kw::Fn.into()))?;
+            let (ident, kind) = self.parse_item_fn(inline)?;
+            items.push(Item {
+                id: self.new_node_id(),
+                ident,
+                vis,
+                kind,
+            });
+        }
+        let hi = self.token.span;
+        self.bump();
+
+        Ok(ItemKind::Impl(Box::new(Impl {
+            of_trait,
+            self_ty,
+            items,
+            span: Span::new(lo.data().lo, hi.data().hi),
         })))
     }
 
+    /// Parse module body: `{ fn foo() {} }`, or `;` of a module loaded from its own file.
+    /// Expect `mod` keyword and the name are already parsed.
+    fn parse_mod(&mut self) -> Result<ItemKind> {
+        if self.consume(&TokenKind::Semi) {
+            return Ok(ItemKind::Mod(Box::new(Mod {
+                items: Vec::new(),
+                inline: false,
+                span: DUMMY_SP,
+            })));
+        }
+
+        let lo = self.token.span;
+        self.expect(&TokenKind::OpenDelim(DelimToken::Brace))?;
+
+        let mut items = Vec::new();
+        while self.token.kind != TokenKind::CloseDelim(DelimToken::Brace) {
+            items.push(self.parse_item()?);
+        }
+        let hi = self.token.span;
+        self.bump();
+
+        Ok(ItemKind::Mod(Box::new(Mod {
+            items,
+            inline: true,
+            span: Span::new(lo.data().lo, hi.data().hi),
+        })))
+    }
+
+    /// Parse the name, type and initializer of a constant or a static: `N: i32 = 4 * 8;`
+    /// Expect `const` or `static` keyword, and `mut` of a static, are already parsed.
+    fn parse_item_const(&mut self) -> Result<(Ident, Ty, Expr)> {
+        let ident = self.expect_ident()?;
+        self.expect(&TokenKind::Colon)?;
+        let ty = self.parse_ty()?;
+        self.expect(&TokenKind::Eq)?;
+        let expr = self.parse_expr()?;
+        self.expect(&TokenKind::Semi)?;
+
+        Ok((ident, ty, expr))
+    }
+
+    /// Parse use tree: `foo::bar;`, `foo::bar as baz;`, `foo::*;`
+    /// Expect `use` keyword is already parsed.
+    fn parse_use_tree(&mut self) -> Result<ItemKind> {
+        let mut segments = vec![self.expect_ident()?];
+        let mut kind = UseTreeKind::Simple(None);
+        while self.consume(&TokenKind::PathSep) {
+            if self.consume(&TokenKind::BinOp(BinOpToken::Star)) {
+                kind = UseTreeKind::Glob;
+                break;
+            }
+            segments.push(self.expect_ident()?);
+        }
+
+        if kind == UseTreeKind::Simple(None) && self.consume_keyword(Kw::As) {
+            kind = UseTreeKind::Simple(Some(self.expect_ident()?));
+        }
+        self.expect(&TokenKind::Semi)?;
+
+        Ok(ItemKind::Use(Box::new(UseTree {
+            prefix: Path {
+                segments,
+                id: self.new_node_id(),
+            },
+            kind,
+        })))
+    }
+
+    /// Parse generic parameters: `<T, U: Foo + Bar>`
+    /// Returns empty generics if the next token is not `<`.
+    fn parse_generics(&mut self) -> Result<Generics> {
+        let mut params = Vec::new();
+        if !self.consume(&TokenKind::Lt) {
+            return Ok(Generics { params });
+        }
+
+        while !self.consume(&TokenKind::Gt) {
+            let ident = self
+                .expect_ident()
+                .with_context(|| format!("Parsing generic parameter."))?;
+
+            let mut bounds = Vec::new();
+            if self.consume(&TokenKind::Colon) {
+                loop {
+                    bounds.push(self.parse_path()?);
+                    if !self.consume(&TokenKind::BinOp(BinOpToken::Plus)) {
+                        break;
+                    }
+                }
+            }
+            params.push(GenericParam {
+                id: self.new_node_id(),
+                ident,
+                bounds,
+            });
+
+            if self.consume(&TokenKind::Gt) {
+                break;
+            }
+
+            self.expect(&TokenKind::Comma)?;
+        }
+
+        Ok(Generics { params })
+    }
+
+    /// Parse parameter: `a: i32`, `mut a: i32`
+    /// The `self` parameter is also accepted: `self`, `mut self`, `&self`, `&mut self`
     fn parse_param(&mut self) -> Result<Param> {
+        let self_ty = Ty::path_with_dummy_span(Kw::SelfUpper);
+
+        // `&self`, `&mut self`
+        if self.consume(&TokenKind::BinOp(BinOpToken::And)) {
+            let ref_mutbl = self.parse_mutability();
+            let ident = self.expect_ident()?;
+            if ident.name != Kw::SelfLower.into() {
+                return Err(ParseError::UnexpectedToken {
+                    expected: vec![TokenKind::Ident(Kw::SelfLower.into())],
+                    found: TokenKind::Ident(ident.name),
+                }
+                .into());
+            }
+
+            return Ok(Param {
+                id: self.new_node_id(),
+                mutbl: Mutability::Not,
+                ident,
+                ty: Ty::ref_(ref_mutbl, self_ty),
+            });
+        }
+
+        let mutbl = self.parse_mutability();
         let ident = self
             .expect_ident()
             .with_context(|| format!("Parsing parameter."))?;
+
+        // `self`, `mut self`
+        if ident.name == Kw::SelfLower.into() && self.token.kind != TokenKind::Colon {
+            return Ok(Param {
+                id: self.new_node_id(),
+                mutbl,
+                ident,
+                ty: self_ty,
+            });
+        }
+
         self.expect(&TokenKind::Colon)?;
         let ty = self.parse_ty()?;
 
-        Ok(Param { ident, ty })
+        Ok(Param {
+            id: self.new_node_id(),
+            mutbl,
+            ident,
+            ty,
+        })
     }
 }
 
@@ -87,7 +487,7 @@ mod tests {
     macro_rules! test_item {
         ($input: expr, $expected: expr) => {
             let tokens = parse_all_token($input);
-            let result = Parser::new(&tokens).parse_item().unwrap();
+            let result = Parser::without_node_ids(&tokens).parse_item().unwrap();
 
             assert_eq!(result, $expected);
         };
@@ -97,7 +497,7 @@ mod tests {
         ($input: expr, $expected: expr) => {
             let expected: Vec<Item> = $expected.into();
             let tokens = parse_all_token($input);
-            let result = Parser::new(&tokens).parse_items().unwrap();
+            let result = Parser::without_node_ids(&tokens).parse_items().unwrap();
 
             assert_eq!(result, expected);
         };
@@ -181,6 +581,251 @@ mod tests {
         );
     }
 
+    #[test]
+    fn fn_decl_node_ids() {
+        let tokens = parse_all_token("fn f(a: i32) -> i32 { let b = a; b }");
+        let item = Parser::new(&tokens).parse_item().unwrap();
+        let ItemKind::Fn(fun) = &item.kind else {
+            panic!("expected a function: {:?}", item);
+        };
+
+        let mut ids = vec![item.id, fun.inputs[0].id];
+        for stmt in &fun.body.stmts {
+            match stmt {
+                Stmt::Local {
+                    id,
+                    init: Expr::Path(path),
+                    ..
+                } => ids.extend([*id, path.id]),
+                Stmt::Expr(Expr::Path(path)) => ids.push(path.id),
+                _ => panic!("unexpected statement: {:?}", stmt),
+            }
+        }
+
+        assert_eq!(ids.len(), 5);
+        assert!(ids.iter().all(|id| *id != DUMMY_NODE_ID));
+        let distinct: std::collections::HashSet<_> = ids.iter().collect();
+        assert_eq!(distinct.len(), ids.len());
+    }
+
+    #[test]
+    fn fn_decl_mut_param() {
+        test_item!(
+            "fn f(mut a:i32, b: bool) {}",
+            Item::fn_dummy(
+                Symbol::ident_nth(0),
+                [
+                    Param::new_mut_dummy(Ty::path_with_dummy_span(Kw::I32), Symbol::ident_nth(1)),
+                    Param::new_dummy(Ty::path_with_dummy_span(Kw::Bool), Symbol::ident_nth(2))
+                ],
+                None,
+                []
+            )
+        );
+    }
+
+    #[test]
+    fn fn_decl_generics() {
+        let generic_fn = |params: Vec<GenericParam>, inputs: Vec<Param>, output| Item {
+            id: DUMMY_NODE_ID,
+            ident: Ident::with_dummy_span(Symbol::ident_nth(0)),
+            vis: Visibility::Inherited,
+            kind: ItemKind::Fn(Box::new(Fn {
+                inline: InlineAttr::None,
+                generics: Generics { params },
+                inputs,
+                output,
+                body: Block::from([]),
+            })),
+        };
+
+        test_item!(
+            "fn f<T>(a: T) -> T {}",
+            generic_fn(
+                vec![GenericParam::new_dummy(Symbol::ident_nth(1))],
+                vec![Param::new_dummy(
+                    Ty::path_with_dummy_span(Symbol::ident_nth(1)),
+                    Symbol::ident_nth(2)
+                )],
+                Some(Ty::path_with_dummy_span(Symbol::ident_nth(1)))
+            )
+        );
+        test_item!(
+            "fn f<T, U,>() {}",
+            generic_fn(
+                vec![
+                    GenericParam::new_dummy(Symbol::ident_nth(1)),
+                    GenericParam::new_dummy(Symbol::ident_nth(2))
+                ],
+                vec![],
+                None
+            )
+        );
+    }
+
+    #[test]
+    fn fn_decl_bounds() {
+        test_item!(
+            "fn f<T: A + B, U: A>() {}",
+            Item {
+                id: DUMMY_NODE_ID,
+                ident: Ident::with_dummy_span(Symbol::ident_nth(0)),
+                vis: Visibility::Inherited,
+                kind: ItemKind::Fn(Box::new(Fn {
+                    inline: InlineAttr::None,
+                    generics: Generics {
+                        params: vec![
+                            GenericParam {
+                                id: DUMMY_NODE_ID,
+                                ident: Ident::with_dummy_span(Symbol::ident_nth(1)),
+                                bounds: vec![
+                                    Path::from_ident(Ident::with_dummy_span(Symbol::ident_nth(2))),
+                                    Path::from_ident(Ident::with_dummy_span(Symbol::ident_nth(3))),
+                                ],
+                            },
+                            GenericParam {
+                                id: DUMMY_NODE_ID,
+                                ident: Ident::with_dummy_span(Symbol::ident_nth(4)),
+                                bounds: vec![Path::from_ident(Ident::with_dummy_span(
+                                    Symbol::ident_nth(2)
+                                ))],
+                            },
+                        ],
+                    },
+                    inputs: vec![],
+                    output: None,
+                    body: Block::from([]),
+                })),
+            }
+        );
+    }
+
+    #[test]
+    fn fn_decl_inline() {
+        // `inline` and `never` are the first identifiers.
+        let inline_fn = |name, inline| Item {
+            id: DUMMY_NODE_ID,
+            ident: Ident::with_dummy_span(Symbol::ident_nth(name)),
+            vis: Visibility::Public,
+            kind: ItemKind::Fn(Box::new(Fn {
+                inline,
+                generics: Generics::default(),
+                inputs: vec![],
+                output: None,
+                body: Block::from([]),
+            })),
+        };
+
+        test_item!("#[inline] pub fn f() {}", inline_fn(1, InlineAttr::Hint));
+        test_item!(
+            "#[inline(never)] pub fn f() {}",
+            inline_fn(2, InlineAttr::Never)
+        );
+
+        let parse_err = |src| {
+            let tokens = parse_all_token(src);
+            let err = Parser::without_node_ids(&tokens)
+                .parse_item()
+                .err()
+                .unwrap();
+            err.downcast::<ParseError>().unwrap()
+        };
+        assert!(matches!(
+            parse_err("#[cold] fn f() {}"),
+            ParseError::UnknownAttribute { name } if name == "cold"
+        ));
+        assert!(matches!(
+            parse_err("#[inline(always)] fn f() {}"),
+            ParseError::MalformedInlineAttr { arg } if arg == "always"
+        ));
+        assert!(matches!(
+            parse_err("#[inline] const N: i32 = 1;"),
+            ParseError::MisplacedInlineAttr
+        ));
+    }
+
+    #[test]
+    fn trait_decl() {
+        test_item!(
+            "trait A { fn f(&self) -> i32; fn g(&mut self, a: i32); }",
+            Item {
+                id: DUMMY_NODE_ID,
+                ident: Ident::with_dummy_span(Symbol::ident_nth(0)),
+                vis: Visibility::Inherited,
+                kind: ItemKind::Trait(Box::new(Trait {
+                    items: vec![
+                        TraitItem {
+                            id: DUMMY_NODE_ID,
+                            ident: Ident::with_dummy_span(Symbol::ident_nth(1)),
+                            inputs: vec![Param::self_dummy(Some(Mutability::Not))],
+                            output: Some(Ty::path_with_dummy_span(Kw::I32)),
+                        },
+                        TraitItem {
+                            id: DUMMY_NODE_ID,
+                            ident: Ident::with_dummy_span(Symbol::ident_nth(2)),
+                            inputs: vec![
+                                Param::self_dummy(Some(Mutability::Mut)),
+                                Param::new_dummy(
+                                    Ty::path_with_dummy_span(Kw::I32),
+                                    Symbol::ident_nth(3)
+                                )
+                            ],
+                            output: None,
+                        },
+                    ],
+                    span: DUMMY_SP,
+                })),
+            }
+        );
+    }
+
+    #[test]
+    fn impl_decl() {
+        test_item!(
+            "impl A for i32 { fn f(self) {} }",
+            Item {
+                id: DUMMY_NODE_ID,
+                ident: Ident::with_dummy_span(Kw::Impl),
+                vis: Visibility::Inherited,
+                kind: ItemKind::Impl(Box::new(Impl {
+                    of_trait: Some(Path::from_ident(Ident::with_dummy_span(Symbol::ident_nth(
+                        0
+                    )))),
+                    self_ty: Ty::path_with_dummy_span(Kw::I32),
+                    items: vec![Item::fn_dummy(
+                        Symbol::ident_nth(1),
+                        [Param::self_dummy(None)],
+                        None,
+                        []
+                    )],
+                    span: DUMMY_SP,
+                })),
+            }
+        );
+        test_item!(
+            "impl &i32 { fn f(mut self) {} }",
+            Item {
+                id: DUMMY_NODE_ID,
+                ident: Ident::with_dummy_span(Kw::Impl),
+                vis: Visibility::Inherited,
+                kind: ItemKind::Impl(Box::new(Impl {
+                    of_trait: None,
+                    self_ty: Ty::ref_(Mutability::Not, Ty::path_with_dummy_span(Kw::I32)),
+                    items: vec![Item::fn_dummy(
+                        Symbol::ident_nth(0),
+                        [Param {
+                            mutbl: Mutability::Mut,
+                            ..Param::self_dummy(None)
+                        }],
+                        None,
+                        []
+                    )],
+                    span: DUMMY_SP,
+                })),
+            }
+        );
+    }
+
     #[test]
     fn items() {
         test_items!(
@@ -193,4 +838,126 @@ fn g() {}",
             ]
         );
     }
+
+    #[test]
+    fn mod_decl() {
+        test_item!(
+            "pub mod a { fn f() {} pub fn g() {} }",
+            Item {
+                id: DUMMY_NODE_ID,
+                ident: Ident::with_dummy_span(Symbol::ident_nth(0)),
+                vis: Visibility::Public,
+                kind: ItemKind::Mod(Box::new(Mod {
+                    items: vec![
+                        Item::fn_dummy(Symbol::ident_nth(1), [], None, []),
+                        Item {
+                            id: DUMMY_NODE_ID,
+                            vis: Visibility::Public,
+                            ..Item::fn_dummy(Symbol::ident_nth(2), [], None, [])
+                        },
+                    ],
+                    inline: true,
+                    span: DUMMY_SP,
+                })),
+            }
+        );
+        test_item!(
+            "mod a;",
+            Item {
+                id: DUMMY_NODE_ID,
+                ident: Ident::with_dummy_span(Symbol::ident_nth(0)),
+                vis: Visibility::Inherited,
+                kind: ItemKind::Mod(Box::new(Mod {
+                    items: vec![],
+                    inline: false,
+                    span: DUMMY_SP,
+                })),
+            }
+        );
+    }
+
+    #[test]
+    fn use_decl() {
+        let use_item = |segments: Vec<Ident>, kind| Item {
+            id: DUMMY_NODE_ID,
+            ident: Ident::with_dummy_span(Kw::Use),
+            vis: Visibility::Inherited,
+            kind: ItemKind::Use(Box::new(UseTree {
+                prefix: Path {
+                    segments,
+                    id: DUMMY_NODE_ID,
+                },
+                kind,
+            })),
+        };
+
+        test_item!(
+            "use a::b;",
+            use_item(
+                vec![
+                    Ident::with_dummy_span(Symbol::ident_nth(0)),
+                    Ident::with_dummy_span(Symbol::ident_nth(1))
+                ],
+                UseTreeKind::Simple(None)
+            )
+        );
+        test_item!(
+            "use super::a as b;",
+            use_item(
+                vec![
+                    Ident::with_dummy_span(Kw::Super),
+                    Ident::with_dummy_span(Symbol::ident_nth(0))
+                ],
+                UseTreeKind::Simple(Some(Ident::with_dummy_span(Symbol::ident_nth(1))))
+            )
+        );
+        test_item!(
+            "use crate::a::*;",
+            use_item(
+                vec![
+                    Ident::with_dummy_span(Kw::Crate),
+                    Ident::with_dummy_span(Symbol::ident_nth(0))
+                ],
+                UseTreeKind::Glob
+            )
+        );
+    }
+
+    #[test]
+    fn const_and_static_decl() {
+        let item = |kind| Item {
+            id: DUMMY_NODE_ID,
+            ident: Ident::with_dummy_span(Symbol::ident_nth(0)),
+            vis: Visibility::Inherited,
+            kind,
+        };
+
+        test_item!(
+            "const N: i32 = 4 * 8;",
+            item(ItemKind::Const(Box::new(Const {
+                ty: Ty::path_with_dummy_span(Kw::I32),
+                expr: Expr::binary(
+                    BinOp::Mul,
+                    Expr::lit_from_value_dummy(4),
+                    Expr::lit_from_value_dummy(8)
+                ),
+            })))
+        );
+        test_item!(
+            "static mut COUNTER: i32 = 0;",
+            item(ItemKind::Static(Box::new(Static {
+                mutbl: Mutability::Mut,
+                ty: Ty::path_with_dummy_span(Kw::I32),
+                expr: Expr::lit_from_value_dummy(0),
+            })))
+        );
+        test_item!(
+            "static FLAG: bool = true;",
+            item(ItemKind::Static(Box::new(Static {
+                mutbl: Mutability::Not,
+                ty: Ty::path_with_dummy_span(Kw::Bool),
+                expr: Expr::lit_from_value_dummy(true),
+            })))
+        );
+    }
 }
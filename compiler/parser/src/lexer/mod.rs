@@ -1,9 +1,15 @@
 use ast::token::*;
-use lexer::{first_token, token};
+use lexer::{first_token, token, tokenize};
 use span::*;
 
 pub fn parse_all_token(src: &str) -> Tokens {
-    let mut lexer = Lexer::new(src);
+    parse_all_token_at(src, 0, SymbolMap::new())
+}
+
+/// Tokenizes a source file whose spans start at `base`,
+/// adding the symbols to the map shared by the files of the program.
+pub fn parse_all_token_at<'a>(src: &'a str, base: usize, symbol_map: SymbolMap<'a>) -> Tokens<'a> {
+    let mut lexer = Lexer::with_symbol_map(src, base, symbol_map);
 
     let mut tokens = Vec::new();
     loop {
@@ -20,18 +26,70 @@ pub fn parse_all_token(src: &str) -> Tokens {
     }
 }
 
+/// Collects the line comments of a source file in order, which the tokens do not include.
+pub fn gather_comments(src: &str) -> Vec<Comment> {
+    let mut comments = Vec::new();
+    let mut pos = 0;
+    let mut code_on_line = false;
+
+    for token in tokenize(src) {
+        let text = &src[pos..pos + token.len];
+        match token.kind {
+            token::TokenKind::Whitespace => {
+                if text.contains('\n') {
+                    code_on_line = false;
+                }
+            }
+            token::TokenKind::LineComment => comments.push(Comment {
+                text: text.trim_end().to_string(),
+                span: Span::new(pos as u32, (pos + token.len) as u32),
+                trailing: code_on_line,
+            }),
+            _ => code_on_line = true,
+        }
+        pos += token.len;
+    }
+
+    comments
+}
+
+/// Collects the positions of the whitespace holding blank lines in a source file, in order.
+/// Consecutive blank lines are one whitespace, so they are collected once.
+pub fn gather_blank_lines(src: &str) -> Vec<u32> {
+    let mut blank_lines = Vec::new();
+    let mut pos = 0;
+
+    for token in tokenize(src) {
+        let text = &src[pos..pos + token.len];
+        if matches!(token.kind, token::TokenKind::Whitespace) && text.matches('\n').count() > 1 {
+            blank_lines.push(pos as u32);
+        }
+        pos += token.len;
+    }
+
+    blank_lines
+}
+
 pub struct Lexer<'a> {
     pos: usize,
     src: &'a str,
     symbol_map: SymbolMap<'a>,
+
+    /// The offset of the spans in this file.
+    base: usize,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(src: &'a str) -> Self {
+        Self::with_symbol_map(src, 0, SymbolMap::new())
+    }
+
+    pub fn with_symbol_map(src: &'a str, base: usize, symbol_map: SymbolMap<'a>) -> Self {
         Self {
             pos: 0,
             src,
-            symbol_map: SymbolMap::new(),
+            symbol_map,
+            base,
         }
     }
 
@@ -40,7 +98,8 @@ impl<'a> Lexer<'a> {
             let text = &self.src[self.pos..];
 
             if text.is_empty() {
-                let span = Span::new(self.pos as u32, self.pos as u32);
+                let pos = (self.base + self.pos) as u32;
+                let span = Span::new(pos, pos);
                 return ast::token::Token::new(ast::token::TokenKind::Eof, span);
             }
 
@@ -50,7 +109,7 @@ impl<'a> Lexer<'a> {
 
             match self.cook_lexer_token(token.kind, start) {
                 Some(kind) => {
-                    let span = Span::new(start as u32, self.pos as u32);
+                    let span = Span::new((self.base + start) as u32, (self.base + self.pos) as u32);
                     return ast::token::Token::new(kind, span);
                 }
                 None => (),
@@ -64,7 +123,7 @@ impl<'a> Lexer<'a> {
 
     fn cook_lexer_token(&mut self, token: token::TokenKind, start: usize) -> Option<TokenKind> {
         Some(match token {
-            token::TokenKind::Whitespace => return None,
+            token::TokenKind::Whitespace | token::TokenKind::LineComment => return None,
             token::TokenKind::Ident => {
                 let ident = self.str_from(start);
                 let symbol = self.symbol_map.insert(ident);
@@ -81,12 +140,16 @@ impl<'a> Lexer<'a> {
             }
             token::TokenKind::Semi => TokenKind::Semi,
             token::TokenKind::Colon => TokenKind::Colon,
+            token::TokenKind::PathSep => TokenKind::PathSep,
             token::TokenKind::Dot => TokenKind::Dot,
             token::TokenKind::Comma => TokenKind::Comma,
             token::TokenKind::OpenParen => TokenKind::OpenDelim(DelimToken::Paren),
             token::TokenKind::CloseParen => TokenKind::CloseDelim(DelimToken::Paren),
             token::TokenKind::OpenBrace => TokenKind::OpenDelim(DelimToken::Brace),
             token::TokenKind::CloseBrace => TokenKind::CloseDelim(DelimToken::Brace),
+            token::TokenKind::OpenBracket => TokenKind::OpenDelim(DelimToken::Bracket),
+            token::TokenKind::CloseBracket => TokenKind::CloseDelim(DelimToken::Bracket),
+            token::TokenKind::Pound => TokenKind::Pound,
             token::TokenKind::Arrow => TokenKind::Arrow,
             token::TokenKind::Eq => TokenKind::Eq,
             token::TokenKind::Bang => todo!(),
@@ -102,6 +165,8 @@ impl<'a> Lexer<'a> {
             token::TokenKind::Plus => TokenKind::BinOp(BinOpToken::Plus),
             token::TokenKind::Star => TokenKind::BinOp(BinOpToken::Star),
             token::TokenKind::Slash => TokenKind::BinOp(BinOpToken::Slash),
+            token::TokenKind::And => TokenKind::BinOp(BinOpToken::And),
+            token::TokenKind::Or => TokenKind::BinOp(BinOpToken::Or),
             token::TokenKind::Unknown => todo!(),
         })
     }
@@ -161,6 +226,20 @@ mod tests {
                 Span::new(0, 1)
             )]
         );
+        test_lexer!(
+            "[",
+            vec![Token::new(
+                TokenKind::OpenDelim(DelimToken::Bracket),
+                Span::new(0, 1)
+            )]
+        );
+        test_lexer!(
+            "]",
+            vec![Token::new(
+                TokenKind::CloseDelim(DelimToken::Bracket),
+                Span::new(0, 1)
+            )]
+        );
     }
 
     #[test]
@@ -174,8 +253,10 @@ mod tests {
         test_lexer!(">", vec![Token::new(TokenKind::Gt, Span::new(0, 1))]);
         test_lexer!(";", vec![Token::new(TokenKind::Semi, Span::new(0, 1))]);
         test_lexer!(":", vec![Token::new(TokenKind::Colon, Span::new(0, 1))]);
+        test_lexer!("::", vec![Token::new(TokenKind::PathSep, Span::new(0, 2))]);
         test_lexer!(".", vec![Token::new(TokenKind::Dot, Span::new(0, 1))]);
         test_lexer!(",", vec![Token::new(TokenKind::Comma, Span::new(0, 1))]);
+        test_lexer!("#", vec![Token::new(TokenKind::Pound, Span::new(0, 1))]);
     }
 
     #[test]
@@ -208,6 +289,20 @@ mod tests {
                 Span::new(0, 1)
             )]
         );
+        test_lexer!(
+            "&",
+            vec![Token::new(
+                TokenKind::BinOp(BinOpToken::And),
+                Span::new(0, 1)
+            )]
+        );
+        test_lexer!(
+            "|",
+            vec![Token::new(
+                TokenKind::BinOp(BinOpToken::Or),
+                Span::new(0, 1)
+            )]
+        );
     }
 
     #[test]
@@ -278,6 +373,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn comment() {
+        test_lexer!(
+            "1 // one",
+            vec![Token::new(
+                TokenKind::Literal(Lit {
+                    kind: LitKind::Integer,
+                    symbol: Symbol::new(KEYWORDS.len())
+                }),
+                Span::new(0, 1)
+            )]
+        );
+
+        assert_eq!(
+            gather_comments("// foo\n1 // bar  \n"),
+            vec![
+                Comment {
+                    text: "// foo".to_string(),
+                    span: Span::new(0, 6),
+                    trailing: false,
+                },
+                Comment {
+                    text: "// bar".to_string(),
+                    span: Span::new(9, 17),
+                    trailing: true,
+                }
+            ]
+        );
+
+        assert_eq!(
+            gather_blank_lines("1\n\n  \n2\n3 // three\n\n4"),
+            vec![1, 18]
+        );
+    }
+
     #[test]
     fn expr() {
         test_lexer!(
@@ -4,9 +4,12 @@ pub mod block;
 pub mod error;
 pub mod expr;
 pub mod item;
+pub mod module;
 pub mod stmt;
 pub mod ty;
 
+pub use module::{parse_crate, parse_crate_from_source_str};
+
 use crate::{error::*, lexer::parse_all_token};
 use ast::{token::*, *};
 use span::*;
@@ -39,6 +42,13 @@ pub struct Parser<'a> {
     token: Token,
     symbol_map: &'a SymbolMap<'a>,
     cursor: TokenCursor<'a>,
+
+    /// The id of the next node, which continues from the files parsed before.
+    next_node_id: usize,
+
+    /// Whether the nodes get their ids, which the tests turn off
+    /// to compare the nodes with ones built with `DUMMY_NODE_ID`.
+    assign_node_ids: bool,
 }
 
 impl<'a> Parser<'a> {
@@ -53,9 +63,29 @@ impl<'a> Parser<'a> {
             token,
             symbol_map: &tokens.map,
             cursor,
+            next_node_id: 0,
+            assign_node_ids: true,
         }
     }
 
+    #[cfg(test)]
+    fn without_node_ids(tokens: &'a Tokens<'a>) -> Self {
+        Self {
+            assign_node_ids: false,
+            ..Self::new(tokens)
+        }
+    }
+
+    fn new_node_id(&mut self) -> NodeId {
+        if !self.assign_node_ids {
+            return DUMMY_NODE_ID;
+        }
+
+        let id = NodeId::from_usize(self.next_node_id);
+        self.next_node_id += 1;
+        id
+    }
+
     /// Advance one token.
     fn bump(&mut self) {
         let next_token = self
@@ -106,6 +136,15 @@ impl<'a> Parser<'a> {
         false
     }
 
+    /// Parse optional `mut` keyword.
+    fn parse_mutability(&mut self) -> Mutability {
+        if self.consume_keyword(Kw::Mut) {
+            Mutability::Mut
+        } else {
+            Mutability::Not
+        }
+    }
+
     /// If the next token is Identifier and that symbol is equal to given keyword,
     /// advance one token and return `true`.
     /// Otherwise, do nothing and return `false`
@@ -194,4 +233,257 @@ mod tests {
             ))
         );
     }
+
+    /// Formats the source, and checks that formatting the output again gives the same output.
+    fn format(src: &str) -> String {
+        let (items, map) = parse_items(src).unwrap();
+        let print = |src: &str, items, map| {
+            let comments = lexer::gather_comments(src);
+            let blank_lines = lexer::gather_blank_lines(src);
+            ast::pp::print_items(map, items, &comments, &blank_lines)
+        };
+        let output = print(src, &items, &map);
+
+        let (items, map) = parse_items(&output).unwrap();
+        let again = print(&output, &items, &map);
+        assert_eq!(output, again);
+
+        output
+    }
+
+    #[test]
+    fn format_items() {
+        assert_eq!(
+            format(
+                "use foo::bar as baz; pub use foo::*; mod ext; mod foo { pub fn bar()->i32{1} }
+                const N:i32=4*8; static mut COUNTER : i32 = 0;
+                trait Shape { fn area(&self)->i32; fn name(self)->i32; }
+                impl Shape for i32 { #[inline] fn area(&self)->i32{*self} #[inline(never)] fn name(mut self)->i32{self} }
+                fn foo<T:Shape+Clone,U>(x:T,mut y:&mut i32,p:*const i32,f:fn(i32)->bool,d:&dyn Shape){}"
+            ),
+            r"use foo::bar as baz;
+
+pub use foo::*;
+
+mod ext;
+
+mod foo {
+    pub fn bar() -> i32 {
+        1
+    }
+}
+
+const N: i32 = 4 * 8;
+
+static mut COUNTER: i32 = 0;
+
+trait Shape {
+    fn area(&self) -> i32;
+    fn name(self) -> i32;
+}
+
+impl Shape for i32 {
+    #[inline]
+    fn area(&self) -> i32 {
+        *self
+    }
+
+    #[inline(never)]
+    fn name(mut self) -> i32 {
+        self
+    }
+}
+
+fn foo<T: Shape + Clone, U>(x: T, mut y: &mut i32, p: *const i32, f: fn(i32) -> bool, d: &dyn Shape) {}
+"
+        );
+    }
+
+    #[test]
+    fn format_exprs() {
+        assert_eq!(
+            format(
+                "fn main() {
+                    let a = a - b - c; let b = (a - b) - c; let c = (a < b) == (c < d);
+                    let d = -(a + b) * c; let e = (if a { 1 } else { 2 }) + 1;
+                    let f = (|x: i32| x)(1); let g = move || -> i32 { a };
+                    (a = b); a = b = c; x.foo(1).bar(); (-a).abs();
+                    if a { println(a); } else if b { return; } else { loop { break 1; } }
+                }"
+            ),
+            r"fn main() {
+    let a = a - b - c;
+    let b = (a - b) - c;
+    let c = (a < b) == (c < d);
+    let d = -(a + b) * c;
+    let e = (if a {
+        1
+    } else {
+        2
+    }) + 1;
+    let f = (|x: i32| x)(1);
+    let g = move || -> i32 {
+        a
+    };
+    a = b;
+    a = b = c;
+    x.foo(1).bar();
+    (-a).abs();
+    if a {
+        println(a);
+    } else if b {
+        return;
+    } else {
+        loop {
+            break 1;
+        }
+    }
+}
+"
+        );
+    }
+
+    #[test]
+    fn format_comments() {
+        assert_eq!(
+            format(
+                "// header
+                fn main() { // main
+                    // leading
+                    let a = 1; // trailing
+                    f(
+                        a // in call
+                    );
+                    {
+                        // only comment
+                    }
+                    // end
+                }
+                // footer"
+            ),
+            r"// header
+fn main() { // main
+    // leading
+    let a = 1; // trailing
+    f(
+        a, // in call
+    );
+    {
+        // only comment
+    }
+    // end
+}
+
+// footer
+"
+        );
+    }
+
+    #[test]
+    fn format_comments_in_braces() {
+        assert_eq!(
+            format(
+                "mod foo { fn bar() {} // bar
+                // end of foo
+                } // after foo
+                trait Shape { fn area(&self) -> i32; // area
+                }
+                impl Shape for i32 { fn area(&self) -> i32 { *self } // area
+                }
+                fn main() { x.foo(1, // one
+                    2); f(g(1) // after g
+                    ); }"
+            ),
+            r"mod foo {
+    fn bar() {} // bar
+    // end of foo
+} // after foo
+
+trait Shape {
+    fn area(&self) -> i32; // area
+}
+
+impl Shape for i32 {
+    fn area(&self) -> i32 {
+        *self
+    } // area
+}
+
+fn main() {
+    x.foo(
+        1, // one
+        2,
+    );
+    f(
+        g(1), // after g
+    );
+}
+"
+        );
+    }
+
+    #[test]
+    fn format_blank_lines() {
+        assert_eq!(
+            format(
+                "fn main() {
+
+                    let a = 1;
+                    let b = 2;
+
+
+                    println(a);
+
+                    // c
+                    let c = { 3 };
+                    // d
+
+                    let d = 4;
+
+                }
+
+
+                fn f() {}"
+            ),
+            r"fn main() {
+    let a = 1;
+    let b = 2;
+
+    println(a);
+
+    // c
+    let c = {
+        3
+    };
+    // d
+
+    let d = 4;
+}
+
+fn f() {}
+"
+        );
+    }
+
+    #[test]
+    fn print_tree() {
+        let (items, map) = parse_items("fn main() -> i32 { let mut a = &1; *a + 2 }").unwrap();
+        let items: Vec<_> = items.iter().collect();
+
+        assert_eq!(
+            ast::pp::print_tree(&map, &items),
+            r"Fn main @3..7
+  Output
+    Path i32 @13..16
+  Block @17..43
+    Local mut a @27..28
+      AddrOf
+        Lit 1 @32..33
+    Expr
+      Binary +
+        Unary *
+          Path a @36..37
+        Lit 2 @40..41"
+        );
+    }
 }
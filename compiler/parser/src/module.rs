@@ -0,0 +1,140 @@
+use crate::{error::ParseError, lexer::parse_all_token_at, Parser};
+
+use ast::{Item, ItemKind};
+use span::{source_map::SourceMap, SymbolMap};
+
+use anyhow::Result;
+use std::path::Path;
+
+/// Parses the program whose root module is in the file,
+/// loading the items of each `mod foo;` from `foo.elaina`.
+/// The file of a module declared in `dir/bar.elaina` is searched in `dir/bar/`.
+///
+/// The sources are added to the source map, which the symbols of all files borrow.
+pub fn parse_crate<'a>(
+    source_map: &'a SourceMap,
+    filename: &str,
+) -> Result<(Vec<Item>, SymbolMap<'a>)> {
+    let src = std::fs::read_to_string(filename)?;
+    parse_crate_from_source_str(source_map, filename, src)
+}
+
+/// Parses the program whose root module is the source of the file,
+/// loading its modules from the files next to it like `parse_crate`.
+pub fn parse_crate_from_source_str<'a>(
+    source_map: &'a SourceMap,
+    filename: &str,
+    src: String,
+) -> Result<(Vec<Item>, SymbolMap<'a>)> {
+    let mut loader = ModuleLoader {
+        source_map,
+        symbol_map: SymbolMap::new(),
+        next_node_id: 0,
+    };
+
+    let path = Path::new(filename);
+    let mut items = loader.parse_file(filename.to_string(), src)?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    loader.load_modules(&mut items, dir)?;
+
+    Ok((items, loader.symbol_map))
+}
+
+struct ModuleLoader<'a> {
+    /// The files, whose spans do not overlap.
+    source_map: &'a SourceMap,
+    symbol_map: SymbolMap<'a>,
+
+    /// The id of the next node, so that the nodes of different files have distinct ids.
+    next_node_id: usize,
+}
+
+impl ModuleLoader<'_> {
+    fn parse_file(&mut self, filename: String, src: String) -> Result<Vec<Item>> {
+        let (start_pos, src) = self.source_map.add_file(filename, src);
+        let symbol_map = std::mem::replace(&mut self.symbol_map, SymbolMap::new());
+        let tokens = parse_all_token_at(src, start_pos, symbol_map);
+
+        let mut parser = Parser::new(&tokens);
+        parser.next_node_id = self.next_node_id;
+        let items = parser.parse_items()?;
+        self.next_node_id = parser.next_node_id;
+        self.symbol_map = tokens.map;
+
+        Ok(items)
+    }
+
+    /// Loads the items of the modules declared without body in `items`, which are in `dir`.
+    fn load_modules(&mut self, items: &mut [Item], dir: &Path) -> Result<()> {
+        for item in items {
+            let module = match &mut item.kind {
+                ItemKind::Mod(module) => module,
+                _ => continue,
+            };
+
+            let name = self.symbol_map.get(item.ident.name);
+            if !module.inline {
+                let path = dir.join(format!("{}.elaina", name));
+                let src =
+                    std::fs::read_to_string(&path).map_err(|_| ParseError::ModuleFileNotFound {
+                        name: name.to_string(),
+                        path: path.display().to_string(),
+                    })?;
+                module.items = self.parse_file(path.display().to_string(), src)?;
+            }
+
+            let dir = dir.join(name);
+            self.load_modules(&mut module.items, &dir)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_module_files() {
+        let dir = std::env::temp_dir().join(format!("elaina-modules-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("a")).unwrap();
+        let main_src = "mod a; fn main() {}";
+        std::fs::write(dir.join("main.elaina"), main_src).unwrap();
+        std::fs::write(dir.join("a.elaina"), "pub mod b;").unwrap();
+        std::fs::write(dir.join("a").join("b.elaina"), "pub fn f() {}").unwrap();
+
+        let source_map = SourceMap::new();
+        let main_path = dir.join("main.elaina");
+        let (items, map) = parse_crate(&source_map, main_path.to_str().unwrap()).unwrap();
+        let submodule = |item: &Item| match &item.kind {
+            ItemKind::Mod(module) => module.items[0].clone(),
+            _ => panic!("expected module"),
+        };
+        let b = submodule(&items[0]);
+        let f = submodule(&b);
+        assert_eq!(map.get(b.ident.name), "b");
+        assert_eq!(map.get(f.ident.name), "f");
+        // The spans of each file follow the spans of the files loaded before.
+        assert!(b.ident.span.data().lo as usize > main_src.len());
+        assert!(f.ident.span.data().lo > b.ident.span.data().hi);
+        let b_path = dir.join("a").join("b.elaina");
+        assert_eq!(
+            source_map.span_to_string(f.ident.span),
+            format!("{}:7:8", b_path.display())
+        );
+        // So do the node ids.
+        assert!(f.id.as_usize() > b.id.as_usize());
+
+        std::fs::write(dir.join("c.elaina"), "mod missing;").unwrap();
+        let err = parse_crate(&SourceMap::new(), dir.join("c.elaina").to_str().unwrap())
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.downcast::<ParseError>().unwrap(),
+            ParseError::ModuleFileNotFound { .. }
+        ));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
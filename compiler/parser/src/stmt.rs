@@ -24,6 +24,7 @@ impl Parser<'_> {
     /// Parse let statement
     /// Expect `let` token is already parsed
     fn parse_let_stmt(&mut self) -> Result<Stmt> {
+        let mutbl = self.parse_mutability();
         let ident = self.expect_ident()?;
 
         let ty = if self.consume(&TokenKind::Colon) {
@@ -36,7 +37,13 @@ impl Parser<'_> {
         let init = self.parse_expr()?;
         self.expect(&TokenKind::Semi)?;
 
-        let local = Stmt::Local { ident, ty, init };
+        let local = Stmt::Local {
+            id: self.new_node_id(),
+            mutbl,
+            ident,
+            ty,
+            init,
+        };
 
         Ok(local)
     }
@@ -73,7 +80,7 @@ mod tests {
     macro_rules! test_stmt {
         ($input: expr, $expected: expr) => {
             let tokens = parse_all_token($input);
-            let result = Parser::new(&tokens).parse_stmt().unwrap();
+            let result = Parser::without_node_ids(&tokens).parse_stmt().unwrap();
 
             assert_eq!(result, $expected);
         };
@@ -133,6 +140,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_local_mut() {
+        test_stmt!(
+            "let mut a = 1;",
+            Stmt::local_mut(
+                Ident::with_dummy_span(Symbol::ident_nth(0)),
+                None,
+                Expr::lit_from_value_dummy(1)
+            )
+        );
+        test_stmt!(
+            "let mut a:i32 = 1;",
+            Stmt::local_mut(
+                Ident::with_dummy_span(Symbol::ident_nth(0)),
+                Some(Ty::path_with_dummy_span(Kw::I32)),
+                Expr::lit_from_value_dummy(1)
+            )
+        );
+    }
+
     #[test]
     fn parse_expr() {
         test_stmt!(
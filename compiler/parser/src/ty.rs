@@ -1,14 +1,99 @@
-use crate::Parser;
+use crate::{error::ParseError, Parser};
 
-use ast::*;
+use ast::{token::*, *};
+use span::*;
 
 use anyhow::Result;
 
 impl Parser<'_> {
     pub fn parse_ty(&mut self) -> Result<Ty> {
+        // Try to parse reference type
+        if self.consume(&TokenKind::BinOp(BinOpToken::And)) {
+            return self.parse_ty_ref();
+        }
+
+        // Try to parse raw pointer type
+        if self.consume(&TokenKind::BinOp(BinOpToken::Star)) {
+            return self.parse_ty_ptr();
+        }
+
+        // Try to parse trait object type
+        if self.consume_keyword(Kw::Dyn) {
+            return self.parse_ty_trait_object();
+        }
+
+        // Try to parse function pointer type
+        if self.consume_keyword(Kw::Fn) {
+            return self.parse_ty_bare_fn();
+        }
+
         self.parse_ty_path()
     }
 
+    /// Parse reference type: `&T`, `&mut T`
+    /// Expect `&` token is already parsed
+    fn parse_ty_ref(&mut self) -> Result<Ty> {
+        let mutbl = self.parse_mutability();
+        let ty = self.parse_ty()?;
+
+        Ok(Ty::ref_(mutbl, ty))
+    }
+
+    /// Parse raw pointer type: `*const T`, `*mut T`
+    /// Expect `*` token is already parsed
+    fn parse_ty_ptr(&mut self) -> Result<Ty> {
+        let mutbl = if self.consume_keyword(Kw::Mut) {
+            Mutability::Mut
+        } else if self.consume_keyword(Kw::Const) {
+            Mutability::Not
+        } else {
+            return Err(ParseError::UnexpectedToken {
+                expected: vec![
+                    TokenKind::Ident(Kw::Mut.into()),
+                    TokenKind::Ident(Kw::Const.into()),
+                ],
+                found: self.token.kind.clone(),
+            }
+            .into());
+        };
+        let ty = self.parse_ty()?;
+
+        Ok(Ty::ptr(mutbl, ty))
+    }
+
+    /// Parse trait object type: `dyn Trait`
+    /// Expect `dyn` keyword is already parsed
+    fn parse_ty_trait_object(&mut self) -> Result<Ty> {
+        let path = self.parse_path()?;
+
+        Ok(Ty::trait_object(path))
+    }
+
+    /// Parse function pointer type: `fn(i32, bool) -> i32`
+    /// Expect `fn` keyword is already parsed
+    fn parse_ty_bare_fn(&mut self) -> Result<Ty> {
+        self.expect(&TokenKind::OpenDelim(DelimToken::Paren))?;
+
+        let mut inputs = Vec::new();
+        while !self.consume(&TokenKind::CloseDelim(DelimToken::Paren)) {
+            inputs.push(self.parse_ty()?);
+
+            if self.consume(&TokenKind::CloseDelim(DelimToken::Paren)) {
+                break;
+            }
+
+            self.expect(&TokenKind::Comma)?;
+        }
+
+        let output = if self.consume(&TokenKind::Arrow) {
+            Some(self.parse_ty()?)
+        } else {
+            None
+        };
+
+        Ok(Ty::bare_fn(inputs, output))
+    }
+
     pub fn parse_ty_path(&mut self) -> Result<Ty> {
         let path = self.parse_path()?;
 
@@ -22,12 +107,11 @@ impl Parser<'_> {
 mod tests {
     use super::*;
     use crate::lexer::parse_all_token;
-    use span::*;
 
     macro_rules! test_ty {
         ($input: expr, $expected: expr) => {
             let tokens = parse_all_token($input);
-            let result = Parser::new(&tokens).parse_ty().unwrap();
+            let result = Parser::without_node_ids(&tokens).parse_ty().unwrap();
 
             assert_eq!(result, $expected);
         };
@@ -38,4 +122,89 @@ mod tests {
         test_ty!("i32", Ty::path_with_dummy_span(Kw::I32));
         test_ty!("bool", Ty::path_with_dummy_span(Kw::Bool));
     }
+
+    #[test]
+    fn parse_ref_types() {
+        test_ty!(
+            "&i32",
+            Ty::ref_(Mutability::Not, Ty::path_with_dummy_span(Kw::I32))
+        );
+        test_ty!(
+            "&mut i32",
+            Ty::ref_(Mutability::Mut, Ty::path_with_dummy_span(Kw::I32))
+        );
+        test_ty!(
+            "&&bool",
+            Ty::ref_(
+                Mutability::Not,
+                Ty::ref_(Mutability::Not, Ty::path_with_dummy_span(Kw::Bool))
+            )
+        );
+    }
+
+    #[test]
+    fn parse_ptr_types() {
+        test_ty!(
+            "*const i32",
+            Ty::ptr(Mutability::Not, Ty::path_with_dummy_span(Kw::I32))
+        );
+        test_ty!(
+            "*mut i32",
+            Ty::ptr(Mutability::Mut, Ty::path_with_dummy_span(Kw::I32))
+        );
+        test_ty!(
+            "*mut &i32",
+            Ty::ptr(
+                Mutability::Mut,
+                Ty::ref_(Mutability::Not, Ty::path_with_dummy_span(Kw::I32))
+            )
+        );
+    }
+
+    #[test]
+    fn parse_trait_object_types() {
+        test_ty!(
+            "&dyn Foo",
+            Ty::ref_(
+                Mutability::Not,
+                Ty::trait_object(Path::from_ident(Ident::with_dummy_span(Symbol::ident_nth(
+                    0
+                ))))
+            )
+        );
+        test_ty!(
+            "*mut dyn Foo",
+            Ty::ptr(
+                Mutability::Mut,
+                Ty::trait_object(Path::from_ident(Ident::with_dummy_span(Symbol::ident_nth(
+                    0
+                ))))
+            )
+        );
+    }
+
+    #[test]
+    fn parse_bare_fn_types() {
+        test_ty!("fn()", Ty::bare_fn(vec![], None));
+        test_ty!(
+            "fn(i32, bool) -> i32",
+            Ty::bare_fn(
+                vec![
+                    Ty::path_with_dummy_span(Kw::I32),
+                    Ty::path_with_dummy_span(Kw::Bool)
+                ],
+                Some(Ty::path_with_dummy_span(Kw::I32))
+            )
+        );
+        test_ty!(
+            "fn(fn(i32) -> i32) -> &i32",
+            Ty::bare_fn(
+                vec![Ty::bare_fn(
+                    vec![Ty::path_with_dummy_span(Kw::I32)],
+                    Some(Ty::path_with_dummy_span(Kw::I32))
+                )],
+                Some(Ty::ref_(Mutability::Not, Ty::path_with_dummy_span(Kw::I32)))
+            )
+        );
+    }
 }
@@ -7,6 +7,7 @@ pub enum Delim {
     Paren,
     Bracket,
     Brace,
+    Angle,
 }
 
 impl Delim {
@@ -15,6 +16,7 @@ impl Delim {
             Delim::Paren => "(",
             Delim::Bracket => "[",
             Delim::Brace => "{",
+            Delim::Angle => "<",
         }
     }
 
@@ -23,6 +25,7 @@ impl Delim {
             Delim::Paren => ")",
             Delim::Bracket => "]",
             Delim::Brace => "}",
+            Delim::Angle => ">",
         }
     }
 }
@@ -8,4 +8,50 @@ pub enum NameResolutionError {
     UnresolvedNameUsed { name: String, span: Span },
     #[error("the name `{name}` is defined multiple times")]
     DefinedMultipleTimes { name: String },
+    #[error("expected trait, found `{name}` at ({span:?})")]
+    ExpectedTrait { name: String, span: Span },
+    #[error("expected type, found `{name}` at ({span:?})")]
+    ExpectedType { name: String, span: Span },
+    #[error("expected module, found `{name}` at ({span:?})")]
+    ExpectedModule { name: String, span: Span },
+    #[error("`{name}` at ({span:?}) is private")]
+    PrivateItem { name: String, span: Span },
+    #[error("there are too many leading `super` keywords at ({span:?})")]
+    TooManySupers { span: Span },
+    #[error("`{name}` at ({span:?}) is ambiguous, as multiple glob imports bring it into scope")]
+    AmbiguousGlobImport { name: String, span: Span },
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum MutabilityError {
+    #[error("cannot assign twice to immutable variable `{name}`: declared at ({decl:?}), assigned at ({assign:?})")]
+    AssignTwice {
+        name: String,
+        decl: Span,
+        assign: Span,
+    },
+    #[error("cannot borrow `{name}` as mutable, as it is not declared as mutable: declared at ({decl:?}), borrowed at ({borrow:?})")]
+    BorrowAsMutable {
+        name: String,
+        decl: Span,
+        borrow: Span,
+    },
+    #[error("cannot assign to constant `{name}` at ({assign:?})")]
+    AssignToConst { name: String, assign: Span },
+    #[error("cannot assign to immutable static item `{name}` at ({assign:?})")]
+    AssignToStatic { name: String, assign: Span },
+    #[error("cannot borrow immutable static item `{name}` at ({borrow:?}) as mutable")]
+    BorrowStaticAsMutable { name: String, borrow: Span },
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum UnusedWarning {
+    #[error("unused variable: `{name}` at ({span:?})")]
+    Variable { name: String, span: Span },
+    #[error("function `{name}` at ({span:?}) is never used")]
+    Function { name: String, span: Span },
+    #[error("constant `{name}` at ({span:?}) is never used")]
+    Const { name: String, span: Span },
+    #[error("static `{name}` at ({span:?}) is never used")]
+    Static { name: String, span: Span },
 }
@@ -1,76 +1,191 @@
 pub mod error;
+pub mod mutability;
+pub mod unused;
 
-use crate::error::NameResolutionError;
+use crate::{error::NameResolutionError, mutability::check_mutability};
 
 use ::ty::res::*;
 use ast::*;
 use span::*;
 
 use anyhow::Result;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 pub fn resolve_items<'a>(
     items: &[Item],
     symbol_map: &'a SymbolMap<'a>,
-) -> Result<HashMap<Span, Res>> {
+) -> Result<HashMap<NodeId, Res>> {
     let mut resolver = ASTNameResolver::new(symbol_map);
     resolver.resolve_items(items)?;
+    let resolution = resolver.finish();
 
-    Ok(resolver.finish())
+    check_mutability(items, &resolution, symbol_map)?;
+
+    Ok(resolution)
 }
 
 pub struct ASTNameResolver<'a> {
     symbol_map: &'a SymbolMap<'a>,
     def_gen: DefIdGen,
-    resolution: HashMap<Span, Res>,
-    scopes: Vec<HashMap<Symbol, Res>>,
+    resolution: HashMap<NodeId, Res>,
+
+    /// The scopes of the locals and the type parameters, the innermost last.
+    scopes: Vec<PerNs<HashMap<Symbol, Res>>>,
+
+    /// The primitive types, which are visible in all modules unless shadowed.
+    prelude: HashMap<Symbol, Res>,
+
+    /// The modules of the program. The first one is the root module.
+    modules: Vec<Module>,
+
+    /// The module defined by each `mod` item.
+    module_ids: HashMap<DefId, ModuleId>,
+
+    /// The module whose items are being resolved.
+    current_module: ModuleId,
+}
+
+type ModuleId = usize;
+
+const ROOT_MODULE: ModuleId = 0;
+
+/// A value for each namespace.
+#[derive(Default)]
+struct PerNs<T> {
+    type_ns: T,
+    value_ns: T,
+}
+
+impl<T> PerNs<T> {
+    fn get(&self, ns: Namespace) -> &T {
+        match ns {
+            Namespace::Type => &self.type_ns,
+            Namespace::Value => &self.value_ns,
+        }
+    }
+
+    fn get_mut(&mut self, ns: Namespace) -> &mut T {
+        match ns {
+            Namespace::Type => &mut self.type_ns,
+            Namespace::Value => &mut self.value_ns,
+        }
+    }
+}
+
+/// The names defined or imported in a module.
+#[derive(Default)]
+struct Module {
+    parent: Option<ModuleId>,
+    bindings: PerNs<HashMap<Symbol, Binding>>,
+
+    /// The modules whose names are imported by `use foo::*;`, with the visibility of the import.
+    globs: Vec<(ModuleId, Visibility)>,
+}
+
+/// An item defined or imported in a module.
+#[derive(Clone, Copy)]
+struct Binding {
+    res: Res,
+    vis: Visibility,
+
+    /// The module which defines or imports the item.
+    module: ModuleId,
+}
+
+/// A use declaration to be resolved after all items are defined.
+struct Import<'i> {
+    module: ModuleId,
+    vis: Visibility,
+    tree: &'i UseTree,
 }
 
 impl<'a> ASTNameResolver<'a> {
     pub fn new(symbol_map: &'a SymbolMap<'a>) -> ASTNameResolver {
+        let mut def_gen = DefIdGen::new();
+        let prelude = PrimTy::ALL
+            .iter()
+            .map(|prim| {
+                let def = def_gen.new_id();
+                let kind = ResKind::PrimTy(*prim);
+                (prim.name().into(), Res { def, kind })
+            })
+            .collect();
+
         ASTNameResolver {
             symbol_map,
-            def_gen: DefIdGen::new(),
+            def_gen,
             resolution: HashMap::new(),
             scopes: Vec::new(),
+            prelude,
+            modules: vec![Module::default()],
+            module_ids: HashMap::new(),
+            current_module: ROOT_MODULE,
         }
     }
 
-    pub fn finish(self) -> HashMap<Span, Res> {
+    pub fn finish(self) -> HashMap<NodeId, Res> {
         self.resolution
     }
 
-    pub fn insert_define(&mut self, name: Symbol, span: Span, kind: ResKind) -> Result<()> {
-        if self.exist_current_scope(&name) {
+    /// Defines a local or a type parameter in the current scope.
+    pub fn insert_define(&mut self, name: Symbol, id: NodeId, kind: ResKind) -> Result<()> {
+        if self.exist_current_scope(&name, kind.ns().unwrap()) {
             let name = self.symbol_map.get(name).to_string();
             return Err(NameResolutionError::DefinedMultipleTimes { name }.into());
         }
 
+        self.insert_shadowing_define(name, id, kind);
+        Ok(())
+    }
+
+    /// Defines the name in the current scope, shadowing any name of the scope.
+    /// The locals of let statements can be shadowed in the same block.
+    pub fn insert_shadowing_define(&mut self, name: Symbol, id: NodeId, kind: ResKind) {
+        let ns = kind.ns().unwrap();
         let def = self.def_gen.new_id();
         let res = Res { def, kind };
-        self.scopes.last_mut().unwrap().insert(name, res);
-        self.resolution.insert(span, res);
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .get_mut(ns)
+            .insert(name, res);
+        self.resolution.insert(id, res);
+    }
 
-        Ok(())
+    /// Defines an associated item, an impl or a closure.
+    /// These are not visible by their name in the enclosing scope.
+    pub fn insert_define_assoc(&mut self, id: NodeId, kind: ResKind) {
+        let def = self.def_gen.new_id();
+        self.resolution.insert(id, Res { def, kind });
     }
 
-    pub fn insert_use(&mut self, name: Symbol, span: Span) -> Result<()> {
-        let res = match self.lookup(&name) {
-            Some(res) => res,
-            None => {
-                let name = self.symbol_map.get(name).to_string();
-                return Err(NameResolutionError::UnresolvedNameUsed { name, span }.into());
-            }
-        };
+    /// Defines an item in the module.
+    fn define_in_module(&mut self, module: ModuleId, item: &Item, kind: ResKind) -> Result<Res> {
+        let def = self.def_gen.new_id();
+        let res = Res { def, kind };
+        let vis = item.vis;
+        self.insert_binding(module, item.ident.name, Binding { res, vis, module })?;
+        self.resolution.insert(item.id, res);
 
-        self.resolution.insert(span, res);
+        Ok(res)
+    }
+
+    /// Adds the name to the module in the namespace of the item it refers to.
+    fn insert_binding(&mut self, module: ModuleId, name: Symbol, binding: Binding) -> Result<()> {
+        let ns = binding.res.kind.ns().unwrap();
+        let bindings = self.modules[module].bindings.get_mut(ns);
+        if bindings.contains_key(&name) {
+            let name = self.symbol_map.get(name).to_string();
+            return Err(NameResolutionError::DefinedMultipleTimes { name }.into());
+        }
+        bindings.insert(name, binding);
 
         Ok(())
     }
 
-    fn lookup(&self, name: &Symbol) -> Option<Res> {
+    fn lookup_local(&self, name: &Symbol, ns: Namespace) -> Option<Res> {
         for scope in self.scopes.iter().rev() {
-            if let Some(def) = scope.get(name) {
+            if let Some(def) = scope.get(ns).get(name) {
                 return Some(*def);
             }
         }
@@ -78,16 +193,71 @@ impl<'a> ASTNameResolver<'a> {
         None
     }
 
-    fn exist_current_scope(&self, name: &Symbol) -> bool {
+    /// Looks up the name defined in the module, or else imported by its glob imports.
+    /// Returns a binding for each distinct item of the name, so more than one means that
+    /// the glob imports make the name ambiguous.
+    /// `visited` is the modules already searched, to stop at cyclic glob imports.
+    fn lookup_in_module(
+        &self,
+        module: ModuleId,
+        name: Symbol,
+        ns: Namespace,
+        visited: &mut HashSet<ModuleId>,
+    ) -> Vec<Binding> {
+        if !visited.insert(module) {
+            return Vec::new();
+        }
+
+        if let Some(binding) = self.modules[module].bindings.get(ns).get(&name) {
+            return vec![*binding];
+        }
+
+        let mut found: Vec<Binding> = Vec::new();
+        for &(source, vis) in &self.modules[module].globs {
+            for binding in self.lookup_in_module(source, name, ns, visited) {
+                // the same item imported by several globs is not ambiguous.
+                let imported = found.iter().any(|found| found.res == binding.res);
+                if self.is_accessible(&binding, module) && !imported {
+                    found.push(Binding {
+                        res: binding.res,
+                        vis,
+                        module,
+                    });
+                }
+            }
+        }
+
+        found
+    }
+
+    /// Returns `true` if the item can be referred from the module,
+    /// that is, the item is public or the module is the one which defines the item or its descendant.
+    fn is_accessible(&self, binding: &Binding, from: ModuleId) -> bool {
+        if binding.vis == Visibility::Public {
+            return true;
+        }
+
+        let mut module = Some(from);
+        while let Some(id) = module {
+            if id == binding.module {
+                return true;
+            }
+            module = self.modules[id].parent;
+        }
+
+        false
+    }
+
+    fn exist_current_scope(&self, name: &Symbol, ns: Namespace) -> bool {
         let current_scope = self.scopes.last().unwrap();
-        current_scope.contains_key(name)
+        current_scope.get(ns).contains_key(name)
     }
 
     pub fn with_new_scope<F>(&mut self, f: F) -> Result<()>
     where
         F: FnOnce(&mut ASTNameResolver) -> Result<()>,
     {
-        self.scopes.push(HashMap::new());
+        self.scopes.push(PerNs::default());
         f(self)?;
         self.scopes.pop();
         Ok(())
@@ -95,26 +265,131 @@ impl<'a> ASTNameResolver<'a> {
 }
 
 impl<'a> ASTNameResolver<'a> {
-    pub fn resolve_ident(&mut self, ident: &Ident) -> Result<()> {
-        self.insert_use(ident.name, ident.span)?;
+    /// Resolves the path to a name in the namespace, from the current module.
+    /// A path of a single segment may also refer to a local or a type parameter.
+    pub fn resolve_path(&mut self, path: &Path, ns: Namespace) -> Result<Res> {
+        if let [ident] = path.segments.as_slice() {
+            if let Some(res) = self.lookup_local(&ident.name, ns) {
+                self.resolution.insert(path.id, res);
+                return Ok(res);
+            }
+        }
 
-        Ok(())
+        let res = self.resolve_path_from(path, ns, self.current_module)?;
+        self.resolution.insert(path.id, res);
+
+        Ok(res)
+    }
+
+    /// Resolves the path of an item relative to the module.
+    /// A primitive type can be named by a single segment unless an item shadows it.
+    fn resolve_path_from(&mut self, path: &Path, ns: Namespace, from: ModuleId) -> Result<Res> {
+        let (ident, prefix) = path.segments.split_last().unwrap();
+        let module = self.resolve_module_path(prefix, from)?;
+
+        let result = self.resolve_in_module(module, ident, ns, from);
+        match self.prelude.get(&ident.name) {
+            Some(res) if result.is_err() && prefix.is_empty() && ns == Namespace::Type => Ok(*res),
+            _ => result,
+        }
+    }
+
+    /// Resolves the path to a module relative to the module `from`.
+    /// The path may start with `crate`, `self` or `super`, and `super` may be repeated.
+    fn resolve_module_path(&mut self, segments: &[Ident], from: ModuleId) -> Result<ModuleId> {
+        let mut module = from;
+        for (i, ident) in segments.iter().enumerate() {
+            let is_leading = i == 0 || segments[i - 1].name == Kw::Super.into();
+
+            module = if i == 0 && ident.name == Kw::Crate.into() {
+                ROOT_MODULE
+            } else if i == 0 && ident.name == Kw::SelfLower.into() {
+                from
+            } else if is_leading && ident.name == Kw::Super.into() {
+                match self.modules[module].parent {
+                    Some(parent) => parent,
+                    None => {
+                        let span = ident.span;
+                        return Err(NameResolutionError::TooManySupers { span }.into());
+                    }
+                }
+            } else {
+                // A value of the name is reported as not being a module.
+                let is_value = !self
+                    .lookup_in_module(module, ident.name, Namespace::Value, &mut HashSet::new())
+                    .is_empty();
+                let res = match self.resolve_in_module(module, ident, Namespace::Type, from) {
+                    Err(_) if is_value => None,
+                    res => Some(res?),
+                };
+
+                match res {
+                    Some(res) if res.kind == ResKind::Mod => self.module_ids[&res.def],
+                    _ => {
+                        let name = self.symbol_map.get(ident.name).to_string();
+                        let span = ident.span;
+                        return Err(NameResolutionError::ExpectedModule { name, span }.into());
+                    }
+                }
+            };
+        }
+
+        Ok(module)
+    }
+
+    /// Resolves the name in the namespace of the module,
+    /// which must be accessible from the module `from`.
+    fn resolve_in_module(
+        &self,
+        module: ModuleId,
+        ident: &Ident,
+        ns: Namespace,
+        from: ModuleId,
+    ) -> Result<Res> {
+        let name = || self.symbol_map.get(ident.name).to_string();
+        let span = ident.span;
+
+        let bindings = self.lookup_in_module(module, ident.name, ns, &mut HashSet::new());
+        let binding = match bindings.as_slice() {
+            [binding] => *binding,
+            [] => {
+                let name = name();
+                return Err(NameResolutionError::UnresolvedNameUsed { name, span }.into());
+            }
+            _ => {
+                let name = name();
+                return Err(NameResolutionError::AmbiguousGlobImport { name, span }.into());
+            }
+        };
+        if !self.is_accessible(&binding, from) {
+            let name = name();
+            return Err(NameResolutionError::PrivateItem { name, span }.into());
+        }
+
+        Ok(binding.res)
     }
 
     pub fn resolve_expr(&mut self, expr: &Expr) -> Result<()> {
         match expr {
-            Expr::Call { fun, args } => {
+            Expr::Call { fun, args, .. } => {
                 self.resolve_expr(fun)?;
 
                 for arg in args {
                     self.resolve_expr(arg)?;
                 }
             }
+            Expr::MethodCall { receiver, args, .. } => {
+                self.resolve_expr(receiver)?;
+
+                for arg in args {
+                    self.resolve_expr(arg)?;
+                }
+            }
             Expr::Binary { lhs, rhs, .. } => {
                 self.resolve_expr(lhs)?;
                 self.resolve_expr(rhs)?;
             }
-            Expr::Unary { expr, .. } => self.resolve_expr(expr)?,
+            Expr::Unary { expr, .. } | Expr::AddrOf { expr, .. } => self.resolve_expr(expr)?,
             Expr::If {
                 cond,
                 then,
@@ -139,7 +414,26 @@ impl<'a> ASTNameResolver<'a> {
                 self.resolve_expr(lhs)?;
             }
             Expr::Lit { .. } => {}
-            Expr::Path(path) => self.resolve_ident(&path.ident)?,
+            Expr::Path(path) => {
+                self.resolve_path(path, Namespace::Value)?;
+            }
+            Expr::Closure {
+                inputs,
+                output,
+                body,
+                id,
+                ..
+            } => {
+                self.insert_define_assoc(*id, ResKind::Closure);
+                if let Some(output) = output {
+                    self.resolve_ty(output)?;
+                }
+
+                self.with_new_scope(|this| {
+                    this.resolve_params(inputs)?;
+                    this.resolve_expr(body)
+                })?;
+            }
         }
 
         Ok(())
@@ -156,9 +450,18 @@ impl<'a> ASTNameResolver<'a> {
 
     pub fn resolve_stmt(&mut self, stmt: &Stmt) -> Result<()> {
         match stmt {
-            Stmt::Local { ident, init, .. } => {
+            Stmt::Local {
+                id,
+                ident,
+                ty,
+                init,
+                ..
+            } => {
+                if let Some(ty) = ty {
+                    self.resolve_ty(ty)?;
+                }
                 self.resolve_expr(init)?;
-                self.insert_define(ident.name, ident.span, ResKind::Local)?;
+                self.insert_shadowing_define(ident.name, *id, ResKind::Local);
             }
             Stmt::Expr(expr) | Stmt::Semi(expr) | Stmt::Println(expr) => self.resolve_expr(expr)?,
         }
@@ -167,37 +470,456 @@ impl<'a> ASTNameResolver<'a> {
     }
 
     pub fn resolve_items(&mut self, items: &[Item]) -> Result<()> {
-        self.with_new_scope(|this| {
-            // resolve item declaration.
-            for item in items {
-                let ident = &item.ident;
+        // resolve item declaration.
+        let mut imports = Vec::new();
+        self.define_items(items, ROOT_MODULE, &mut imports)?;
+        self.resolve_imports(imports)?;
 
-                let kind = match item.kind {
-                    ItemKind::Fn(_) => ResKind::Fn,
-                };
+        self.resolve_module_items(items, ROOT_MODULE)
+    }
 
-                this.insert_define(ident.name, ident.span, kind)?;
+    /// Defines the items in the module and its submodules,
+    /// and collects the use declarations in them.
+    fn define_items<'i>(
+        &mut self,
+        items: &'i [Item],
+        module: ModuleId,
+        imports: &mut Vec<Import<'i>>,
+    ) -> Result<()> {
+        for item in items {
+            match &item.kind {
+                ItemKind::Fn(_) => {
+                    self.define_in_module(module, item, ResKind::Fn)?;
+                }
+                ItemKind::Trait(_) => {
+                    self.define_in_module(module, item, ResKind::Trait)?;
+                }
+                ItemKind::Const(_) => {
+                    self.define_in_module(module, item, ResKind::Const)?;
+                }
+                ItemKind::Static(_) => {
+                    self.define_in_module(module, item, ResKind::Static)?;
+                }
+                ItemKind::Impl(_) => self.insert_define_assoc(item.id, ResKind::Impl),
+                ItemKind::Mod(m) => {
+                    let res = self.define_in_module(module, item, ResKind::Mod)?;
+                    let id = self.modules.len();
+                    self.modules.push(Module {
+                        parent: Some(module),
+                        ..Module::default()
+                    });
+                    self.module_ids.insert(res.def, id);
+
+                    self.define_items(&m.items, id, imports)?;
+                }
+                ItemKind::Use(tree) => imports.push(Import {
+                    module,
+                    vis: item.vis,
+                    tree: tree.as_ref(),
+                }),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Resolves the use declarations.
+    /// Since an import may refer to the names brought by other imports,
+    /// the unresolved ones are retried until no more imports get resolved.
+    fn resolve_imports(&mut self, mut imports: Vec<Import>) -> Result<()> {
+        while !imports.is_empty() {
+            let count = imports.len();
+            let mut first_err = None;
+            let mut unresolved = Vec::new();
+
+            for import in imports {
+                if let Err(err) = self.resolve_import(&import) {
+                    first_err.get_or_insert(err);
+                    unresolved.push(import);
+                }
             }
 
-            this.with_new_scope(|this| {
-                for item in items {
-                    match &item.kind {
-                        ItemKind::Fn(fun) => this.resolve_item_fn(fun.as_ref())?,
+            if unresolved.len() == count {
+                return Err(first_err.unwrap());
+            }
+            imports = unresolved;
+        }
+
+        Ok(())
+    }
+
+    fn resolve_import(&mut self, import: &Import) -> Result<()> {
+        let tree = import.tree;
+        match &tree.kind {
+            UseTreeKind::Simple(alias) => {
+                let (ident, prefix) = tree.prefix.segments.split_last().unwrap();
+                let source = self.resolve_module_path(prefix, import.module)?;
+                let name = alias.as_ref().unwrap_or(ident).name;
+
+                // The import brings the name into every namespace it is defined in.
+                let mut errs = Vec::new();
+                for ns in Namespace::ALL {
+                    match self.resolve_in_module(source, ident, ns, import.module) {
+                        Ok(res) => {
+                            let binding = Binding {
+                                res,
+                                vis: import.vis,
+                                module: import.module,
+                            };
+                            self.insert_binding(import.module, name, binding)?;
+                        }
+                        Err(err) => errs.push(err),
                     }
                 }
+
+                if errs.len() < Namespace::ALL.len() {
+                    return Ok(());
+                }
+                // A private item is reported over the namespaces where the name is undefined.
+                let private = errs.iter().position(|err| {
+                    matches!(
+                        err.downcast_ref(),
+                        Some(NameResolutionError::PrivateItem { .. })
+                    )
+                });
+                Err(errs.swap_remove(private.unwrap_or(0)))
+            }
+            UseTreeKind::Glob => {
+                let source = self.resolve_module_path(&tree.prefix.segments, import.module)?;
+                self.modules[import.module].globs.push((source, import.vis));
+
                 Ok(())
-            })
-        })
+            }
+        }
+    }
+
+    /// Resolves the bodies of the items in the module.
+    fn resolve_module_items(&mut self, items: &[Item], module: ModuleId) -> Result<()> {
+        let parent_module = self.current_module;
+        self.current_module = module;
+
+        for item in items {
+            match &item.kind {
+                ItemKind::Fn(fun) => self.resolve_item_fn(fun.as_ref())?,
+                ItemKind::Trait(tr) => self.resolve_item_trait(tr.as_ref())?,
+                ItemKind::Impl(imp) => self.resolve_item_impl(imp.as_ref())?,
+                ItemKind::Mod(m) => {
+                    let def = self.resolution[&item.id].def;
+                    self.resolve_module_items(&m.items, self.module_ids[&def])?;
+                }
+                ItemKind::Use(_) => {}
+                ItemKind::Const(c) => self.resolve_item_const(&c.ty, &c.expr)?,
+                ItemKind::Static(s) => self.resolve_item_const(&s.ty, &s.expr)?,
+            }
+        }
+
+        self.current_module = parent_module;
+        Ok(())
     }
 
     pub fn resolve_item_fn(&mut self, fun: &Fn) -> Result<()> {
         self.with_new_scope(|this| {
-            for param in &fun.inputs {
-                let ident = &param.ident;
-                this.insert_define(ident.name, ident.span, ResKind::Local)?;
+            for param in &fun.generics.params {
+                this.insert_define(param.ident.name, param.id, ResKind::TyParam)?;
+            }
+            for param in &fun.generics.params {
+                for bound in &param.bounds {
+                    this.resolve_trait_path(bound)?;
+                }
             }
 
+            if let Some(output) = &fun.output {
+                this.resolve_ty(output)?;
+            }
+
+            this.resolve_params(&fun.inputs)?;
             this.resolve_block(&fun.body)
         })
     }
+
+    /// Resolves the type and the initializer of a constant or a static.
+    pub fn resolve_item_const(&mut self, ty: &Ty, expr: &Expr) -> Result<()> {
+        self.resolve_ty(ty)?;
+        self.with_new_scope(|this| this.resolve_expr(expr))
+    }
+
+    pub fn resolve_item_trait(&mut self, tr: &Trait) -> Result<()> {
+        self.check_method_names(tr.items.iter().map(|item| item.ident.name))?;
+
+        for item in &tr.items {
+            self.insert_define_assoc(item.id, ResKind::Fn);
+            if let Some(output) = &item.output {
+                self.resolve_ty(output)?;
+            }
+            self.with_new_scope(|this| this.resolve_params(&item.inputs))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn resolve_item_impl(&mut self, imp: &Impl) -> Result<()> {
+        if let Some(path) = &imp.of_trait {
+            self.resolve_trait_path(path)?;
+        }
+        self.resolve_ty(&imp.self_ty)?;
+
+        self.check_method_names(imp.items.iter().map(|item| item.ident.name))?;
+
+        for item in &imp.items {
+            self.insert_define_assoc(item.id, ResKind::Fn);
+            match &item.kind {
+                ItemKind::Fn(fun) => self.resolve_item_fn(fun.as_ref())?,
+                _ => unreachable!(),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn resolve_params(&mut self, params: &[Param]) -> Result<()> {
+        for param in params {
+            self.resolve_ty(&param.ty)?;
+
+            self.insert_define(param.ident.name, param.id, ResKind::Local)?;
+        }
+
+        Ok(())
+    }
+
+    /// Resolves the type names and the traits of the trait object types in the type.
+    /// `Self` is left to the lowering, which knows the type of the impl.
+    fn resolve_ty(&mut self, ty: &Ty) -> Result<()> {
+        match &ty.kind {
+            TyKind::Path(path) if is_self_ty(path) => Ok(()),
+            TyKind::Path(path) => {
+                let res = self.resolve_path(path, Namespace::Type)?;
+                match res.kind {
+                    ResKind::PrimTy(_) | ResKind::TyParam => Ok(()),
+                    _ => {
+                        let ident = path.ident();
+                        let name = self.symbol_map.get(ident.name).to_string();
+                        let span = ident.span;
+                        Err(NameResolutionError::ExpectedType { name, span }.into())
+                    }
+                }
+            }
+            TyKind::Ref(_, ty) | TyKind::Ptr(_, ty) => self.resolve_ty(ty),
+            TyKind::TraitObject(path) => self.resolve_trait_path(path),
+            TyKind::BareFn(bare_fn) => {
+                for input in &bare_fn.inputs {
+                    self.resolve_ty(input)?;
+                }
+                match &bare_fn.output {
+                    Some(output) => self.resolve_ty(output),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+
+    /// Resolves the path in a trait bound, an impl or a trait object type,
+    /// which must refer to a trait.
+    fn resolve_trait_path(&mut self, path: &Path) -> Result<()> {
+        let res = self.resolve_path(path, Namespace::Type)?;
+
+        if res.kind != ResKind::Trait {
+            let ident = path.ident();
+            let name = self.symbol_map.get(ident.name).to_string();
+            let span = ident.span;
+            return Err(NameResolutionError::ExpectedTrait { name, span }.into());
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the methods in a trait or an impl have distinct names.
+    fn check_method_names<I: Iterator<Item = Symbol>>(&self, names: I) -> Result<()> {
+        let mut defined = HashSet::new();
+        for name in names {
+            if !defined.insert(name) {
+                let name = self.symbol_map.get(name).to_string();
+                return Err(NameResolutionError::DefinedMultipleTimes { name }.into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns whether the path is `Self`, the type of the impl.
+fn is_self_ty(path: &Path) -> bool {
+    matches!(path.segments.as_slice(), [ident] if ident.name == Kw::SelfUpper.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::NameResolutionError, resolve_items};
+
+    use parser::parse_items;
+
+    fn resolve(src: &str) -> Option<NameResolutionError> {
+        let (items, map) = parse_items(src).unwrap();
+        let err = resolve_items(items.as_slice(), &map).err()?;
+
+        Some(err.downcast::<NameResolutionError>().unwrap())
+    }
+
+    #[test]
+    fn module_paths() {
+        let src = r"
+mod a {
+    pub fn f() -> i32 { b::g() }
+    mod b {
+        pub fn g() -> i32 { super::h() + crate::main() }
+    }
+    fn h() -> i32 { 1 }
+}
+fn main() -> i32 { a::f() + self::a::f() }
+";
+        assert_eq!(resolve(src), None);
+    }
+
+    #[test]
+    fn private_items() {
+        assert!(matches!(
+            resolve("mod a { fn f() {} } fn main() { a::f(); }"),
+            Some(NameResolutionError::PrivateItem { .. })
+        ));
+        assert!(matches!(
+            resolve("mod a { mod b { pub fn f() {} } } fn main() { a::b::f(); }"),
+            Some(NameResolutionError::PrivateItem { .. })
+        ));
+        assert!(matches!(
+            resolve("fn f() {} fn main() { f::g(); }"),
+            Some(NameResolutionError::ExpectedModule { .. })
+        ));
+        assert!(matches!(
+            resolve("fn main() { super::main(); }"),
+            Some(NameResolutionError::TooManySupers { .. })
+        ));
+    }
+
+    #[test]
+    fn use_declarations() {
+        let src = r"
+mod a {
+    pub fn f() {}
+    pub fn g() {}
+    pub use b::*;
+    mod b {
+        pub fn h() {}
+    }
+}
+use a::f as first;
+use a::*;
+use c::g;
+mod c {
+    pub use crate::a::g;
+}
+fn main() { first(); f(); g(); h(); }
+";
+        assert_eq!(resolve(src), None);
+        assert!(matches!(
+            resolve("mod a { fn f() {} } use a::f; fn main() { }"),
+            Some(NameResolutionError::PrivateItem { .. })
+        ));
+        assert!(matches!(
+            resolve("mod a { pub fn f() {} } use a::f; fn f() {}"),
+            Some(NameResolutionError::DefinedMultipleTimes { .. })
+        ));
+        assert!(matches!(
+            resolve("mod a { pub fn f() {} } use a::g; fn main() { }"),
+            Some(NameResolutionError::UnresolvedNameUsed { .. })
+        ));
+    }
+
+    #[test]
+    fn ambiguous_glob_imports() {
+        let src = r"
+mod a { pub fn f() {} pub fn g() {} }
+mod b { pub fn f() {} fn g() {} }
+use a::*;
+use b::*;
+fn main() { f(); }
+";
+        assert!(matches!(
+            resolve(src),
+            Some(NameResolutionError::AmbiguousGlobImport { .. })
+        ));
+
+        // a private item is not imported, an item defined in the module shadows the globs,
+        // and the same item imported twice is not ambiguous.
+        let src = r"
+mod a { pub fn f() {} pub fn g() {} pub fn h() {} }
+mod b { pub fn f() {} fn g() {} pub use crate::a::h; }
+use a::*;
+use b::*;
+fn f() {}
+fn main() { f(); g(); h(); }
+";
+        assert_eq!(resolve(src), None);
+    }
+
+    #[test]
+    fn namespaces() {
+        let src = r"
+trait f {}
+fn f(x: &dyn f) -> i32 { 1 }
+fn g<T: f>(x: T, y: bool) -> T { f(&1); x }
+";
+        assert_eq!(resolve(src), None);
+        assert!(matches!(
+            resolve("fn f() -> i32 { i32 }"),
+            Some(NameResolutionError::UnresolvedNameUsed { .. })
+        ));
+        assert!(matches!(
+            resolve("fn f<T>(x: T) { T; }"),
+            Some(NameResolutionError::UnresolvedNameUsed { .. })
+        ));
+        assert!(matches!(
+            resolve("fn f(x: u8) {}"),
+            Some(NameResolutionError::UnresolvedNameUsed { .. })
+        ));
+        assert!(matches!(
+            resolve("mod a {} fn f(x: a) {}"),
+            Some(NameResolutionError::ExpectedType { .. })
+        ));
+    }
+
+    #[test]
+    fn shadowed_primitive_types() {
+        assert!(matches!(
+            resolve("trait i32 {} fn f(x: i32) {}"),
+            Some(NameResolutionError::ExpectedType { .. })
+        ));
+        assert_eq!(
+            resolve("fn i32() {} fn f(x: i32) -> i32 { i32(); x }"),
+            None
+        );
+    }
+
+    #[test]
+    fn const_and_static_items() {
+        let src = r"
+mod a {
+    pub const N: i32 = 4 * M;
+    const M: i32 = 8;
+}
+static mut COUNTER: i32 = a::N;
+fn main() { COUNTER = COUNTER + a::N; }
+";
+        assert_eq!(resolve(src), None);
+        assert!(matches!(
+            resolve("const N: i32 = 1; fn N() {}"),
+            Some(NameResolutionError::DefinedMultipleTimes { .. })
+        ));
+        assert!(matches!(
+            resolve("fn main() { let x = 1; } static S: i32 = x;"),
+            Some(NameResolutionError::UnresolvedNameUsed { .. })
+        ));
+        assert!(matches!(
+            resolve("mod a { const N: i32 = 1; } fn main() -> i32 { a::N }"),
+            Some(NameResolutionError::PrivateItem { .. })
+        ));
+    }
 }
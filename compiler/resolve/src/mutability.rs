@@ -0,0 +1,309 @@
+use crate::error::MutabilityError;
+
+use ::ty::res::*;
+use ast::*;
+use span::*;
+
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+
+/// Check that immutable locals are neither reassigned nor mutably borrowed.
+/// Constants and immutable statics cannot be assigned to either.
+/// This check needs the result of name resolution.
+pub fn check_mutability<'a>(
+    items: &[Item],
+    resolution: &HashMap<NodeId, Res>,
+    symbol_map: &'a SymbolMap<'a>,
+) -> Result<()> {
+    let mut checker = MutabilityChecker::new(resolution, symbol_map);
+    checker.declare_statics(items);
+    checker.check_items(items)
+}
+
+struct MutabilityChecker<'a> {
+    resolution: &'a HashMap<NodeId, Res>,
+    symbol_map: &'a SymbolMap<'a>,
+
+    /// Mutability and span of the declaration of each local.
+    locals: HashMap<DefId, (Mutability, Span)>,
+
+    /// The statics declared without `mut`.
+    immutable_statics: HashSet<DefId>,
+}
+
+impl<'a> MutabilityChecker<'a> {
+    fn new(resolution: &'a HashMap<NodeId, Res>, symbol_map: &'a SymbolMap<'a>) -> Self {
+        MutabilityChecker {
+            resolution,
+            symbol_map,
+            locals: HashMap::new(),
+            immutable_statics: HashSet::new(),
+        }
+    }
+
+    fn declare(&mut self, mutbl: Mutability, id: NodeId, ident: &Ident) {
+        let res = self.resolution[&id];
+        self.locals.insert(res.def, (mutbl, ident.span));
+    }
+
+    /// Returns the name and the declaration span of the immutable local referred by `expr`.
+    fn immutable_local(&self, expr: &Expr) -> Option<(String, Span)> {
+        let path = match expr {
+            Expr::Path(path) => path,
+            _ => return None,
+        };
+        let ident = path.ident();
+        let res = self.resolution[&path.id];
+
+        match self.locals.get(&res.def) {
+            Some((Mutability::Not, decl)) => {
+                let name = self.symbol_map.get(ident.name).to_string();
+                Some((name, *decl))
+            }
+            _ => None,
+        }
+    }
+
+    /// Records the immutable statics in the items and their modules,
+    /// since a function may refer to a static declared after it.
+    fn declare_statics(&mut self, items: &[Item]) {
+        for item in items {
+            match &item.kind {
+                ItemKind::Static(s) if s.mutbl == Mutability::Not => {
+                    self.immutable_statics.insert(self.resolution[&item.id].def);
+                }
+                ItemKind::Mod(module) => self.declare_statics(&module.items),
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns the kind, the name and the span of the constant or the immutable static
+    /// referred by `expr`.
+    fn immutable_item(&self, expr: &Expr) -> Option<(ResKind, String, Span)> {
+        let path = match expr {
+            Expr::Path(path) => path,
+            _ => return None,
+        };
+        let ident = path.ident();
+        let res = self.resolution[&path.id];
+
+        match res.kind {
+            ResKind::Const => {}
+            ResKind::Static if self.immutable_statics.contains(&res.def) => {}
+            _ => return None,
+        }
+        let name = self.symbol_map.get(ident.name).to_string();
+        Some((res.kind, name, ident.span))
+    }
+
+    fn check_items(&mut self, items: &[Item]) -> Result<()> {
+        for item in items {
+            match &item.kind {
+                ItemKind::Fn(fun) => {
+                    for param in &fun.inputs {
+                        self.declare(param.mutbl, param.id, &param.ident);
+                    }
+                    self.check_block(&fun.body)?;
+                }
+                ItemKind::Trait(_) | ItemKind::Use(_) => {}
+                ItemKind::Impl(imp) => self.check_items(&imp.items)?,
+                ItemKind::Mod(module) => self.check_items(&module.items)?,
+                ItemKind::Const(c) => self.check_expr(&c.expr)?,
+                ItemKind::Static(s) => self.check_expr(&s.expr)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_block(&mut self, block: &Block) -> Result<()> {
+        for stmt in &block.stmts {
+            match stmt {
+                Stmt::Local {
+                    id,
+                    mutbl,
+                    ident,
+                    init,
+                    ..
+                } => {
+                    self.check_expr(init)?;
+                    self.declare(*mutbl, *id, ident);
+                }
+                Stmt::Expr(expr) | Stmt::Semi(expr) | Stmt::Println(expr) => {
+                    self.check_expr(expr)?
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_expr(&mut self, expr: &Expr) -> Result<()> {
+        match expr {
+            Expr::Assign { lhs, rhs } => {
+                self.check_expr(rhs)?;
+                self.check_expr(lhs)?;
+
+                if let Some((name, decl)) = self.immutable_local(lhs) {
+                    let assign = match lhs.as_ref() {
+                        Expr::Path(path) => path.ident().span,
+                        _ => unreachable!(),
+                    };
+                    return Err(MutabilityError::AssignTwice { name, decl, assign }.into());
+                }
+                if let Some((kind, name, assign)) = self.immutable_item(lhs) {
+                    return Err(match kind {
+                        ResKind::Const => MutabilityError::AssignToConst { name, assign },
+                        _ => MutabilityError::AssignToStatic { name, assign },
+                    }
+                    .into());
+                }
+            }
+            Expr::AddrOf { mutbl, expr } => {
+                self.check_expr(expr)?;
+
+                if *mutbl == Mutability::Mut {
+                    if let Some((name, decl)) = self.immutable_local(expr) {
+                        let borrow = match expr.as_ref() {
+                            Expr::Path(path) => path.ident().span,
+                            _ => unreachable!(),
+                        };
+                        return Err(MutabilityError::BorrowAsMutable { name, decl, borrow }.into());
+                    }
+                    // A constant is borrowed as a temporary copy of its value.
+                    if let Some((ResKind::Static, name, borrow)) = self.immutable_item(expr) {
+                        return Err(MutabilityError::BorrowStaticAsMutable { name, borrow }.into());
+                    }
+                }
+            }
+            Expr::Call { fun, args, .. } => {
+                self.check_expr(fun)?;
+
+                for arg in args {
+                    self.check_expr(arg)?;
+                }
+            }
+            Expr::MethodCall { receiver, args, .. } => {
+                self.check_expr(receiver)?;
+
+                for arg in args {
+                    self.check_expr(arg)?;
+                }
+            }
+            Expr::Binary { lhs, rhs, .. } => {
+                self.check_expr(lhs)?;
+                self.check_expr(rhs)?;
+            }
+            Expr::Unary { expr, .. } => self.check_expr(expr)?,
+            Expr::If {
+                cond,
+                then,
+                else_opt,
+            } => {
+                self.check_expr(cond)?;
+                self.check_block(then)?;
+
+                if let Some(else_expr) = else_opt {
+                    self.check_expr(else_expr)?;
+                }
+            }
+            Expr::Loop { block } | Expr::Block { block } => self.check_block(block)?,
+            Expr::Break { expr } | Expr::Continue { expr } | Expr::Return { expr } => {
+                if let Some(expr) = expr {
+                    self.check_expr(expr)?
+                }
+            }
+            Expr::Closure { inputs, body, .. } => {
+                for param in inputs {
+                    self.declare(param.mutbl, param.id, &param.ident);
+                }
+                self.check_expr(body)?;
+            }
+            Expr::Lit { .. } | Expr::Path(_) => {}
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::MutabilityError;
+    use crate::resolve_items;
+
+    use parser::parse_items;
+
+    fn check(src: &str) -> Option<MutabilityError> {
+        let (items, map) = parse_items(src).unwrap();
+        let err = resolve_items(items.as_slice(), &map).err()?;
+
+        Some(err.downcast::<MutabilityError>().unwrap())
+    }
+
+    #[test]
+    fn assign_mut_local() {
+        assert_eq!(check("fn f() { let mut a = 1; a = 2; }"), None);
+        assert_eq!(check("fn f(mut a: i32) { a = 2; }"), None);
+    }
+
+    #[test]
+    fn assign_captured_immutable_local() {
+        assert_eq!(check("fn f() { let mut a = 1; let c = || a = 2; }"), None);
+        assert!(matches!(
+            check("fn f() { let a = 1; let c = || a = 2; }"),
+            Some(MutabilityError::AssignTwice { .. })
+        ));
+    }
+
+    #[test]
+    fn assign_immutable_local() {
+        assert!(matches!(
+            check("fn f() { let a = 1; a = 2; }"),
+            Some(MutabilityError::AssignTwice { .. })
+        ));
+        assert!(matches!(
+            check("fn f(a: i32) { a = 2; }"),
+            Some(MutabilityError::AssignTwice { .. })
+        ));
+    }
+
+    #[test]
+    fn assign_shadowed_local() {
+        assert_eq!(
+            check("fn f() { let a = 1; { let mut a = a; a = 2; } }"),
+            None
+        );
+    }
+
+    #[test]
+    fn borrow_immutable_local() {
+        assert_eq!(check("fn f() { let a = 1; let b = &a; }"), None);
+        assert_eq!(check("fn f() { let mut a = 1; let b = &mut a; }"), None);
+        assert!(matches!(
+            check("fn f() { let a = 1; let b = &mut a; }"),
+            Some(MutabilityError::BorrowAsMutable { .. })
+        ));
+    }
+
+    #[test]
+    fn mutate_const_and_static() {
+        assert_eq!(
+            check("fn f() { S = 1; let p = &mut S; } static mut S: i32 = 0;"),
+            None
+        );
+        assert_eq!(check("const N: i32 = 1; fn f() { let p = &mut N; }"), None);
+        assert!(matches!(
+            check("const N: i32 = 1; fn f() { N = 2; }"),
+            Some(MutabilityError::AssignToConst { .. })
+        ));
+        assert!(matches!(
+            check("fn f() { S = 2; } static S: i32 = 1;"),
+            Some(MutabilityError::AssignToStatic { .. })
+        ));
+        assert!(matches!(
+            check("mod a { pub static S: i32 = 1; } fn f() { let p = &mut a::S; }"),
+            Some(MutabilityError::BorrowStaticAsMutable { .. })
+        ));
+    }
+}
@@ -0,0 +1,337 @@
+use crate::error::UnusedWarning;
+
+use ::ty::res::*;
+use ast::*;
+use span::*;
+
+use std::collections::{HashMap, HashSet};
+
+/// Returns the warnings for the locals which are never used, and for the functions,
+/// the constants and the statics which are unreachable.
+/// An item is reachable from `main`, from the public items and from the methods, through the
+/// items their bodies and initializers use, so an item used only by an unreachable one is warned.
+/// A name starting with `_` is not warned about, nor is the `main` function.
+/// This check needs the result of name resolution.
+pub fn check_unused<'a>(
+    items: &[Item],
+    resolution: &HashMap<NodeId, Res>,
+    symbol_map: &'a SymbolMap<'a>,
+) -> Vec<UnusedWarning> {
+    let mut checker = UnusedChecker::new(resolution, symbol_map);
+    checker.check_items(items);
+
+    for item in items {
+        if let ItemKind::Fn(_) = item.kind {
+            if symbol_map.get(item.ident.name) == "main" {
+                checker.roots.push(resolution[&item.id].def);
+            }
+        }
+    }
+
+    checker.finish()
+}
+
+struct UnusedChecker<'a> {
+    resolution: &'a HashMap<NodeId, Res>,
+    symbol_map: &'a SymbolMap<'a>,
+
+    /// The locals and the items in order of definition.
+    defs: Vec<(Res, Ident)>,
+    used: HashSet<DefId>,
+
+    /// The definitions used by the body or the initializer of each item.
+    uses: HashMap<DefId, Vec<DefId>>,
+
+    /// The items reachable by themselves, and the definitions used by the methods.
+    roots: Vec<DefId>,
+
+    /// The item whose body or initializer is being checked, or `None` in a method.
+    current_item: Option<DefId>,
+}
+
+impl<'a> UnusedChecker<'a> {
+    fn new(resolution: &'a HashMap<NodeId, Res>, symbol_map: &'a SymbolMap<'a>) -> Self {
+        UnusedChecker {
+            resolution,
+            symbol_map,
+            defs: Vec::new(),
+            used: HashSet::new(),
+            uses: HashMap::new(),
+            roots: Vec::new(),
+            current_item: None,
+        }
+    }
+
+    /// Returns the items reachable from the roots through their uses.
+    fn reachable(&self) -> HashSet<DefId> {
+        let mut reachable = HashSet::new();
+        let mut stack = self.roots.clone();
+        while let Some(def) = stack.pop() {
+            if reachable.insert(def) {
+                stack.extend(self.uses.get(&def).into_iter().flatten());
+            }
+        }
+
+        reachable
+    }
+
+    fn finish(self) -> Vec<UnusedWarning> {
+        let reachable = self.reachable();
+        self.defs
+            .iter()
+            .filter(|(res, _)| match res.kind {
+                ResKind::Fn | ResKind::Const | ResKind::Static => !reachable.contains(&res.def),
+                _ => !self.used.contains(&res.def),
+            })
+            .filter_map(|(res, ident)| {
+                let name = self.symbol_map.get(ident.name);
+                if name.starts_with('_') {
+                    return None;
+                }
+
+                let name = name.to_string();
+                let span = ident.span;
+                match res.kind {
+                    ResKind::Fn => Some(UnusedWarning::Function { name, span }),
+                    ResKind::Const => Some(UnusedWarning::Const { name, span }),
+                    ResKind::Static => Some(UnusedWarning::Static { name, span }),
+                    _ => Some(UnusedWarning::Variable { name, span }),
+                }
+            })
+            .collect()
+    }
+
+    fn define(&mut self, id: NodeId, ident: &Ident) {
+        let res = self.resolution[&id];
+        self.defs.push((res, ident.clone()));
+    }
+
+    /// Records the item, which is a root if it is public.
+    fn define_item(&mut self, item: &Item) {
+        self.define(item.id, &item.ident);
+        if item.vis == Visibility::Public {
+            self.roots.push(self.resolution[&item.id].def);
+        }
+    }
+
+    /// Records the locals of the parameters, except `self`.
+    fn define_params(&mut self, params: &[Param]) {
+        for param in params {
+            if param.ident.name != Kw::SelfLower.into() {
+                self.define(param.id, &param.ident);
+            }
+        }
+    }
+
+    fn check_items(&mut self, items: &[Item]) {
+        for item in items {
+            match &item.kind {
+                ItemKind::Fn(fun) => {
+                    self.define_item(item);
+                    self.current_item = Some(self.resolution[&item.id].def);
+                    self.check_fn(fun);
+                }
+                // The methods are resolved when the types are known, so only their bodies are
+                // checked, and the items they use are roots.
+                ItemKind::Impl(imp) => {
+                    for item in &imp.items {
+                        if let ItemKind::Fn(fun) = &item.kind {
+                            self.check_fn(fun);
+                        }
+                    }
+                }
+                ItemKind::Mod(module) => self.check_items(&module.items),
+                ItemKind::Const(c) => {
+                    self.define_item(item);
+                    self.current_item = Some(self.resolution[&item.id].def);
+                    self.check_expr(&c.expr);
+                }
+                ItemKind::Static(s) => {
+                    self.define_item(item);
+                    self.current_item = Some(self.resolution[&item.id].def);
+                    self.check_expr(&s.expr);
+                }
+                ItemKind::Trait(_) | ItemKind::Use(_) => {}
+            }
+            self.current_item = None;
+        }
+    }
+
+    fn check_fn(&mut self, fun: &Fn) {
+        self.define_params(&fun.inputs);
+        self.check_block(&fun.body);
+    }
+
+    fn check_block(&mut self, block: &Block) {
+        for stmt in &block.stmts {
+            match stmt {
+                Stmt::Local {
+                    id, ident, init, ..
+                } => {
+                    self.check_expr(init);
+                    self.define(*id, ident);
+                }
+                Stmt::Expr(expr) | Stmt::Semi(expr) | Stmt::Println(expr) => self.check_expr(expr),
+            }
+        }
+    }
+
+    fn check_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Path(path) => {
+                let def = self.resolution[&path.id].def;
+                self.used.insert(def);
+                match self.current_item {
+                    Some(item) => self.uses.entry(item).or_default().push(def),
+                    None => self.roots.push(def),
+                }
+            }
+            Expr::Assign { lhs, rhs } => {
+                self.check_expr(rhs);
+                // writing to a name is not a use of it, but writing through a pointer is.
+                if !matches!(**lhs, Expr::Path(_)) {
+                    self.check_expr(lhs);
+                }
+            }
+            Expr::AddrOf { expr, .. } | Expr::Unary { expr, .. } => self.check_expr(expr),
+            Expr::Call { fun, args, .. } => {
+                self.check_expr(fun);
+                for arg in args {
+                    self.check_expr(arg);
+                }
+            }
+            Expr::MethodCall { receiver, args, .. } => {
+                self.check_expr(receiver);
+                for arg in args {
+                    self.check_expr(arg);
+                }
+            }
+            Expr::Binary { lhs, rhs, .. } => {
+                self.check_expr(lhs);
+                self.check_expr(rhs);
+            }
+            Expr::If {
+                cond,
+                then,
+                else_opt,
+            } => {
+                self.check_expr(cond);
+                self.check_block(then);
+                if let Some(else_expr) = else_opt {
+                    self.check_expr(else_expr);
+                }
+            }
+            Expr::Loop { block } | Expr::Block { block } => self.check_block(block),
+            Expr::Break { expr } | Expr::Continue { expr } | Expr::Return { expr } => {
+                if let Some(expr) = expr {
+                    self.check_expr(expr);
+                }
+            }
+            Expr::Closure { inputs, body, .. } => {
+                self.define_params(inputs);
+                self.check_expr(body);
+            }
+            Expr::Lit { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::UnusedWarning, resolve_items, unused::check_unused};
+
+    use parser::parse_items;
+
+    fn check(src: &str) -> Vec<UnusedWarning> {
+        let (items, map) = parse_items(src).unwrap();
+        let resolution = resolve_items(items.as_slice(), &map).unwrap();
+
+        check_unused(items.as_slice(), &resolution, &map)
+    }
+
+    fn names(warnings: &[UnusedWarning]) -> Vec<&str> {
+        warnings
+            .iter()
+            .map(|warning| match warning {
+                UnusedWarning::Variable { name, .. }
+                | UnusedWarning::Function { name, .. }
+                | UnusedWarning::Const { name, .. }
+                | UnusedWarning::Static { name, .. } => name.as_str(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unused_variables() {
+        let warnings = check("fn main() { let a = 1; let b = 2; let c = |x: i32| b; }");
+        assert_eq!(names(&warnings), ["a", "x", "c"]);
+        assert!(matches!(warnings[0], UnusedWarning::Variable { .. }));
+
+        assert!(check("fn main() -> i32 { let a = 1; let a = a + 1; a }").is_empty());
+        assert_eq!(
+            names(&check("fn main() { let a = 1; let a = 2; a; }")),
+            ["a"]
+        );
+    }
+
+    #[test]
+    fn write_only_variables() {
+        let warnings = check("fn main() { let mut y = 0; y = 3; }");
+        assert_eq!(names(&warnings), ["y"]);
+
+        assert!(check("fn main() -> i32 { let mut y = 0; y = 3; y }").is_empty());
+        assert!(check("fn main() { let mut y = 0; let p = &mut y; *p = 3; }").is_empty());
+    }
+
+    #[test]
+    fn unused_functions() {
+        let src = r"
+fn f(n: i32) -> i32 { if n == 0 { 0 } else { f(n - 1) } }
+fn g() {}
+mod m { pub fn h() {} fn k() {} }
+fn main() { g(); m::h(); }
+";
+        let warnings = check(src);
+        assert_eq!(names(&warnings), ["f", "k"]);
+        assert!(matches!(warnings[0], UnusedWarning::Function { .. }));
+    }
+
+    #[test]
+    fn unreachable_items() {
+        let src = r"
+fn f() -> i32 { g() + N }
+fn g() -> i32 { h() }
+fn h() -> i32 { g() }
+const N: i32 = M;
+const M: i32 = 1;
+pub fn p() -> i32 { q() }
+fn q() -> i32 { 1 }
+fn r() -> i32 { 2 }
+impl i32 { fn s(self) -> i32 { r() } }
+fn main() {}
+";
+        let warnings = check(src);
+        assert_eq!(names(&warnings), ["f", "g", "h", "N", "M"]);
+    }
+
+    #[test]
+    fn unused_consts_and_statics() {
+        let src = r"
+const N: i32 = M + 1;
+const M: i32 = 1;
+const K: i32 = 2;
+static mut S: i32 = 0;
+static T: i32 = 0;
+fn main() -> i32 { S = N; S }
+";
+        let warnings = check(src);
+        assert_eq!(names(&warnings), ["K", "T"]);
+        assert!(matches!(warnings[0], UnusedWarning::Const { .. }));
+        assert!(matches!(warnings[1], UnusedWarning::Static { .. }));
+    }
+
+    #[test]
+    fn underscore_names() {
+        assert!(check("fn _f(_x: i32) {} fn main() { let _ = 1; let _a = 2; }").is_empty());
+    }
+}
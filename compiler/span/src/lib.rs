@@ -3,7 +3,9 @@ pub mod symbol;
 
 pub use symbol::*;
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+use serde::{Serialize, Serializer};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize)]
 pub struct SpanData {
     pub lo: u32,
     pub hi: u32,
@@ -41,6 +43,12 @@ impl Span {
         }
     }
 
+    /// Returns `true` for `DUMMY_SP`, which `==` does not tell apart from any span.
+    #[inline]
+    pub fn is_dummy(self) -> bool {
+        self.index == DUMMY_SP.index && self.len == DUMMY_SP.len
+    }
+
     #[inline]
     pub fn data(self) -> SpanData {
         SpanData {
@@ -62,3 +70,13 @@ impl PartialEq for Span {
     }
 }
 
+/// A span is serialized as its `SpanData`, or as `null` if it is `DUMMY_SP`.
+impl Serialize for Span {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.is_dummy() {
+            serializer.serialize_none()
+        } else {
+            serializer.serialize_some(&self.data())
+        }
+    }
+}
@@ -1,22 +1,86 @@
 use crate::*;
 
+use std::cell::RefCell;
+use typed_arena::Arena;
+
+/// The source files of a crate.
+/// A file can be added while the sources of the files before it are borrowed by the symbols,
+/// since a source stays in place until the map is dropped.
 pub struct SourceMap {
-    file: SourceFile,
+    srcs: Arena<String>,
+    files: RefCell<Vec<SourceFile>>,
 }
 
 impl SourceMap {
-    pub fn new(file: SourceFile) -> Self {
-        Self { file }
+    pub fn new() -> Self {
+        Self {
+            srcs: Arena::new(),
+            files: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Adds the file, whose spans start after the spans of the files added before,
+    /// and returns the position where its spans start and its source.
+    pub fn add_file(&self, name: String, src: String) -> (usize, &str) {
+        let mut files = self.files.borrow_mut();
+        // a position is left between files for the end of file.
+        let start_pos = files.last().map_or(0, |file| file.start_pos + file.len + 1);
+        files.push(SourceFile {
+            name,
+            start_pos,
+            len: src.len(),
+        });
+
+        (start_pos, self.srcs.alloc(src))
     }
 
+    /// Formats the span as the name of its file and its positions in the file: `main.elaina:4:9`
     pub fn span_to_string(&self, sp: Span) -> String {
         let data = sp.data();
-        format!("{}:{}:{}", self.file.name, data.lo, data.hi)
+        let files = self.files.borrow();
+        let file = files
+            .iter()
+            .rev()
+            .find(|file| file.start_pos <= data.lo as usize)
+            .unwrap_or_else(|| panic!("{:?} is not in any file.", sp));
+
+        let lo = data.lo as usize - file.start_pos;
+        let hi = data.hi as usize - file.start_pos;
+        format!("{}:{}:{}", file.name, lo, hi)
+    }
+}
+
+impl Default for SourceMap {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 #[derive(Clone)]
 pub struct SourceFile {
     pub name: String,
-    pub src: String,
+
+    /// The position where the spans of the file start.
+    pub start_pos: usize,
+    pub len: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn span_to_string() {
+        let source_map = SourceMap::new();
+        let (start, src) = source_map.add_file("main.elaina".to_string(), "mod a;".to_string());
+        assert_eq!((start, src), (0, "mod a;"));
+        let (start, _) = source_map.add_file("a.elaina".to_string(), "fn f() {}".to_string());
+        assert_eq!(start, 7);
+
+        assert_eq!(
+            source_map.span_to_string(Span::new(4, 5)),
+            "main.elaina:4:5"
+        );
+        assert_eq!(source_map.span_to_string(Span::new(10, 11)), "a.elaina:3:4");
+    }
 }
@@ -1,9 +1,10 @@
 use crate::*;
 
 use derive_more::{From, Into};
+use serde::{ser::Error, Serialize, Serializer};
 use typed_index_collections::TiVec;
 
-use std::collections::HashMap;
+use std::{cell::RefCell, collections::HashMap, fmt};
 
 #[derive(Debug, From, Into, PartialEq, Eq, Clone, Copy, Hash)]
 pub struct Symbol(usize);
@@ -24,7 +25,33 @@ impl Symbol {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// A symbol is serialized as its string, so it fails to serialize outside of `with_symbol_map`.
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SYMBOL_STRINGS.with(|strings| match &*strings.borrow() {
+            Some(strings) => serializer.serialize_str(&strings[self.0]),
+            None => Err(S::Error::custom(
+                "a symbol is serialized outside of `with_symbol_map`",
+            )),
+        })
+    }
+}
+
+/// A symbol is displayed as its string in `with_symbol_map`.
+/// Outside of it, only a keyword is displayed as its string, and the others as their index.
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        SYMBOL_STRINGS.with(|strings| match &*strings.borrow() {
+            Some(strings) => write!(f, "{}", strings[self.0]),
+            None => match KEYWORDS.get(self.0) {
+                Some(kw) => write!(f, "{}", kw.as_str()),
+                None => write!(f, "#{}", self.0),
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct Ident {
     pub name: Symbol,
     pub span: Span,
@@ -86,6 +113,30 @@ impl<'a> SymbolMap<'a> {
     }
 }
 
+thread_local! {
+    /// The strings of the symbols serialized or displayed in `with_symbol_map`.
+    static SYMBOL_STRINGS: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Calls `f`, in which the symbols are serialized and displayed as their strings in the map.
+pub fn with_symbol_map<R, F: FnOnce() -> R>(map: &SymbolMap, f: F) -> R {
+    let strings = map.strings.iter().map(|s| s.to_string()).collect();
+    let prev = SYMBOL_STRINGS.with(|cell| cell.replace(Some(strings)));
+    let _restore = RestoreSymbolStrings(prev);
+
+    f()
+}
+
+/// Restores the strings of the enclosing `with_symbol_map` when dropped, even if `f` panics.
+struct RestoreSymbolStrings(Option<Vec<String>>);
+
+impl Drop for RestoreSymbolStrings {
+    fn drop(&mut self) {
+        let prev = self.0.take();
+        SYMBOL_STRINGS.with(|cell| cell.replace(prev));
+    }
+}
+
 macro_rules! keywords {
     ($( $name:ident : $string:expr),* ) => {
         #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -132,12 +183,30 @@ keywords![
     Println:"println",
 
     Fn: "fn",
+    Trait: "trait",
+    Impl: "impl",
+    For: "for",
+    Dyn: "dyn",
+    SelfLower: "self",
+    SelfUpper: "Self",
+
+    Mod: "mod",
+    Use: "use",
+    Pub: "pub",
+    As: "as",
+    Crate: "crate",
+    Super: "super",
 
     Loop: "loop",
     Break: "break",
     Continue: "continue",
     Return: "return",
 
+    Mut: "mut",
+    Const: "const",
+    Static: "static",
+    Move: "move",
+
     I32: "i32",
     Bool: "bool"
 ];
@@ -158,12 +227,30 @@ mod tests {
         assert_eq!(Kw::Println.as_symbol(), m.insert("println"));
 
         assert_eq!(Kw::Fn.as_symbol(), m.insert("fn"));
+        assert_eq!(Kw::Trait.as_symbol(), m.insert("trait"));
+        assert_eq!(Kw::Impl.as_symbol(), m.insert("impl"));
+        assert_eq!(Kw::For.as_symbol(), m.insert("for"));
+        assert_eq!(Kw::Dyn.as_symbol(), m.insert("dyn"));
+        assert_eq!(Kw::SelfLower.as_symbol(), m.insert("self"));
+        assert_eq!(Kw::SelfUpper.as_symbol(), m.insert("Self"));
+
+        assert_eq!(Kw::Mod.as_symbol(), m.insert("mod"));
+        assert_eq!(Kw::Use.as_symbol(), m.insert("use"));
+        assert_eq!(Kw::Pub.as_symbol(), m.insert("pub"));
+        assert_eq!(Kw::As.as_symbol(), m.insert("as"));
+        assert_eq!(Kw::Crate.as_symbol(), m.insert("crate"));
+        assert_eq!(Kw::Super.as_symbol(), m.insert("super"));
 
         assert_eq!(Kw::Loop.as_symbol(), m.insert("loop"));
         assert_eq!(Kw::Break.as_symbol(), m.insert("break"));
         assert_eq!(Kw::Continue.as_symbol(), m.insert("continue"));
         assert_eq!(Kw::Return.as_symbol(), m.insert("return"));
 
+        assert_eq!(Kw::Mut.as_symbol(), m.insert("mut"));
+        assert_eq!(Kw::Const.as_symbol(), m.insert("const"));
+        assert_eq!(Kw::Static.as_symbol(), m.insert("static"));
+        assert_eq!(Kw::Move.as_symbol(), m.insert("move"));
+
         assert_eq!(Kw::I32.as_symbol(), m.insert("i32"));
         assert_eq!(Kw::Bool.as_symbol(), m.insert("bool"));
     }
@@ -178,6 +265,50 @@ mod tests {
         assert_eq!(foo, foo2);
     }
 
+    #[test]
+    fn serialize() {
+        let mut m = SymbolMap::new();
+        let foo = Ident::with_dummy_span(m.insert("foo"));
+
+        let json = with_symbol_map(&m, || serde_json::to_string(&foo).unwrap());
+        assert_eq!(json, r#"{"name":"foo","span":null}"#);
+
+        let err = serde_json::to_string(&foo).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "a symbol is serialized outside of `with_symbol_map`"
+        );
+    }
+
+    #[test]
+    fn display() {
+        let mut m = SymbolMap::new();
+        let foo = m.insert("foo");
+
+        assert_eq!(with_symbol_map(&m, || foo.to_string()), "foo");
+        assert_eq!(foo.to_string(), format!("#{}", KEYWORDS.len()));
+        assert_eq!(Symbol::from(Kw::SelfUpper).to_string(), "Self");
+    }
+
+    #[test]
+    fn restore_symbol_map() {
+        let mut outer = SymbolMap::new();
+        let foo = outer.insert("foo");
+        let mut inner = SymbolMap::new();
+        inner.insert("bar");
+
+        let json = with_symbol_map(&outer, || {
+            let panicked = std::panic::catch_unwind(|| {
+                with_symbol_map(&inner, || panic!("serialization panics"));
+            });
+            assert!(panicked.is_err());
+
+            serde_json::to_string(&foo).unwrap()
+        });
+        assert_eq!(json, r#""foo""#);
+        assert!(serde_json::to_string(&foo).is_err());
+    }
+
     #[test]
     fn is_keyword() {
         let mut m = SymbolMap::new();
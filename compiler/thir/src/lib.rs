@@ -1,53 +1,41 @@
 pub mod pp;
 
 use ast::op::{BinOp, UnOp};
+use serde::Serialize;
 use span::*;
 use ty::{
     self,
     res::{DefId, Res},
+    Mutability,
 };
 
-#[derive(Debug, PartialEq, Clone)]
+pub use ty::{FnHeader, Param, Upvar};
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct Item<'tcx> {
     pub res: Res,
     pub name: Symbol,
     pub kind: ItemKind<'tcx>,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub enum ItemKind<'tcx> {
     Fn(Box<Fn<'tcx>>),
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct Fn<'tcx> {
     pub header: FnHeader<'tcx>,
     pub body: Block<'tcx>,
 }
 
-#[derive(Debug, PartialEq, Clone)]
-pub struct FnHeader<'tcx> {
-    pub def: DefId,
-    pub name: Symbol,
-
-    pub inputs: Vec<Param<'tcx>>,
-    pub output: ty::Ty<'tcx>,
-}
-
-#[derive(Debug, PartialEq, Clone)]
-pub struct Param<'tcx> {
-    pub res: Res,
-    pub name: Symbol,
-    pub ty: ty::Ty<'tcx>,
-}
-
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct Pat<'tcx> {
     pub ty: ty::Ty<'tcx>,
     pub kind: Box<PatKind<'tcx>>,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub enum PatKind<'tcx> {
     Binding {
         res: Res,
@@ -56,14 +44,14 @@ pub enum PatKind<'tcx> {
     },
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct Block<'tcx> {
     pub stmts: Vec<Stmt<'tcx>>,
     pub expr: Option<Expr<'tcx>>,
     pub ty: ty::Ty<'tcx>,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub enum Stmt<'tcx> {
     /// Local represents a let statement: `let <ident> = <expr>;`
     Local { pat: Pat<'tcx>, init: Expr<'tcx> },
@@ -76,7 +64,7 @@ pub enum Stmt<'tcx> {
     Println(Expr<'tcx>),
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub enum Expr<'tcx> {
     /// A function call: `foo(a, b)`
     Call {
@@ -100,6 +88,44 @@ pub enum Expr<'tcx> {
         ty: ty::Ty<'tcx>,
     },
 
+    /// A dereference of a reference or a raw pointer: `*x`
+    Deref {
+        expr: Box<Expr<'tcx>>,
+        ty: ty::Ty<'tcx>,
+    },
+
+    /// A borrow of a place: `&a`, `&mut a`
+    Borrow {
+        mutbl: Mutability,
+        expr: Box<Expr<'tcx>>,
+        ty: ty::Ty<'tcx>,
+    },
+
+    /// A raw pointer to a place, created by coercing a reference: `&a as *const T`
+    AddressOf {
+        mutbl: Mutability,
+        expr: Box<Expr<'tcx>>,
+        ty: ty::Ty<'tcx>,
+    },
+
+    /// An unsizing coercion of a pointer: `&T` to `&dyn Trait`
+    Unsize {
+        expr: Box<Expr<'tcx>>,
+        ty: ty::Ty<'tcx>,
+    },
+
+    /// A coercion of a function item to a function pointer: `f` to `fn(i32) -> i32`
+    ReifyFnPointer {
+        expr: Box<Expr<'tcx>>,
+        ty: ty::Ty<'tcx>,
+    },
+
+    /// A closure, that is, the environment holding the captured variables: `{&a, b}`
+    Closure {
+        upvars: Vec<Expr<'tcx>>,
+        ty: ty::Ty<'tcx>,
+    },
+
     /// An if expression: `if <cond> { <then> } else { <else_opt> }`
     If {
         cond: Box<Expr<'tcx>>,
@@ -140,10 +166,44 @@ pub enum Expr<'tcx> {
     },
 
     /// A literal in place of an expression: `1`
-    Lit { lit: Lit, ty: ty::Ty<'tcx> },
+    Lit {
+        lit: Lit,
+        ty: ty::Ty<'tcx>,
+        span: Span,
+    },
 
     /// Local variable.
-    VarRef { res: Res, ty: ty::Ty<'tcx> },
+    VarRef {
+        res: Res,
+        ty: ty::Ty<'tcx>,
+        span: Span,
+    },
+
+    /// A constant, replaced by its value evaluated at compile time: `N`
+    NamedConst {
+        def: DefId,
+        value: ty::Const<'tcx>,
+        span: Span,
+    },
+
+    /// A pointer to a static, which a use of the static dereferences: `COUNTER` is `*&COUNTER`.
+    /// The pointer is a raw pointer for a mutable static, and a shared reference otherwise.
+    StaticRef {
+        def: DefId,
+        ty: ty::Ty<'tcx>,
+        span: Span,
+    },
+}
+
+impl Stmt<'_> {
+    /// Returns `true` if the statement never completes: `return 1;`
+    pub fn diverges(&self) -> bool {
+        match self {
+            Stmt::Local { init: expr, .. } | Stmt::Expr(expr) | Stmt::Println(expr) => {
+                expr.ty().is_never()
+            }
+        }
+    }
 }
 
 impl<'tcx> Expr<'tcx> {
@@ -152,6 +212,12 @@ impl<'tcx> Expr<'tcx> {
             Expr::Call { ty, .. } => ty.clone(),
             Expr::Binary { ty, .. } => ty.clone(),
             Expr::Unary { ty, .. } => ty.clone(),
+            Expr::Deref { ty, .. } => ty.clone(),
+            Expr::Borrow { ty, .. } => ty.clone(),
+            Expr::AddressOf { ty, .. } => ty.clone(),
+            Expr::Unsize { ty, .. } => ty.clone(),
+            Expr::ReifyFnPointer { ty, .. } => ty.clone(),
+            Expr::Closure { ty, .. } => ty.clone(),
             Expr::If { ty, .. } => ty.clone(),
             Expr::Loop { block } => block.ty.clone(),
             Expr::Break { ty, .. } => ty.clone(),
@@ -161,6 +227,37 @@ impl<'tcx> Expr<'tcx> {
             Expr::Assign { ty, .. } => ty.clone(),
             Expr::Lit { ty, .. } => ty.clone(),
             Expr::VarRef { ty, .. } => ty.clone(),
+            Expr::NamedConst { value, .. } => value.ty,
+            Expr::StaticRef { ty, .. } => *ty,
+        }
+    }
+
+    /// Returns `true` if the expression denotes a memory location.
+    pub fn is_place(&self) -> bool {
+        matches!(self, Expr::VarRef { .. } | Expr::Deref { .. })
+    }
+
+    /// Returns the span of the expression, which is taken from the first variable or literal in it.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Expr::Lit { span, .. }
+            | Expr::VarRef { span, .. }
+            | Expr::NamedConst { span, .. }
+            | Expr::StaticRef { span, .. } => Some(*span),
+            Expr::Call { fun: expr, .. }
+            | Expr::Binary { lhs: expr, .. }
+            | Expr::Unary { expr, .. }
+            | Expr::Deref { expr, .. }
+            | Expr::Borrow { expr, .. }
+            | Expr::AddressOf { expr, .. }
+            | Expr::Unsize { expr, .. }
+            | Expr::ReifyFnPointer { expr, .. }
+            | Expr::Assign { lhs: expr, .. } => expr.span(),
+            Expr::Break { expr, .. } | Expr::Continue { expr, .. } | Expr::Return { expr, .. } => {
+                expr.as_ref().and_then(|expr| expr.span())
+            }
+            Expr::If { cond, .. } => cond.span(),
+            Expr::Loop { .. } | Expr::Block { .. } | Expr::Closure { .. } => None,
         }
     }
 
@@ -170,9 +267,21 @@ impl<'tcx> Expr<'tcx> {
             Break { .. } | Continue { .. } | Return { .. } => PREC_JUMP,
             Binary { op, .. } => op.precedence() as i8,
             Assign { .. } => PREC_ASSIGN,
-            Unary { .. } => PREC_PREFIX,
+            Unary { .. }
+            | Deref { .. }
+            | Borrow { .. }
+            | AddressOf { .. }
+            | Unsize { .. }
+            | ReifyFnPointer { .. } => PREC_PREFIX,
             Call { .. } => PREC_POSTFIX,
-            Lit { .. } | VarRef { .. } | If { .. } | Loop { .. } | Block { .. } => PREC_PAREN,
+            Lit { .. }
+            | VarRef { .. }
+            | NamedConst { .. }
+            | StaticRef { .. }
+            | Closure { .. }
+            | If { .. }
+            | Loop { .. }
+            | Block { .. } => PREC_PAREN,
         }
     }
 }
@@ -187,7 +296,7 @@ pub const PREC_POSTFIX: i8 = 60;
 pub const PREC_PAREN: i8 = 99;
 pub const PREC_FORCE_PAREN: i8 = 100;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub enum Lit {
     /// A boolean literal: `true`, `false`
     Bool { value: bool },
@@ -196,7 +305,7 @@ pub enum Lit {
     Int(LitInt),
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct LitInt {
     pub value: u128,
 }
@@ -18,6 +18,33 @@ impl THIRPrinter<'_> {
             Expr::Unary { op, expr, .. } => {
                 this.print_expr_unary(op, expr);
             }
+            Expr::Deref { expr, .. } => {
+                this.print("*");
+                this.print_expr_maybe_paren(expr, crate::PREC_PREFIX);
+            }
+            Expr::Borrow { mutbl, expr, .. } => {
+                this.print("&");
+                this.print(mutbl.prefix_str());
+                this.print_expr_maybe_paren(expr, crate::PREC_PREFIX);
+            }
+            Expr::AddressOf { mutbl, expr, .. } => {
+                this.print("&raw");
+                match mutbl {
+                    ty::Mutability::Mut => this.space_print_space("mut"),
+                    ty::Mutability::Not => this.space_print_space("const"),
+                }
+                this.print_expr_maybe_paren(expr, crate::PREC_PREFIX);
+            }
+            Expr::Unsize { expr, ty } | Expr::ReifyFnPointer { expr, ty } => {
+                this.print_expr_maybe_paren(expr, crate::PREC_PREFIX);
+                this.space_print_space("as");
+                this.print_ty(ty);
+            }
+            Expr::Closure { upvars, .. } => {
+                this.list(upvars.iter(), Delim::Brace, |this, upvar| {
+                    this.print_expr(upvar);
+                });
+            }
             Expr::If {
                 cond,
                 then,
@@ -66,6 +93,16 @@ impl THIRPrinter<'_> {
             Expr::VarRef { res, .. } => {
                 this.print_def(res.def);
             }
+            Expr::NamedConst { value, .. } => this.print_const(value),
+            Expr::StaticRef { def, ty, .. } => {
+                this.print("&");
+                // only a mutable static is accessed through a raw pointer.
+                if let ty::TyKind::RawPtr(..) = ty.kind() {
+                    this.print("raw");
+                    this.space_print_space("mut");
+                }
+                this.print_def(*def);
+            }
         });
     }
 
@@ -76,6 +113,14 @@ impl THIRPrinter<'_> {
         }
     }
 
+    fn print_const(&mut self, value: &ty::Const) {
+        let ty::ConstLit::Scalar(scalar) = &value.literal;
+        match value.ty.kind() {
+            ty::TyKind::Bool => self.print(scalar.data != 0),
+            _ => self.print(scalar),
+        }
+    }
+
     fn print_expr_maybe_paren(&mut self, expr: &Expr, prec: i8) {
         self.print_expr_cond_paren(expr, expr.precedence() < prec);
     }
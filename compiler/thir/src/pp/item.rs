@@ -23,6 +23,7 @@ impl THIRPrinter<'_> {
             ItemKind::Fn(fun) => self.print_fn(
                 fun.header.def,
                 fun.header.name,
+                &fun.header.generics,
                 &fun.header.inputs,
                 &fun.header.output,
                 &fun.body,
@@ -34,12 +35,18 @@ impl THIRPrinter<'_> {
         &mut self,
         def: DefId,
         name: Symbol,
+        generics: &[ty::ParamTy],
         inputs: &Vec<Param>,
         output: &ty::Ty,
         body: &Block,
     ) {
         self.print_space("fn");
         self.print_ident(def, name);
+        if !generics.is_empty() {
+            self.list(generics.iter(), Delim::Angle, |this, param| {
+                this.print(this.map.get(param.name));
+            });
+        }
 
         // print fn args: (arg1:ty1, arg2:ty2, ..)
         self.list(inputs.iter(), Delim::Paren, |this, param| {
@@ -93,11 +93,55 @@ impl<'a> THIRPrinter<'a> {
                     this.print_ty(ty);
                 });
             }
-            ty::TyKind::FnDef(def) => {
+            ty::TyKind::Ref(ty, mutbl) => {
+                self.print("&");
+                self.print(mutbl.prefix_str());
+                self.print_ty(ty);
+            }
+            ty::TyKind::RawPtr(ty, mutbl) => {
+                self.print("*");
+                match mutbl {
+                    ty::Mutability::Mut => self.print_space("mut"),
+                    ty::Mutability::Not => self.print_space("const"),
+                }
+                self.print_ty(ty);
+            }
+            ty::TyKind::FnDef(def, substs) => {
                 self.print("FnDef");
                 self.with_delim(Delim::Paren, false, |this| {
                     this.print_def(*def);
+                    if !substs.is_empty() {
+                        this.list(substs.iter(), Delim::Angle, |this, ty| {
+                            this.print_ty(ty);
+                        });
+                    }
+                });
+            }
+            ty::TyKind::FnPtr(sig) => {
+                self.print("fn");
+                self.list(sig.inputs.iter(), Delim::Paren, |this, ty| {
+                    this.print_ty(ty);
                 });
+                if !sig.output.is_zst() {
+                    self.space_print_space("->");
+                    self.print_ty(&sig.output);
+                }
+            }
+            ty::TyKind::Closure(closure) => {
+                self.with_delim(Delim::Brace, false, |this| {
+                    this.print_space("closure");
+                    this.print_def(closure.def);
+                    if !closure.substs.is_empty() {
+                        this.list(closure.substs.iter(), Delim::Angle, |this, ty| {
+                            this.print_ty(ty);
+                        });
+                    }
+                });
+            }
+            ty::TyKind::Param(param) => self.print(self.map.get(param.name)),
+            ty::TyKind::Dynamic(def) => {
+                self.print_space("dyn");
+                self.print_def(*def);
             }
             ty::TyKind::Never => self.print("!"),
         }
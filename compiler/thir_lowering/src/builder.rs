@@ -15,6 +15,14 @@ impl<'tcx> MirBuilder<'tcx> {
         }
     }
 
+    pub(crate) fn set_substs(&mut self, substs: ty::SubstsRef<'tcx>) {
+        self.body.substs = substs;
+    }
+
+    pub(crate) fn set_inline(&mut self, inline: ty::InlineAttr) {
+        self.body.inline = inline;
+    }
+
     pub(crate) fn set_arg_count(&mut self, count: usize) {
         self.body.arg_count = count;
     }
@@ -32,16 +40,28 @@ impl<'tcx> MirBuilder<'tcx> {
         self.body.blocks.push_and_get_key(Block::new(terminator))
     }
 
-    pub(crate) fn set_terminator(&mut self, target: BlockId, terminator: Terminator<'tcx>) {
+    pub(crate) fn set_terminator(
+        &mut self,
+        target: BlockId,
+        source_info: SourceInfo,
+        kind: TerminatorKind<'tcx>,
+    ) {
         let block = self
             .body
             .blocks
             .get_mut(target)
             .expect("Given target is invalid.");
-        block.terminator = Some(terminator);
+        block.terminator = Some(Terminator::new(source_info, kind));
     }
 
-    pub(crate) fn push_stmt(&mut self, target: BlockId, stmt: Statement<'tcx>) {
-        self.body.blocks[target].stmts.push(stmt);
+    pub(crate) fn push_stmt(
+        &mut self,
+        target: BlockId,
+        source_info: SourceInfo,
+        kind: StatementKind<'tcx>,
+    ) {
+        self.body.blocks[target]
+            .stmts
+            .push(Statement::new(source_info, kind));
     }
 }
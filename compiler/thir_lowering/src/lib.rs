@@ -64,6 +64,10 @@ pub struct LoweringCtx<'ast, 'tcx> {
 
     local_def: HashMap<DefId, Place>,
     tcx: &'ast TyCtx<'ast, 'tcx>,
+
+    /// The source location of the expression being lowered,
+    /// or the name of the function outside of any expression.
+    source_info: SourceInfo,
 }
 
 impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
@@ -77,6 +81,8 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
 
             local_def: HashMap::new(),
             tcx,
+
+            source_info: SourceInfo::new(DUMMY_SP),
         }
     }
 
@@ -84,16 +90,21 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
         self.builder.build()
     }
 
-    pub fn lower_item_fun(
-        &mut self,
-        inputs: &Vec<thir::Param<'tcx>>,
-        output: &ty::Ty<'tcx>,
-        body: &thir::Block<'tcx>,
-    ) {
-        let return_place = Some(
-            self.builder
-                .push_local_decl(LocalDecl::new(Some("ret".into()), output.clone())),
-        );
+    pub fn lower_item_fun(&mut self, header: &thir::FnHeader<'tcx>, body: &thir::Block<'tcx>) {
+        let (inputs, output) = (&header.inputs, &header.output);
+        self.source_info = SourceInfo::new(header.span);
+        let identity_substs = header
+            .generics
+            .iter()
+            .map(|param| self.tcx.intern(ty::TyKind::Param(*param)))
+            .collect();
+        self.builder
+            .set_substs(self.tcx.intern_substs(identity_substs));
+        self.builder.set_inline(header.inline);
+
+        let return_place = self
+            .builder
+            .push_local_decl(LocalDecl::new(Some("ret".into()), output.clone()));
         for input in inputs {
             let name = self.tcx.symbol_map.get(input.name).to_string();
             let ty = input.ty.clone();
@@ -102,15 +113,38 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
         }
         self.builder.set_arg_count(inputs.len());
 
-        self.return_resolver.push_scope(return_place);
+        // the captured variables of a closure are the fields of the environment,
+        // which is behind the reference passed as the first argument.
+        for (idx, upvar) in header.upvars.iter().enumerate() {
+            let env = self.local_def[&inputs[0].res.def].clone();
+            let field = env.deref().field(idx);
+            let place = match upvar.by_ref {
+                Some(_) => field.deref(),
+                None => field,
+            };
+            self.local_def.insert(upvar.res.def, place);
+        }
+
+        self.return_resolver.push_scope(Some(return_place.clone()));
 
         let entry_block = self.builder.push_block(None);
-        let (tail, _) = self.lower_block(entry_block, &body.stmts, &body.expr);
+        let (tail, value) = self.lower_block(entry_block, &body.stmts, &body.expr);
 
-        let return_block = self.builder.push_block(Some(Terminator::Return));
+        // the value of the tail expression is the return value, unless the tail diverges.
+        if let Some(expr) = body.expr.as_ref().filter(|expr| !expr.ty().is_never()) {
+            let stmt = StatementKind::Assign(Box::new((return_place, RValue::Use(value))));
+            let source_info = self.source_info_of(expr);
+            self.builder.push_stmt(tail, source_info, stmt);
+        }
+
+        let return_block = self.builder.push_block(Some(Terminator::new(
+            self.source_info,
+            TerminatorKind::Return,
+        )));
         self.builder.set_terminator(
             tail,
-            Terminator::Goto {
+            self.source_info,
+            TerminatorKind::Goto {
                 target: return_block,
             },
         );
@@ -118,7 +152,8 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
         for target in return_from {
             self.builder.set_terminator(
                 target,
-                Terminator::Goto {
+                self.source_info,
+                TerminatorKind::Goto {
                     target: return_block,
                 },
             )
@@ -156,8 +191,9 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
                 };
                 let (tail, operand) = self.lower_expr(entry_block, init);
                 let rvalue = RValue::Use(operand);
-                let stmt = Statement::Assign(Box::new((place, rvalue)));
-                self.builder.push_stmt(tail, stmt);
+                let stmt = StatementKind::Assign(Box::new((place, rvalue)));
+                let source_info = self.source_info_of(init);
+                self.builder.push_stmt(tail, source_info, stmt);
 
                 tail
             }
@@ -167,8 +203,9 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
             }
             thir::Stmt::Println(expr) => {
                 let (tail, operand) = self.lower_expr(entry_block, expr);
-                let stmt = Statement::Println(operand);
-                self.builder.push_stmt(tail, stmt);
+                let stmt = StatementKind::Println(operand);
+                let source_info = self.source_info_of(expr);
+                self.builder.push_stmt(tail, source_info, stmt);
                 tail
             }
         }
@@ -178,6 +215,26 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
         &mut self,
         entry_block: BlockId,
         expr: &thir::Expr<'tcx>,
+    ) -> (BlockId, Operand<'tcx>) {
+        let outer = self.source_info;
+        self.source_info = self.source_info_of(expr);
+
+        let result = self.lower_expr_kind(entry_block, expr);
+        self.source_info = outer;
+
+        result
+    }
+
+    /// Returns the source location of the expression,
+    /// or the location of the enclosing expression if it has no span.
+    fn source_info_of(&self, expr: &thir::Expr<'tcx>) -> SourceInfo {
+        expr.span().map(SourceInfo::new).unwrap_or(self.source_info)
+    }
+
+    fn lower_expr_kind(
+        &mut self,
+        entry_block: BlockId,
+        expr: &thir::Expr<'tcx>,
     ) -> (BlockId, Operand<'tcx>) {
         match expr {
             thir::Expr::Call { fun, args, ty } => {
@@ -189,6 +246,52 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
             thir::Expr::Unary { op, expr, ty } => {
                 self.lower_expr_unary(entry_block, *op, expr, ty.clone())
             }
+            thir::Expr::Deref { ty, .. } => {
+                let (tail, place) = self.lower_place(entry_block, expr);
+                (tail, operand_from_place(place, *ty))
+            }
+            thir::Expr::Borrow { mutbl, expr, ty } => {
+                let kind = match mutbl {
+                    ty::Mutability::Mut => BorrowKind::Mut,
+                    ty::Mutability::Not => BorrowKind::Shared,
+                };
+                let (tail, place) = self.lower_place(entry_block, expr);
+                let rvalue = RValue::Ref(kind, place);
+
+                self.lower_rvalue_to_temp(tail, rvalue, ty.clone())
+            }
+            thir::Expr::AddressOf { mutbl, expr, ty } => {
+                let (tail, place) = self.lower_place(entry_block, expr);
+                let rvalue = RValue::AddressOf(*mutbl, place);
+
+                self.lower_rvalue_to_temp(tail, rvalue, ty.clone())
+            }
+            thir::Expr::Unsize { expr, ty } => {
+                let (tail, operand) = self.lower_expr(entry_block, expr);
+                let rvalue = RValue::Cast(CastKind::Unsize, operand, *ty);
+
+                self.lower_rvalue_to_temp(tail, rvalue, *ty)
+            }
+            thir::Expr::ReifyFnPointer { expr, ty } => {
+                let (tail, operand) = self.lower_expr(entry_block, expr);
+                let rvalue = RValue::Cast(CastKind::ReifyFnPointer, operand, *ty);
+
+                self.lower_rvalue_to_temp(tail, rvalue, *ty)
+            }
+            thir::Expr::Closure { upvars, ty } => {
+                let (tail, operands) =
+                    upvars
+                        .iter()
+                        .fold((entry_block, Vec::new()), |(tail, mut operands), expr| {
+                            let (tail, operand) = self.lower_expr(tail, expr);
+                            operands.push(operand);
+
+                            (tail, operands)
+                        });
+                let rvalue = RValue::Aggregate(Box::new(AggregateKind::Closure(*ty)), operands);
+
+                self.lower_rvalue_to_temp(tail, rvalue, *ty)
+            }
             thir::Expr::If {
                 cond,
                 then,
@@ -209,18 +312,27 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
             thir::Expr::Return { expr, .. } => self.lower_expr_return(entry_block, expr),
             thir::Expr::Block { block } => {
                 let id = self.builder.push_block(None);
-                self.builder
-                    .set_terminator(entry_block, Terminator::Goto { target: id });
+                self.builder.set_terminator(
+                    entry_block,
+                    self.source_info,
+                    TerminatorKind::Goto { target: id },
+                );
 
                 self.lower_block(id, &block.stmts, &block.expr)
             }
             thir::Expr::Assign { lhs, rhs, ty } => {
                 self.lower_expr_assign(entry_block, lhs.as_ref(), rhs.as_ref(), ty.clone())
             }
-            thir::Expr::Lit { lit, ty } => (entry_block, self.lower_expr_lit(lit, ty.clone())),
-            thir::Expr::VarRef { res, ty } => {
+            thir::Expr::Lit { lit, ty, .. } => (entry_block, self.lower_expr_lit(lit, ty.clone())),
+            thir::Expr::VarRef { res, ty, .. } => {
                 (entry_block, self.lower_expr_var_ref(res.def, ty.clone()))
             }
+            thir::Expr::NamedConst { value, .. } => {
+                (entry_block, Operand::Constant(Box::new(*value)))
+            }
+            thir::Expr::StaticRef { def, ty, .. } => {
+                self.lower_rvalue_to_temp(entry_block, RValue::StaticRef(*def), *ty)
+            }
         }
     }
 
@@ -244,14 +356,15 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
         let ret = self
             .builder
             .push_local_decl(LocalDecl::new(Some("call".into()), ty));
-        let terminator = Terminator::Call {
+        let terminator = TerminatorKind::Call {
             fun,
             args,
             destination: Some((ret.clone(), end)),
         };
-        self.builder.set_terminator(tail, terminator);
+        self.builder
+            .set_terminator(tail, self.source_info, terminator);
 
-        (end, Operand::Copy(ret))
+        (end, operand_from_place(ret, ty))
     }
 
     fn lower_expr_binary(
@@ -280,13 +393,20 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
         let (tail, lhs) = self.lower_expr(entry_block, lhs);
         let (tail, rhs) = self.lower_expr(tail, rhs);
 
+        // comparison operators only read their operands, so they never move them.
+        let (lhs, rhs) = if op.is_comparison() {
+            (lhs.to_copy(), rhs.to_copy())
+        } else {
+            (lhs, rhs)
+        };
+
         let op = lower_bin_op(op);
 
         let rvalue = RValue::BinaryOp(op, Box::new((lhs, rhs)));
         let place = self.push_temp(ty);
-        let stmt = Statement::Assign(Box::new((place.clone(), rvalue)));
+        let stmt = StatementKind::Assign(Box::new((place.clone(), rvalue)));
 
-        self.builder.push_stmt(tail, stmt);
+        self.builder.push_stmt(tail, self.source_info, stmt);
 
         (tail, Operand::Copy(place))
     }
@@ -301,6 +421,7 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
         fn lower_un_op(op: UnOp) -> mir::stmt::UnOp {
             match op {
                 UnOp::Neg => mir::stmt::UnOp::Neg,
+                UnOp::Deref => unreachable!("dereference is lowered as a place"),
             }
         }
 
@@ -308,9 +429,9 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
         let op = lower_un_op(op);
         let rvalue = RValue::UnaryOp(op, Box::new(expr));
         let place = self.push_temp(ty);
-        let stmt = Statement::Assign(Box::new((place.clone(), rvalue)));
+        let stmt = StatementKind::Assign(Box::new((place.clone(), rvalue)));
 
-        self.builder.push_stmt(tail, stmt);
+        self.builder.push_stmt(tail, self.source_info, stmt);
 
         (tail, Operand::Copy(place))
     }
@@ -326,12 +447,16 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
         // Create cond block that represents condition expression.
         // Current Block jumps to cond block.
         let cond_entry = self.builder.push_block(None);
-        self.builder
-            .set_terminator(entry_block, Terminator::Goto { target: cond_entry });
+        self.builder.set_terminator(
+            entry_block,
+            self.source_info,
+            TerminatorKind::Goto { target: cond_entry },
+        );
         let (cond_tail, cond_operand) = self.lower_expr(cond_entry, cond);
 
         // If `ty` is not ZST(Zero Size Type), create local and treat it as the value of the expression.
-        let expr_val = if !ty.is_zst() {
+        // A branch which diverges produces no value to assign.
+        let expr_val = if !ty.is_zst() && !ty.is_never() {
             Some(self.push_temp(ty.clone()))
         } else {
             None
@@ -340,10 +465,10 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
         // Create then block.
         let then_entry = self.builder.push_block(None);
         let (then_tail, then_operand) = self.lower_block(then_entry, &then.stmts, &then.expr);
-        if let Some(p) = &expr_val {
+        if let Some(p) = expr_val.as_ref().filter(|_| !then.ty.is_never()) {
             let rvalue = RValue::Use(then_operand);
-            let stmt = Statement::Assign(Box::new((p.clone(), rvalue)));
-            self.builder.push_stmt(then_tail, stmt);
+            let stmt = StatementKind::Assign(Box::new((p.clone(), rvalue)));
+            self.builder.push_stmt(then_tail, self.source_info, stmt);
         }
 
         // Create opt block if it exists.
@@ -352,10 +477,10 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
                 let else_entry = self.builder.push_block(None);
                 let (else_tail, else_operand) = self.lower_expr(else_entry, e);
 
-                if let Some(p) = &expr_val {
+                if let Some(p) = expr_val.as_ref().filter(|_| !e.ty().is_never()) {
                     let rvalue = RValue::Use(else_operand);
-                    let stmt = Statement::Assign(Box::new((p.clone(), rvalue)));
-                    self.builder.push_stmt(else_tail, stmt);
+                    let stmt = StatementKind::Assign(Box::new((p.clone(), rvalue)));
+                    self.builder.push_stmt(else_tail, self.source_info, stmt);
                 }
 
                 (Some(else_entry), Some(else_tail))
@@ -381,28 +506,35 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
                 }
             };
 
-            Terminator::SwitchInt {
+            TerminatorKind::SwitchInt {
                 discr: cond_operand,
                 switch_ty: self.tcx.common_types.bool,
                 targets,
             }
         };
-        self.builder.set_terminator(cond_tail, cond_terminator);
+        self.builder
+            .set_terminator(cond_tail, self.source_info, cond_terminator);
 
         // Create terminator: then_block -> end_block.
-        self.builder
-            .set_terminator(then_tail, Terminator::Goto { target: end_entry });
+        self.builder.set_terminator(
+            then_tail,
+            self.source_info,
+            TerminatorKind::Goto { target: end_entry },
+        );
 
         // Create terminator: else_block -> end_block.
         if let Some(else_tail) = else_tail {
-            self.builder
-                .set_terminator(else_tail, Terminator::Goto { target: end_entry });
+            self.builder.set_terminator(
+                else_tail,
+                self.source_info,
+                TerminatorKind::Goto { target: end_entry },
+            );
         }
 
         // If expr_val exists, the operand is assigned evaluated value,
         // otherwise it is unit.
         let operand = match expr_val {
-            Some(p) => Operand::Copy(p),
+            Some(p) => operand_from_place(p, ty),
             None => Operand::Constant(Box::new(self.tcx.common_consts.unit)),
         };
 
@@ -415,10 +547,13 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
         block: &thir::Block<'tcx>,
     ) -> (BlockId, Operand<'tcx>) {
         let loop_head = self.builder.push_block(None);
-        self.builder
-            .set_terminator(entry_block, Terminator::Goto { target: loop_head });
+        self.builder.set_terminator(
+            entry_block,
+            self.source_info,
+            TerminatorKind::Goto { target: loop_head },
+        );
 
-        let break_place = if block.ty.is_zst() {
+        let break_place = if block.ty.is_zst() || block.ty.is_never() {
             None
         } else {
             Some(
@@ -428,7 +563,7 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
         };
 
         self.break_resolver.push_scope(break_place.clone());
-        let continue_place = if block.ty.is_zst() {
+        let continue_place = if block.ty.is_zst() || block.ty.is_never() {
             None
         } else {
             Some(
@@ -442,25 +577,34 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
         let end_head = self.builder.push_block(None);
 
         // Set terminator to tail of loop body.
-        self.builder
-            .set_terminator(loop_tail, Terminator::Goto { target: loop_head });
+        self.builder.set_terminator(
+            loop_tail,
+            self.source_info,
+            TerminatorKind::Goto { target: loop_head },
+        );
 
         // Set terminator <continue> -> <loop_head>
         let continue_from = self.continue_resolver.pop_scope();
         for target in continue_from {
-            self.builder
-                .set_terminator(target, Terminator::Goto { target: loop_head });
+            self.builder.set_terminator(
+                target,
+                self.source_info,
+                TerminatorKind::Goto { target: loop_head },
+            );
         }
 
         // Set terminator <break> -> <end_head>
         let break_from = self.break_resolver.pop_scope();
         for target in break_from {
-            self.builder
-                .set_terminator(target, Terminator::Goto { target: end_head });
+            self.builder.set_terminator(
+                target,
+                self.source_info,
+                TerminatorKind::Goto { target: end_head },
+            );
         }
 
         let operand = match break_place {
-            Some(place) => Operand::Copy(place),
+            Some(place) => operand_from_place(place, block.ty),
             None => Operand::Constant(Box::new(self.tcx.common_consts.unit)),
         };
         (end_head, operand)
@@ -483,8 +627,8 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
                     .get_place()
                     .unwrap_or_else(|| panic!("Cannot found place of break value."));
                 let rvalue = RValue::Use(operand);
-                let stmt = Statement::Assign(Box::new((place.clone(), rvalue)));
-                self.builder.push_stmt(block, stmt);
+                let stmt = StatementKind::Assign(Box::new((place.clone(), rvalue)));
+                self.builder.push_stmt(block, self.source_info, stmt);
 
                 block
             }
@@ -516,8 +660,8 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
                     .get_place()
                     .unwrap_or_else(|| panic!("Cannot found place of continue value."));
                 let rvalue = RValue::Use(operand);
-                let stmt = Statement::Assign(Box::new((place.clone(), rvalue)));
-                self.builder.push_stmt(block, stmt);
+                let stmt = StatementKind::Assign(Box::new((place.clone(), rvalue)));
+                self.builder.push_stmt(block, self.source_info, stmt);
 
                 block
             }
@@ -548,8 +692,8 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
                     .get_place()
                     .unwrap_or_else(|| panic!("Cannot found place of return value."));
                 let rvalue = RValue::Use(operand);
-                let stmt = Statement::Assign(Box::new((place.clone(), rvalue)));
-                self.builder.push_stmt(block, stmt);
+                let stmt = StatementKind::Assign(Box::new((place.clone(), rvalue)));
+                self.builder.push_stmt(block, self.source_info, stmt);
 
                 block
             }
@@ -573,19 +717,21 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
     ) -> (BlockId, Operand<'tcx>) {
         let (block, rhs) = self.lower_expr(entry_block, rhs);
 
-        match lhs {
-            thir::Expr::VarRef { res, ty: _ } => {
+        let (block, place) = match lhs {
+            thir::Expr::VarRef { res, .. } => {
                 let place = self
                     .local_def
                     .get(&res.def)
                     .expect("error: cannot found place of given def")
                     .clone();
-                let rvalue = RValue::Use(rhs);
-                let stmt = Statement::Assign(Box::new((place, rvalue)));
-                self.builder.push_stmt(block, stmt);
+                (block, place)
             }
+            thir::Expr::Deref { .. } => self.lower_place(block, lhs),
             _ => unreachable!(),
-        }
+        };
+        let rvalue = RValue::Use(rhs);
+        let stmt = StatementKind::Assign(Box::new((place, rvalue)));
+        self.builder.push_stmt(block, self.source_info, stmt);
 
         (
             block,
@@ -593,6 +739,48 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
         )
     }
 
+    /// Lower the expression as a place.
+    /// If the expression does not denote a place, its value is stored to a temporary.
+    fn lower_place(&mut self, entry_block: BlockId, expr: &thir::Expr<'tcx>) -> (BlockId, Place) {
+        match expr {
+            thir::Expr::VarRef { res, .. } if self.local_def.contains_key(&res.def) => {
+                (entry_block, self.local_def[&res.def].clone())
+            }
+            thir::Expr::Deref { expr, .. } => {
+                let (tail, pointer) = self.lower_place(entry_block, expr);
+                (tail, pointer.deref())
+            }
+            _ => {
+                let ty = expr.ty();
+                let (tail, operand) = self.lower_expr(entry_block, expr);
+                match operand {
+                    Operand::Copy(place) | Operand::Move(place) => (tail, place),
+                    operand => {
+                        let place = self.push_temp(ty);
+                        let stmt =
+                            StatementKind::Assign(Box::new((place.clone(), RValue::Use(operand))));
+                        self.builder.push_stmt(tail, self.source_info, stmt);
+
+                        (tail, place)
+                    }
+                }
+            }
+        }
+    }
+
+    fn lower_rvalue_to_temp(
+        &mut self,
+        block: BlockId,
+        rvalue: RValue<'tcx>,
+        ty: ty::Ty<'tcx>,
+    ) -> (BlockId, Operand<'tcx>) {
+        let place = self.push_temp(ty);
+        let stmt = StatementKind::Assign(Box::new((place.clone(), rvalue)));
+        self.builder.push_stmt(block, self.source_info, stmt);
+
+        (block, operand_from_place(place, ty))
+    }
+
     fn lower_expr_lit(&mut self, lit: &thir::Lit, ty: ty::Ty<'tcx>) -> Operand<'tcx> {
         match &lit {
             thir::Lit::Int(thir::LitInt { value }) => {
@@ -618,10 +806,10 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
 
     fn lower_expr_var_ref(&mut self, def: DefId, ty: ty::Ty<'tcx>) -> Operand<'tcx> {
         if let Some(local) = self.local_def.get(&def) {
-            return Operand::Copy(local.clone());
+            return operand_from_place(local.clone(), ty);
         }
 
-        if let ty::TyKind::FnDef(_) = *ty {
+        if let ty::TyKind::FnDef(..) = *ty {
             let constant = self.tcx.intern_const_zst(ty);
             return Operand::Constant(Box::new(constant));
         }
@@ -644,3 +832,13 @@ impl<'ast, 'tcx> LoweringCtx<'ast, 'tcx> {
         self.builder.push_local_decl(decl)
     }
 }
+
+/// Returns the operand that uses the value of the place.
+/// The value is moved out of the place if its type is not `Copy`.
+fn operand_from_place<'tcx>(place: Place, ty: ty::Ty<'tcx>) -> Operand<'tcx> {
+    if ty.is_copy() {
+        Operand::Copy(place)
+    } else {
+        Operand::Move(place)
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{CommonTypes, Interner, Ty};
+use crate::{res::DefId, CommonTypes, Interner, Mutability, Ty};
+
+use serde::Serialize;
+use span::Symbol;
 
 use std::fmt;
 use std::ops::Deref;
@@ -33,7 +36,7 @@ impl<'tcx> CommonConsts<'tcx> {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub struct Const<'tcx>(pub(crate) &'tcx ConstValue<'tcx>);
 
 impl<'tcx> Const<'tcx> {
@@ -52,13 +55,23 @@ impl<'tcx> Deref for Const<'tcx> {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+/// A static item, with the value of its initializer evaluated at compile time.
+/// The type of the static is the type of the value.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StaticDef<'tcx> {
+    pub def: DefId,
+    pub name: Symbol,
+    pub mutbl: Mutability,
+    pub value: Const<'tcx>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
 pub struct ConstValue<'tcx> {
     pub ty: Ty<'tcx>,
     pub literal: ConstLit,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
 pub enum ConstLit {
     Scalar(ScalarInt),
 }
@@ -75,14 +88,33 @@ impl fmt::Display for ConstLit {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
 pub struct ScalarInt {
     pub data: u128,
     pub size: u8,
 }
 
+impl ScalarInt {
+    /// Returns the scalar holding the two's complement bits of the `i32` value.
+    pub fn from_i32(value: i32) -> ScalarInt {
+        ScalarInt {
+            data: value as u32 as u128,
+            size: 32,
+        }
+    }
+
+    /// Returns the value of the scalar as an `i32`.
+    pub fn to_i32(&self) -> i32 {
+        self.data as u32 as i32
+    }
+}
+
+/// An `i32` scalar is printed as a signed integer.
 impl fmt::Display for ScalarInt {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.data)
+        match self.size {
+            32 => write!(f, "{}", self.to_i32()),
+            _ => write!(f, "{}", self.data),
+        }
     }
 }
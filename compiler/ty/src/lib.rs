@@ -1,12 +1,18 @@
 pub mod constant;
+pub mod query;
 pub mod res;
+pub mod sig;
+pub mod traits;
 
 pub use constant::*;
+pub use sig::*;
+pub use traits::*;
 
-use res::DefId;
+use query::Query;
+use res::{DefId, PrimTy};
+use serde::{Serialize, Serializer};
 use span::{Symbol, SymbolMap};
 
-use std::collections::HashMap;
 use std::fmt;
 use std::ops::Deref;
 use typed_arena::Arena;
@@ -17,7 +23,17 @@ pub struct TyCtx<'ast, 'tcx> {
     pub common_types: CommonTypes<'tcx>,
     pub common_consts: CommonConsts<'tcx>,
     pub symbol_map: &'ast SymbolMap<'ast>,
-    pub def_map: HashMap<DefId, Ty<'tcx>>,
+
+    /// The type of each function, parameter, local, closure, constant and static.
+    pub type_of: Query<Ty<'tcx>>,
+
+    /// The signature of each function and closure.
+    pub fn_sig: Query<FnHeader<'tcx>>,
+
+    pub traits: Traits<'tcx>,
+
+    /// The statics of the crate, in the order of definition.
+    pub statics: Vec<StaticDef<'tcx>>,
 }
 
 impl<'ast, 'tcx> TyCtx<'ast, 'tcx> {
@@ -32,7 +48,10 @@ impl<'ast, 'tcx> TyCtx<'ast, 'tcx> {
             common_types,
             common_consts,
             symbol_map,
-            def_map: HashMap::new(),
+            type_of: Query::new("type_of"),
+            fn_sig: Query::new("fn_sig"),
+            traits: Traits::default(),
+            statics: Vec::new(),
         }
     }
 
@@ -45,11 +64,55 @@ impl<'ast, 'tcx> TyCtx<'ast, 'tcx> {
         self.intern(TyKind::Tuple(fields.as_slice()))
     }
 
-    pub fn common_type_from_name(&self, name: Symbol) -> Ty<'tcx> {
-        match self.symbol_map.get(name) {
-            "bool" => self.common_types.bool,
-            "i32" => self.common_types.i32,
-            unknown => panic!("The type {} does not exist", unknown),
+    pub fn intern_substs(&self, substs: Vec<Ty<'tcx>>) -> SubstsRef<'tcx> {
+        self.arena.substs.alloc(substs).as_slice()
+    }
+
+    pub fn intern_fn_def(&self, def: DefId, substs: SubstsRef<'tcx>) -> Ty<'tcx> {
+        self.intern(TyKind::FnDef(def, substs))
+    }
+
+    pub fn intern_param(&self, index: usize, name: Symbol, def: DefId) -> Ty<'tcx> {
+        self.intern(TyKind::Param(ParamTy { index, name, def }))
+    }
+
+    pub fn intern_ref(&self, ty: Ty<'tcx>, mutbl: Mutability) -> Ty<'tcx> {
+        self.intern(TyKind::Ref(ty, mutbl))
+    }
+
+    pub fn intern_ptr(&self, ty: Ty<'tcx>, mutbl: Mutability) -> Ty<'tcx> {
+        self.intern(TyKind::RawPtr(ty, mutbl))
+    }
+
+    pub fn intern_dynamic(&self, trait_def: DefId) -> Ty<'tcx> {
+        self.intern(TyKind::Dynamic(trait_def))
+    }
+
+    pub fn intern_closure(
+        &self,
+        def: DefId,
+        substs: Vec<Ty<'tcx>>,
+        upvars: Vec<Ty<'tcx>>,
+    ) -> Ty<'tcx> {
+        self.intern(TyKind::Closure(ClosureTy {
+            def,
+            substs: self.intern_substs(substs),
+            upvars: self.intern_substs(upvars),
+        }))
+    }
+
+    pub fn intern_fn_ptr(&self, inputs: Vec<Ty<'tcx>>, output: Ty<'tcx>) -> Ty<'tcx> {
+        let inputs = self.arena.fn_inputs.alloc(inputs);
+        self.intern(TyKind::FnPtr(FnSig {
+            inputs: inputs.as_slice(),
+            output,
+        }))
+    }
+
+    pub fn prim_ty(&self, prim: PrimTy) -> Ty<'tcx> {
+        match prim {
+            PrimTy::Bool => self.common_types.bool,
+            PrimTy::I32 => self.common_types.i32,
         }
     }
 
@@ -57,12 +120,78 @@ impl<'ast, 'tcx> TyCtx<'ast, 'tcx> {
         self.interner.intern_const(value)
     }
 
+    /// Replaces the type parameters in `ty` with the types in `substs`.
+    pub fn subst(&self, ty: Ty<'tcx>, substs: SubstsRef<'tcx>) -> Ty<'tcx> {
+        if !ty.has_params() {
+            return ty;
+        }
+
+        match ty.kind() {
+            TyKind::Param(param) => substs[param.index],
+            TyKind::Tuple(fields) => {
+                let fields = fields.iter().map(|ty| self.subst(*ty, substs)).collect();
+                self.intern_tuple(fields)
+            }
+            TyKind::Ref(ty, mutbl) => self.intern_ref(self.subst(*ty, substs), *mutbl),
+            TyKind::RawPtr(ty, mutbl) => self.intern_ptr(self.subst(*ty, substs), *mutbl),
+            TyKind::FnDef(def, fn_substs) => {
+                let fn_substs = fn_substs.iter().map(|ty| self.subst(*ty, substs)).collect();
+                self.intern_fn_def(*def, self.intern_substs(fn_substs))
+            }
+            TyKind::Closure(closure) => {
+                let closure_substs = closure
+                    .substs
+                    .iter()
+                    .map(|ty| self.subst(*ty, substs))
+                    .collect();
+                let upvars = closure
+                    .upvars
+                    .iter()
+                    .map(|ty| self.subst(*ty, substs))
+                    .collect();
+                self.intern_closure(closure.def, closure_substs, upvars)
+            }
+            TyKind::FnPtr(sig) => {
+                let inputs = sig
+                    .inputs
+                    .iter()
+                    .map(|ty| self.subst(*ty, substs))
+                    .collect();
+                self.intern_fn_ptr(inputs, self.subst(sig.output, substs))
+            }
+            TyKind::Bool | TyKind::Int(_) | TyKind::Dynamic(_) | TyKind::Never => ty,
+        }
+    }
+
+    pub fn subst_const(&self, constant: Const<'tcx>, substs: SubstsRef<'tcx>) -> Const<'tcx> {
+        if !constant.ty.has_params() {
+            return constant;
+        }
+
+        self.intern_const(ConstValue {
+            ty: self.subst(constant.ty, substs),
+            literal: constant.literal.clone(),
+        })
+    }
+
     pub fn intern_const_zst(&self, ty: Ty<'tcx>) -> Const<'tcx> {
         self.interner.intern_const(ConstValue {
             ty,
             literal: ConstLit::ZST,
         })
     }
+
+    /// Returns the functions in the vtable of `self_ty` for the trait,
+    /// in the declaration order of the trait methods.
+    pub fn vtable_entries(&self, trait_def: DefId, self_ty: Ty<'tcx>) -> Vec<Instance<'tcx>> {
+        let substs = self.intern_substs(vec![self_ty]);
+        self.traits
+            .trait_def(trait_def)
+            .methods
+            .iter()
+            .map(|(_, method)| self.traits.resolve_instance(*method, substs))
+            .collect()
+    }
 }
 
 // pub fn with_context<'ast, F, T>(symbol_map: &'ast SymbolMap<'ast>, f: F) -> T
@@ -77,6 +206,8 @@ impl<'ast, 'tcx> TyCtx<'ast, 'tcx> {
 pub struct TyArena<'tcx> {
     types: Arena<TyKind<'tcx>>,
     tuple_fields: Arena<Vec<Ty<'tcx>>>,
+    substs: Arena<Vec<Ty<'tcx>>>,
+    fn_inputs: Arena<Vec<Ty<'tcx>>>,
     consts: Arena<ConstValue<'tcx>>,
 }
 
@@ -85,6 +216,8 @@ impl<'tcx> TyArena<'tcx> {
         TyArena {
             types: Arena::new(),
             tuple_fields: Arena::new(),
+            substs: Arena::new(),
+            fn_inputs: Arena::new(),
             consts: Arena::new(),
         }
     }
@@ -128,7 +261,7 @@ impl<'tcx> CommonTypes<'tcx> {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Ty<'tcx>(&'tcx TyKind<'tcx>);
 
 impl<'tcx> Ty<'tcx> {
@@ -138,6 +271,13 @@ impl<'tcx> Ty<'tcx> {
     }
 }
 
+/// A type is serialized as it is displayed.
+impl Serialize for Ty<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
 impl fmt::Display for Ty<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self.kind() {
@@ -150,7 +290,30 @@ impl fmt::Display for Ty<'_> {
                 .iter()
                 .fold(&mut f.debug_tuple(""), |f, field| f.field(field))
                 .finish(),
-            TyKind::FnDef(def) => write!(f, "FnDef(%{})", def),
+            TyKind::Ref(ty, mutbl) => write!(f, "&{}{}", mutbl.prefix_str(), ty),
+            TyKind::RawPtr(ty, mutbl) => match mutbl {
+                Mutability::Mut => write!(f, "*mut {}", ty),
+                Mutability::Not => write!(f, "*const {}", ty),
+            },
+            TyKind::FnDef(def, []) => write!(f, "FnDef(%{})", def),
+            TyKind::FnDef(def, substs) => write!(f, "FnDef(%{}<{}>)", def, SubstsDisplay(substs)),
+            TyKind::Closure(ClosureTy {
+                def, substs: [], ..
+            }) => {
+                write!(f, "{{closure %{}}}", def)
+            }
+            TyKind::Closure(ClosureTy { def, substs, .. }) => {
+                write!(f, "{{closure %{}<{}>}}", def, SubstsDisplay(substs))
+            }
+            TyKind::FnPtr(sig) => {
+                write!(f, "fn({})", SubstsDisplay(sig.inputs))?;
+                if !sig.output.is_zst() {
+                    write!(f, " -> {}", sig.output)?;
+                }
+                Ok(())
+            }
+            TyKind::Param(param) => write!(f, "{}", param.name),
+            TyKind::Dynamic(def) => write!(f, "dyn %{}", def),
             TyKind::Never => write!(f, "!"),
         }
     }
@@ -172,9 +335,62 @@ impl<'tcx> Ty<'tcx> {
             _ => false,
         }
     }
+
+    /// Returns `true` if the type is `!`, the type of the expressions which never produce a value.
+    pub fn is_never(&self) -> bool {
+        matches!(self.kind(), TyKind::Never)
+    }
+
+    /// Returns `true` if values of the type are copied rather than moved.
+    /// Only mutable references, type parameters and closures are moved.
+    pub fn is_copy(&self) -> bool {
+        match self.kind() {
+            TyKind::Ref(_, Mutability::Mut) | TyKind::Param(_) | TyKind::Closure(..) => false,
+            TyKind::Tuple(ts) => ts.iter().all(|ty| ty.is_copy()),
+            _ => true,
+        }
+    }
+
+    /// Returns `true` if the type contains type parameters.
+    pub fn has_params(&self) -> bool {
+        match self.kind() {
+            TyKind::Param(_) => true,
+            TyKind::Tuple(tys) | TyKind::FnDef(_, tys) => tys.iter().any(|ty| ty.has_params()),
+            TyKind::Closure(closure) => closure
+                .substs
+                .iter()
+                .chain(closure.upvars)
+                .any(|ty| ty.has_params()),
+            TyKind::Ref(ty, _) | TyKind::RawPtr(ty, _) => ty.has_params(),
+            TyKind::FnPtr(sig) => {
+                sig.inputs.iter().any(|ty| ty.has_params()) || sig.output.has_params()
+            }
+            TyKind::Bool | TyKind::Int(_) | TyKind::Dynamic(_) | TyKind::Never => false,
+        }
+    }
+
+    /// Returns `true` if the type is a trait object: `dyn Trait`
+    pub fn is_trait_object(&self) -> bool {
+        matches!(self.kind(), TyKind::Dynamic(_))
+    }
+
+    /// Returns `true` if the type is a pointer which carries a vtable along with the address:
+    /// `&dyn Trait`, `*const dyn Trait`
+    pub fn is_fat_ptr(&self) -> bool {
+        self.builtin_deref()
+            .is_some_and(|(pointee, _)| pointee.is_trait_object())
+    }
+
+    /// Returns the type and mutability of `*ty` if `ty` is a reference or a raw pointer.
+    pub fn builtin_deref(&self) -> Option<(Ty<'tcx>, Mutability)> {
+        match self.kind() {
+            TyKind::Ref(ty, mutbl) | TyKind::RawPtr(ty, mutbl) => Some((*ty, *mutbl)),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum TyKind<'tcx> {
     Bool,
 
@@ -182,12 +398,128 @@ pub enum TyKind<'tcx> {
 
     Tuple(&'tcx [Ty<'tcx>]),
 
-    FnDef(DefId),
+    /// A reference: `&T`, `&mut T`
+    Ref(Ty<'tcx>, Mutability),
+
+    /// A raw pointer: `*const T`, `*mut T`
+    RawPtr(Ty<'tcx>, Mutability),
+
+    /// The type of a function item, with the types substituted for its type parameters.
+    FnDef(DefId, SubstsRef<'tcx>),
+
+    /// A function pointer: `fn(i32) -> bool`
+    /// A function item is coerced to it to be stored or passed as a value.
+    FnPtr(FnSig<'tcx>),
+
+    /// The type of a closure. Its value is the environment holding the captured variables.
+    Closure(ClosureTy<'tcx>),
+
+    /// A type parameter: `T`
+    Param(ParamTy),
+
+    /// A trait object of the trait: `dyn Trait`
+    /// Its size is unknown, so it is only used behind a reference or a raw pointer.
+    Dynamic(DefId),
 
     Never,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The types substituted for the type parameters of an item, in declaration order.
+pub type SubstsRef<'tcx> = &'tcx [Ty<'tcx>];
+
+struct SubstsDisplay<'a, 'tcx>(&'a [Ty<'tcx>]);
+
+impl fmt::Display for SubstsDisplay<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, ty) in self.0.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", ty)?;
+        }
+        Ok(())
+    }
+}
+
+/// The signature of a function pointer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FnSig<'tcx> {
+    pub inputs: &'tcx [Ty<'tcx>],
+    pub output: Ty<'tcx>,
+}
+
+/// The type of a closure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ClosureTy<'tcx> {
+    pub def: DefId,
+
+    /// The types substituted for the type parameters of the enclosing function.
+    pub substs: SubstsRef<'tcx>,
+
+    /// The types of the fields in the environment, one for each captured variable.
+    pub upvars: &'tcx [Ty<'tcx>],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct ParamTy {
+    /// The position of the parameter in the generics of the item.
+    pub index: usize,
+    pub name: Symbol,
+
+    /// The definition of the parameter.
+    /// The `Self` parameter of the trait methods is defined by the trait.
+    pub def: DefId,
+}
+
+/// A function item together with the types substituted for its type parameters.
+/// Each instance of a generic function is generated as a separate function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Instance<'tcx> {
+    pub def: DefId,
+    pub substs: SubstsRef<'tcx>,
+}
+
+impl<'tcx> Instance<'tcx> {
+    pub fn new(def: DefId, substs: SubstsRef<'tcx>) -> Self {
+        Instance { def, substs }
+    }
+}
+
+impl fmt::Display for Instance<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "%{}", self.def)?;
+        if !self.substs.is_empty() {
+            write!(f, "<{}>", SubstsDisplay(self.substs))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
 pub enum IntTy {
     I32,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum Mutability {
+    Mut,
+    Not,
+}
+
+/// The inlining attribute of a function: `#[inline]`, `#[inline(never)]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum InlineAttr {
+    None,
+    Hint,
+    Never,
+}
+
+impl Mutability {
+    /// Returns `"mut "` or `""` depending on the mutability.
+    pub fn prefix_str(&self) -> &'static str {
+        match self {
+            Mutability::Mut => "mut ",
+            Mutability::Not => "",
+        }
+    }
+}
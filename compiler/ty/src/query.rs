@@ -0,0 +1,170 @@
+use crate::res::DefId;
+
+use thiserror::Error;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A function of the definitions whose results are memoized.
+/// A result is computed by a provider on the first demand, or fed when it is known beforehand,
+/// like the type of a parameter once the signature of its function is lowered.
+///
+/// The definitions whose results are being computed are tracked, so that a result depending on
+/// itself, like a constant whose initializer refers to the constant, is reported as a cycle
+/// instead of recursing forever.
+pub struct Query<V> {
+    name: &'static str,
+    results: RefCell<HashMap<DefId, V>>,
+
+    /// The definitions whose results are being computed, the innermost last.
+    active: RefCell<Vec<DefId>>,
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("cycle detected when computing `{query}`: {}", CycleDisplay(.cycle))]
+pub struct CycleError {
+    pub query: &'static str,
+
+    /// The definitions whose results depend on the next one, starting and ending with the same one.
+    pub cycle: Vec<DefId>,
+}
+
+struct CycleDisplay<'a>(&'a [DefId]);
+
+impl fmt::Display for CycleDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, def) in self.0.iter().enumerate() {
+            if i != 0 {
+                write!(f, " -> ")?;
+            }
+            write!(f, "%{}", def)?;
+        }
+        Ok(())
+    }
+}
+
+impl<V: Clone> Query<V> {
+    pub fn new(name: &'static str) -> Self {
+        Query {
+            name,
+            results: RefCell::new(HashMap::new()),
+            active: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Returns the result for the definition if it is already computed.
+    pub fn get(&self, def: DefId) -> Option<V> {
+        self.results.borrow().get(&def).cloned()
+    }
+
+    /// Records the result for the definition, which is known without a provider.
+    pub fn feed(&self, def: DefId, value: V) {
+        self.results.borrow_mut().insert(def, value);
+    }
+
+    /// Returns the memoized result for the definition, or `None` after marking the definition
+    /// as being computed, in which case the caller computes the result and passes it to `complete`.
+    /// Fails if the result for the definition is already being computed.
+    /// A definition whose computation fails stays marked, as the failure ends the compilation.
+    pub fn start(&self, def: DefId) -> Result<Option<V>, CycleError> {
+        if let Some(value) = self.get(def) {
+            return Ok(Some(value));
+        }
+
+        let mut active = self.active.borrow_mut();
+        if let Some(index) = active.iter().position(|active| *active == def) {
+            let mut cycle = active[index..].to_vec();
+            cycle.push(def);
+            return Err(CycleError {
+                query: self.name,
+                cycle,
+            });
+        }
+        active.push(def);
+
+        Ok(None)
+    }
+
+    /// Memoizes the result for the definition marked by `start`.
+    pub fn complete(&self, def: DefId, value: V) -> V {
+        let mut active = self.active.borrow_mut();
+        let index = active
+            .iter()
+            .rposition(|active| *active == def)
+            .unwrap_or_else(|| panic!("`{}` of %{} is not being computed.", self.name, def));
+        active.remove(index);
+        self.feed(def, value.clone());
+
+        value
+    }
+
+    /// Returns the result for the definition, calling `provider` to compute it on the first demand.
+    pub fn compute<F>(&self, def: DefId, provider: F) -> Result<V, CycleError>
+    where
+        F: FnOnce(DefId) -> V,
+    {
+        if let Some(value) = self.start(def)? {
+            return Ok(value);
+        }
+
+        let value = provider(def);
+        Ok(self.complete(def, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn def(id: usize) -> DefId {
+        DefId::from_usize(id)
+    }
+
+    #[test]
+    fn memoize() {
+        let query = Query::new("double");
+        let mut calls = 0;
+        for _ in 0..2 {
+            let value = query.compute(def(3), |def| {
+                calls += 1;
+                format!("{}{}", def, def)
+            });
+            assert_eq!(value, Ok("33".to_string()));
+        }
+        assert_eq!(calls, 1);
+
+        query.feed(def(4), "fed".to_string());
+        assert_eq!(
+            query.compute(def(4), |_| unreachable!()),
+            Ok("fed".to_string())
+        );
+    }
+
+    #[test]
+    fn detect_cycle() {
+        // each definition depends on the next one, and the last one on the first one.
+        fn provide(query: &Query<usize>, def: DefId) -> Result<usize, CycleError> {
+            if let Some(value) = query.start(def)? {
+                return Ok(value);
+            }
+            let next = if def == DefId::from_usize(0) {
+                DefId::from_usize(1)
+            } else if def == DefId::from_usize(1) {
+                DefId::from_usize(2)
+            } else {
+                DefId::from_usize(0)
+            };
+            let value = provide(query, next)? + 1;
+            Ok(query.complete(def, value))
+        }
+
+        let query = Query::new("depth");
+        let err = provide(&query, def(0)).unwrap_err();
+        assert_eq!(err.cycle, vec![def(0), def(1), def(2), def(0)]);
+        assert_eq!(
+            err.to_string(),
+            "cycle detected when computing `depth`: %0 -> %1 -> %2 -> %0"
+        );
+    }
+}
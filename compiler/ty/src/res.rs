@@ -1,18 +1,78 @@
+use serde::Serialize;
+use span::Kw;
+
 use std::fmt;
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize)]
 pub struct Res {
     pub def: DefId,
     pub kind: ResKind,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize)]
 pub enum ResKind {
     Local,
     Fn,
+    Const,
+    Static,
+    Trait,
+    Impl,
+    Closure,
+    Mod,
+
+    /// A type parameter of a generic function: `T` in `fn foo<T>(x: T)`
+    TyParam,
+
+    /// A builtin type: `i32`, `bool`
+    PrimTy(PrimTy),
+}
+
+impl ResKind {
+    /// Returns the namespace of the names referring to this kind of definition,
+    /// or `None` for the definitions which have no name.
+    pub fn ns(&self) -> Option<Namespace> {
+        match self {
+            ResKind::Local | ResKind::Fn | ResKind::Const | ResKind::Static | ResKind::Closure => {
+                Some(Namespace::Value)
+            }
+            ResKind::Trait | ResKind::Mod | ResKind::TyParam | ResKind::PrimTy(_) => {
+                Some(Namespace::Type)
+            }
+            ResKind::Impl => None,
+        }
+    }
+}
+
+/// The namespaces of names.
+/// A type and a value can have the same name, since they are used in different places.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize)]
+pub enum Namespace {
+    Type,
+    Value,
+}
+
+impl Namespace {
+    pub const ALL: [Namespace; 2] = [Namespace::Type, Namespace::Value];
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize)]
+pub enum PrimTy {
+    I32,
+    Bool,
+}
+
+impl PrimTy {
+    pub const ALL: [PrimTy; 2] = [PrimTy::I32, PrimTy::Bool];
+
+    pub fn name(&self) -> Kw {
+        match self {
+            PrimTy::I32 => Kw::I32,
+            PrimTy::Bool => Kw::Bool,
+        }
+    }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize)]
 pub struct DefId(usize);
 
 impl DefId {
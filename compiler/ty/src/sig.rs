@@ -0,0 +1,57 @@
+use crate::{
+    res::{DefId, Res},
+    InlineAttr, Mutability, ParamTy, TraitRef, Ty,
+};
+
+use serde::Serialize;
+use span::{Span, Symbol};
+
+/// The signature of a function or a closure.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct FnHeader<'tcx> {
+    pub def: DefId,
+    pub name: Symbol,
+
+    /// The span of the name of the function, or of the function enclosing a closure.
+    pub span: Span,
+
+    /// Type parameters of the function, in declaration order.
+    pub generics: Vec<ParamTy>,
+
+    /// Trait bounds on the type parameters.
+    pub predicates: Vec<TraitRef<'tcx>>,
+
+    /// Always `None` for closures.
+    pub inline: InlineAttr,
+
+    pub inputs: Vec<Param<'tcx>>,
+    pub output: Ty<'tcx>,
+
+    /// The variables captured by a closure, in the order of the fields of its environment.
+    /// The environment is passed as the first input. Empty for functions.
+    pub upvars: Vec<Upvar<'tcx>>,
+}
+
+/// A variable captured by a closure.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Upvar<'tcx> {
+    pub res: Res,
+    pub name: Symbol,
+
+    /// The mutability of the reference stored in the environment,
+    /// or `None` if the variable is moved into the environment.
+    pub by_ref: Option<Mutability>,
+
+    /// The type of the captured variable.
+    pub ty: Ty<'tcx>,
+
+    /// The first use of the variable in the closure.
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Param<'tcx> {
+    pub res: Res,
+    pub name: Symbol,
+    pub ty: Ty<'tcx>,
+}
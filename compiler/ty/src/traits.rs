@@ -0,0 +1,154 @@
+use crate::{res::DefId, Instance, SubstsRef, Ty};
+
+use serde::Serialize;
+use span::Symbol;
+
+use std::collections::HashMap;
+
+/// A trait bound on a type: `T: Foo`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct TraitRef<'tcx> {
+    pub def: DefId,
+    pub self_ty: Ty<'tcx>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraitDef {
+    pub def: DefId,
+    pub name: Symbol,
+
+    /// Names and definitions of the methods, in declaration order.
+    pub methods: Vec<(Symbol, DefId)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImplDef<'tcx> {
+    /// The implemented trait, or `None` for an inherent impl.
+    pub trait_def: Option<DefId>,
+    pub self_ty: Ty<'tcx>,
+
+    /// Names and definitions of the methods, in declaration order.
+    pub methods: Vec<(Symbol, DefId)>,
+}
+
+impl ImplDef<'_> {
+    pub fn method(&self, name: Symbol) -> Option<DefId> {
+        find_method(&self.methods, name)
+    }
+}
+
+impl TraitDef {
+    pub fn method(&self, name: Symbol) -> Option<DefId> {
+        find_method(&self.methods, name)
+    }
+}
+
+fn find_method(methods: &[(Symbol, DefId)], name: Symbol) -> Option<DefId> {
+    methods
+        .iter()
+        .find(|(method, _)| *method == name)
+        .map(|(_, def)| *def)
+}
+
+/// The traits and impls in the program.
+#[derive(Default)]
+pub struct Traits<'tcx> {
+    traits: HashMap<DefId, TraitDef>,
+
+    /// The trait that each trait method belongs to.
+    trait_methods: HashMap<DefId, DefId>,
+
+    impls: Vec<ImplDef<'tcx>>,
+}
+
+impl<'tcx> Traits<'tcx> {
+    pub fn insert_trait(&mut self, trait_def: TraitDef) {
+        for (_, method) in &trait_def.methods {
+            self.trait_methods.insert(*method, trait_def.def);
+        }
+        self.traits.insert(trait_def.def, trait_def);
+    }
+
+    pub fn insert_impl(&mut self, impl_def: ImplDef<'tcx>) {
+        self.impls.push(impl_def);
+    }
+
+    pub fn trait_def(&self, def: DefId) -> &TraitDef {
+        self.traits
+            .get(&def)
+            .unwrap_or_else(|| panic!("not found trait(defId:{}).", def))
+    }
+
+    pub fn impls(&self) -> &[ImplDef<'tcx>] {
+        &self.impls
+    }
+
+    /// Returns the impl of the trait for the type.
+    pub fn find_impl(&self, trait_def: DefId, self_ty: Ty<'tcx>) -> Option<&ImplDef<'tcx>> {
+        self.impls
+            .iter()
+            .find(|imp| imp.trait_def == Some(trait_def) && imp.self_ty == self_ty)
+    }
+
+    /// Returns the method defined in an inherent impl of the type.
+    pub fn inherent_method(&self, self_ty: Ty<'tcx>, name: Symbol) -> Option<DefId> {
+        self.impls
+            .iter()
+            .filter(|imp| imp.trait_def.is_none() && imp.self_ty == self_ty)
+            .find_map(|imp| imp.method(name))
+    }
+
+    /// Returns the methods with the name in the trait impls of the type.
+    pub fn trait_impl_methods(&self, self_ty: Ty<'tcx>, name: Symbol) -> Vec<DefId> {
+        self.impls
+            .iter()
+            .filter(|imp| imp.trait_def.is_some() && imp.self_ty == self_ty)
+            .filter_map(|imp| imp.method(name))
+            .collect()
+    }
+
+    /// Returns the position of the method in the vtable
+    /// if the call of `def` is dispatched dynamically,
+    /// that is, `def` is a trait method and its `Self` type is a trait object.
+    pub fn vtable_index(&self, def: DefId, substs: SubstsRef<'tcx>) -> Option<usize> {
+        let trait_def = self.trait_methods.get(&def)?;
+        if !substs[0].is_trait_object() {
+            return None;
+        }
+
+        self.trait_def(*trait_def)
+            .methods
+            .iter()
+            .position(|(_, method)| *method == def)
+    }
+
+    /// Resolves a call of `def` to the function that is actually called.
+    /// A trait method whose `Self` type is known resolves to the method in the impl of the type.
+    /// The first type in `substs` of a trait method is the `Self` type.
+    /// A call on a trait object is left as it is, and is dispatched through the vtable.
+    pub fn resolve_instance(&self, def: DefId, substs: SubstsRef<'tcx>) -> Instance<'tcx> {
+        let trait_def = match self.trait_methods.get(&def) {
+            Some(trait_def) if !substs[0].has_params() && !substs[0].is_trait_object() => {
+                self.trait_def(*trait_def)
+            }
+            _ => return Instance::new(def, substs),
+        };
+
+        let (name, _) = trait_def
+            .methods
+            .iter()
+            .find(|(_, method)| *method == def)
+            .unwrap();
+        let method = self
+            .find_impl(trait_def.def, substs[0])
+            .and_then(|imp| imp.method(*name))
+            .unwrap_or_else(|| {
+                panic!(
+                    "not found impl of trait %{} for {}.",
+                    trait_def.def, substs[0]
+                )
+            });
+
+        Instance::new(method, &substs[1..])
+    }
+}
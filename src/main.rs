@@ -1,15 +1,14 @@
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use clap::{ArgEnum, Parser, Subcommand};
 
-use ast_lowering;
 use codegen_llvm::{codegen_and_execute, codegen_string};
-use hir_lowering;
-use parser::lexer::parse_all_token;
-use parser::{self, parse_block_from_source_str, parse_items};
-use resolve::resolve_items;
-#[allow(unused_imports)]
-use thir_lowering;
-use ty::{TyArena, TyCtx};
+use driver::{Callbacks, Compilation, Options, Session};
+use mir::Body;
+use mir_interpret::interpret;
+use parser::lexer::{gather_blank_lines, gather_comments, parse_all_token};
+use serde::Serialize;
+use span::{source_map::SourceMap, SymbolMap};
+use ty::TyCtx;
 
 use std::{
     fs::File,
@@ -26,11 +25,44 @@ struct Args {
 enum Commands {
     Run {
         filename: String,
+
+        /// Executes the MIR with the interpreter instead of LLVM's JIT.
+        #[clap(long)]
+        interp: bool,
+
+        /// Runs the optimization passes on the MIR.
+        #[clap(short = 'O')]
+        optimize: bool,
     },
     Print {
         #[clap(arg_enum)]
         mode: PrintMode,
         filename: String,
+
+        /// Runs the optimization passes on the MIR before printing `mir` or `llvm`.
+        #[clap(short = 'O')]
+        optimize: bool,
+
+        /// Prints the MIR before and after each optimization pass. Implies `-O`.
+        #[clap(long)]
+        dump_passes: bool,
+
+        /// Prints `token`, `ast`, `hir`, `thir` or `mir` as JSON.
+        #[clap(long, arg_enum, default_value = "text")]
+        format: Format,
+
+        /// Prints only the items at the path in `ast` or `ast-tree`: `main`, `shapes::area`
+        #[clap(long)]
+        item: Option<String>,
+    },
+    /// Rewrites the files in the canonical format.
+    Fmt {
+        #[clap(required = true)]
+        files: Vec<String>,
+
+        /// Reports the files which are not formatted instead of rewriting them.
+        #[clap(long)]
+        check: bool,
     },
 }
 
@@ -38,32 +70,73 @@ enum Commands {
 enum PrintMode {
     Token,
     AST,
+    /// The AST as an indented tree of nodes with their spans.
+    AstTree,
     HIR,
     THIR,
     MIR,
+    /// The control flow graphs of the MIR in DOT format.
+    MirCfg,
     LLVM,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, ArgEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
 fn main() -> Result<()> {
     let args = Args::parse();
 
     match args.command {
-        Commands::Run { filename } => {
-            let input = read_file(&filename)?;
-            run_input(&input)?;
-        }
-
-        Commands::Print { mode, filename } => {
-            let input = read_file(&filename)?;
-            match mode {
-                PrintMode::Token => print_token(&input)?,
-                PrintMode::AST => print_ast(&input)?,
-                PrintMode::HIR => print_hir(&input)?,
-                PrintMode::THIR => print_thir(&input)?,
-                PrintMode::MIR => print_mir(&input)?,
-                PrintMode::LLVM => print_llvm(&input)?,
+        Commands::Run {
+            filename,
+            interp,
+            optimize,
+        } => run_input(&filename, interp, optimize)?,
+
+        Commands::Print {
+            mode,
+            filename,
+            optimize,
+            dump_passes,
+            format,
+            item,
+        } => match mode {
+            _ if item.is_some() && !matches!(mode, PrintMode::AST | PrintMode::AstTree) => {
+                bail!("`--item` can be given only with `ast` or `ast-tree`")
             }
-        }
+            PrintMode::Token => print_token(&read_file(&filename)?, format)?,
+            PrintMode::AST => print_ast(&filename, item.as_deref(), false, format)?,
+            PrintMode::AstTree if format == Format::Json => {
+                bail!("`{:?}` cannot be printed as JSON", mode)
+            }
+            PrintMode::AstTree => print_ast(&filename, item.as_deref(), true, format)?,
+            PrintMode::MIR if dump_passes && format == Format::Json => {
+                bail!("`--dump-passes` cannot be printed as JSON")
+            }
+            PrintMode::MirCfg | PrintMode::LLVM if format == Format::Json => {
+                bail!("`{:?}` cannot be printed as JSON", mode)
+            }
+            _ => {
+                // the passes are dumped only before printing the MIR.
+                let dump_passes = dump_passes && matches!(mode, PrintMode::MIR);
+                let options = Options {
+                    optimize: optimize || dump_passes,
+                    emit_warnings: true,
+                };
+                let source_map = SourceMap::new();
+                let sess = Session::new(&source_map, &filename, options)?;
+                sess.run(&mut Printer {
+                    mode,
+                    format,
+                    dump_passes,
+                })?
+            }
+        },
+
+        Commands::Fmt { files, check } => format_files(&files, check)?,
     }
 
     Ok(())
@@ -79,146 +152,240 @@ fn read_file(filename: &str) -> Result<String> {
     Ok(input)
 }
 
-fn run_input(input: &str) -> Result<()> {
-    let (ast, map) = parse_items(input)?;
-    let res = resolve_items(ast.as_slice(), &map)?;
-    let hir = ast_lowering::LoweringCtx::new(res).lower_items(ast.as_slice());
-
-    let arena = TyArena::new();
-    let context = TyCtx::new(&arena, &map);
-
-    let mut hir_lowering_ctx = hir_lowering::HIRLoweringCtx::new(context);
-    let thir = hir_lowering_ctx.lower_items(&hir);
-    let context = hir_lowering_ctx.finish();
-    let mir = {
-        let mut mir = Vec::new();
-        for item in thir {
-            let mir_item = match item.kind {
-                thir::ItemKind::Fn(fun) => {
-                    let mut ctx =
-                        thir_lowering::LoweringCtx::new(fun.header.def, fun.header.name, &context);
-                    ctx.lower_item_fun(&fun.header.inputs, &fun.header.output, &fun.body);
-                    ctx.build()
-                }
-            };
-            mir.push(mir_item);
-        }
+/// Prints the value as JSON, with the symbols resolved to their strings in the map.
+fn print_json<T: Serialize>(map: &SymbolMap, value: &T) -> Result<()> {
+    println!("{}", driver::to_json(map, value)?);
+    Ok(())
+}
 
-        mir
+fn run_input(filename: &str, interp: bool, optimize: bool) -> Result<()> {
+    let options = Options {
+        optimize,
+        emit_warnings: true,
     };
-    codegen_and_execute(mir.as_slice(), &map)?;
-    Ok(())
+    let source_map = SourceMap::new();
+    let sess = Session::new(&source_map, filename, options)?;
+    sess.run(&mut Execute { interp })
 }
 
-fn print_token(input: &str) -> Result<()> {
-    let tokens = parse_all_token(input).tokens;
-    for token in tokens {
-        println!("{:?}", token);
+/// Executes the program once it is monomorphized.
+struct Execute {
+    /// Executes the MIR with the interpreter instead of LLVM's JIT.
+    interp: bool,
+}
+
+impl Callbacks for Execute {
+    fn after_monomorphization<'tcx>(
+        &mut self,
+        _sess: &Session,
+        tcx: &TyCtx<'_, 'tcx>,
+        mir: &[Body<'tcx>],
+    ) -> Result<Compilation> {
+        if self.interp {
+            interpret(tcx, mir)?;
+        } else {
+            codegen_and_execute(tcx, mir)?;
+        }
+        Ok(Compilation::Stop)
     }
-    Ok(())
 }
 
-fn print_ast(input: &str) -> Result<()> {
-    let (ast, _) = parse_block_from_source_str(input)?;
+/// Prints the output of the phase of `mode`, then stops the compilation.
+struct Printer {
+    mode: PrintMode,
+    format: Format,
 
-    println!("{:#?}", ast);
-    Ok(())
+    /// Prints the MIR before and after each optimization pass.
+    dump_passes: bool,
 }
 
-fn print_hir(input: &str) -> Result<()> {
-    let (ast, map) = parse_items(input)?;
-    let res = resolve_items(ast.as_slice(), &map)?;
-    let hir = ast_lowering::LoweringCtx::new(res).lower_items(ast.as_slice());
-
-    let hir_print = hir::pp::print_items(&map, hir.as_slice());
-    println!("{}", hir_print);
-    Ok(())
+impl Printer {
+    fn print_mir(&self, map: &SymbolMap, mir: &[Body]) -> Result<Compilation> {
+        if self.format == Format::Json {
+            print_json(map, &mir)?;
+        } else if let PrintMode::MirCfg = self.mode {
+            println!("{}", mir::pp::graphviz::print_cfgs(map, mir));
+        } else {
+            println!("{}", mir::pp::print_bodies(map, mir));
+        }
+        Ok(Compilation::Stop)
+    }
 }
 
-fn print_thir(input: &str) -> Result<()> {
-    let (ast, map) = parse_items(input)?;
-    let res = resolve_items(ast.as_slice(), &map)?;
-    let hir = ast_lowering::LoweringCtx::new(res).lower_items(ast.as_slice());
+impl Callbacks for Printer {
+    fn after_hir_lowering(&mut self, sess: &Session, hir: &[hir::Item]) -> Result<Compilation> {
+        if !matches!(self.mode, PrintMode::HIR) {
+            return Ok(Compilation::Continue);
+        }
 
-    let arena = TyArena::new();
-    let context = TyCtx::new(&arena, &map);
+        if self.format == Format::Json {
+            print_json(&sess.symbol_map, &hir)?;
+        } else {
+            println!("{}", hir::pp::print_items(&sess.symbol_map, hir));
+        }
+        Ok(Compilation::Stop)
+    }
 
-    let thir = hir_lowering::HIRLoweringCtx::new(context).lower_items(&hir);
+    fn after_thir_lowering<'tcx>(
+        &mut self,
+        sess: &Session,
+        thir: &[thir::Item<'tcx>],
+    ) -> Result<Compilation> {
+        if !matches!(self.mode, PrintMode::THIR) {
+            return Ok(Compilation::Continue);
+        }
 
-    let thir_print = thir::pp::print_items(&map, thir.as_slice());
-    println!("{}", thir_print);
+        if self.format == Format::Json {
+            print_json(&sess.symbol_map, &thir)?;
+        } else {
+            println!("{}", thir::pp::print_items(&sess.symbol_map, thir));
+        }
+        Ok(Compilation::Stop)
+    }
 
-    Ok(())
-}
+    fn after_mir_building<'tcx>(
+        &mut self,
+        sess: &Session,
+        _tcx: &TyCtx<'_, 'tcx>,
+        mir: &[Body<'tcx>],
+    ) -> Result<Compilation> {
+        // without optimization, the MIR is printed even if it does not pass the borrow checker.
+        match self.mode {
+            PrintMode::MIR | PrintMode::MirCfg if !sess.options.optimize => {
+                self.print_mir(&sess.symbol_map, mir)
+            }
+            _ => Ok(Compilation::Continue),
+        }
+    }
 
-fn print_mir(input: &str) -> Result<()> {
-    let (ast, map) = parse_items(input)?;
-    let res = resolve_items(ast.as_slice(), &map)?;
-    let hir = ast_lowering::LoweringCtx::new(res).lower_items(ast.as_slice());
-
-    let arena = TyArena::new();
-    let context = TyCtx::new(&arena, &map);
-
-    let mut hir_lowering_ctx = hir_lowering::HIRLoweringCtx::new(context);
-    let thir = hir_lowering_ctx.lower_items(&hir);
-    let mut context = hir_lowering_ctx.finish();
-
-    let mir = {
-        let mut mir = Vec::new();
-        for item in thir {
-            let mir_item = match item.kind {
-                thir::ItemKind::Fn(fun) => {
-                    let mut ctx = thir_lowering::LoweringCtx::new(
-                        fun.header.def,
-                        fun.header.name,
-                        &mut context,
-                    );
-                    ctx.lower_item_fun(&fun.header.inputs, &fun.header.output, &fun.body);
-                    ctx.build()
-                }
-            };
-            mir.push(mir_item);
+    fn after_pass<'tcx>(&mut self, sess: &Session, pass: &str, body: &Body<'tcx>) {
+        if self.dump_passes {
+            println!("// {}", pass);
+            println!(
+                "{}",
+                mir::pp::print_bodies(&sess.symbol_map, std::slice::from_ref(body))
+            );
         }
+    }
 
-        mir
-    };
+    fn after_optimization<'tcx>(
+        &mut self,
+        sess: &Session,
+        _tcx: &TyCtx<'_, 'tcx>,
+        mir: &[Body<'tcx>],
+    ) -> Result<Compilation> {
+        match self.mode {
+            PrintMode::MIR | PrintMode::MirCfg => self.print_mir(&sess.symbol_map, mir),
+            _ => Ok(Compilation::Continue),
+        }
+    }
 
-    let mir_print = mir::pp::print_bodies(&map, mir.as_slice());
-    println!("{}", mir_print);
+    fn after_monomorphization<'tcx>(
+        &mut self,
+        _sess: &Session,
+        tcx: &TyCtx<'_, 'tcx>,
+        mir: &[Body<'tcx>],
+    ) -> Result<Compilation> {
+        if let PrintMode::LLVM = self.mode {
+            println!("{}", codegen_string(tcx, mir));
+        }
+        Ok(Compilation::Stop)
+    }
+}
+
+/// Formats the files in place, or with `check`, fails if any of them is not formatted.
+fn format_files(files: &[String], check: bool) -> Result<()> {
+    let mut unformatted = Vec::new();
+    for filename in files {
+        let input = read_file(filename)?;
+        let output = format_source(&input).with_context(|| format!("Formatting {}", filename))?;
+        if output == input {
+            continue;
+        }
+
+        if check {
+            unformatted.push(filename.as_str());
+        } else {
+            std::fs::write(filename, output)?;
+        }
+    }
 
+    if !unformatted.is_empty() {
+        bail!("These files are not formatted: {}", unformatted.join(", "));
+    }
     Ok(())
 }
 
-fn print_llvm(input: &str) -> Result<()> {
-    let (ast, map) = parse_items(input)?;
-    let res = resolve_items(ast.as_slice(), &map)?;
-    let hir = ast_lowering::LoweringCtx::new(res).lower_items(ast.as_slice());
+fn format_source(input: &str) -> Result<String> {
+    let (items, map) = parser::parse_items(input)?;
+    let comments = gather_comments(input);
+    let blank_lines = gather_blank_lines(input);
 
-    let arena = TyArena::new();
-    let context = TyCtx::new(&arena, &map);
+    Ok(ast::pp::print_items(&map, &items, &comments, &blank_lines))
+}
 
-    let mut hir_lowering_ctx = hir_lowering::HIRLoweringCtx::new(context);
-    let thir = hir_lowering_ctx.lower_items(&hir);
-    let context = hir_lowering_ctx.finish();
+fn print_token(input: &str, format: Format) -> Result<()> {
+    let tokens = parse_all_token(input);
+    if format == Format::Json {
+        return print_json(&tokens.map, &tokens.tokens);
+    }
 
-    let mir = {
-        let mut mir = Vec::new();
-        for item in thir {
-            let mir_item = match item.kind {
-                thir::ItemKind::Fn(fun) => {
-                    let mut ctx =
-                        thir_lowering::LoweringCtx::new(fun.header.def, fun.header.name, &context);
-                    ctx.lower_item_fun(&fun.header.inputs, &fun.header.output, &fun.body);
-                    ctx.build()
-                }
-            };
-            mir.push(mir_item);
-        }
+    for token in tokens.tokens {
+        println!("{:?}", token);
+    }
+    Ok(())
+}
 
-        mir
+/// Prints the items of the crate, or only the items at the path `item`.
+/// With `tree`, the items are printed as a tree of nodes instead of their `Debug` output.
+fn print_ast(filename: &str, item: Option<&str>, tree: bool, format: Format) -> Result<()> {
+    let source_map = SourceMap::new();
+    let sess = Session::new(&source_map, filename, Options::default())?;
+    let map = &sess.symbol_map;
+    let items = match item {
+        Some(path) => find_items(sess.ast(), map, path)?,
+        None => sess.ast().iter().collect(),
     };
-    let llvm_ir = codegen_string(mir.as_slice(), &map);
-    println!("{}", llvm_ir);
+    if format == Format::Json {
+        return print_json(map, &items);
+    }
 
+    if tree {
+        println!("{}", ast::pp::print_tree(map, &items));
+    } else {
+        println!("{:#?}", items);
+    }
     Ok(())
 }
+
+/// Returns the items named by the path, whose leading segments are the modules from the root of the crate.
+fn find_items<'a>(
+    items: &'a [ast::Item],
+    map: &SymbolMap,
+    path: &str,
+) -> Result<Vec<&'a ast::Item>> {
+    let segments: Vec<&str> = path.split("::").collect();
+    let (name, modules) = segments.split_last().unwrap();
+
+    let mut items = items;
+    for module in modules {
+        items = items
+            .iter()
+            .find_map(|item| match &item.kind {
+                ast::ItemKind::Mod(m) if map.get(item.ident.name) == *module => {
+                    Some(m.items.as_slice())
+                }
+                _ => None,
+            })
+            .with_context(|| format!("There is no module `{}` in `{}`", module, path))?;
+    }
+
+    let found: Vec<_> = items
+        .iter()
+        .filter(|item| map.get(item.ident.name) == *name)
+        .collect();
+    if found.is_empty() {
+        bail!("There is no item `{}`", path);
+    }
+    Ok(found)
+}